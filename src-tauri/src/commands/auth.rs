@@ -0,0 +1,96 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tauri::State;
+
+use crate::db::Database;
+use crate::models::Website;
+use crate::services::auth::AuthProvider;
+
+/// 网站认证状态
+#[derive(Debug, Clone, Serialize)]
+pub struct AuthStatus {
+    pub website_id: String,
+    /// 网站是否需要认证
+    pub enabled: bool,
+    pub has_token: bool,
+    /// 凭证过期时间（仅 JWT 凭证可知）
+    pub expires_at: Option<DateTime<Utc>>,
+    pub expired: bool,
+    /// 是否配置了登录脚本
+    pub can_refresh: bool,
+}
+
+impl AuthStatus {
+    fn of(website: &Website) -> Self {
+        let provider = AuthProvider::for_website(website);
+        Self {
+            website_id: website.id.clone(),
+            enabled: provider.is_enabled(),
+            has_token: provider.token().is_some(),
+            expires_at: provider.expires_at(),
+            expired: provider.is_expired(),
+            can_refresh: provider.can_refresh(),
+        }
+    }
+}
+
+async fn find_website(db: &Database, website_id: &str) -> Result<Website, String> {
+    db.get_all_websites()
+        .await
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .find(|w| w.id == website_id)
+        .ok_or_else(|| format!("网站不存在: {}", website_id))
+}
+
+/// 获取网站认证状态
+#[tauri::command]
+pub async fn get_website_auth_status(db: State<'_, Database>, website_id: String) -> Result<AuthStatus, String> {
+    let website = find_website(&db, &website_id).await?;
+    Ok(AuthStatus::of(&website))
+}
+
+/// 执行登录脚本刷新网站凭证
+#[tauri::command]
+pub async fn refresh_website_auth(db: State<'_, Database>, website_id: String) -> Result<AuthStatus, String> {
+    let website = find_website(&db, &website_id).await?;
+    let website = refresh_auth(&db, &website).await?;
+    Ok(AuthStatus::of(&website))
+}
+
+/// 刷新凭证并保存到网站配置，同时改写该网站已保存视频的 m3u8 地址
+pub(crate) async fn refresh_auth(db: &Database, website: &Website) -> Result<Website, String> {
+    let token = AuthProvider::for_website(website).refresh().await?;
+
+    let mut website = website.clone();
+    website.local_storage = AuthProvider::for_website(&website).with_token(&token);
+    db.save_website(&website).await.map_err(|e| e.to_string())?;
+
+    let provider = AuthProvider::for_website(&website);
+    let mut rewritten = 0;
+    for (id, m3u8_url) in db.get_video_urls_by_website(&website.name).await.map_err(|e| e.to_string())? {
+        let new_url = provider.apply_to_url(&m3u8_url);
+        if new_url != m3u8_url {
+            db.update_video_m3u8_url(&id, &new_url).await.map_err(|e| e.to_string())?;
+            rewritten += 1;
+        }
+    }
+    tracing::info!("[auth] {} 凭证已刷新，改写 {} 个 m3u8 地址", website.name, rewritten);
+
+    Ok(website)
+}
+
+/// 凭证已过期且可刷新时先刷新，刷新失败时沿用原配置
+pub(crate) async fn ensure_fresh_auth(db: &Database, website: Website) -> Website {
+    let provider = AuthProvider::for_website(&website);
+    if !(provider.is_expired() && provider.can_refresh()) {
+        return website;
+    }
+    match refresh_auth(db, &website).await {
+        Ok(website) => website,
+        Err(e) => {
+            tracing::warn!("[auth] {} 凭证已过期，刷新失败: {}", website.name, e);
+            website
+        }
+    }
+}
@@ -0,0 +1,106 @@
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tauri::State;
+
+use crate::services::cookies::load_cookie_file;
+use crate::services::AppState;
+
+/// 应用管理的 cookies.txt 概要
+#[derive(Debug, Clone, Serialize)]
+pub struct CookieFileInfo {
+    pub name: String,
+    /// 文件路径（作为 CookieSource::File 的 path）
+    pub path: String,
+    pub cookie_count: usize,
+    /// 包含的域名（去重）
+    pub domains: Vec<String>,
+    pub modified_at: Option<DateTime<Utc>>,
+}
+
+/// Cookie 文件根目录
+fn cookies_root(state: &AppState) -> PathBuf {
+    state.data_dir.join("cookies")
+}
+
+fn cookie_file_info(path: &Path) -> Result<CookieFileInfo, String> {
+    let cookies = load_cookie_file(path)?;
+    let mut domains: Vec<String> = cookies
+        .iter()
+        .map(|c| c.domain.trim_start_matches('.').to_string())
+        .collect();
+    domains.sort();
+    domains.dedup();
+
+    Ok(CookieFileInfo {
+        name: path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        path: path.to_string_lossy().to_string(),
+        cookie_count: cookies.len(),
+        domains,
+        modified_at: std::fs::metadata(path)
+            .and_then(|m| m.modified())
+            .ok()
+            .map(DateTime::<Utc>::from),
+    })
+}
+
+/// 导入 Netscape 格式的 cookies.txt，复制到应用数据目录统一管理（同名文件会被覆盖）
+#[tauri::command]
+pub async fn import_cookie_file(
+    state: State<'_, AppState>,
+    source_path: String,
+    name: String,
+) -> Result<CookieFileInfo, String> {
+    let cookies = load_cookie_file(Path::new(&source_path))?;
+    if cookies.is_empty() {
+        return Err("文件中没有有效的 Cookie，请使用 Netscape 格式的 cookies.txt".to_string());
+    }
+
+    let safe_name: String = name
+        .trim()
+        .chars()
+        .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    if safe_name.is_empty() {
+        return Err("Cookie 文件名称不能为空".to_string());
+    }
+
+    let root = cookies_root(&state);
+    std::fs::create_dir_all(&root).map_err(|e| format!("创建 Cookie 目录失败: {}", e))?;
+    let target = root.join(format!("{}.txt", safe_name));
+    std::fs::copy(&source_path, &target).map_err(|e| format!("导入 Cookie 文件失败: {}", e))?;
+
+    cookie_file_info(&target)
+}
+
+/// 获取所有已导入的 Cookie 文件
+#[tauri::command]
+pub async fn get_cookie_files(state: State<'_, AppState>) -> Result<Vec<CookieFileInfo>, String> {
+    let Ok(entries) = std::fs::read_dir(cookies_root(&state)) else {
+        return Ok(Vec::new());
+    };
+
+    let mut files: Vec<CookieFileInfo> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "txt"))
+        .filter_map(|path| cookie_file_info(&path).ok())
+        .collect();
+    files.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(files)
+}
+
+/// 删除 Cookie 文件（只允许删除 Cookie 目录下的文件）
+#[tauri::command]
+pub async fn delete_cookie_file(state: State<'_, AppState>, path: String) -> Result<(), String> {
+    let root = cookies_root(&state);
+    let file = PathBuf::from(&path);
+    if file.parent() != Some(root.as_path()) {
+        return Err(format!("不是 Cookie 文件: {}", path));
+    }
+    std::fs::remove_file(&file).map_err(|e| format!("删除 Cookie 文件失败: {}", e))
+}
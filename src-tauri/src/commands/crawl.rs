@@ -0,0 +1,343 @@
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+
+use chrono::Utc;
+use tauri::{AppHandle, Emitter, State};
+use tokio::sync::Mutex;
+
+use super::auth::ensure_fresh_auth;
+use super::save_scrape_results;
+use crate::db::Database;
+use crate::models::{CrawlJob, CrawlMode, CrawlStatus, ScrapeErrorKind, ScrapeResult, Website};
+use crate::services::{ScrapeContext, ScraperCapability, ScraperFactory};
+
+/// 增量爬取默认的连续已存在视频阈值
+const DEFAULT_STOP_AFTER_KNOWN: u32 = 5;
+
+/// 对正在运行的批量爬取任务的请求
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CrawlSignal {
+    Run,
+    /// 当前页爬完后暂停
+    Pause,
+    /// 当前页爬完后停止并删除任务
+    Delete,
+}
+
+/// 正在运行的批量爬取任务
+static RUNNING_CRAWLS: Lazy<Mutex<HashMap<String, CrawlSignal>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// 向正在运行的任务发送请求，任务没有运行时返回 false
+async fn signal_crawl(job_id: &str, signal: CrawlSignal) -> bool {
+    match RUNNING_CRAWLS.lock().await.get_mut(job_id) {
+        Some(current) => {
+            *current = signal;
+            true
+        }
+        None => false,
+    }
+}
+
+/// 创建并开始批量爬取任务
+#[tauri::command]
+pub async fn start_crawl_job(
+    app_handle: AppHandle,
+    db: State<'_, Database>,
+    website_id: String,
+    mode: CrawlMode,
+    start_page: u32,
+    end_page: Option<u32>,
+    stop_after_known: Option<u32>,
+) -> Result<CrawlJob, String> {
+    let website = db
+        .get_all_websites()
+        .await
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .find(|w| w.id == website_id)
+        .ok_or_else(|| format!("网站不存在: {}", website_id))?;
+
+    if mode == CrawlMode::Range && end_page.is_none() {
+        return Err("页码范围模式需要指定结束页".to_string());
+    }
+    if let Some(end) = end_page {
+        if end < start_page.max(1) {
+            return Err(format!("结束页 {} 小于起始页 {}", end, start_page));
+        }
+    }
+
+    ensure_paginated(&website)?;
+
+    let mut job = CrawlJob::new(&website, mode, start_page, end_page);
+    if mode == CrawlMode::Incremental {
+        job = job.with_stop_after_known(stop_after_known.unwrap_or(DEFAULT_STOP_AFTER_KNOWN));
+    }
+    db.save_crawl_job(&job).await.map_err(|e| e.to_string())?;
+
+    run_crawl_job(&app_handle, &db, job).await.map(|(job, _)| job)
+}
+
+/// 对多个网站依次执行增量爬取（website_ids 为空时爬取所有网站），返回每个网站的任务结果
+#[tauri::command]
+pub async fn start_incremental_crawl(
+    app_handle: AppHandle,
+    db: State<'_, Database>,
+    website_ids: Option<Vec<String>>,
+    stop_after_known: Option<u32>,
+    max_pages: Option<u32>,
+) -> Result<Vec<CrawlJob>, String> {
+    let websites: Vec<_> = db
+        .get_all_websites()
+        .await
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter(|w| website_ids.as_ref().map(|ids| ids.contains(&w.id)).unwrap_or(true))
+        .collect();
+
+    let mut jobs = Vec::new();
+    for website in websites {
+        if let Err(e) = ensure_paginated(&website) {
+            let _ = app_handle.emit("scrape-log", format!("{}: 跳过增量爬取 - {}", website.name, e));
+            continue;
+        }
+        let job = CrawlJob::new(&website, CrawlMode::Incremental, 1, max_pages)
+            .with_stop_after_known(stop_after_known.unwrap_or(DEFAULT_STOP_AFTER_KNOWN));
+        db.save_crawl_job(&job).await.map_err(|e| e.to_string())?;
+
+        match run_crawl_job(&app_handle, &db, job).await {
+            Ok((job, _)) => {
+                let _ = app_handle.emit("scrape-log", format!("{}: 新增 {} 个视频", job.website_name, job.new_count));
+                jobs.push(job);
+            }
+            Err(e) => {
+                let _ = app_handle.emit("scrape-log", format!("{}: 增量爬取失败 - {}", website.name, e));
+            }
+        }
+    }
+    Ok(jobs)
+}
+
+/// 从上次保存的游标继续爬取
+#[tauri::command]
+pub async fn resume_crawl_job(
+    app_handle: AppHandle,
+    db: State<'_, Database>,
+    job_id: String,
+) -> Result<CrawlJob, String> {
+    let job = db
+        .get_crawl_job(&job_id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("爬取任务不存在: {}", job_id))?;
+
+    if job.status == CrawlStatus::Completed {
+        return Err("爬取任务已完成".to_string());
+    }
+
+    run_crawl_job(&app_handle, &db, job).await.map(|(job, _)| job)
+}
+
+/// 暂停爬取任务（当前页爬完后停止）
+#[tauri::command]
+pub async fn pause_crawl_job(job_id: String) -> Result<(), String> {
+    signal_crawl(&job_id, CrawlSignal::Pause).await;
+    Ok(())
+}
+
+/// 获取所有爬取任务
+#[tauri::command]
+pub async fn get_crawl_jobs(db: State<'_, Database>) -> Result<Vec<CrawlJob>, String> {
+    db.get_all_crawl_jobs().await.map_err(|e| e.to_string())
+}
+
+/// 删除爬取任务（运行中的任务在当前页爬完后由任务自己删除）
+#[tauri::command]
+pub async fn delete_crawl_job(db: State<'_, Database>, job_id: String) -> Result<(), String> {
+    if signal_crawl(&job_id, CrawlSignal::Delete).await {
+        return Ok(());
+    }
+    db.delete_crawl_job(&job_id).await.map_err(|e| e.to_string())
+}
+
+/// 批量爬取把页码传给爬虫，不支持翻页的爬虫会把页码当作视频ID或忽略它
+pub(crate) fn ensure_paginated(website: &Website) -> Result<(), String> {
+    let info = ScraperFactory::scraper_info(website).map_err(|e| e.to_string())?;
+    if !info.supports(ScraperCapability::Pagination) {
+        return Err(format!("爬虫 {} 不支持翻页，无法批量爬取", info.name));
+    }
+    Ok(())
+}
+
+/// 逐页执行爬取任务，每页结束后持久化游标，返回任务和新增视频ID
+pub(crate) async fn run_crawl_job(
+    app_handle: &AppHandle,
+    db: &Database,
+    mut job: CrawlJob,
+) -> Result<(CrawlJob, Vec<String>), String> {
+    {
+        let mut running = RUNNING_CRAWLS.lock().await;
+        if running.contains_key(&job.id) {
+            return Err("爬取任务正在运行".to_string());
+        }
+        running.insert(job.id.clone(), CrawlSignal::Run);
+    }
+
+    let website = db
+        .get_all_websites()
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|websites| {
+            websites
+                .into_iter()
+                .find(|w| w.id == job.website_id)
+                .ok_or_else(|| format!("网站不存在: {}", job.website_name))
+        });
+    // 凭证已过期时先刷新
+    let website = match website {
+        Ok(website) => Ok(ensure_fresh_auth(db, website).await),
+        Err(e) => Err(e),
+    };
+    let scraper = match website.and_then(|w| ScraperFactory::create_scraper(&w).map_err(|e| e.to_string())) {
+        Ok(scraper) => scraper,
+        Err(e) => {
+            job.status = CrawlStatus::Failed;
+            job.message = e;
+            finish_job(app_handle, db, &mut job).await;
+            return Ok((job, Vec::new()));
+        }
+    };
+
+    let log = {
+        let app_handle = app_handle.clone();
+        move |log: String| {
+            let _ = app_handle.emit("scrape-log", log);
+        }
+    };
+
+    job.status = CrawlStatus::Running;
+    job.message = String::new();
+    update_job(app_handle, db, &mut job).await;
+    log(format!("开始批量爬取: {} (从第{}页开始)", job.website_name, job.next_page()));
+
+    // 增量模式下连续已存在视频计数（跨页累计）
+    let mut known_streak = 0u32;
+    let mut new_ids = Vec::new();
+    loop {
+        let page = job.next_page();
+        if job.is_past_end(page) {
+            job.status = CrawlStatus::Completed;
+            job.message = format!("已爬取到第{}页", job.current_page);
+            break;
+        }
+        if RUNNING_CRAWLS.lock().await.get(&job.id) != Some(&CrawlSignal::Run) {
+            job.status = CrawlStatus::Paused;
+            job.message = format!("已暂停，下次从第{}页继续", page);
+            break;
+        }
+
+        let page_str = page.to_string();
+        log(format!("正在爬取第{}页", page));
+        let results = scraper.scrape_all(&page_str, ScrapeContext::new(log.clone())).await;
+
+        // 翻页模式下翻到最后一页之后的空页是正常结束
+        if is_empty_page(&results) && matches!(job.mode, CrawlMode::UntilNoNew | CrawlMode::Incremental) {
+            job.status = CrawlStatus::Completed;
+            job.message = format!("第{}页没有视频，停止翻页", page);
+            break;
+        }
+        if !results.iter().any(|r| r.success) {
+            // 游标不前进，续爬时会重试该页
+            job.status = CrawlStatus::Failed;
+            job.message = results
+                .iter()
+                .find(|r| !r.success)
+                .map(|r| format!("第{}页爬取失败: {}", page, r.message))
+                .unwrap_or_else(|| format!("第{}页没有视频", page));
+            break;
+        }
+
+        let saved = match save_scrape_results(db, &results, &job.website_name, &page_str, &log).await {
+            Ok(saved) => saved,
+            Err(e) => {
+                job.status = CrawlStatus::Failed;
+                job.message = format!("第{}页保存失败: {}", page, e);
+                break;
+            }
+        };
+
+        job.current_page = page;
+        job.last_item = results
+            .iter()
+            .rev()
+            .filter(|r| r.success)
+            .find_map(|r| r.video_id.clone())
+            .or(job.last_item.take());
+        new_ids.extend(saved.new_ids.iter().cloned());
+        job.new_count += saved.saved as i64;
+        job.duplicate_count += saved.duplicates as i64;
+        job.message = format!("第{}页: 新增 {}, 已存在 {}", page, saved.saved, saved.duplicates);
+        update_job(app_handle, db, &mut job).await;
+
+        match job.mode {
+            CrawlMode::UntilNoNew if saved.saved == 0 => {
+                job.status = CrawlStatus::Completed;
+                job.message = format!("第{}页没有新视频，停止翻页", page);
+                break;
+            }
+            CrawlMode::Incremental => {
+                let threshold = job.stop_after_known.unwrap_or(DEFAULT_STOP_AFTER_KNOWN);
+                let reached = saved.known.iter().any(|&known| {
+                    known_streak = if known { known_streak + 1 } else { 0 };
+                    known_streak >= threshold
+                });
+                if reached {
+                    job.status = CrawlStatus::Completed;
+                    job.message = format!("连续 {} 个视频已存在，停止增量爬取", threshold);
+                    break;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    finish_job(app_handle, db, &mut job).await;
+    log(format!(
+        "批量爬取结束: {} (新增: {}, 已存在: {})",
+        job.message, job.new_count, job.duplicate_count
+    ));
+
+    // 通知前端视频列表已更新
+    if let Ok(videos) = db.get_all_videos().await {
+        let _ = app_handle.emit("videos-updated", videos);
+    }
+
+    Ok((job, new_ids))
+}
+
+/// 页面没有任何视频（空列表，或只有"未找到视频"这类失败），区别于网络错误和被拦截
+fn is_empty_page(results: &[ScrapeResult]) -> bool {
+    results.iter().all(|r| {
+        !r.success && matches!(r.error_kind, Some(ScrapeErrorKind::ParseFailed | ScrapeErrorKind::NotFound))
+    })
+}
+
+/// 任务结束：移出运行列表，保存最终状态；运行期间被请求删除时删除任务
+async fn finish_job(app_handle: &AppHandle, db: &Database, job: &mut CrawlJob) {
+    if RUNNING_CRAWLS.lock().await.remove(&job.id) == Some(CrawlSignal::Delete) {
+        if let Err(e) = db.delete_crawl_job(&job.id).await {
+            tracing::warn!("[crawl] 删除爬取任务失败: {} - {}", job.id, e);
+        }
+        return;
+    }
+    update_job(app_handle, db, job).await;
+}
+
+/// 保存任务进度并通知前端
+async fn update_job(app_handle: &AppHandle, db: &Database, job: &mut CrawlJob) {
+    job.updated_at = Utc::now();
+    if let Err(e) = db.save_crawl_job(job).await {
+        tracing::warn!("[crawl] 保存爬取任务失败: {} - {}", job.id, e);
+    }
+    let _ = app_handle.emit("crawl-progress", job.clone());
+}
@@ -0,0 +1,156 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tauri::{Emitter, State, WebviewWindow};
+
+use crate::db::Database;
+use crate::models::{ScrapeResult, Website};
+use crate::services::fixture::{FixtureMode, FixtureRecorder, ScrapeFixture};
+use crate::services::{AppState, ScrapeContext, ScraperFactory};
+
+/// 录制结果
+#[derive(Debug, Clone, Serialize)]
+pub struct FixtureRecording {
+    /// 夹具目录
+    pub fixture_dir: String,
+    pub results: Vec<ScrapeResult>,
+}
+
+/// 夹具概要（用于列表展示）
+#[derive(Debug, Clone, Serialize)]
+pub struct ScrapeFixtureInfo {
+    pub fixture_dir: String,
+    pub website_id: String,
+    pub spider: String,
+    pub input: String,
+    pub recorded_at: DateTime<Utc>,
+    pub page_count: usize,
+    pub response_count: usize,
+}
+
+/// 夹具根目录
+fn fixtures_root(state: &AppState) -> PathBuf {
+    state.data_dir.join("fixtures")
+}
+
+async fn find_website(db: &Database, website_id: &str) -> Result<Website, String> {
+    db.get_all_websites()
+        .await
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .find(|w| w.id == website_id)
+        .ok_or_else(|| format!("网站不存在: {}", website_id))
+}
+
+/// 录制一次爬取：正常访问网络，同时把页面、网络响应和 localStorage 保存为夹具（结果不入库）
+#[tauri::command]
+pub async fn record_scrape(
+    window: WebviewWindow,
+    db: State<'_, Database>,
+    state: State<'_, AppState>,
+    website_id: String,
+    input: String,
+) -> Result<FixtureRecording, String> {
+    let website = find_website(&db, &website_id).await?;
+    let scraper = ScraperFactory::create_scraper(&website).map_err(|e| e.to_string())?;
+
+    let recorder = Arc::new(FixtureRecorder::new(&website.id, scraper.id(), &input));
+    let ctx = ScrapeContext::new({
+        let window = window.clone();
+        move |log: String| {
+            let _ = window.emit("scrape-log", log);
+        }
+    })
+    .with_fixture(FixtureMode::Record(recorder.clone()));
+
+    let _ = window.emit("scrape-log", format!("开始录制: {} ({})", website.name, input));
+    let results = scraper.scrape_all(&input, ctx).await;
+
+    // 目录名: 爬虫_输入_时间，输入中的特殊字符替换为下划线
+    let safe_input: String = input
+        .chars()
+        .take(40)
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '_' })
+        .collect();
+    let dir = fixtures_root(&state).join(format!(
+        "{}_{}_{}",
+        scraper.id(),
+        safe_input,
+        Utc::now().format("%Y%m%d_%H%M%S")
+    ));
+    recorder.save(&dir)?;
+    let _ = window.emit("scrape-log", format!("夹具已保存: {}", dir.display()));
+
+    Ok(FixtureRecording {
+        fixture_dir: dir.to_string_lossy().to_string(),
+        results,
+    })
+}
+
+/// 用夹具回放爬取，不访问网络（结果不入库）
+#[tauri::command]
+pub async fn replay_scrape(
+    window: WebviewWindow,
+    db: State<'_, Database>,
+    fixture_dir: String,
+) -> Result<Vec<ScrapeResult>, String> {
+    let fixture = ScrapeFixture::load(Path::new(&fixture_dir))?;
+    let website = find_website(&db, &fixture.website_id).await?;
+    let scraper = ScraperFactory::create_scraper(&website).map_err(|e| e.to_string())?;
+    if scraper.id() != fixture.spider {
+        return Err(format!(
+            "夹具由 {} 爬虫录制，网站当前使用 {} 爬虫",
+            fixture.spider,
+            scraper.id()
+        ));
+    }
+
+    let input = fixture.input.clone();
+    let ctx = ScrapeContext::new(move |log: String| {
+        let _ = window.emit("scrape-log", log);
+    })
+    .with_fixture(FixtureMode::Replay(Arc::new(fixture)));
+
+    Ok(scraper.scrape_all(&input, ctx).await)
+}
+
+/// 获取所有已录制的夹具（按录制时间倒序）
+#[tauri::command]
+pub async fn get_scrape_fixtures(state: State<'_, AppState>) -> Result<Vec<ScrapeFixtureInfo>, String> {
+    let root = fixtures_root(&state);
+    let Ok(entries) = std::fs::read_dir(&root) else {
+        return Ok(Vec::new());
+    };
+
+    let mut fixtures: Vec<ScrapeFixtureInfo> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let dir = entry.path();
+            let fixture = ScrapeFixture::load(&dir).ok()?;
+            Some(ScrapeFixtureInfo {
+                fixture_dir: dir.to_string_lossy().to_string(),
+                website_id: fixture.website_id,
+                spider: fixture.spider,
+                input: fixture.input,
+                recorded_at: fixture.recorded_at,
+                page_count: fixture.pages.len(),
+                response_count: fixture.responses.len(),
+            })
+        })
+        .collect();
+    fixtures.sort_by_key(|f| std::cmp::Reverse(f.recorded_at));
+    Ok(fixtures)
+}
+
+/// 删除夹具目录（只允许删除夹具根目录下的目录）
+#[tauri::command]
+pub async fn delete_scrape_fixture(state: State<'_, AppState>, fixture_dir: String) -> Result<(), String> {
+    let root = fixtures_root(&state);
+    let dir = PathBuf::from(&fixture_dir);
+    if dir.parent() != Some(root.as_path()) {
+        return Err(format!("不是夹具目录: {}", fixture_dir));
+    }
+    std::fs::remove_dir_all(&dir).map_err(|e| format!("删除夹具失败: {}", e))
+}
@@ -0,0 +1,242 @@
+use futures::stream::{self, StreamExt};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, State};
+
+use super::auth::ensure_fresh_auth;
+use crate::db::{dedup_key, Database};
+use crate::models::{ScrapeResult, VideoItem, VideoStatus};
+use crate::services::cancel::{cancel_job, register_job, JobKind};
+use crate::services::link_check::{LinkChecker, LinkState};
+use crate::services::{ScrapeContext, ScraperFactory};
+
+/// 同时检查的视频数（同一主机仍受网站限速约束）
+const LINK_CHECK_CONCURRENCY: usize = 4;
+
+/// 失效检查进度（通过 link-check-progress 事件推送）
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LinkCheckProgress {
+    pub job_id: String,
+    pub total: usize,
+    pub checked: usize,
+    pub alive: usize,
+    pub expired: usize,
+    /// 无法判断（网络错误、超时等）的数量
+    pub unknown: usize,
+    /// 无法检查的网站及原因（这些网站的视频计入 unknown）
+    pub errors: Vec<String>,
+    pub finished: bool,
+    pub cancelled: bool,
+}
+
+/// 重新爬取失效视频的结果
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RescrapeSummary {
+    pub total: usize,
+    pub refreshed: usize,
+    pub failed: usize,
+    pub cancelled: bool,
+}
+
+/// 按网站名称分组（保持网站首次出现的顺序）
+fn group_by_website(videos: Vec<VideoItem>) -> Vec<(String, Vec<VideoItem>)> {
+    let mut groups: Vec<(String, Vec<VideoItem>)> = Vec::new();
+    for video in videos {
+        match groups.iter_mut().find(|(name, _)| *name == video.website_name) {
+            Some((_, group)) => group.push(video),
+            None => groups.push((video.website_name.clone(), vec![video])),
+        }
+    }
+    groups
+}
+
+/// 创建检查网站视频用的检查器（凭证已过期时先刷新，避免把凭证问题误判为链接失效）
+async fn website_checker(db: &Database, website_name: &str) -> Result<LinkChecker, String> {
+    let website = match db.get_website_by_name(website_name).await.map_err(|e| e.to_string())? {
+        Some(website) => Some(ensure_fresh_auth(db, website).await),
+        None => None,
+    };
+    LinkChecker::new(website.as_ref())
+}
+
+/// 检查已爬取视频的 m3u8 是否失效，失效的标记为 Expired（website_name 为空时检查所有网站）
+#[tauri::command]
+pub async fn check_video_links(
+    app_handle: AppHandle,
+    db: State<'_, Database>,
+    website_name: Option<String>,
+    job_id: Option<String>,
+) -> Result<LinkCheckProgress, String> {
+    let videos = db
+        .get_all_videos_by_status(VideoStatus::Scraped, website_name.as_deref())
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let job = register_job(JobKind::LinkCheck, job_id)?;
+    let cancel = job.token();
+    let mut progress = LinkCheckProgress {
+        job_id: job.id().to_string(),
+        total: videos.len(),
+        ..Default::default()
+    };
+    let _ = app_handle.emit("link-check-progress", progress.clone());
+
+    for (website_name, videos) in group_by_website(videos) {
+        if cancel.is_cancelled() {
+            break;
+        }
+        let checker = match website_checker(&db, &website_name).await {
+            Ok(checker) => checker,
+            Err(e) => {
+                // 跳过这个网站，继续检查其他网站
+                tracing::warn!("[link-check] 无法检查网站: {} - {}", website_name, e);
+                progress.errors.push(format!("{}: {}", website_name, e));
+                progress.checked += videos.len();
+                progress.unknown += videos.len();
+                let _ = app_handle.emit("link-check-progress", progress.clone());
+                continue;
+            }
+        };
+        let checker = &checker;
+
+        let mut checks = stream::iter(videos)
+            .map(move |video| async move {
+                let state = checker.check(&video.m3u8_url).await;
+                (video, state)
+            })
+            .buffer_unordered(LINK_CHECK_CONCURRENCY);
+
+        loop {
+            let next = tokio::select! {
+                next = checks.next() => next,
+                _ = cancel.cancelled() => None,
+            };
+            let Some((video, state)) = next else {
+                break;
+            };
+
+            progress.checked += 1;
+            match state {
+                LinkState::Alive => progress.alive += 1,
+                LinkState::Dead(reason) => {
+                    tracing::info!("[link-check] 已失效: {} - {}", video.name, reason);
+                    match db.update_video_status(&video.id, VideoStatus::Expired, None).await {
+                        Ok(_) => progress.expired += 1,
+                        Err(e) => tracing::warn!("[link-check] 更新状态失败: {} - {}", video.id, e),
+                    }
+                }
+                LinkState::Unknown(reason) => {
+                    tracing::info!("[link-check] 无法判断: {} - {}", video.name, reason);
+                    progress.unknown += 1;
+                }
+            }
+            let _ = app_handle.emit("link-check-progress", progress.clone());
+        }
+    }
+
+    progress.finished = true;
+    progress.cancelled = cancel.is_cancelled();
+    let _ = app_handle.emit("link-check-progress", progress.clone());
+
+    if progress.expired > 0 {
+        if let Ok(videos) = db.get_all_videos().await {
+            let _ = app_handle.emit("videos-updated", videos);
+        }
+    }
+    Ok(progress)
+}
+
+/// 取消正在进行的失效检查
+#[tauri::command]
+pub async fn cancel_link_check(job_id: String) -> Result<bool, String> {
+    Ok(cancel_job(JobKind::LinkCheck, &job_id))
+}
+
+/// 重新爬取已失效的视频，成功后更新 m3u8 地址并恢复为已爬取（website_name 为空时处理所有网站）
+///
+/// 日志通过 scrape-log 事件推送，可以用 cancel_scrape 取消
+#[tauri::command]
+pub async fn rescrape_expired_videos(
+    app_handle: AppHandle,
+    db: State<'_, Database>,
+    website_name: Option<String>,
+    job_id: Option<String>,
+) -> Result<RescrapeSummary, String> {
+    let videos = db
+        .get_all_videos_by_status(VideoStatus::Expired, website_name.as_deref())
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let job = register_job(JobKind::Scrape, job_id)?;
+    let _ = app_handle.emit("scrape-started", job.id());
+    let cancel = job.token();
+    let log = |message: String| {
+        let _ = app_handle.emit("scrape-log", message);
+    };
+
+    let mut summary = RescrapeSummary {
+        total: videos.len(),
+        ..Default::default()
+    };
+    for (website_name, videos) in group_by_website(videos) {
+        if cancel.is_cancelled() {
+            break;
+        }
+        let Some(website) = db.get_website_by_name(&website_name).await.map_err(|e| e.to_string())? else {
+            log(format!("网站不存在，跳过 {} 个视频: {}", videos.len(), website_name));
+            summary.failed += videos.len();
+            continue;
+        };
+        let website = ensure_fresh_auth(&db, website).await;
+        let scraper = ScraperFactory::create_scraper(&website).map_err(|e| e.to_string())?;
+
+        for video in videos {
+            if cancel.is_cancelled() {
+                break;
+            }
+            log(format!("重新爬取: {}", video.name));
+            let ctx = ScrapeContext::new({
+                let app_handle = app_handle.clone();
+                move |log: String| {
+                    let _ = app_handle.emit("scrape-log", log);
+                }
+            })
+            .with_cancel(cancel.clone());
+
+            let result = tokio::select! {
+                result = scraper.refresh(&video.scrape_id, ctx) => result,
+                _ = cancel.cancelled() => ScrapeResult::cancelled(Some(video.scrape_id.clone())),
+            };
+            // 爬虫回退为按页爬取时可能返回别的视频，只接受同一视频的结果
+            let same_video = result.video_id.as_deref().is_none_or(|id| id == video.scrape_id);
+            if !result.success || result.m3u8_url.is_empty() || !same_video {
+                if !cancel.is_cancelled() {
+                    summary.failed += 1;
+                    log(format!("重新爬取失败: {} - {}", video.name, result.message));
+                }
+                continue;
+            }
+
+            let key = dedup_key(&website_name, &video.scrape_id, &result.m3u8_url);
+            match db.update_video_source(&video.id, &result.m3u8_url, &key).await {
+                Ok(_) => {
+                    summary.refreshed += 1;
+                    log(format!("已更新 m3u8 地址: {}", video.name));
+                }
+                Err(e) => {
+                    summary.failed += 1;
+                    log(format!("更新失败: {} - {}", video.name, e));
+                }
+            }
+        }
+    }
+    summary.cancelled = cancel.is_cancelled();
+
+    log(format!(
+        "重新爬取结束: 成功 {} / {} 个，失败 {} 个",
+        summary.refreshed, summary.total, summary.failed
+    ));
+    if let Ok(videos) = db.get_all_videos().await {
+        let _ = app_handle.emit("videos-updated", videos);
+    }
+    Ok(summary)
+}
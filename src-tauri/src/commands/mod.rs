@@ -1,19 +1,29 @@
 use chrono::Utc;
-use tokio::sync::broadcast;
-use tokio::task;
 use tauri::{Emitter, State, WebviewWindow};
 use tauri_plugin_dialog::DialogExt;
 
+pub mod auth;
 pub mod cast;
 pub mod converter;
+pub mod cookies;
+pub mod crawl;
+pub mod fixture;
+pub mod link_check;
 pub mod player;
+pub mod proxy;
+pub mod queue;
+pub mod schedule;
 pub mod sniffer;
 
-use crate::db::{Database, PaginatedVideos};
+use crate::db::{dedup_key, Database, PaginatedVideos, TagCount, UpsertOutcome, VideoFilter};
 use crate::models::{
-    AppConfig, DownloadProgress, LocalVideo, ScrapeResult, VideoItem, VideoStatus, Website,
-    YtdlpConfig, YtdlpTask, YtdlpTaskStatus,
+    AppConfig, CookieSource, DownloadKind, LinkInfo, LocalVideo, PlaylistInfo, ScrapeErrorKind,
+    ScrapeResult, TaskCollection, VideoItem, VideoMetadata, VideoStatus, Website, YtdlpConfig, YtdlpTask, YtdlpTaskStatus,
 };
+use crate::services::auth::AuthProvider;
+use crate::services::cancel::{cancel_job, register_job, JobKind};
+use crate::services::cover_cache;
+use auth::{ensure_fresh_auth, refresh_auth};
 use crate::services::get_sidecar_path;
 
 /// 清理下载临时文件（.part 文件等）
@@ -68,7 +78,12 @@ pub async fn get_config(db: State<'_, Database>) -> Result<AppConfig, String> {
 
 #[tauri::command]
 pub async fn update_config(db: State<'_, Database>, config: AppConfig) -> Result<(), String> {
-    db.save_config(&config).await.map_err(|e| e.to_string())
+    crate::services::proxy::validate(&config.proxy)?;
+    crate::services::retry::validate(&config.retry)?;
+    db.save_config(&config).await.map_err(|e| e.to_string())?;
+    crate::services::proxy::set_default_proxy(config.proxy);
+    crate::services::retry::set_default_policy(config.retry);
+    Ok(())
 }
 
 #[tauri::command]
@@ -78,7 +93,15 @@ pub async fn get_ytdlp_config(db: State<'_, Database>) -> Result<YtdlpConfig, St
 
 #[tauri::command]
 pub async fn update_ytdlp_config(db: State<'_, Database>, config: YtdlpConfig) -> Result<(), String> {
-    db.save_ytdlp_config(&config).await.map_err(|e| e.to_string())
+    crate::services::filename::validate(&config.filename_template)?;
+    crate::services::bandwidth::validate_windows(&config.download_windows)?;
+    crate::services::post_process::validate(&config.post_process)?;
+    db.save_ytdlp_config(&config).await.map_err(|e| e.to_string())?;
+    crate::services::bandwidth::set_global_limit(crate::services::bandwidth::kb_to_bytes(config.bandwidth_limit_kb));
+    crate::services::cookies::set_default_source(config.cookie_source);
+    // 并发数或下载时间段可能变化，重新调度队列
+    queue::wake();
+    Ok(())
 }
 
 #[tauri::command]
@@ -147,8 +170,9 @@ pub async fn get_videos_paginated(
     db: State<'_, Database>,
     page: i32,
     page_size: i32,
+    filter: Option<VideoFilter>,
 ) -> Result<PaginatedVideos, String> {
-    db.get_videos_paginated(page, page_size)
+    db.get_videos_paginated(page, page_size, &filter.unwrap_or_default())
         .await
         .map_err(|e| e.to_string())
 }
@@ -160,22 +184,123 @@ pub async fn search_videos(
     query: String,
     page: i32,
     page_size: i32,
+    filter: Option<VideoFilter>,
 ) -> Result<PaginatedVideos, String> {
-    db.search_videos(&query, page, page_size)
+    db.search_videos(&query, page, page_size, &filter.unwrap_or_default())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// 获取所有视频标签
+#[tauri::command]
+pub async fn get_video_tags(db: State<'_, Database>) -> Result<Vec<TagCount>, String> {
+    db.get_tags()
         .await
         .map_err(|e| e.to_string())
 }
 
 // ===== 桌面端爬虫相关命令 =====
 
-use crate::services::{batch_download_concurrent, Scraper, ScraperFactory, ScraperInfo, get_available_scrapers};
+use crate::services::{NetworkOptions, ScrapeContext, ScraperFactory, ScraperInfo, get_available_scrapers};
+
+/// 爬取结果入库汇总
+#[derive(Debug, Default)]
+pub(crate) struct SavedResults {
+    /// 新增视频数
+    pub saved: usize,
+    /// 已存在视频数
+    pub duplicates: usize,
+    /// 按结果顺序记录每个成功结果是否已存在（增量爬取用来统计连续已知视频）
+    pub known: Vec<bool>,
+    /// 新增视频的数据库ID
+    pub new_ids: Vec<String>,
+}
+
+/// 将爬取结果按去重键写入 videos 表，已存在的视频只刷新播放数和收藏数
+///
+/// `fallback_id` 在爬虫没有返回视频ID时作为 scrape_id（通常是输入的页码/ID）
+pub(crate) async fn save_scrape_results(
+    db: &Database,
+    results: &[ScrapeResult],
+    website_name: &str,
+    fallback_id: &str,
+    log: impl Fn(String),
+) -> Result<SavedResults, String> {
+    let mut summary = SavedResults::default();
+    for result in results.iter().filter(|r| r.success) {
+        // 使用爬虫返回的实际视频ID，如果没有则使用输入的页码/ID
+        let actual_video_id = result.video_id.clone().unwrap_or_else(|| fallback_id.to_string());
+
+        // base64 封面直接存入本地缓存，不再写入数据库
+        let (cover_url, cover_key) = match result.cover_url.as_deref() {
+            Some(url) if url.starts_with("data:") => match cover_cache::store_data_url(url) {
+                Ok(key) => (None, Some(key)),
+                Err(e) => {
+                    log(format!("缓存封面失败: {} - {}", result.name, e));
+                    (None, None)
+                }
+            },
+            _ => (result.cover_url.clone(), None),
+        };
+
+        let video = VideoItem {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: result.name.clone(),
+            m3u8_url: result.m3u8_url.clone(),
+            status: VideoStatus::Scraped,
+            created_at: Utc::now(),
+            downloaded_at: None,
+            scrape_id: actual_video_id,
+            website_name: website_name.to_string(),
+            cover_url,
+            cover_key,
+            cover_path: None,
+            favorite_count: result.favorite_count,
+            view_count: result.view_count,
+            file_path: None,
+            metadata: result.metadata.clone(),
+            post_process: Vec::new(),
+            verification: None,
+        };
+        let key = dedup_key(website_name, &video.scrape_id, &video.m3u8_url);
+        match db.upsert_video(&video, &key).await {
+            Ok(UpsertOutcome::Inserted) => {
+                summary.saved += 1;
+                summary.known.push(false);
+                summary.new_ids.push(video.id.clone());
+                log(format!("保存成功: {}", result.name));
+            }
+            Ok(UpsertOutcome::Updated(_)) => {
+                summary.duplicates += 1;
+                summary.known.push(true);
+                log(format!("视频已存在，已更新播放数和收藏数: {}", result.name));
+            }
+            Err(e) => {
+                summary.known.push(false);
+                log(format!("保存失败: {} - {}", result.name, e));
+            }
+        }
+    }
 
+    // 远程封面在后台下载到本地缓存
+    if !summary.new_ids.is_empty() {
+        let db = db.clone();
+        let ids = summary.new_ids.clone();
+        tokio::spawn(async move {
+            cover_cache::cache_pending(&db, Some(&ids)).await;
+        });
+    }
+    Ok(summary)
+}
+
+/// 爬取视频（job_id 用于 cancel_scrape，未指定时自动生成并通过 scrape-started 事件通知）
 #[tauri::command]
 pub async fn scrape_video(
     window: WebviewWindow,
     db: State<'_, Database>,
     url: String,
     website_id: Option<String>,
+    job_id: Option<String>,
 ) -> Result<ScrapeResult, String> {
     // 获取网站配置
     let website = if let Some(id) = website_id {
@@ -195,9 +320,8 @@ pub async fn scrape_video(
             id: "default".to_string(),
             name: "默认网站".to_string(),
             base_url: "https://d1ibyof3mbdf0n.cloudfront.net/".to_string(),
-            local_storage: vec![],
             is_default: true,
-            spider: "d1".to_string(),
+            ..Default::default()
         }, "默认网站".to_string())
     };
 
@@ -206,81 +330,65 @@ pub async fn scrape_video(
 
     let _ = window.emit("scrape-log", format!("使用网站配置: {}", website_name));
 
+    // 凭证已过期时先刷新
+    let website = ensure_fresh_auth(&db, website).await;
+
     // 使用工厂模式创建对应的爬虫
-    let scraper = ScraperFactory::create_scraper(&website);
+    let mut scraper = ScraperFactory::create_scraper(&website).map_err(|e| e.to_string())?;
     let _ = window.emit("scrape-log", format!("使用爬虫: {}", scraper.id()));
 
+    let job = register_job(JobKind::Scrape, job_id)?;
+    let _ = window.emit("scrape-started", job.id());
+    let cancel = job.token();
+
     // 调用 scrape_all 获取所有结果（SRL 爬虫会返回多个视频）
     // 注意：不再检查整个页面是否已爬取，因为 SRL 爬虫可能返回多个新视频
-    let results = scraper.scrape_all(&url, {
+    let ctx = ScrapeContext::new({
         let window = window.clone();
         move |log: String| {
             let _ = window.emit("scrape-log", log);
         }
     })
-    .await;
+    .with_cancel(cancel.clone());
 
-    // 保存每个成功的视频到数据库
-    let mut saved_count = 0;
-    let mut duplicate_count = 0;
-    for result in results.iter() {
-        if result.success {
-            // 获取所有视频，用于重复检查
-            let all_videos = db.get_all_videos().await.map_err(|e| e.to_string())?;
-
-            // 检查是否已存在：
-            // 1. 如果 m3u8_url 非空，使用 m3u8_url 检查（SRL/D1 爬虫）
-            // 2. 如果 m3u8_url 为空（列表爬虫如 D2），使用 video_id 或 name 检查
-            let exists = if !result.m3u8_url.is_empty() {
-                // 有 m3u8_url 的情况：检查 URL 是否已存在
-                all_videos.iter().any(|v| v.m3u8_url == result.m3u8_url)
-            } else {
-                // 没有 m3u8_url 的情况（列表爬虫）：检查 video_id 是否已存在
-                let result_video_id = result.video_id.clone().unwrap_or_default();
-                if !result_video_id.is_empty() {
-                    all_videos.iter().any(|v| v.scrape_id == result_video_id)
-                } else {
-                    // 都没有的话，检查 name 是否已存在
-                    all_videos.iter().any(|v| v.name == result.name)
-                }
-            };
+    // 取消时直接丢弃爬虫 future，借出的标签页随之关闭
+    let mut results = tokio::select! {
+        results = scraper.scrape_all(&url, ctx.clone()) => results,
+        _ = cancel.cancelled() => vec![ScrapeResult::cancelled(Some(url.clone()))],
+    };
 
-            if exists {
-                duplicate_count += 1;
-                let _ = window.emit("scrape-log", format!("视频已存在，跳过: {}", result.name));
-                continue;
+    // 凭证被拒绝时刷新凭证后重试一次
+    let auth_expired = results.iter().any(|r| r.error_kind == Some(ScrapeErrorKind::AuthExpired));
+    if auth_expired && !cancel.is_cancelled() && AuthProvider::for_website(&website).can_refresh() {
+        let _ = window.emit("scrape-log", "凭证已失效，正在重新登录...".to_string());
+        match refresh_auth(&db, &website).await {
+            Ok(website) => {
+                scraper = ScraperFactory::create_scraper(&website).map_err(|e| e.to_string())?;
+                results = tokio::select! {
+                    results = scraper.scrape_all(&url, ctx) => results,
+                    _ = cancel.cancelled() => vec![ScrapeResult::cancelled(Some(url.clone()))],
+                };
             }
-
-            // 使用爬虫返回的实际视频ID，如果没有则使用输入的URL
-            let actual_video_id = result.video_id.clone().unwrap_or_else(|| url.clone());
-
-            let video = VideoItem {
-                id: uuid::Uuid::new_v4().to_string(),
-                name: result.name.clone(),
-                m3u8_url: result.m3u8_url.clone(),
-                status: VideoStatus::Scraped,
-                created_at: Utc::now(),
-                downloaded_at: None,
-                scrape_id: actual_video_id,
-                website_name: website_name.clone(),
-                // 优先使用 cover_url，没有就用视频第一帧的Base64
-                cover_url: result.cover_url.clone(),
-                favorite_count: result.favorite_count,
-                view_count: result.view_count,
-                file_path: None,
-            };
-            match db.add_video(&video).await {
-                Ok(_) => {
-                    saved_count += 1;
-                    let _ = window.emit("scrape-log", format!("保存成功: {}", result.name));
-                }
-                Err(e) => {
-                    let _ = window.emit("scrape-log", format!("保存失败: {} - {}", result.name, e));
-                }
+            Err(e) => {
+                let _ = window.emit("scrape-log", format!("刷新凭证失败: {}", e));
             }
         }
     }
 
+    if cancel.is_cancelled() {
+        let _ = window.emit("scrape-log", "爬取已取消".to_string());
+        return Ok(ScrapeResult {
+            name: format!("第{}页", url),
+            ..ScrapeResult::cancelled(Some(url.clone()))
+        });
+    }
+
+    // 保存每个成功的视频到数据库
+    let saved = save_scrape_results(&db, &results, &website_name, &url, |log| {
+        let _ = window.emit("scrape-log", log);
+    })
+    .await?;
+
     // 通知前端视频列表已更新
     let videos = db.get_all_videos().await.map_err(|e| e.to_string())?;
     let _ = window.emit("videos-updated", videos);
@@ -294,11 +402,13 @@ pub async fn scrape_video(
             success: true,
             name: format!("第{}页", url),
             m3u8_url: String::new(),
-            message: format!("成功爬取 {} / {} 个视频 (新增: {}, 已存在: {})", success_count, total_count, saved_count, duplicate_count),
+            message: format!("成功爬取 {} / {} 个视频 (新增: {}, 已存在: {})", success_count, total_count, saved.saved, saved.duplicates),
             video_id: Some(url.clone()),
             view_count: None,
             favorite_count: None,
             cover_url: None,
+            error_kind: None,
+            metadata: VideoMetadata::default(),
         })
     } else if let Some(first_fail) = results.iter().find(|r| !r.success) {
         Ok(ScrapeResult {
@@ -310,6 +420,8 @@ pub async fn scrape_video(
             view_count: None,
             favorite_count: None,
             cover_url: None,
+            error_kind: first_fail.error_kind,
+            metadata: VideoMetadata::default(),
         })
     } else {
         Ok(ScrapeResult {
@@ -321,29 +433,58 @@ pub async fn scrape_video(
             view_count: None,
             favorite_count: None,
             cover_url: None,
+            error_kind: None,
+            metadata: VideoMetadata::default(),
         })
     }
 }
 
+/// 取消正在进行的爬取
+#[tauri::command]
+pub async fn cancel_scrape(job_id: String) -> Result<bool, String> {
+    Ok(cancel_job(JobKind::Scrape, &job_id))
+}
+
 #[tauri::command]
 pub async fn delete_video(db: State<'_, Database>, video_id: String) -> Result<(), String> {
-    db.delete_video(&video_id).await.map_err(|e| e.to_string())
+    db.delete_video(&video_id).await.map_err(|e| e.to_string())?;
+    spawn_cover_gc(&db);
+    Ok(())
 }
 
+/// 把视频加入下载队列
 #[tauri::command]
 pub async fn download_video(
     app_handle: tauri::AppHandle,
-    window: WebviewWindow,
     db: State<'_, Database>,
     video_id: String,
 ) -> Result<(), String> {
-    // 复用 batch_download 的逻辑
-    batch_download(app_handle, window, db, vec![video_id]).await
+    queue::enqueue_videos(&app_handle, &db, &[video_id]).await.map(|_| ())
 }
 
 #[tauri::command]
 pub async fn clear_downloaded(db: State<'_, Database>) -> Result<(), String> {
-    db.clear_downloaded().await.map_err(|e| e.to_string())
+    db.clear_downloaded().await.map_err(|e| e.to_string())?;
+    spawn_cover_gc(&db);
+    Ok(())
+}
+
+/// 删除视频后在后台清理不再被引用的封面
+fn spawn_cover_gc(db: &Database) {
+    let db = db.clone();
+    tokio::spawn(async move {
+        if let Err(e) = cover_cache::collect_garbage(&db).await {
+            tracing::warn!("[cover] {}", e);
+        }
+    });
+}
+
+/// 缓存所有未缓存的封面并清理无用的封面文件，返回（新缓存数, 删除文件数）
+#[tauri::command]
+pub async fn rebuild_cover_cache(db: State<'_, Database>) -> Result<(usize, usize), String> {
+    let cached = cover_cache::cache_pending(&db, None).await;
+    let removed = cover_cache::collect_garbage(&db).await?;
+    Ok((cached, removed))
 }
 
 #[tauri::command]
@@ -351,134 +492,14 @@ pub fn check_ffmpeg(app_handle: tauri::AppHandle) -> bool {
     crate::services::check_ffmpeg(&app_handle)
 }
 
+/// 批量加入下载队列，每个视频按所属网站的下载配置下载
 #[tauri::command]
 pub async fn batch_download(
     app_handle: tauri::AppHandle,
-    window: WebviewWindow,
     db: State<'_, Database>,
     video_ids: Vec<String>,
 ) -> Result<(), String> {
-    let config = db.get_config().await.map_err(|e| e.to_string())?;
-    let download_path = config.download_path;
-
-    // 直接根据 video_ids 获取视频
-    let videos = db.get_videos_by_ids(&video_ids).await.map_err(|e| e.to_string())?;
-
-    if videos.is_empty() {
-        return Err("没有可下载的视频".to_string());
-    }
-
-    // 先批量更新所有视频状态为 downloading
-    for video in &videos {
-        if let Err(e) = db.update_video_status(&video.id, VideoStatus::Downloading, None).await {
-            tracing::error!("[DOWNLOAD] 设置下载中状态失败: {} - {}", video.id, e);
-        }
-    }
-
-    // 假设批量下载都来自同一个网站，取第一个视频的 website_name 获取 token
-    let website_name = &videos[0].website_name;
-    let website_opt = db.get_website_by_name(website_name).await.map_err(|e| e.to_string())?;
-
-    // 获取 token
-    let mut token = None;
-    if let Some(website) = website_opt {
-        for item in &website.local_storage {
-            if item.key == "token" {
-                token = Some(item.value.clone());
-                break;
-            }
-        }
-    }
-
-    // 准备下载列表（使用已更新 token 的 URL）
-    let videos_to_download: Vec<(String, String, String, std::path::PathBuf)> = videos
-        .into_iter()
-        .map(|video| {
-            // 更新 URL 中的 token
-            let final_url = if let Some(ref t) = token {
-                if let Ok(parsed) = url::Url::parse(&video.m3u8_url) {
-                    if let Some(query) = parsed.query() {
-                        let new_query: String = query
-                            .split('&')
-                            .map(|p| {
-                                if p.starts_with("token=") {
-                                    format!("token={}", t)
-                                } else {
-                                    p.to_string()
-                                }
-                            })
-                            .collect::<Vec<_>>()
-                            .join("&");
-                        let mut new_url = parsed;
-                        new_url.set_query(Some(&new_query));
-                        new_url.to_string()
-                    } else {
-                        video.m3u8_url.clone()
-                    }
-                } else {
-                    video.m3u8_url.clone()
-                }
-            } else {
-                video.m3u8_url.clone()
-            };
-
-            (video.id.clone(), video.name, final_url, std::path::PathBuf::from(&download_path))
-        })
-        .collect();
-
-    let videos = db.get_all_videos().await.map_err(|e| e.to_string())?;
-    let _ = window.emit("videos-updated", videos);
-
-    let (progress_tx, _) = broadcast::channel::<DownloadProgress>(100);
-
-    let window_clone = window.clone();
-    let mut progress_rx = progress_tx.subscribe();
-    task::spawn(async move {
-        while let Ok(progress) = progress_rx.recv().await {
-            let _ = window_clone.emit("event", progress);
-        }
-    });
-
-    let results = batch_download_concurrent(&app_handle, videos_to_download, 3, progress_tx).await;
-
-    for (id, result) in results.iter() {
-        if let Ok(ytdlp_result) = result {
-            if let Err(e) = db.update_video_status(id, VideoStatus::Downloaded, Some(Utc::now())).await {
-                tracing::error!("[DOWNLOAD] 更新下载状态失败: {} - {}", id, e);
-            }
-
-            // 添加到本地视频管理
-            let local_video = LocalVideo {
-                id: uuid::Uuid::new_v4().to_string(),
-                name: ytdlp_result.title.clone(),
-                file_path: ytdlp_result.file_path.clone(),
-                file_size: format_file_size(ytdlp_result.file_size),
-                duration: String::new(),
-                resolution: String::new(),
-                added_at: chrono::Utc::now(),
-            };
-
-            if let Err(e) = db.add_local_video(&local_video).await {
-                tracing::warn!("[DOWNLOAD] 添加到本地视频失败: {}", e);
-            } else {
-                tracing::info!("[DOWNLOAD] 已添加到本地视频管理: {}", ytdlp_result.title);
-            }
-        } else {
-            if let Err(e) = db.update_video_status(id, VideoStatus::Scraped, None).await {
-                tracing::error!("[DOWNLOAD] 更新失败状态失败: {} - {}", id, e);
-            }
-        }
-    }
-
-    let videos = db.get_all_videos().await.map_err(|e| e.to_string())?;
-    let _ = window.emit("videos-updated", videos);
-
-    let failed_count = results.iter().filter(|(_, r)| r.is_err()).count();
-    if failed_count > 0 {
-        return Err(format!("有 {} 个视频下载失败", failed_count));
-    }
-
-    Ok(())
+    queue::enqueue_videos(&app_handle, &db, &video_ids).await.map(|_| ())
 }
 
 // ===== 网站管理命令 =====
@@ -495,7 +516,21 @@ pub async fn get_website_by_name(db: State<'_, Database>, name: String) -> Resul
 
 #[tauri::command]
 pub async fn save_website(db: State<'_, Database>, website: Website) -> Result<(), String> {
-    db.save_website(&website).await.map_err(|e| e.to_string())
+    // 保存前先尝试创建爬虫，校验爬虫 id 和配置，避免爬取时才发现规则写错
+    ScraperFactory::create_scraper(&website).map_err(|e| e.to_string())?;
+    if let Some(ref proxy) = website.proxy {
+        crate::services::proxy::validate(proxy)?;
+    }
+    crate::services::rate_limit::validate(&website.rate_limit)?;
+    if !website.download_profile.filename_template.trim().is_empty() {
+        crate::services::filename::validate(&website.download_profile.filename_template)?;
+    }
+    if let Some(ref steps) = website.download_profile.post_process {
+        crate::services::post_process::validate(steps)?;
+    }
+    db.save_website(&website).await.map_err(|e| e.to_string())?;
+    crate::services::rate_limit::register_website(&website);
+    Ok(())
 }
 
 #[tauri::command]
@@ -530,21 +565,30 @@ pub async fn get_videos_by_website(
 // ==================== yt-dlp 下载命令 ====================
 
 #[tauri::command]
-pub async fn get_video_info(app_handle: tauri::AppHandle, url: String, quality: u32) -> Result<YtdlpTask, String> {
-    crate::services::get_video_info(&app_handle, &url, quality).await
+pub async fn get_video_info(
+    app_handle: tauri::AppHandle,
+    url: String,
+    quality: u32,
+    cookie_source: Option<CookieSource>,
+) -> Result<YtdlpTask, String> {
+    let network = NetworkOptions::resolve(cookie_source.as_ref(), None);
+    crate::services::get_video_info(&app_handle, &url, quality, &network).await
 }
 
+/// 添加下载任务，cookie_source 为任务单独指定的 Cookie 来源（为空时使用全局来源）
 #[tauri::command]
 pub async fn add_ytdlp_tasks(
     app_handle: tauri::AppHandle,
     db: State<'_, Database>,
     urls: Vec<String>,
     quality: u32,
+    cookie_source: Option<CookieSource>,
 ) -> Result<Vec<YtdlpTask>, String> {
+    let network = NetworkOptions::resolve(cookie_source.as_ref(), None);
     // 获取视频信息并创建任务
     let mut tasks = Vec::new();
     for url in &urls {
-        match crate::services::get_video_info(&app_handle, url, quality).await {
+        match crate::services::get_video_info(&app_handle, url, quality, &network).await {
             Ok(task) => {
                 // 创建简化版任务
                 let ytdlp_task = YtdlpTask {
@@ -560,6 +604,12 @@ pub async fn add_ytdlp_tasks(
                     completed_at: None,
                     resolution: task.resolution,
                     file_size: task.file_size,
+                    cookie_source: cookie_source.clone(),
+                    rate_limit: String::new(),
+                    post_process: Vec::new(),
+                    verification: None,
+                    collection: None,
+                    duration: task.duration,
                 };
                 tasks.push(ytdlp_task);
             }
@@ -581,13 +631,64 @@ pub async fn add_ytdlp_tasks(
     Ok(tasks)
 }
 
+/// 获取链接信息，播放列表和频道返回各项的标题和时长供用户选择
+#[tauri::command]
+pub async fn get_link_info(
+    app_handle: tauri::AppHandle,
+    url: String,
+    quality: u32,
+    cookie_source: Option<CookieSource>,
+) -> Result<LinkInfo, String> {
+    let network = NetworkOptions::resolve(cookie_source.as_ref(), None);
+    crate::services::get_link_info(&app_handle, &url, quality, &network).await
+}
+
+/// 从播放列表添加任务，playlist.entries 为用户选中的项目，每项创建一个任务，归入同一个合集
+#[tauri::command]
+pub async fn add_playlist_tasks(
+    db: State<'_, Database>,
+    playlist: PlaylistInfo,
+    cookie_source: Option<CookieSource>,
+) -> Result<Vec<YtdlpTask>, String> {
+    if playlist.entries.is_empty() {
+        return Err("没有选中任何视频".to_string());
+    }
+
+    let collection_id = uuid::Uuid::new_v4().to_string();
+    let tasks: Vec<YtdlpTask> = playlist
+        .entries
+        .iter()
+        .map(|entry| YtdlpTask {
+            url: entry.url.clone(),
+            title: entry.title.clone(),
+            status: YtdlpTaskStatus::Pending,
+            message: "等待下载".to_string(),
+            cookie_source: cookie_source.clone(),
+            collection: Some(TaskCollection {
+                id: collection_id.clone(),
+                title: playlist.title.clone(),
+                url: playlist.url.clone(),
+                index: entry.index,
+            }),
+            duration: entry.duration,
+            ..Default::default()
+        })
+        .collect();
+
+    for task in &tasks {
+        db.save_ytdlp_task(task).await.map_err(|e| e.to_string())?;
+    }
+    tracing::info!("从播放列表 {} 添加了 {} 个任务", playlist.title, tasks.len());
+
+    Ok(tasks)
+}
+
 #[tauri::command]
 pub async fn delete_ytdlp_task(task_id: String, db: State<'_, Database>) -> Result<(), String> {
+    // 移出下载队列（正在下载的会被停止）
+    queue::dequeue_target(&db, DownloadKind::Ytdlp, &task_id).await?;
     // 从数据库删除
     db.delete_ytdlp_task(&task_id).await.map_err(|e| e.to_string())?;
-    // 从内存中移除
-    let mut tasks = crate::services::get_all_tasks().await;
-    tasks.retain(|t| t.id != task_id);
     Ok(())
 }
 
@@ -599,13 +700,13 @@ pub async fn stop_ytdlp_task(task_id: String, db: State<'_, Database>) -> Result
     
     let current_progress = task_opt.as_ref().map(|t| t.progress).unwrap_or(0);
     tracing::info!("[yt-dlp] 准备暂停任务 {}, 当前进度: {}%", task_id, current_progress);
-    
-    // 取消下载进程（这会杀死进程树，包括所有子进程）
-    let killed = crate::services::cancel_task(&task_id);
-    if killed {
-        tracing::info!("[yt-dlp] 已发送终止信号到任务 {}", task_id);
+
+    // 移出下载队列，正在下载的取消下载进程（这会杀死进程树，包括所有子进程）
+    let queued = queue::dequeue_target(&db, DownloadKind::Ytdlp, &task_id).await?;
+    if queued {
+        tracing::info!("[yt-dlp] 已移出下载队列: {}", task_id);
     } else {
-        tracing::warn!("[yt-dlp] 未找到运行中的进程: {}", task_id);
+        tracing::warn!("[yt-dlp] 任务不在下载队列中: {}", task_id);
     }
 
     // 等待足够时间让进程及其子进程完全终止
@@ -627,123 +728,15 @@ pub async fn stop_ytdlp_task(task_id: String, db: State<'_, Database>) -> Result
     Ok(())
 }
 
+/// 把任务加入下载队列，按队列的并发数和优先级开始下载（支持断点续传）
 #[tauri::command]
 pub async fn start_ytdlp_task(
     app_handle: tauri::AppHandle,
-    window: WebviewWindow,
     db: State<'_, Database>,
     task_id: String,
     output_path: String,
 ) -> Result<(), String> {
-    // 获取任务信息（从数据库获取）
-    let task_opt = db.get_ytdlp_task_by_id(&task_id).await
-        .map_err(|e| e.to_string())?;
-
-    if task_opt.is_none() {
-        return Err("任务不存在".to_string());
-    }
-
-    let task = task_opt.unwrap();
-    let config = db.get_ytdlp_config().await
-        .map_err(|e| e.to_string())?;
-
-    // 获取已保存的进度（用于断点续传）
-    let saved_progress = task.progress;
-    tracing::info!("[rust] 开始下载任务 {}, URL: {}, 已保存进度: {}%", task_id, task.url, saved_progress);
-
-    // 只有全新下载时才清理临时文件（进度为0时），保留进度时需要断点续传
-    if saved_progress == 0 {
-        tracing::info!("[rust] 清理临时文件...");
-        clean_temp_files(&output_path, &task.title);
-    } else {
-        tracing::info!("[rust] 检测到已保存进度 {}%，尝试断点续传...", saved_progress);
-    }
-
-    // 更新任务状态为下载中（保留已保存的进度）
-    let mut update_task = task.clone();
-    update_task.status = YtdlpTaskStatus::Downloading;
-    update_task.message = "正在下载...".to_string();
-    // 保留之前保存的进度，从该进度继续下载
-    update_task.progress = saved_progress;
-
-    db.save_ytdlp_task(&update_task).await.map_err(|e| e.to_string())?;
-
-    // 启动下载
-    let (progress_tx, _) = broadcast::channel::<YtdlpTask>(100);
-
-    let window_clone = window.clone();
-    let task_id_clone = task_id.clone();
-    let db_for_spawn = db.inner().clone();
-
-    // 启动后台任务监听进度并更新数据库
-    let progress_tx_for_spawn = progress_tx.clone();
-    task::spawn(async move {
-        let mut progress_rx = progress_tx_for_spawn.subscribe();
-        while let Ok(progress) = progress_rx.recv().await {
-            // 发送进度到前端
-            let _ = window_clone.emit("ytdlp-progress", progress.clone());
-
-            // 实时更新数据库中的进度（只更新progress和file_path字段，不创建新记录）
-            // speed 是实时广播的，不入库
-            let _ = db_for_spawn.update_ytdlp_task_progress(
-                &task_id_clone,
-                progress.progress,
-                progress.file_path.clone()
-            ).await;
-        }
-    });
-
-    // 执行下载（使用新的统一下载入口）
-    let result = crate::services::download_video(
-        &app_handle,
-        &task.url,
-        &output_path,
-        &task_id,
-        &task.title,  // 传递任务标题用于重命名文件
-        &config,
-        move |p| {
-            let _ = progress_tx.send(p);
-        }
-    ).await;
-
-    // 更新最终状态到数据库（更新同一记录，不创建新记录）
-    // 先检查当前数据库状态，避免覆盖用户暂停操作
-    let current_task = db.get_ytdlp_task_by_id(&task_id).await
-        .map_err(|e| e.to_string())?
-        .ok_or("任务不存在")?;
-    
-    // 如果任务已被标记为暂停（用户主动暂停），不要覆盖为失败状态
-    if current_task.status == YtdlpTaskStatus::Paused {
-        tracing::info!("[rust] 任务已被用户暂停，跳过失败状态更新: {}", task_id);
-        return Ok(());
-    }
-    
-    let mut completed_task = task.clone();
-    completed_task.status = match result {
-        Ok(_) => YtdlpTaskStatus::Completed,
-        Err(_) => YtdlpTaskStatus::Failed,
-    };
-    completed_task.message = match &result {
-        Ok(r) => {
-            completed_task.file_path = Some(r.file_path.clone());
-            tracing::info!("[rust] 下载完成: {}", task_id);
-            "下载完成".to_string()
-        },
-        Err(e) => {
-            tracing::info!("[rust] 下载失败: {} - {}", task_id, e);
-            format!("下载失败: {}", e)
-        },
-    };
-    completed_task.completed_at = Some(chrono::Utc::now());
-    completed_task.progress = 100;
-
-    db.save_ytdlp_task(&completed_task).await
-        .map_err(|e| e.to_string())?;
-
-    // 发送完成事件，通知前端刷新状态
-    let _ = window.emit("ytdlp-complete", completed_task.clone());
-
-    Ok(())
+    queue::enqueue_ytdlp_task(&app_handle, &db, &task_id, &output_path).await
 }
 
 #[tauri::command]
@@ -755,8 +748,6 @@ pub async fn get_ytdlp_tasks(db: State<'_, Database>) -> Result<Vec<YtdlpTask>,
 
 #[tauri::command]
 pub async fn cleanup_ytdlp_tasks(db: State<'_, Database>) -> Result<(), String> {
-    // 清理内存中的任务
-    crate::services::cleanup_tasks().await;
     // 清理数据库中的任务
     let _ = db.cleanup_ytdlp_tasks().await.map_err(|e| e.to_string())?;
     Ok(())
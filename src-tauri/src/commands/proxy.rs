@@ -0,0 +1,85 @@
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+use crate::models::ProxyConfig;
+use crate::services::proxy;
+
+/// 通过代理请求测试地址的超时
+const TEST_REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// 代理连通性测试结果
+#[derive(Debug, Clone, Serialize)]
+pub struct ProxyTestResult {
+    pub success: bool,
+    /// 代理端口能否连通（直连时为 None）
+    pub proxy_reachable: Option<bool>,
+    /// 连接代理端口耗时（毫秒）
+    pub connect_ms: Option<u64>,
+    /// 测试地址的响应状态码
+    pub status: Option<u16>,
+    /// 请求测试地址耗时（毫秒）
+    pub latency_ms: Option<u64>,
+    pub message: String,
+}
+
+/// 测试代理连通性：先连接代理端口（通常是本机的代理客户端），再通过代理请求测试地址
+///
+/// proxy 为空时测试全局代理；target_url 为空时只检查代理端口
+#[tauri::command]
+pub async fn test_proxy(proxy: Option<ProxyConfig>, target_url: Option<String>) -> Result<ProxyTestResult, String> {
+    let proxy = proxy::resolve(proxy.as_ref());
+    proxy::validate(&proxy)?;
+
+    let mut result = ProxyTestResult {
+        success: false,
+        proxy_reachable: None,
+        connect_ms: None,
+        status: None,
+        latency_ms: None,
+        message: String::new(),
+    };
+
+    if !proxy.url.is_empty() {
+        match proxy::check_endpoint(&proxy).await {
+            Ok(elapsed) => {
+                result.proxy_reachable = Some(true);
+                result.connect_ms = Some(elapsed.as_millis() as u64);
+            }
+            Err(e) => {
+                result.proxy_reachable = Some(false);
+                result.message = e;
+                return Ok(result);
+            }
+        }
+    }
+
+    let Some(target_url) = target_url.filter(|u| !u.trim().is_empty()) else {
+        result.success = result.proxy_reachable.unwrap_or(false);
+        result.message = if result.success {
+            "代理端口连接正常".to_string()
+        } else {
+            "未配置代理，也未指定测试地址".to_string()
+        };
+        return Ok(result);
+    };
+
+    let builder = reqwest::Client::builder().timeout(TEST_REQUEST_TIMEOUT);
+    let client = proxy::apply_to_client(builder, &proxy)
+        .build()
+        .map_err(|e| format!("创建 HTTP 客户端失败: {}", e))?;
+
+    let started = Instant::now();
+    match client.get(target_url.trim()).send().await {
+        Ok(resp) => {
+            result.latency_ms = Some(started.elapsed().as_millis() as u64);
+            result.status = Some(resp.status().as_u16());
+            result.success = !resp.status().is_server_error();
+            result.message = format!("测试地址返回 {}", resp.status());
+        }
+        Err(e) => {
+            result.message = format!("通过代理请求测试地址失败: {}", e);
+        }
+    }
+    Ok(result)
+}
@@ -0,0 +1,894 @@
+use once_cell::sync::Lazy;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use chrono::Local;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tokio::sync::{broadcast, Notify};
+
+use super::auth::{ensure_fresh_auth, refresh_auth};
+use super::{clean_temp_files, format_file_size};
+use crate::db::Database;
+use crate::models::{
+    DownloadKind, DownloadProgress, DownloadQueueEntry, LocalVideo, PostProcessRecord, PostProcessStatus,
+    QueueEntryStatus, VerifyReport, VideoStatus, Website, YtdlpConfig, YtdlpResult, YtdlpTask, YtdlpTaskStatus,
+};
+use crate::services::auth::AuthProvider;
+use crate::services::bandwidth;
+use crate::services::filename::FilenameVars;
+use crate::services::post_process::{self, PostProcessJob};
+use crate::services::verify;
+use crate::services::NetworkOptions;
+
+/// 没有事件唤醒时调度器检查队列的间隔
+const QUEUE_TICK: Duration = Duration::from_secs(30);
+
+/// 唤醒调度器（加入队列、下载结束、恢复队列、调整顺序时）
+static QUEUE_WAKE: Lazy<Notify> = Lazy::new(Notify::new);
+
+/// 正在下载的队列项（队列项ID -> 所属网站）
+static RUNNING: Lazy<Mutex<HashMap<String, Option<String>>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// 当前是否在下载时间段之外（用于识别进入和离开时间段）
+static OUTSIDE_WINDOW: AtomicBool = AtomicBool::new(false);
+
+/// 因暂停队列而中断的队列项，结束后放回队列而不是移除
+static INTERRUPTED: Lazy<Mutex<HashSet<String>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+/// 下载队列状态（通过 download-queue-updated 事件推送）
+#[derive(Debug, Clone, Serialize)]
+pub struct DownloadQueueState {
+    pub paused: bool,
+    /// 全局最多同时下载数
+    pub max_concurrent: u32,
+    /// 当前是否在允许下载的时间段内
+    pub window_open: bool,
+    /// 全局下载限速 (KB/s)，0 表示不限
+    pub bandwidth_limit_kb: u32,
+    pub entries: Vec<DownloadQueueEntry>,
+}
+
+/// 启动下载队列：恢复上次退出时中断的下载，然后按优先级持续调度
+pub fn start_download_queue(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let db = app_handle.state::<Database>().inner().clone();
+        reconcile_interrupted(&db).await;
+        emit_queue_state(&app_handle, &db).await;
+
+        loop {
+            dispatch(&app_handle, &db).await;
+            tokio::select! {
+                _ = QUEUE_WAKE.notified() => {}
+                _ = tokio::time::sleep(QUEUE_TICK) => {}
+            }
+        }
+    });
+}
+
+/// 把上次退出时中断的下载重新放回队列
+async fn reconcile_interrupted(db: &Database) {
+    match db.requeue_running_downloads().await {
+        Ok(count) if count > 0 => tracing::info!("[queue] {} 个中断的下载已放回队列", count),
+        Ok(_) => {}
+        Err(e) => tracing::warn!("[queue] 恢复队列状态失败: {}", e),
+    }
+
+    // 队列之外仍处于下载中的任务和视频（旧版本或异常退出遗留）
+    let mut tasks = db.get_ytdlp_tasks_by_status("Downloading").await.unwrap_or_default();
+    tasks.extend(db.get_ytdlp_tasks_by_status("Queued").await.unwrap_or_default());
+    for mut task in tasks {
+        let entry = DownloadQueueEntry::new(DownloadKind::Ytdlp, &task.id, &task.title);
+        if let Err(e) = db.enqueue_download(&entry).await {
+            tracing::warn!("[queue] 恢复任务失败: {} - {}", task.id, e);
+            continue;
+        }
+        task.status = YtdlpTaskStatus::Queued;
+        task.message = "应用退出时中断，等待继续下载".to_string();
+        let _ = db.save_ytdlp_task(&task).await;
+    }
+
+    let videos = db
+        .get_all_videos_by_status(VideoStatus::Downloading, None)
+        .await
+        .unwrap_or_default();
+    for video in videos {
+        let mut entry = DownloadQueueEntry::new(DownloadKind::Video, &video.id, &video.name);
+        entry.website_name = Some(video.website_name.clone());
+        if let Err(e) = db.enqueue_download(&entry).await {
+            tracing::warn!("[queue] 恢复视频下载失败: {} - {}", video.id, e);
+        }
+    }
+}
+
+/// 唤醒调度器
+pub(crate) fn wake() {
+    QUEUE_WAKE.notify_one();
+}
+
+/// 当前的队列状态
+async fn queue_state(db: &Database) -> Result<DownloadQueueState, String> {
+    let config = db.get_ytdlp_config().await.map_err(|e| e.to_string())?;
+    Ok(DownloadQueueState {
+        paused: db.is_download_queue_paused().await.map_err(|e| e.to_string())?,
+        max_concurrent: config.concurrent_downloads.max(1) as u32,
+        window_open: bandwidth::in_download_window(&config.download_windows, Local::now()),
+        bandwidth_limit_kb: config.bandwidth_limit_kb,
+        entries: db.get_download_queue().await.map_err(|e| e.to_string())?,
+    })
+}
+
+async fn emit_queue_state(app_handle: &AppHandle, db: &Database) {
+    match queue_state(db).await {
+        Ok(state) => {
+            let _ = app_handle.emit("download-queue-updated", state);
+        }
+        Err(e) => tracing::warn!("[queue] 读取队列失败: {}", e),
+    }
+}
+
+/// 在全局和网站并发数允许的范围内启动等待中的队列项
+///
+/// 离开下载时间段时停止正在下载的队列项并放回队列，回到时间段内后继续调度
+async fn dispatch(app_handle: &AppHandle, db: &Database) {
+    if db.is_download_queue_paused().await.unwrap_or(false) {
+        return;
+    }
+    let config = match db.get_ytdlp_config().await {
+        Ok(config) => config,
+        Err(e) => {
+            tracing::warn!("[queue] 读取下载配置失败: {}", e);
+            return;
+        }
+    };
+    if !bandwidth::in_download_window(&config.download_windows, Local::now()) {
+        if !OUTSIDE_WINDOW.swap(true, Ordering::SeqCst) {
+            tracing::info!("[queue] 不在下载时间段内，暂停下载");
+            interrupt_running(app_handle, db, "不在下载时间段内").await;
+            emit_queue_state(app_handle, db).await;
+        }
+        return;
+    }
+    if OUTSIDE_WINDOW.swap(false, Ordering::SeqCst) {
+        tracing::info!("[queue] 进入下载时间段，继续下载");
+        emit_queue_state(app_handle, db).await;
+    }
+    let max_concurrent = config.concurrent_downloads.max(1) as usize;
+    let entries = match db.get_download_queue().await {
+        Ok(entries) => entries,
+        Err(e) => {
+            tracing::warn!("[queue] 读取队列失败: {}", e);
+            return;
+        }
+    };
+    let websites = db.get_all_websites().await.unwrap_or_default();
+
+    let mut to_start = Vec::new();
+    {
+        let mut running = RUNNING.lock().unwrap();
+        for entry in entries.into_iter().filter(|e| e.status == QueueEntryStatus::Queued) {
+            if running.len() >= max_concurrent {
+                break;
+            }
+            if running.contains_key(&entry.id) {
+                continue;
+            }
+            // 网站单独限制的并发数
+            if let Some(ref name) = entry.website_name {
+                let limit = websites
+                    .iter()
+                    .find(|w| w.name == *name)
+                    .map_or(0, |w| w.download_profile.max_concurrent) as usize;
+                if limit > 0 && running.values().filter(|w| w.as_ref() == Some(name)).count() >= limit {
+                    continue;
+                }
+            }
+            running.insert(entry.id.clone(), entry.website_name.clone());
+            to_start.push(entry);
+        }
+    }
+    if to_start.is_empty() {
+        return;
+    }
+
+    for entry in to_start {
+        if let Err(e) = db.set_download_queue_status(&entry.id, QueueEntryStatus::Running).await {
+            tracing::warn!("[queue] 更新队列状态失败: {} - {}", entry.id, e);
+        }
+        tracing::info!("[queue] 开始下载: {} ({:?})", entry.title, entry.kind);
+        let app_handle = app_handle.clone();
+        let db = db.clone();
+        tauri::async_runtime::spawn(async move {
+            run_entry(&app_handle, &db, entry).await;
+        });
+    }
+    emit_queue_state(app_handle, db).await;
+}
+
+/// 执行一个队列项，结束后移出队列（因暂停队列中断的放回等待状态）
+async fn run_entry(app_handle: &AppHandle, db: &Database, entry: DownloadQueueEntry) {
+    let result = match entry.kind {
+        DownloadKind::Ytdlp => run_ytdlp_task(app_handle, db, &entry).await,
+        DownloadKind::Video => run_video_download(app_handle, db, &entry).await,
+    };
+
+    RUNNING.lock().unwrap().remove(&entry.id);
+    let interrupted = INTERRUPTED.lock().unwrap().remove(&entry.id);
+
+    if interrupted {
+        tracing::info!("[queue] 队列已暂停，放回队列: {}", entry.title);
+        let _ = db.set_download_queue_status(&entry.id, QueueEntryStatus::Queued).await;
+    } else {
+        if let Err(ref e) = result {
+            tracing::warn!("[queue] 下载失败: {} - {}", entry.title, e);
+            if entry.kind == DownloadKind::Video {
+                if let Err(e) = db.update_video_status(&entry.target_id, VideoStatus::Scraped, None).await {
+                    tracing::error!("[DOWNLOAD] 更新失败状态失败: {} - {}", entry.target_id, e);
+                }
+            }
+        }
+        let _ = db.remove_download_queue_entry(&entry.id).await;
+    }
+
+    if entry.kind == DownloadKind::Video {
+        if let Ok(videos) = db.get_all_videos().await {
+            let _ = app_handle.emit("videos-updated", videos);
+        }
+    }
+    emit_queue_state(app_handle, db).await;
+    wake();
+}
+
+/// 执行 yt-dlp 任务（断点续传）
+async fn run_ytdlp_task(app_handle: &AppHandle, db: &Database, entry: &DownloadQueueEntry) -> Result<(), String> {
+    let task_id = &entry.target_id;
+    let task = db.get_ytdlp_task_by_id(task_id).await
+        .map_err(|e| e.to_string())?
+        .ok_or("任务不存在")?;
+    let config = db.get_ytdlp_config().await
+        .map_err(|e| e.to_string())?;
+    let output_path = match entry.output_path {
+        Some(ref path) => path.clone(),
+        None => db.get_config().await.map_err(|e| e.to_string())?.download_path,
+    };
+
+    // 获取已保存的进度（用于断点续传）
+    let saved_progress = task.progress;
+    tracing::info!("[rust] 开始下载任务 {}, URL: {}, 已保存进度: {}%", task_id, task.url, saved_progress);
+
+    // 只有全新下载时才清理临时文件（进度为0时），保留进度时需要断点续传
+    if saved_progress == 0 {
+        tracing::info!("[rust] 清理临时文件...");
+        clean_temp_files(&output_path, &task.title);
+    } else {
+        tracing::info!("[rust] 检测到已保存进度 {}%，尝试断点续传...", saved_progress);
+    }
+
+    // 更新任务状态为下载中（保留已保存的进度）
+    let mut update_task = task.clone();
+    update_task.status = YtdlpTaskStatus::Downloading;
+    update_task.message = "正在下载...".to_string();
+    // 保留之前保存的进度，从该进度继续下载
+    update_task.progress = saved_progress;
+
+    db.save_ytdlp_task(&update_task).await.map_err(|e| e.to_string())?;
+    let _ = app_handle.emit("ytdlp-progress", update_task.clone());
+
+    // 启动下载
+    let (progress_tx, _) = broadcast::channel::<YtdlpTask>(100);
+
+    let app_handle_clone = app_handle.clone();
+    let task_id_clone = task_id.clone();
+    let db_for_spawn = db.clone();
+
+    // 启动后台任务监听进度并更新数据库
+    let progress_tx_for_spawn = progress_tx.clone();
+    tokio::spawn(async move {
+        let mut progress_rx = progress_tx_for_spawn.subscribe();
+        while let Ok(progress) = progress_rx.recv().await {
+            // 发送进度到前端
+            let _ = app_handle_clone.emit("ytdlp-progress", progress.clone());
+
+            // 实时更新数据库中的进度（只更新progress和file_path字段，不创建新记录）
+            // speed 是实时广播的，不入库
+            let _ = db_for_spawn.update_ytdlp_task_progress(
+                &task_id_clone,
+                progress.progress,
+                progress.file_path.clone()
+            ).await;
+        }
+    });
+
+    // 执行下载（使用新的统一下载入口）
+    let naming = FilenameVars {
+        title: task.title.clone(),
+        id: task.id.clone(),
+        ..Default::default()
+    };
+    let result = download_verified(
+        app_handle,
+        &task.url,
+        &output_path,
+        task_id,
+        &naming,
+        &config,
+        &NetworkOptions {
+            bandwidth_limit: bandwidth::kb_to_bytes(entry.bandwidth_limit_kb),
+            ..NetworkOptions::resolve(task.cookie_source.as_ref(), None)
+        },
+        task.duration,
+        {
+            // 下载进度中没有合集信息，补上以便前端按合集显示
+            let collection = task.collection.clone();
+            move |mut p: YtdlpTask| {
+                p.collection = collection.clone();
+                let _ = progress_tx.send(p);
+            }
+        }
+    ).await;
+
+    // 更新最终状态到数据库（更新同一记录，不创建新记录）
+    // 先检查当前数据库状态，避免覆盖用户暂停操作
+    let current_task = db.get_ytdlp_task_by_id(task_id).await
+        .map_err(|e| e.to_string())?
+        .ok_or("任务不存在")?;
+
+    // 如果任务已被标记为暂停（用户主动暂停）或因暂停队列放回队列，不要覆盖为失败状态
+    if matches!(current_task.status, YtdlpTaskStatus::Paused | YtdlpTaskStatus::Queued) {
+        tracing::info!("[rust] 任务已被用户暂停，跳过失败状态更新: {}", task_id);
+        return result.map(|_| ());
+    }
+
+    let mut completed_task = task.clone();
+    // 清除上一次下载的后处理状态和校验结果
+    completed_task.post_process.clear();
+    completed_task.verification = None;
+    let mut corrupt = false;
+    if let Ok((ref r, ref report)) = result {
+        completed_task.file_path = Some(r.file_path.clone());
+        if let Some(report) = report {
+            corrupt = !report.passed;
+            completed_task.resolution = report.resolution.clone();
+            completed_task.verification = Some(report.clone());
+        }
+        // 损坏的文件不做后处理
+        if !corrupt && !config.post_process.is_empty() {
+            let job = PostProcessJob {
+                task_id: task_id.clone(),
+                file_path: r.file_path.clone().into(),
+                vars: naming,
+                source_url: task.url.clone(),
+                metadata: None,
+                collision_policy: config.collision_policy,
+            };
+            let mut processing = completed_task.clone();
+            processing.status = YtdlpTaskStatus::Downloading;
+            processing.progress = 100;
+            let outcome = post_process::run(app_handle, &job, &config.post_process, |records| {
+                processing.post_process = records.to_vec();
+                processing.message = post_process_message(records);
+                let _ = app_handle.emit("ytdlp-progress", processing.clone());
+            })
+            .await;
+            completed_task.file_path = Some(outcome.file_path.to_string_lossy().to_string());
+            completed_task.post_process = outcome.records;
+        }
+    }
+    completed_task.status = match result {
+        Ok(_) if corrupt => YtdlpTaskStatus::Corrupt,
+        Ok(_) => YtdlpTaskStatus::Completed,
+        Err(_) => YtdlpTaskStatus::Failed,
+    };
+    completed_task.message = match &result {
+        Ok((_, Some(report))) if corrupt => {
+            tracing::warn!("[rust] 文件已损坏: {}", task_id);
+            corrupt_message(report)
+        },
+        Ok(_) => {
+            tracing::info!("[rust] 下载完成: {}", task_id);
+            completed_message(&completed_task.post_process)
+        },
+        Err(e) => {
+            tracing::info!("[rust] 下载失败: {} - {}", task_id, e);
+            format!("下载失败: {}", e)
+        },
+    };
+    completed_task.completed_at = Some(chrono::Utc::now());
+    completed_task.progress = 100;
+
+    db.save_ytdlp_task(&completed_task).await
+        .map_err(|e| e.to_string())?;
+
+    // 发送完成事件，通知前端刷新状态
+    let _ = app_handle.emit("ytdlp-complete", completed_task.clone());
+
+    result.map(|_| ())
+}
+
+/// 刷新网站凭证：过期时先刷新，凭证不是 JWT 无法预知过期时用 m3u8 试探是否被拒绝
+async fn prepare_website(db: &Database, website: Website, probe_url: &str) -> Website {
+    let mut website = ensure_fresh_auth(db, website).await;
+    let provider = AuthProvider::for_website(&website);
+    if provider.can_refresh() && provider.playlist_rejected(probe_url).await {
+        match refresh_auth(db, &website).await {
+            Ok(refreshed) => website = refreshed,
+            Err(e) => tracing::warn!("[DOWNLOAD] 刷新凭证失败: {}", e),
+        }
+    }
+    website
+}
+
+/// 下载爬取的视频，按所属网站的凭证、网络选项和下载配置下载，成功后加入本地视频管理
+async fn run_video_download(app_handle: &AppHandle, db: &Database, entry: &DownloadQueueEntry) -> Result<(), String> {
+    let video = db
+        .get_videos_by_ids(std::slice::from_ref(&entry.target_id))
+        .await
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .next()
+        .ok_or("视频不存在")?;
+    let app_config = db.get_config().await.map_err(|e| e.to_string())?;
+    let ytdlp_config = db.get_ytdlp_config().await.map_err(|e| e.to_string())?;
+
+    let website = match db.get_website_by_name(&video.website_name).await.map_err(|e| e.to_string())? {
+        Some(website) => Some(prepare_website(db, website, &video.m3u8_url).await),
+        None => None,
+    };
+    let (config, download_path, network, url) = match website {
+        Some(ref website) => (
+            website.download_profile.apply(&ytdlp_config),
+            website.download_profile.output_dir_or(&app_config.download_path),
+            NetworkOptions::for_website(website),
+            AuthProvider::for_website(website).apply_to_url(&video.m3u8_url),
+        ),
+        None => (
+            ytdlp_config,
+            app_config.download_path.clone(),
+            NetworkOptions::resolve(None, None),
+            video.m3u8_url.clone(),
+        ),
+    };
+    let output_path = entry.output_path.clone().unwrap_or(download_path);
+    let network = NetworkOptions {
+        bandwidth_limit: bandwidth::kb_to_bytes(entry.bandwidth_limit_kb),
+        ..network
+    };
+
+    let send_progress = {
+        let app_handle = app_handle.clone();
+        move |progress: DownloadProgress| {
+            let _ = app_handle.emit("event", progress);
+        }
+    };
+    send_progress(DownloadProgress {
+        video_id: video.id.clone(),
+        progress: 0,
+        status: "准备下载...".to_string(),
+        speed: "0 KB/s".to_string(),
+        eta: "--:--".to_string(),
+        rate_limit: String::new(),
+    });
+
+    let naming = FilenameVars {
+        title: video.name.clone(),
+        website: video.website_name.clone(),
+        scrape_id: video.scrape_id.clone(),
+        id: video.id.clone(),
+    };
+    let result = download_verified(
+        app_handle,
+        &url,
+        &output_path,
+        &video.id,
+        &naming,
+        &config,
+        &network,
+        video.metadata.duration.map(|d| d as f64),
+        {
+            let send_progress = send_progress.clone();
+            move |task: YtdlpTask| {
+                send_progress(DownloadProgress {
+                    video_id: task.id.clone(),
+                    progress: task.progress,
+                    status: task.message.clone(),
+                    speed: task.speed.clone(),
+                    eta: "--:--".to_string(),
+                    rate_limit: task.rate_limit.clone(),
+                });
+            }
+        },
+    ).await;
+
+    let corrupt_report = match result {
+        Ok((_, Some(ref report))) if !report.passed => Some(report.clone()),
+        _ => None,
+    };
+    send_progress(DownloadProgress {
+        video_id: video.id.clone(),
+        progress: 100,
+        status: match (&result, &corrupt_report) {
+            (_, Some(report)) => corrupt_message(report),
+            (Ok(_), None) => "下载完成".to_string(),
+            (Err(_), None) => "下载失败".to_string(),
+        },
+        speed: "0 KB/s".to_string(),
+        eta: "--:--".to_string(),
+        rate_limit: String::new(),
+    });
+    let (ytdlp_result, report) = result?;
+
+    if let Some(ref report) = report {
+        if let Err(e) = db.set_video_verification(&video.id, report).await {
+            tracing::warn!("[DOWNLOAD] 保存校验结果失败: {} - {}", video.id, e);
+        }
+    }
+    // 损坏的文件保留在磁盘上，不做后处理也不加入本地视频管理，可以重新下载
+    if corrupt_report.is_some() {
+        if let Err(e) = db.update_video_status(&video.id, VideoStatus::Corrupt, Some(chrono::Utc::now())).await {
+            tracing::error!("[DOWNLOAD] 更新损坏状态失败: {} - {}", video.id, e);
+        }
+        return Ok(());
+    }
+    if let Err(e) = db.update_video_status(&video.id, VideoStatus::Downloaded, Some(chrono::Utc::now())).await {
+        tracing::error!("[DOWNLOAD] 更新下载状态失败: {} - {}", video.id, e);
+    }
+
+    // 下载后处理（转换、移动等可能改变文件路径）
+    let (file_path, file_size) = if config.post_process.is_empty() {
+        (ytdlp_result.file_path.clone(), ytdlp_result.file_size)
+    } else {
+        let job = PostProcessJob {
+            task_id: video.id.clone(),
+            file_path: ytdlp_result.file_path.clone().into(),
+            vars: naming,
+            source_url: video.m3u8_url.clone(),
+            metadata: Some(video.metadata.clone()),
+            collision_policy: config.collision_policy,
+        };
+        let outcome = post_process::run(app_handle, &job, &config.post_process, |records| {
+            send_progress(DownloadProgress {
+                video_id: video.id.clone(),
+                progress: 100,
+                status: post_process_message(records),
+                speed: "0 KB/s".to_string(),
+                eta: "--:--".to_string(),
+                rate_limit: String::new(),
+            });
+        })
+        .await;
+        if let Err(e) = db.set_video_post_process(&video.id, &outcome.records).await {
+            tracing::warn!("[DOWNLOAD] 保存后处理状态失败: {} - {}", video.id, e);
+        }
+        send_progress(DownloadProgress {
+            video_id: video.id.clone(),
+            progress: 100,
+            status: completed_message(&outcome.records),
+            speed: "0 KB/s".to_string(),
+            eta: "--:--".to_string(),
+            rate_limit: String::new(),
+        });
+        let file_size = std::fs::metadata(&outcome.file_path).map(|m| m.len()).unwrap_or(ytdlp_result.file_size);
+        (outcome.file_path.to_string_lossy().to_string(), file_size)
+    };
+
+    // 添加到本地视频管理
+    let local_video = LocalVideo {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: ytdlp_result.title.clone(),
+        file_path,
+        file_size: format_file_size(file_size),
+        duration: report
+            .as_ref()
+            .and_then(|r| r.duration)
+            .map(verify::format_duration)
+            .unwrap_or_default(),
+        resolution: report.map(|r| r.resolution).unwrap_or_default(),
+        added_at: chrono::Utc::now(),
+    };
+
+    if let Err(e) = db.add_local_video(&local_video).await {
+        tracing::warn!("[DOWNLOAD] 添加到本地视频失败: {}", e);
+    } else {
+        tracing::info!("[DOWNLOAD] 已添加到本地视频管理: {}", ytdlp_result.title);
+    }
+    Ok(())
+}
+
+/// 下载并校验文件完整性，损坏时删除文件重新下载（最多 config.verify.retries 次），
+/// 返回下载结果和最后一次的校验结果（未启用校验或无法校验时为 None）
+#[allow(clippy::too_many_arguments)]
+async fn download_verified(
+    app_handle: &AppHandle,
+    url: &str,
+    output_path: &str,
+    task_id: &str,
+    naming: &FilenameVars,
+    config: &YtdlpConfig,
+    network: &NetworkOptions,
+    metadata_duration: Option<f64>,
+    mut progress_callback: impl FnMut(YtdlpTask) + Send,
+) -> Result<(YtdlpResult, Option<VerifyReport>), String> {
+    let mut retries = 0;
+    loop {
+        let result = crate::services::download_video(
+            app_handle,
+            url,
+            output_path,
+            task_id,
+            naming,
+            config,
+            network,
+            &mut progress_callback,
+        )
+        .await?;
+        // 保留的已有文件不是这次下载的，不校验也不删除
+        if !config.verify.enabled || result.skipped {
+            return Ok((result, None));
+        }
+
+        progress_callback(YtdlpTask {
+            id: task_id.to_string(),
+            url: url.to_string(),
+            title: naming.title.clone(),
+            progress: 100,
+            file_path: Some(result.file_path.clone()),
+            status: YtdlpTaskStatus::Downloading,
+            message: "正在校验文件...".to_string(),
+            ..Default::default()
+        });
+        // 优先与播放列表的 EXTINF 总时长比较，不是 m3u8 时使用爬取的元数据
+        let expected = match crate::services::playlist_duration(url, config.quality, network).await {
+            Some(duration) => Some(duration),
+            None => metadata_duration,
+        };
+        let path = std::path::Path::new(&result.file_path);
+        let mut report = match verify::verify_file(app_handle, path, expected, &config.verify).await {
+            Ok(report) => report,
+            Err(e) => {
+                tracing::warn!("[queue] 无法校验文件，跳过校验: {} - {}", result.file_path, e);
+                return Ok((result, None));
+            }
+        };
+        report.retries = retries;
+        if report.passed || retries >= config.verify.retries {
+            return Ok((result, Some(report)));
+        }
+
+        retries += 1;
+        tracing::warn!(
+            "[queue] 文件校验不通过，重新下载 ({}/{}): {} - {}",
+            retries, config.verify.retries, result.file_path, report.errors.join("; ")
+        );
+        if let Err(e) = std::fs::remove_file(path) {
+            tracing::warn!("[queue] 删除损坏的文件失败: {} - {}", result.file_path, e);
+        }
+    }
+}
+
+/// 文件损坏的状态消息
+fn corrupt_message(report: &VerifyReport) -> String {
+    format!("文件已损坏: {}", report.errors.join("; "))
+}
+
+/// 后处理进行中的状态消息
+fn post_process_message(records: &[PostProcessRecord]) -> String {
+    match records.iter().position(|r| r.status == PostProcessStatus::Running) {
+        Some(index) => format!("后处理 ({}/{}): {}...", index + 1, records.len(), records[index].step),
+        None => "正在后处理...".to_string(),
+    }
+}
+
+/// 下载完成的状态消息（带上失败的后处理步骤）
+fn completed_message(records: &[PostProcessRecord]) -> String {
+    let failed: Vec<&str> = records
+        .iter()
+        .filter(|r| r.status == PostProcessStatus::Failed)
+        .map(|r| r.step.as_str())
+        .collect();
+    if failed.is_empty() {
+        "下载完成".to_string()
+    } else {
+        format!("下载完成，后处理失败: {}", failed.join("、"))
+    }
+}
+
+/// 把爬取的视频加入下载队列并标记为下载中，返回新加入的数量
+pub(crate) async fn enqueue_videos(app_handle: &AppHandle, db: &Database, video_ids: &[String]) -> Result<usize, String> {
+    let videos = db.get_videos_by_ids(video_ids).await.map_err(|e| e.to_string())?;
+    if videos.is_empty() {
+        return Err("没有可下载的视频".to_string());
+    }
+
+    let mut added = 0;
+    for video in &videos {
+        let mut entry = DownloadQueueEntry::new(DownloadKind::Video, &video.id, &video.name);
+        entry.website_name = Some(video.website_name.clone());
+        if db.enqueue_download(&entry).await.map_err(|e| e.to_string())? {
+            added += 1;
+        }
+        if let Err(e) = db.update_video_status(&video.id, VideoStatus::Downloading, None).await {
+            tracing::error!("[DOWNLOAD] 设置下载中状态失败: {} - {}", video.id, e);
+        }
+    }
+
+    if let Ok(videos) = db.get_all_videos().await {
+        let _ = app_handle.emit("videos-updated", videos);
+    }
+    emit_queue_state(app_handle, db).await;
+    wake();
+    Ok(added)
+}
+
+/// 把 yt-dlp 任务加入下载队列
+pub(crate) async fn enqueue_ytdlp_task(
+    app_handle: &AppHandle,
+    db: &Database,
+    task_id: &str,
+    output_path: &str,
+) -> Result<(), String> {
+    let mut task = db.get_ytdlp_task_by_id(task_id).await
+        .map_err(|e| e.to_string())?
+        .ok_or("任务不存在")?;
+
+    let mut entry = DownloadQueueEntry::new(DownloadKind::Ytdlp, &task.id, &task.title);
+    entry.output_path = Some(output_path.to_string()).filter(|path| !path.is_empty());
+    db.enqueue_download(&entry).await.map_err(|e| e.to_string())?;
+
+    if task.status != YtdlpTaskStatus::Downloading {
+        task.status = YtdlpTaskStatus::Queued;
+        task.message = "等待下载".to_string();
+        db.save_ytdlp_task(&task).await.map_err(|e| e.to_string())?;
+        let _ = app_handle.emit("ytdlp-progress", task);
+    }
+
+    emit_queue_state(app_handle, db).await;
+    wake();
+    Ok(())
+}
+
+/// 把任务或视频移出队列，正在下载的会被停止，返回是否在队列中
+pub(crate) async fn dequeue_target(db: &Database, kind: DownloadKind, target_id: &str) -> Result<bool, String> {
+    let Some(entry) = db
+        .get_download_queue_entry_by_target(kind, target_id)
+        .await
+        .map_err(|e| e.to_string())?
+    else {
+        return Ok(false);
+    };
+    db.remove_download_queue_entry(&entry.id).await.map_err(|e| e.to_string())?;
+    if RUNNING.lock().unwrap().contains_key(&entry.id) {
+        crate::services::cancel_task(target_id);
+    }
+    Ok(true)
+}
+
+/// 停止所有正在下载的队列项，结束后放回队列（reason 显示在任务状态中）
+async fn interrupt_running(app_handle: &AppHandle, db: &Database, reason: &str) {
+    let running: Vec<String> = RUNNING.lock().unwrap().keys().cloned().collect();
+    for id in running {
+        let Ok(Some(entry)) = db.get_download_queue_entry(&id).await else {
+            continue;
+        };
+        INTERRUPTED.lock().unwrap().insert(entry.id.clone());
+        if entry.kind == DownloadKind::Ytdlp {
+            // 先标记为等待中，避免被当作失败
+            if let Ok(Some(mut task)) = db.get_ytdlp_task_by_id(&entry.target_id).await {
+                task.status = YtdlpTaskStatus::Queued;
+                task.message = format!("{} (进度: {}%)", reason, task.progress);
+                let _ = db.save_ytdlp_task(&task).await;
+                let _ = app_handle.emit("ytdlp-progress", task);
+            }
+        }
+        crate::services::cancel_task(&entry.target_id);
+    }
+}
+
+/// 获取下载队列
+#[tauri::command]
+pub async fn get_download_queue(db: State<'_, Database>) -> Result<DownloadQueueState, String> {
+    queue_state(&db).await
+}
+
+/// 暂停整个队列：不再开始新的下载，正在下载的停止后放回队列
+#[tauri::command]
+pub async fn pause_download_queue(app_handle: AppHandle, db: State<'_, Database>) -> Result<(), String> {
+    db.set_download_queue_paused(true).await.map_err(|e| e.to_string())?;
+    interrupt_running(&app_handle, &db, "队列已暂停").await;
+    emit_queue_state(&app_handle, &db).await;
+    Ok(())
+}
+
+/// 恢复队列
+#[tauri::command]
+pub async fn resume_download_queue(app_handle: AppHandle, db: State<'_, Database>) -> Result<(), String> {
+    db.set_download_queue_paused(false).await.map_err(|e| e.to_string())?;
+    emit_queue_state(&app_handle, &db).await;
+    wake();
+    Ok(())
+}
+
+/// 设置队列项优先级（越大越先下载）
+#[tauri::command]
+pub async fn set_download_priority(
+    app_handle: AppHandle,
+    db: State<'_, Database>,
+    entry_id: String,
+    priority: i32,
+) -> Result<(), String> {
+    db.set_download_priority(&entry_id, priority).await.map_err(|e| e.to_string())?;
+    emit_queue_state(&app_handle, &db).await;
+    wake();
+    Ok(())
+}
+
+/// 设置队列项单独的限速 (KB/s，0 表示只受全局限速)
+///
+/// 正在用内置引擎下载的立即生效，yt-dlp 下载在下次开始时生效
+#[tauri::command]
+pub async fn set_download_bandwidth_limit(
+    app_handle: AppHandle,
+    db: State<'_, Database>,
+    entry_id: String,
+    bandwidth_limit_kb: u32,
+) -> Result<(), String> {
+    let Some(entry) = db.get_download_queue_entry(&entry_id).await.map_err(|e| e.to_string())? else {
+        return Err("队列项不存在".to_string());
+    };
+    db.set_download_bandwidth_limit(&entry_id, bandwidth_limit_kb)
+        .await
+        .map_err(|e| e.to_string())?;
+    bandwidth::set_task_limit(&entry.target_id, bandwidth::kb_to_bytes(bandwidth_limit_kb));
+    emit_queue_state(&app_handle, &db).await;
+    Ok(())
+}
+
+/// 按给定顺序重新排列队列项
+#[tauri::command]
+pub async fn reorder_download_queue(
+    app_handle: AppHandle,
+    db: State<'_, Database>,
+    entry_ids: Vec<String>,
+) -> Result<(), String> {
+    db.reorder_download_queue(&entry_ids).await.map_err(|e| e.to_string())?;
+    emit_queue_state(&app_handle, &db).await;
+    wake();
+    Ok(())
+}
+
+/// 移出队列，正在下载的会被停止（yt-dlp 任务标记为暂停，视频恢复为已爬取）
+#[tauri::command]
+pub async fn remove_from_download_queue(
+    app_handle: AppHandle,
+    db: State<'_, Database>,
+    entry_id: String,
+) -> Result<(), String> {
+    let Some(entry) = db.get_download_queue_entry(&entry_id).await.map_err(|e| e.to_string())? else {
+        return Ok(());
+    };
+    match entry.kind {
+        DownloadKind::Ytdlp => {
+            if let Some(mut task) = db.get_ytdlp_task_by_id(&entry.target_id).await.map_err(|e| e.to_string())? {
+                task.status = YtdlpTaskStatus::Paused;
+                task.message = format!("已移出队列 (进度: {}%)", task.progress);
+                db.save_ytdlp_task(&task).await.map_err(|e| e.to_string())?;
+                let _ = app_handle.emit("ytdlp-progress", task);
+            }
+        }
+        DownloadKind::Video => {
+            db.update_video_status(&entry.target_id, VideoStatus::Scraped, None)
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+    }
+    dequeue_target(&db, entry.kind, &entry.target_id).await?;
+
+    if entry.kind == DownloadKind::Video {
+        if let Ok(videos) = db.get_all_videos().await {
+            let _ = app_handle.emit("videos-updated", videos);
+        }
+    }
+    emit_queue_state(&app_handle, &db).await;
+    Ok(())
+}
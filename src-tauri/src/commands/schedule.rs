@@ -0,0 +1,236 @@
+use once_cell::sync::Lazy;
+use std::collections::HashSet;
+use std::time::Duration;
+
+use chrono::Utc;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tokio::sync::Mutex;
+
+use super::crawl::{ensure_paginated, run_crawl_job};
+use super::queue::enqueue_videos;
+use crate::db::Database;
+use crate::models::{CrawlSchedule, CrawlScheduleRun, CrawlStatus};
+
+/// 调度器检查到期计划的间隔
+const SCHEDULER_TICK: Duration = Duration::from_secs(60);
+
+/// 查询执行记录时返回的最大条数
+const RUN_HISTORY_LIMIT: i64 = 50;
+
+/// 正在执行的计划ID，上一次执行未结束时不重复执行
+static RUNNING_SCHEDULES: Lazy<Mutex<HashSet<String>>> =
+    Lazy::new(|| Mutex::new(HashSet::new()));
+
+/// 启动后台调度器，定期执行到期的定时爬取计划（每个计划单独执行，互不等待）
+pub fn start_scheduler(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(SCHEDULER_TICK);
+        loop {
+            interval.tick().await;
+
+            let db = app_handle.state::<Database>().inner().clone();
+            let due = match db.get_due_crawl_schedules(Utc::now()).await {
+                Ok(due) => due,
+                Err(e) => {
+                    tracing::warn!("[schedule] 获取到期计划失败: {}", e);
+                    continue;
+                }
+            };
+
+            for schedule in due {
+                // 先推进下次执行时间，避免下一次检查时被重复触发
+                let schedule = advance_schedule(&db, schedule).await;
+                if !RUNNING_SCHEDULES.lock().await.insert(schedule.id.clone()) {
+                    tracing::info!("[schedule] 上一次执行尚未结束，跳过: {} ({})", schedule.website_name, schedule.id);
+                    continue;
+                }
+
+                tracing::info!("[schedule] 执行定时爬取: {} ({})", schedule.website_name, schedule.id);
+                let app_handle = app_handle.clone();
+                let db = db.clone();
+                tauri::async_runtime::spawn(async move {
+                    let schedule_id = schedule.id.clone();
+                    run_schedule(&app_handle, &db, schedule).await;
+                    RUNNING_SCHEDULES.lock().await.remove(&schedule_id);
+                });
+            }
+        }
+    });
+}
+
+/// 记录本次执行时间并推进下次执行时间
+async fn advance_schedule(db: &Database, mut schedule: CrawlSchedule) -> CrawlSchedule {
+    let now = Utc::now();
+    schedule.last_run_at = Some(now);
+    schedule.next_run_at = schedule.next_run_after(now);
+    if let Err(e) = db.save_crawl_schedule(&schedule).await {
+        tracing::warn!("[schedule] 更新计划失败: {} - {}", schedule.id, e);
+    }
+    schedule
+}
+
+/// 执行一次定时爬取计划并记录执行结果
+async fn run_schedule(app_handle: &AppHandle, db: &Database, schedule: CrawlSchedule) -> CrawlScheduleRun {
+    let mut run = CrawlScheduleRun::new(&schedule.id);
+    let _ = db.save_crawl_schedule_run(&run).await;
+
+    let website = db
+        .get_all_websites()
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|websites| {
+            websites
+                .into_iter()
+                .find(|w| w.id == schedule.website_id)
+                .ok_or_else(|| format!("网站不存在: {}", schedule.website_name))
+        })
+        // 计划创建后网站可能改用了不支持翻页的爬虫
+        .and_then(|website| ensure_paginated(&website).map(|_| website));
+
+    let result = match website {
+        Ok(website) => {
+            let job = schedule.to_job(&website);
+            run.crawl_job_id = Some(job.id.clone());
+            match db.save_crawl_job(&job).await {
+                Ok(_) => run_crawl_job(app_handle, db, job).await,
+                Err(e) => Err(e.to_string()),
+            }
+        }
+        Err(e) => Err(e),
+    };
+
+    match result {
+        Ok((job, new_ids)) => {
+            run.new_count = job.new_count;
+            if job.status == CrawlStatus::Failed {
+                run.error = Some(job.message.clone());
+            }
+
+            if schedule.auto_download && !new_ids.is_empty() {
+                let app_handle = app_handle.clone();
+                let db = db.clone();
+                tauri::async_runtime::spawn(async move {
+                    if let Err(e) = enqueue_videos(&app_handle, &db, &new_ids).await {
+                        tracing::warn!("[schedule] 加入下载队列失败: {}", e);
+                    }
+                });
+            }
+        }
+        Err(e) => run.error = Some(e),
+    }
+
+    run.finished_at = Some(Utc::now());
+    if let Err(e) = db.save_crawl_schedule_run(&run).await {
+        tracing::warn!("[schedule] 保存执行记录失败: {} - {}", run.id, e);
+    }
+    let _ = app_handle.emit("crawl-schedule-run", run.clone());
+    run
+}
+
+/// 获取所有定时爬取计划
+#[tauri::command]
+pub async fn get_crawl_schedules(db: State<'_, Database>) -> Result<Vec<CrawlSchedule>, String> {
+    db.get_all_crawl_schedules().await.map_err(|e| e.to_string())
+}
+
+/// 添加或更新定时爬取计划
+#[tauri::command]
+pub async fn save_crawl_schedule(
+    db: State<'_, Database>,
+    mut schedule: CrawlSchedule,
+) -> Result<CrawlSchedule, String> {
+    schedule.cron = schedule.cron.trim().to_string();
+    if !schedule.cron.is_empty() {
+        CrawlSchedule::parse_cron(&schedule.cron)?;
+    } else if schedule.interval_minutes == 0 {
+        return Err("执行间隔必须大于 0 分钟".to_string());
+    }
+    if let Some(end) = schedule.end_page {
+        if end < schedule.start_page.max(1) {
+            return Err(format!("结束页 {} 小于起始页 {}", end, schedule.start_page));
+        }
+    }
+
+    let website = db
+        .get_all_websites()
+        .await
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .find(|w| w.id == schedule.website_id)
+        .ok_or_else(|| format!("网站不存在: {}", schedule.website_id))?;
+    ensure_paginated(&website)?;
+    schedule.website_name = website.name;
+
+    let existing = if schedule.id.is_empty() {
+        None
+    } else {
+        db.get_crawl_schedule(&schedule.id).await.map_err(|e| e.to_string())?
+    };
+
+    // 从未执行过的计划：按间隔执行的在调度器下一次检查时立即执行，cron 计划等到下一个匹配时间
+    let first_run = if schedule.cron.is_empty() {
+        Utc::now()
+    } else {
+        schedule.next_run_after(Utc::now())
+    };
+    match existing {
+        Some(existing) => {
+            schedule.created_at = existing.created_at;
+            schedule.last_run_at = existing.last_run_at;
+            schedule.next_run_at = existing
+                .last_run_at
+                .map(|t| schedule.next_run_after(t))
+                .unwrap_or(first_run);
+        }
+        None => {
+            if schedule.id.is_empty() {
+                schedule.id = uuid::Uuid::new_v4().to_string();
+            }
+            schedule.created_at = Utc::now();
+            schedule.last_run_at = None;
+            schedule.next_run_at = first_run;
+        }
+    }
+
+    db.save_crawl_schedule(&schedule).await.map_err(|e| e.to_string())?;
+    Ok(schedule)
+}
+
+/// 删除定时爬取计划
+#[tauri::command]
+pub async fn delete_crawl_schedule(db: State<'_, Database>, schedule_id: String) -> Result<(), String> {
+    db.delete_crawl_schedule(&schedule_id).await.map_err(|e| e.to_string())
+}
+
+/// 获取计划的执行记录
+#[tauri::command]
+pub async fn get_crawl_schedule_runs(
+    db: State<'_, Database>,
+    schedule_id: String,
+) -> Result<Vec<CrawlScheduleRun>, String> {
+    db.get_crawl_schedule_runs(&schedule_id, RUN_HISTORY_LIMIT)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 立即执行一次定时爬取计划
+#[tauri::command]
+pub async fn run_crawl_schedule_now(
+    app_handle: AppHandle,
+    db: State<'_, Database>,
+    schedule_id: String,
+) -> Result<CrawlScheduleRun, String> {
+    let schedule = db
+        .get_crawl_schedule(&schedule_id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("定时计划不存在: {}", schedule_id))?;
+
+    if !RUNNING_SCHEDULES.lock().await.insert(schedule.id.clone()) {
+        return Err("该计划正在执行".to_string());
+    }
+    let schedule = advance_schedule(&db, schedule).await;
+    let run = run_schedule(&app_handle, &db, schedule).await;
+    RUNNING_SCHEDULES.lock().await.remove(&schedule_id);
+    Ok(run)
+}
@@ -1,26 +1,36 @@
 use tauri::{Emitter, State, WebviewWindow};
 use crate::db::{Database, SniffedMediaRecord};
+use crate::services::cancel::{cancel_job, register_job, JobKind};
 use crate::services::sniffer::{SniffResult, sniff_page};
 
-/// 嗅探页面中的媒体资源
+/// 嗅探页面中的媒体资源（job_id 用于 cancel_sniff，未指定时自动生成并通过 sniff-started 事件通知）
 #[tauri::command]
 pub async fn sniff_media(
     window: WebviewWindow,
     db: State<'_, Database>,
     url: String,
     timeout_secs: Option<u64>,
+    job_id: Option<String>,
 ) -> Result<SniffResult, String> {
     let timeout = timeout_secs.unwrap_or(5);
 
+    let job = register_job(JobKind::Sniff, job_id)?;
+    let _ = window.emit("sniff-started", job.id());
+    let cancel = job.token();
+
     // headless_chrome 是同步的，需要在阻塞线程中执行
     let result = tokio::task::spawn_blocking(move || {
-        sniff_page(&url, timeout, |log| {
+        sniff_page(&url, timeout, &cancel, |log| {
             let _ = window.emit("sniff-log", log);
         })
     })
     .await
     .map_err(|e| format!("嗅探任务执行失败: {}", e))?;
 
+    if result.cancelled {
+        return Ok(result);
+    }
+
     if result.success {
         // 保存到数据库
         let now = chrono::Utc::now().to_rfc3339();
@@ -74,3 +84,9 @@ pub async fn clear_sniffed_records(
         .await
         .map_err(|e| format!("清空嗅探记录失败: {}", e))
 }
+
+/// 取消正在进行的嗅探
+#[tauri::command]
+pub async fn cancel_sniff(job_id: String) -> Result<bool, String> {
+    Ok(cancel_job(JobKind::Sniff, &job_id))
+}
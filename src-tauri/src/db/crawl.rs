@@ -0,0 +1,120 @@
+use sqlx::prelude::*;
+use sqlx::sqlite::SqliteRow;
+use chrono::{DateTime, Utc};
+
+use super::Database;
+use crate::models::{CrawlJob, CrawlMode, CrawlStatus};
+
+/// 从数据库行解析 CrawlJob
+fn row_to_crawl_job(row: &SqliteRow) -> Result<CrawlJob, sqlx::Error> {
+    let mode_str: String = row.try_get("mode")?;
+    let status_str: String = row.try_get("status")?;
+    let end_page: Option<i64> = row.try_get("end_page")?;
+    let current_page: i64 = row.try_get("current_page")?;
+    let start_page: i64 = row.try_get("start_page")?;
+    let stop_after_known: Option<i64> = row.try_get("stop_after_known").unwrap_or_default();
+    let created_at: DateTime<Utc> = row.try_get::<String, _>("created_at")?
+        .parse()
+        .unwrap_or_else(|_| Utc::now());
+    let updated_at: DateTime<Utc> = row.try_get::<String, _>("updated_at")?
+        .parse()
+        .unwrap_or_else(|_| Utc::now());
+
+    Ok(CrawlJob {
+        id: row.try_get("id")?,
+        website_id: row.try_get("website_id")?,
+        website_name: row.try_get("website_name")?,
+        mode: serde_json::from_str(&mode_str).unwrap_or(CrawlMode::Range),
+        start_page: start_page as u32,
+        end_page: end_page.map(|p| p as u32),
+        current_page: current_page as u32,
+        last_item: row.try_get("last_item")?,
+        stop_after_known: stop_after_known.map(|n| n as u32),
+        status: serde_json::from_str(&status_str).unwrap_or(CrawlStatus::Paused),
+        new_count: row.try_get("new_count")?,
+        duplicate_count: row.try_get("duplicate_count")?,
+        message: row.try_get("message").unwrap_or_default(),
+        created_at,
+        updated_at,
+    })
+}
+
+impl Database {
+    // ===== 批量爬取任务 =====
+
+    /// 添加或更新爬取任务（每爬完一页调用一次，持久化游标）
+    pub async fn save_crawl_job(&self, job: &CrawlJob) -> Result<(), sqlx::Error> {
+        let mode = serde_json::to_string(&job.mode)
+            .map_err(|e| sqlx::Error::Protocol(e.to_string()))?;
+        let status = serde_json::to_string(&job.status)
+            .map_err(|e| sqlx::Error::Protocol(e.to_string()))?;
+
+        sqlx::query(r#"
+            INSERT OR REPLACE INTO crawl_jobs (id, website_id, website_name, mode, start_page, end_page, current_page, last_item, stop_after_known, status, new_count, duplicate_count, message, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#)
+            .bind(&job.id)
+            .bind(&job.website_id)
+            .bind(&job.website_name)
+            .bind(mode)
+            .bind(job.start_page as i64)
+            .bind(job.end_page.map(|p| p as i64))
+            .bind(job.current_page as i64)
+            .bind(&job.last_item)
+            .bind(job.stop_after_known.map(|n| n as i64))
+            .bind(status)
+            .bind(job.new_count)
+            .bind(job.duplicate_count)
+            .bind(&job.message)
+            .bind(job.created_at.to_rfc3339())
+            .bind(job.updated_at.to_rfc3339())
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// 获取单个爬取任务
+    pub async fn get_crawl_job(&self, id: &str) -> Result<Option<CrawlJob>, sqlx::Error> {
+        let row = sqlx::query("SELECT * FROM crawl_jobs WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        row.as_ref().map(row_to_crawl_job).transpose()
+    }
+
+    /// 获取所有爬取任务（按创建时间倒序）
+    pub async fn get_all_crawl_jobs(&self) -> Result<Vec<CrawlJob>, sqlx::Error> {
+        let rows = sqlx::query("SELECT * FROM crawl_jobs ORDER BY created_at DESC")
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut jobs = Vec::new();
+        for row in rows {
+            jobs.push(row_to_crawl_job(&row)?);
+        }
+        Ok(jobs)
+    }
+
+    /// 删除爬取任务
+    pub async fn delete_crawl_job(&self, id: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM crawl_jobs WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// 启动时把上次异常退出仍处于运行中的任务标记为暂停，等待续爬
+    pub async fn pause_interrupted_crawl_jobs(&self) -> Result<u64, sqlx::Error> {
+        let running = serde_json::to_string(&CrawlStatus::Running).unwrap_or_default();
+        let paused = serde_json::to_string(&CrawlStatus::Paused).unwrap_or_default();
+
+        let result = sqlx::query("UPDATE crawl_jobs SET status = ?, message = '应用退出时中断，可继续爬取' WHERE status = ?")
+            .bind(paused)
+            .bind(running)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+}
@@ -0,0 +1,147 @@
+use std::collections::HashSet;
+
+use super::Database;
+
+/// URL 中已知的签名和令牌参数（CDN 鉴权、防盗链），计算去重键时去掉
+///
+/// 只列出专用于签名的参数名；`e`、`t` 这类短名称在很多站点上用来区分内容，不能去掉
+const VOLATILE_PARAMS: &[&str] = &[
+    "token", "sign", "signature", "expires", "auth_key", "hdnts", "hdnea", "key-pair-id",
+    "wssecret", "wstime", "txsecret", "txtime",
+];
+
+/// 去重键的计算方式版本，变化时重新计算所有视频的去重键
+const DEDUP_KEY_VERSION: &str = "2";
+/// 已计算的去重键版本在 settings 表中的键
+const DEDUP_KEY_VERSION_SETTING: &str = "videos_dedup_key_version";
+
+/// 规范化 URL：去掉片段和签名参数，剩余参数按名称排序（无法解析时原样返回）
+pub fn normalize_url(url: &str) -> String {
+    let url = url.trim();
+    let Ok(mut parsed) = url::Url::parse(url) else {
+        return url.to_string();
+    };
+    parsed.set_fragment(None);
+
+    let mut params: Vec<(String, String)> = parsed
+        .query_pairs()
+        .filter(|(name, _)| {
+            let name = name.to_lowercase();
+            !VOLATILE_PARAMS.contains(&name.as_str()) && !name.starts_with("x-amz-")
+        })
+        .map(|(name, value)| (name.into_owned(), value.into_owned()))
+        .collect();
+    params.sort();
+
+    if params.is_empty() {
+        parsed.set_query(None);
+    } else {
+        parsed.query_pairs_mut().clear().extend_pairs(params);
+    }
+    parsed.to_string()
+}
+
+/// 计算视频的去重键：网站 + scrape_id + 规范化后的 m3u8 地址
+///
+/// 入库和补充旧数据都用保存到 videos 表的 scrape_id 计算，两者得到的键一致
+pub fn dedup_key(website_name: &str, scrape_id: &str, m3u8_url: &str) -> String {
+    format!("{}|{}|{}", website_name, scrape_id.trim(), normalize_url(m3u8_url))
+}
+
+impl Database {
+    /// 为旧数据补充去重键（在创建唯一索引之前执行），计算方式变化后重新计算所有去重键
+    ///
+    /// 旧数据中重复的视频只有最早的一条设置去重键，其余保持为空
+    pub(super) async fn backfill_dedup_keys(&self) -> Result<(), sqlx::Error> {
+        let recompute = self.get_setting(DEDUP_KEY_VERSION_SETTING).await?.as_deref() != Some(DEDUP_KEY_VERSION);
+        if recompute {
+            sqlx::query("UPDATE videos SET dedup_key = NULL").execute(&self.pool).await?;
+        }
+
+        let rows: Vec<(String, String, String, String)> = sqlx::query_as(
+            "SELECT id, COALESCE(website_name, ''), m3u8_url, COALESCE(scrape_id, '') FROM videos WHERE dedup_key IS NULL ORDER BY created_at"
+        )
+            .fetch_all(&self.pool)
+            .await?;
+        if rows.is_empty() {
+            if recompute {
+                self.set_setting(DEDUP_KEY_VERSION_SETTING, DEDUP_KEY_VERSION).await?;
+            }
+            return Ok(());
+        }
+
+        let mut taken: HashSet<String> = sqlx::query_scalar::<_, String>("SELECT dedup_key FROM videos WHERE dedup_key IS NOT NULL")
+            .fetch_all(&self.pool)
+            .await?
+            .into_iter()
+            .collect();
+
+        let mut tx = self.pool.begin().await?;
+        let mut duplicates = 0;
+        for (id, website_name, m3u8_url, scrape_id) in rows {
+            let key = dedup_key(&website_name, &scrape_id, &m3u8_url);
+            if !taken.insert(key.clone()) {
+                duplicates += 1;
+                continue;
+            }
+            sqlx::query("UPDATE videos SET dedup_key = ? WHERE id = ?")
+                .bind(key)
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+        if recompute {
+            self.set_setting(DEDUP_KEY_VERSION_SETTING, DEDUP_KEY_VERSION).await?;
+        }
+
+        if duplicates > 0 {
+            tracing::info!("[db] {} 条重复的旧视频记录未设置去重键", duplicates);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_url_strips_signing_params_and_fragment() {
+        assert_eq!(
+            normalize_url("https://cdn.example.com/v/index.m3u8?token=abc&b=2&Expires=1700000000&a=1#t=10"),
+            "https://cdn.example.com/v/index.m3u8?a=1&b=2"
+        );
+        assert_eq!(
+            normalize_url("https://s3.example.com/v.m3u8?X-Amz-Signature=sig&X-Amz-Date=20240101"),
+            "https://s3.example.com/v.m3u8"
+        );
+    }
+
+    #[test]
+    fn normalize_url_keeps_content_params() {
+        assert_eq!(
+            normalize_url("https://example.com/play.m3u8?t=2&e=1&id=9"),
+            "https://example.com/play.m3u8?e=1&id=9&t=2"
+        );
+        assert_ne!(
+            normalize_url("https://example.com/play.m3u8?v=1"),
+            normalize_url("https://example.com/play.m3u8?v=2")
+        );
+    }
+
+    #[test]
+    fn normalize_url_returns_unparsable_input_trimmed() {
+        assert_eq!(normalize_url("  /relative/index.m3u8 "), "/relative/index.m3u8");
+    }
+
+    #[test]
+    fn dedup_key_combines_website_scrape_id_and_url() {
+        let a = dedup_key("site", " 42 ", "https://example.com/v.m3u8?sign=1");
+        let b = dedup_key("site", "42", "https://example.com/v.m3u8?sign=2");
+        assert_eq!(a, b);
+        assert_eq!(a, "site|42|https://example.com/v.m3u8");
+        assert_ne!(a, dedup_key("other", "42", "https://example.com/v.m3u8"));
+        assert_ne!(a, dedup_key("site", "43", "https://example.com/v.m3u8"));
+    }
+}
@@ -1,11 +1,23 @@
 use std::path::PathBuf;
 use sqlx::sqlite::{SqlitePool, SqliteRow, SqliteConnectOptions};
 use sqlx::prelude::*;
+use sqlx::{QueryBuilder, Sqlite};
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
 use std::str::FromStr;
 
-pub use crate::models::{AppConfig, LocalStorageItem, LocalVideo, VideoItem, VideoStatus, Website, YtdlpConfig, YtdlpTask, YtdlpTaskStatus};
+mod crawl;
+mod dedup;
+mod queue;
+mod schedule;
+
+pub use dedup::dedup_key;
+
+pub use crate::models::{AppConfig, LocalStorageItem, LocalVideo, PostProcessRecord, VerifyReport, VideoItem, VideoMetadata, VideoStatus, Website, YtdlpConfig, YtdlpTask, YtdlpTaskStatus};
+
+/// 视频查询的列（videos 表别名为 v），tags 为按原有顺序排列的 JSON 数组
+const VIDEO_COLUMNS: &str = "v.id, v.name, v.m3u8_url, v.status, v.created_at, v.downloaded_at, v.scrape_id, v.website_name, v.cover_url, v.cover_key, v.favorite_count, v.view_count, v.duration, v.description, v.uploader, v.published_at, v.post_process, v.verification, \
+    (SELECT json_group_array(name) FROM (SELECT t.name FROM video_tags vt JOIN tags t ON t.id = vt.tag_id WHERE vt.video_id = v.id ORDER BY vt.position)) AS tags";
 
 /// 从数据库行解析 VideoItem
 fn row_to_video_item(row: &SqliteRow) -> Result<VideoItem, sqlx::Error> {
@@ -27,8 +39,38 @@ fn row_to_video_item(row: &SqliteRow) -> Result<VideoItem, sqlx::Error> {
     let scrape_id: String = row.try_get("scrape_id").unwrap_or_default();
     let website_name: String = row.try_get("website_name").unwrap_or_default();
     let cover_url: Option<String> = row.try_get("cover_url").ok().filter(|s: &String| !s.is_empty());
+    let cover_key: Option<String> = row.try_get("cover_key").ok().filter(|s: &String| !s.is_empty());
+    let cover_path = cover_key
+        .as_deref()
+        .map(|key| crate::services::cover_cache::path_of(key).to_string_lossy().to_string());
     let favorite_count: i64 = row.try_get("favorite_count").unwrap_or(0);
     let view_count: i64 = row.try_get("view_count").unwrap_or(0);
+    let metadata = VideoMetadata {
+        duration: row.try_get::<Option<i64>, _>("duration").ok().flatten(),
+        tags: row
+            .try_get::<String, _>("tags")
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default(),
+        description: row.try_get::<Option<String>, _>("description").ok().flatten().filter(|s| !s.is_empty()),
+        uploader: row.try_get::<Option<String>, _>("uploader").ok().flatten().filter(|s| !s.is_empty()),
+        published_at: row
+            .try_get::<Option<String>, _>("published_at")
+            .ok()
+            .flatten()
+            .and_then(|s| s.parse().ok()),
+    };
+    let post_process = row
+        .try_get::<Option<String>, _>("post_process")
+        .ok()
+        .flatten()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default();
+    let verification = row
+        .try_get::<Option<String>, _>("verification")
+        .ok()
+        .flatten()
+        .and_then(|json| serde_json::from_str(&json).ok());
 
     Ok(VideoItem {
         id,
@@ -40,9 +82,14 @@ fn row_to_video_item(row: &SqliteRow) -> Result<VideoItem, sqlx::Error> {
         scrape_id,
         website_name,
         cover_url,
+        cover_key,
+        cover_path,
         favorite_count: Some(favorite_count),
         view_count: Some(view_count),
         file_path: None,
+        metadata,
+        post_process,
+        verification,
     })
 }
 
@@ -53,6 +100,47 @@ fn row_to_video_item_with_path(row: &SqliteRow) -> Result<VideoItem, sqlx::Error
     Ok(item)
 }
 
+/// 从数据库行解析 Website
+fn row_to_website(row: &SqliteRow) -> Result<Website, sqlx::Error> {
+    let local_storage_json: String = row.try_get("local_storage")?;
+    let local_storage: Vec<LocalStorageItem> = serde_json::from_str(&local_storage_json)
+        .unwrap_or_default();
+    let is_default: i32 = row.try_get("is_default")?;
+
+    Ok(Website {
+        id: row.try_get("id")?,
+        name: row.try_get("name")?,
+        base_url: row.try_get("base_url")?,
+        local_storage,
+        is_default: is_default == 1,
+        spider: row.try_get("spider")?,
+        spider_config: row.try_get("spider_config").unwrap_or_default(),
+        auth: row
+            .try_get::<String, _>("auth")
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default(),
+        cookie_source: row
+            .try_get::<String, _>("cookie_source")
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok()),
+        proxy: row
+            .try_get::<String, _>("proxy")
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok()),
+        rate_limit: row
+            .try_get::<String, _>("rate_limit")
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default(),
+        download_profile: row
+            .try_get::<String, _>("download_profile")
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default(),
+    })
+}
+
 /// 从数据库行解析 YtdlpTask（简化版）
 fn row_to_ytdlp_task(row: &SqliteRow) -> Result<YtdlpTask, sqlx::Error> {
     let id: String = row.try_get("id")?;
@@ -68,6 +156,7 @@ fn row_to_ytdlp_task(row: &SqliteRow) -> Result<YtdlpTask, sqlx::Error> {
         "Failed" => YtdlpTaskStatus::Failed,
         "Cancelled" => YtdlpTaskStatus::Cancelled,
         "Queued" => YtdlpTaskStatus::Queued,
+        "Corrupt" => YtdlpTaskStatus::Corrupt,
         _ => YtdlpTaskStatus::Pending,
     };
     let message: String = row.try_get("message")?;
@@ -80,6 +169,23 @@ fn row_to_ytdlp_task(row: &SqliteRow) -> Result<YtdlpTask, sqlx::Error> {
     // 新字段：resolution 和 file_size（数据库可能没有这些列，使用默认值）
     let resolution: String = row.try_get("resolution").ok().unwrap_or_default();
     let file_size: String = row.try_get("file_size").ok().unwrap_or_default();
+    let cookie_source = row.try_get::<Option<String>, _>("cookie_source")
+        .ok()
+        .flatten()
+        .and_then(|json| serde_json::from_str(&json).ok());
+    let post_process = row.try_get::<String, _>("post_process")
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default();
+    let verification = row.try_get::<Option<String>, _>("verification")
+        .ok()
+        .flatten()
+        .and_then(|json| serde_json::from_str(&json).ok());
+    let collection = row.try_get::<Option<String>, _>("collection")
+        .ok()
+        .flatten()
+        .and_then(|json| serde_json::from_str(&json).ok());
+    let duration: Option<f64> = row.try_get("duration").ok().flatten();
 
     Ok(YtdlpTask {
         id,
@@ -94,6 +200,12 @@ fn row_to_ytdlp_task(row: &SqliteRow) -> Result<YtdlpTask, sqlx::Error> {
         completed_at,
         resolution,
         file_size,
+        cookie_source,
+        rate_limit: String::new(),
+        post_process,
+        verification,
+        collection,
+        duration,
     })
 }
 
@@ -107,6 +219,51 @@ pub struct PaginatedVideos {
     pub has_more: bool,
 }
 
+/// 视频列表筛选条件
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct VideoFilter {
+    /// 标签（精确匹配）
+    pub tag: Option<String>,
+    /// 最短时长（秒）
+    pub min_duration: Option<i64>,
+    /// 最长时长（秒）
+    pub max_duration: Option<i64>,
+}
+
+impl VideoFilter {
+    /// 追加筛选条件（查询已有 WHERE 子句，videos 表别名为 v）
+    fn push_conditions(&self, query: &mut QueryBuilder<'_, Sqlite>) {
+        if let Some(tag) = self.tag.as_deref().map(str::trim).filter(|t| !t.is_empty()) {
+            query.push(" AND EXISTS (SELECT 1 FROM video_tags vt JOIN tags t ON t.id = vt.tag_id WHERE vt.video_id = v.id AND t.name = ")
+                .push_bind(tag.to_string())
+                .push(")");
+        }
+        if let Some(min) = self.min_duration {
+            query.push(" AND v.duration >= ").push_bind(min);
+        }
+        if let Some(max) = self.max_duration {
+            query.push(" AND v.duration <= ").push_bind(max);
+        }
+    }
+}
+
+/// upsert_video 的结果
+#[derive(Debug, Clone, PartialEq)]
+pub enum UpsertOutcome {
+    /// 新增了视频
+    Inserted,
+    /// 视频已存在（已有视频的ID），已刷新播放数和收藏数
+    Updated(String),
+}
+
+/// 标签及其视频数
+#[derive(Debug, Clone, Serialize)]
+pub struct TagCount {
+    pub name: String,
+    pub count: i64,
+}
+
 /// 数据库管理器
 #[derive(Clone)]
 pub struct Database {
@@ -164,6 +321,41 @@ impl Database {
         sqlx::query("CREATE INDEX IF NOT EXISTS idx_videos_scrape_id ON videos(scrape_id)").execute(&self.pool).await?;
         sqlx::query("CREATE INDEX IF NOT EXISTS idx_videos_website_name ON videos(website_name)").execute(&self.pool).await?;
 
+        // 兼容旧数据库：补充视频元数据列（时长为秒数）
+        for column in ["duration INTEGER", "description TEXT", "uploader TEXT", "published_at TEXT"] {
+            let _ = sqlx::query(&format!("ALTER TABLE videos ADD COLUMN {}", column))
+                .execute(&self.pool)
+                .await;
+        }
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_videos_duration ON videos(duration)").execute(&self.pool).await?;
+        // 兼容旧数据库：补充本地封面缓存键列
+        let _ = sqlx::query("ALTER TABLE videos ADD COLUMN cover_key TEXT")
+            .execute(&self.pool)
+            .await;
+        // 兼容旧数据库：补充下载后处理状态和文件校验结果列（JSON）
+        for column in ["post_process TEXT", "verification TEXT"] {
+            let _ = sqlx::query(&format!("ALTER TABLE videos ADD COLUMN {}", column))
+                .execute(&self.pool)
+                .await;
+        }
+
+        // 标签表和视频标签关联表（position 保持标签原有顺序）
+        sqlx::query(r#"
+            CREATE TABLE IF NOT EXISTS tags (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL UNIQUE
+            )
+        "#).execute(&self.pool).await?;
+        sqlx::query(r#"
+            CREATE TABLE IF NOT EXISTS video_tags (
+                video_id TEXT NOT NULL,
+                tag_id INTEGER NOT NULL,
+                position INTEGER NOT NULL DEFAULT 0,
+                PRIMARY KEY (video_id, tag_id)
+            )
+        "#).execute(&self.pool).await?;
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_video_tags_tag_id ON video_tags(tag_id)").execute(&self.pool).await?;
+
         // 配置表 (key-value 结构)
         sqlx::query(r#"
             CREATE TABLE IF NOT EXISTS settings (
@@ -172,6 +364,13 @@ impl Database {
             )
         "#).execute(&self.pool).await?;
 
+        // 兼容旧数据库：补充去重键列，为旧数据计算去重键后再建唯一索引（计算方式的版本记录在配置表中）
+        let _ = sqlx::query("ALTER TABLE videos ADD COLUMN dedup_key TEXT")
+            .execute(&self.pool)
+            .await;
+        self.backfill_dedup_keys().await?;
+        sqlx::query("CREATE UNIQUE INDEX IF NOT EXISTS idx_videos_dedup_key ON videos(dedup_key)").execute(&self.pool).await?;
+
         // 网站配置表
         sqlx::query(r#"
             CREATE TABLE IF NOT EXISTS websites (
@@ -184,6 +383,31 @@ impl Database {
             )
         "#).execute(&self.pool).await?;
 
+        // 兼容旧数据库：补充爬虫配置列
+        let _ = sqlx::query("ALTER TABLE websites ADD COLUMN spider_config TEXT NOT NULL DEFAULT ''")
+            .execute(&self.pool)
+            .await;
+        // 兼容旧数据库：补充认证配置列
+        let _ = sqlx::query("ALTER TABLE websites ADD COLUMN auth TEXT NOT NULL DEFAULT ''")
+            .execute(&self.pool)
+            .await;
+        // 兼容旧数据库：补充 Cookie 来源列（空表示使用全局来源）
+        let _ = sqlx::query("ALTER TABLE websites ADD COLUMN cookie_source TEXT NOT NULL DEFAULT ''")
+            .execute(&self.pool)
+            .await;
+        // 兼容旧数据库：补充代理列（空表示使用全局代理）
+        let _ = sqlx::query("ALTER TABLE websites ADD COLUMN proxy TEXT NOT NULL DEFAULT ''")
+            .execute(&self.pool)
+            .await;
+        // 兼容旧数据库：补充限速列（空表示不限速）
+        let _ = sqlx::query("ALTER TABLE websites ADD COLUMN rate_limit TEXT NOT NULL DEFAULT ''")
+            .execute(&self.pool)
+            .await;
+        // 兼容旧数据库：补充下载配置列（空表示使用全局配置）
+        let _ = sqlx::query("ALTER TABLE websites ADD COLUMN download_profile TEXT NOT NULL DEFAULT ''")
+            .execute(&self.pool)
+            .await;
+
         // 创建索引
         sqlx::query("CREATE INDEX IF NOT EXISTS idx_websites_is_default ON websites(is_default DESC)").execute(&self.pool).await?;
 
@@ -203,6 +427,26 @@ impl Database {
         "#).execute(&self.pool).await?;
 
         // 创建索引
+        // 兼容旧数据库：补充任务 Cookie 来源列（NULL 表示使用全局来源）
+        let _ = sqlx::query("ALTER TABLE ytdlp_tasks ADD COLUMN cookie_source TEXT")
+            .execute(&self.pool)
+            .await;
+        // 兼容旧数据库：补充下载后处理状态列（JSON）
+        let _ = sqlx::query("ALTER TABLE ytdlp_tasks ADD COLUMN post_process TEXT NOT NULL DEFAULT '[]'")
+            .execute(&self.pool)
+            .await;
+        // 兼容旧数据库：补充文件校验结果列（JSON，NULL 表示未校验）
+        let _ = sqlx::query("ALTER TABLE ytdlp_tasks ADD COLUMN verification TEXT")
+            .execute(&self.pool)
+            .await;
+        // 兼容旧数据库：补充所属合集（JSON）和视频时长列
+        let _ = sqlx::query("ALTER TABLE ytdlp_tasks ADD COLUMN collection TEXT")
+            .execute(&self.pool)
+            .await;
+        let _ = sqlx::query("ALTER TABLE ytdlp_tasks ADD COLUMN duration REAL")
+            .execute(&self.pool)
+            .await;
+
         sqlx::query("CREATE INDEX IF NOT EXISTS idx_ytdlp_tasks_status ON ytdlp_tasks(status)").execute(&self.pool).await?;
         sqlx::query("CREATE INDEX IF NOT EXISTS idx_ytdlp_tasks_created_at ON ytdlp_tasks(created_at DESC)").execute(&self.pool).await?;
 
@@ -247,12 +491,101 @@ impl Database {
         sqlx::query("CREATE INDEX IF NOT EXISTS idx_sniffed_media_sniffed_at ON sniffed_media(sniffed_at DESC)").execute(&self.pool).await?;
         sqlx::query("CREATE INDEX IF NOT EXISTS idx_sniffed_media_page_url ON sniffed_media(page_url)").execute(&self.pool).await?;
 
+        // 批量爬取任务表（保存翻页游标，支持断点续爬）
+        sqlx::query(r#"
+            CREATE TABLE IF NOT EXISTS crawl_jobs (
+                id TEXT PRIMARY KEY,
+                website_id TEXT NOT NULL,
+                website_name TEXT NOT NULL DEFAULT '',
+                mode TEXT NOT NULL,
+                start_page INTEGER NOT NULL DEFAULT 1,
+                end_page INTEGER,
+                current_page INTEGER NOT NULL DEFAULT 0,
+                last_item TEXT,
+                status TEXT NOT NULL,
+                new_count INTEGER NOT NULL DEFAULT 0,
+                duplicate_count INTEGER NOT NULL DEFAULT 0,
+                message TEXT NOT NULL DEFAULT '',
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            )
+        "#).execute(&self.pool).await?;
+
+        // 兼容旧数据库：补充增量爬取阈值列
+        let _ = sqlx::query("ALTER TABLE crawl_jobs ADD COLUMN stop_after_known INTEGER")
+            .execute(&self.pool)
+            .await;
+
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_crawl_jobs_created_at ON crawl_jobs(created_at DESC)").execute(&self.pool).await?;
+
+        // 定时爬取计划表
+        sqlx::query(r#"
+            CREATE TABLE IF NOT EXISTS crawl_schedules (
+                id TEXT PRIMARY KEY,
+                website_id TEXT NOT NULL,
+                website_name TEXT NOT NULL DEFAULT '',
+                mode TEXT NOT NULL,
+                start_page INTEGER NOT NULL DEFAULT 1,
+                end_page INTEGER,
+                stop_after_known INTEGER,
+                interval_minutes INTEGER NOT NULL,
+                auto_download INTEGER NOT NULL DEFAULT 0,
+                enabled INTEGER NOT NULL DEFAULT 1,
+                last_run_at TEXT,
+                next_run_at TEXT NOT NULL,
+                created_at TEXT NOT NULL
+            )
+        "#).execute(&self.pool).await?;
+
+        // 定时爬取执行记录表
+        sqlx::query(r#"
+            CREATE TABLE IF NOT EXISTS crawl_schedule_runs (
+                id TEXT PRIMARY KEY,
+                schedule_id TEXT NOT NULL,
+                crawl_job_id TEXT,
+                started_at TEXT NOT NULL,
+                finished_at TEXT,
+                new_count INTEGER NOT NULL DEFAULT 0,
+                error TEXT
+            )
+        "#).execute(&self.pool).await?;
+
+        // 兼容旧数据库：补充 cron 表达式列（为空表示按间隔执行）
+        let _ = sqlx::query("ALTER TABLE crawl_schedules ADD COLUMN cron TEXT NOT NULL DEFAULT ''")
+            .execute(&self.pool)
+            .await;
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_crawl_schedules_next_run_at ON crawl_schedules(next_run_at)").execute(&self.pool).await?;
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_crawl_schedule_runs_schedule ON crawl_schedule_runs(schedule_id, started_at DESC)").execute(&self.pool).await?;
+
+        // 下载队列表（yt-dlp 任务和爬取的视频共用，下载结束后移除）
+        sqlx::query(r#"
+            CREATE TABLE IF NOT EXISTS download_queue (
+                id TEXT PRIMARY KEY,
+                kind TEXT NOT NULL,
+                target_id TEXT NOT NULL,
+                title TEXT NOT NULL DEFAULT '',
+                website_name TEXT,
+                output_path TEXT,
+                priority INTEGER NOT NULL DEFAULT 0,
+                position INTEGER NOT NULL DEFAULT 0,
+                status TEXT NOT NULL,
+                created_at TEXT NOT NULL
+            )
+        "#).execute(&self.pool).await?;
+
+        // 迁移：队列项单独的限速
+        let _ = sqlx::query("ALTER TABLE download_queue ADD COLUMN bandwidth_limit_kb INTEGER NOT NULL DEFAULT 0")
+            .execute(&self.pool).await;
+
+        sqlx::query("CREATE UNIQUE INDEX IF NOT EXISTS idx_download_queue_target ON download_queue(kind, target_id)").execute(&self.pool).await?;
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_download_queue_order ON download_queue(priority DESC, position)").execute(&self.pool).await?;
+
         Ok(())
     }
 
     /// 获取所有视频（按创建时间倒序）
     pub async fn get_all_videos(&self) -> Result<Vec<VideoItem>, sqlx::Error> {
-        let rows = sqlx::query("SELECT v.id, v.name, v.m3u8_url, v.status, v.created_at, v.downloaded_at, v.scrape_id, v.website_name, v.cover_url, v.favorite_count, v.view_count, l.file_path FROM videos v LEFT JOIN local_videos l ON v.name = l.name OR v.id = l.id ORDER BY v.created_at DESC")
+        let rows = sqlx::query(&format!("SELECT {}, l.file_path FROM videos v LEFT JOIN local_videos l ON v.name = l.name OR v.id = l.id ORDER BY v.created_at DESC", VIDEO_COLUMNS))
             .fetch_all(&self.pool)
             .await?;
 
@@ -272,7 +605,8 @@ impl Database {
         // 构建占位符: ?,?,?
         let placeholders: Vec<String> = ids.iter().map(|_| "?".to_string()).collect();
         let sql = format!(
-            "SELECT v.id, v.name, v.m3u8_url, v.status, v.created_at, v.downloaded_at, v.scrape_id, v.website_name, v.cover_url, v.favorite_count, v.view_count, l.file_path FROM videos v LEFT JOIN local_videos l ON v.name = l.name OR v.id = l.id WHERE v.id IN ({}) ORDER BY v.created_at DESC",
+            "SELECT {}, l.file_path FROM videos v LEFT JOIN local_videos l ON v.name = l.name OR v.id = l.id WHERE v.id IN ({}) ORDER BY v.created_at DESC",
+            VIDEO_COLUMNS,
             placeholders.join(",")
         );
 
@@ -295,18 +629,26 @@ impl Database {
         &self,
         page: i32,
         page_size: i32,
+        filter: &VideoFilter,
     ) -> Result<PaginatedVideos, sqlx::Error> {
         let offset = (page - 1) * page_size;
 
         // 获取总数
-        let total: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM videos")
+        let mut count_query = QueryBuilder::<Sqlite>::new("SELECT COUNT(*) FROM videos v WHERE 1 = 1");
+        filter.push_conditions(&mut count_query);
+        let total: i64 = count_query.build_query_scalar()
             .fetch_one(&self.pool)
             .await?;
 
         // 获取分页数据
-        let rows = sqlx::query("SELECT v.id, v.name, v.m3u8_url, v.status, v.created_at, v.downloaded_at, v.scrape_id, v.website_name, v.cover_url, v.favorite_count, v.view_count, l.file_path FROM videos v LEFT JOIN local_videos l ON v.name = l.name OR v.id = l.id ORDER BY v.created_at DESC LIMIT ? OFFSET ?")
-            .bind(page_size)
-            .bind(offset)
+        let mut query = QueryBuilder::<Sqlite>::new(format!(
+            "SELECT {}, l.file_path FROM videos v LEFT JOIN local_videos l ON v.name = l.name OR v.id = l.id WHERE 1 = 1",
+            VIDEO_COLUMNS
+        ));
+        filter.push_conditions(&mut query);
+        query.push(" ORDER BY v.created_at DESC LIMIT ").push_bind(page_size)
+            .push(" OFFSET ").push_bind(offset);
+        let rows = query.build()
             .fetch_all(&self.pool)
             .await?;
 
@@ -331,25 +673,35 @@ impl Database {
         query: &str,
         page: i32,
         page_size: i32,
+        filter: &VideoFilter,
     ) -> Result<PaginatedVideos, sqlx::Error> {
         let search_pattern = format!("%{}%", query.to_uppercase());
         let offset = (page - 1) * page_size;
 
         // 获取总数
-        let total: i64 = sqlx::query_scalar(
-            "SELECT COUNT(*) FROM videos WHERE UPPER(name) LIKE ? OR UPPER(id) LIKE ?"
-        )
-            .bind(&search_pattern)
-            .bind(&search_pattern)
+        let mut count_query = QueryBuilder::<Sqlite>::new(
+            "SELECT COUNT(*) FROM videos v WHERE (UPPER(v.name) LIKE "
+        );
+        count_query.push_bind(search_pattern.clone())
+            .push(" OR UPPER(v.id) LIKE ").push_bind(search_pattern.clone())
+            .push(")");
+        filter.push_conditions(&mut count_query);
+        let total: i64 = count_query.build_query_scalar()
             .fetch_one(&self.pool)
             .await?;
 
         // 获取分页数据
-        let rows = sqlx::query("SELECT v.id, v.name, v.m3u8_url, v.status, v.created_at, v.downloaded_at, v.scrape_id, v.website_name, v.cover_url, v.favorite_count, v.view_count, l.file_path FROM videos v LEFT JOIN local_videos l ON v.name = l.name OR v.id = l.id WHERE UPPER(v.name) LIKE ? OR UPPER(v.id) LIKE ? ORDER BY v.created_at DESC LIMIT ? OFFSET ?")
-            .bind(&search_pattern)
-            .bind(&search_pattern)
-            .bind(page_size)
-            .bind(offset)
+        let mut rows_query = QueryBuilder::<Sqlite>::new(format!(
+            "SELECT {}, l.file_path FROM videos v LEFT JOIN local_videos l ON v.name = l.name OR v.id = l.id WHERE (UPPER(v.name) LIKE ",
+            VIDEO_COLUMNS
+        ));
+        rows_query.push_bind(search_pattern.clone())
+            .push(" OR UPPER(v.id) LIKE ").push_bind(search_pattern)
+            .push(")");
+        filter.push_conditions(&mut rows_query);
+        rows_query.push(" ORDER BY v.created_at DESC LIMIT ").push_bind(page_size)
+            .push(" OFFSET ").push_bind(offset);
+        let rows = rows_query.build()
             .fetch_all(&self.pool)
             .await?;
 
@@ -368,6 +720,16 @@ impl Database {
         })
     }
 
+    /// 获取所有标签及其视频数（按视频数倒序）
+    pub async fn get_tags(&self) -> Result<Vec<TagCount>, sqlx::Error> {
+        let rows: Vec<(String, i64)> = sqlx::query_as(
+            "SELECT t.name, COUNT(vt.video_id) AS count FROM tags t JOIN video_tags vt ON vt.tag_id = t.id GROUP BY t.id ORDER BY count DESC, t.name"
+        )
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(rows.into_iter().map(|(name, count)| TagCount { name, count }).collect())
+    }
+
     /// 按状态筛选视频
     pub async fn get_videos_by_status(
         &self,
@@ -387,7 +749,7 @@ impl Database {
             .await?;
 
         // 获取分页数据
-        let rows = sqlx::query("SELECT id, name, m3u8_url, status, created_at, downloaded_at, scrape_id, website_name, cover_url, favorite_count, view_count FROM videos WHERE status = ? ORDER BY created_at DESC LIMIT ? OFFSET ?")
+        let rows = sqlx::query(&format!("SELECT {} FROM videos v WHERE v.status = ? ORDER BY v.created_at DESC LIMIT ? OFFSET ?", VIDEO_COLUMNS))
             .bind(&status_str)
             .bind(page_size)
             .bind(offset)
@@ -427,7 +789,7 @@ impl Database {
             .await?;
 
         // 获取分页数据
-        let rows = sqlx::query("SELECT id, name, m3u8_url, status, created_at, downloaded_at, scrape_id, website_name, cover_url, favorite_count, view_count FROM videos WHERE website_name = ? ORDER BY created_at DESC LIMIT ? OFFSET ?")
+        let rows = sqlx::query(&format!("SELECT {} FROM videos v WHERE v.website_name = ? ORDER BY v.created_at DESC LIMIT ? OFFSET ?", VIDEO_COLUMNS))
             .bind(website_name)
             .bind(page_size)
             .bind(offset)
@@ -449,14 +811,21 @@ impl Database {
         })
     }
 
-    /// 添加视频
-    pub async fn add_video(&self, video: &VideoItem) -> Result<(), sqlx::Error> {
+    /// 按去重键写入视频：不存在时新增（同时写入标签），已存在时刷新播放数和收藏数
+    pub async fn upsert_video(&self, video: &VideoItem, dedup_key: &str) -> Result<UpsertOutcome, sqlx::Error> {
         let status_str = serde_json::to_string(&video.status).unwrap_or_default();
         let created_at_str = video.created_at.to_rfc3339();
         let downloaded_at_str = video.downloaded_at.map(|d| d.to_rfc3339());
-        sqlx::query(r#"
-            INSERT OR REPLACE INTO videos (id, name, m3u8_url, status, created_at, downloaded_at, scrape_id, website_name, cover_url, favorite_count, view_count)
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        let published_at_str = video.metadata.published_at.map(|d| d.to_rfc3339());
+
+        let mut tx = self.pool.begin().await?;
+        let id: String = sqlx::query_scalar(r#"
+            INSERT INTO videos (id, name, m3u8_url, status, created_at, downloaded_at, scrape_id, website_name, cover_url, cover_key, favorite_count, view_count, duration, description, uploader, published_at, dedup_key)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, COALESCE(?, 0), COALESCE(?, 0), ?, ?, ?, ?, ?)
+            ON CONFLICT(dedup_key) DO UPDATE SET
+                favorite_count = COALESCE(?, favorite_count),
+                view_count = COALESCE(?, view_count)
+            RETURNING id
         "#)
             .bind(video.id.clone())
             .bind(video.name.clone())
@@ -467,22 +836,37 @@ impl Database {
             .bind(video.scrape_id.clone())
             .bind(video.website_name.clone())
             .bind(video.cover_url.clone())
-            .bind(video.favorite_count.unwrap_or(0))
-            .bind(video.view_count.unwrap_or(0))
-            .execute(&self.pool).await?;
-        Ok(())
-    }
+            .bind(video.cover_key.clone())
+            .bind(video.favorite_count)
+            .bind(video.view_count)
+            .bind(video.metadata.duration)
+            .bind(video.metadata.description.clone())
+            .bind(video.metadata.uploader.clone())
+            .bind(published_at_str)
+            .bind(dedup_key)
+            .bind(video.favorite_count)
+            .bind(video.view_count)
+            .fetch_one(&mut *tx).await?;
+
+        if id != video.id {
+            tx.commit().await?;
+            return Ok(UpsertOutcome::Updated(id));
+        }
 
-    /// 检查视频是否已存在（通过 scrape_id 和 website_name）
-    pub async fn video_exists(&self, scrape_id: &str, website_name: &str) -> Result<bool, sqlx::Error> {
-        let count: i64 = sqlx::query_scalar(
-            "SELECT COUNT(*) FROM videos WHERE scrape_id = ? AND website_name = ?"
-        )
-            .bind(scrape_id)
-            .bind(website_name)
-            .fetch_one(&self.pool)
-            .await?;
-        Ok(count > 0)
+        for (position, tag) in video.metadata.tags.iter().enumerate() {
+            sqlx::query("INSERT OR IGNORE INTO tags (name) VALUES (?)")
+                .bind(tag)
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query("INSERT OR IGNORE INTO video_tags (video_id, tag_id, position) SELECT ?, id, ? FROM tags WHERE name = ?")
+                .bind(&video.id)
+                .bind(position as i64)
+                .bind(tag)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(UpsertOutcome::Inserted)
     }
 
     /// 更新视频状态
@@ -506,12 +890,124 @@ impl Database {
         Ok(())
     }
 
+    /// 记录视频最近一次下载后各处理步骤的状态
+    pub async fn set_video_post_process(&self, id: &str, records: &[PostProcessRecord]) -> Result<(), sqlx::Error> {
+        let json = serde_json::to_string(records).map_err(|e| sqlx::Error::Protocol(e.to_string()))?;
+        sqlx::query("UPDATE videos SET post_process = ? WHERE id = ?")
+            .bind(json)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// 记录视频最近一次下载的文件校验结果
+    pub async fn set_video_verification(&self, id: &str, report: &VerifyReport) -> Result<(), sqlx::Error> {
+        let json = serde_json::to_string(report).map_err(|e| sqlx::Error::Protocol(e.to_string()))?;
+        sqlx::query("UPDATE videos SET verification = ? WHERE id = ?")
+            .bind(json)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// 获取指定状态的所有视频（可按网站筛选）
+    pub async fn get_all_videos_by_status(
+        &self,
+        status: VideoStatus,
+        website_name: Option<&str>,
+    ) -> Result<Vec<VideoItem>, sqlx::Error> {
+        let status_str = serde_json::to_string(&status).unwrap_or_default();
+        let mut query = QueryBuilder::<Sqlite>::new(format!("SELECT {} FROM videos v WHERE v.status = ", VIDEO_COLUMNS));
+        query.push_bind(status_str);
+        if let Some(website_name) = website_name {
+            query.push(" AND v.website_name = ").push_bind(website_name.to_string());
+        }
+        query.push(" ORDER BY v.created_at DESC");
+        let rows = query.build()
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut videos = Vec::new();
+        for row in rows {
+            videos.push(row_to_video_item(&row)?);
+        }
+        Ok(videos)
+    }
+
+    /// 重新爬取后更新视频的 m3u8 地址和去重键，状态恢复为已爬取
+    pub async fn update_video_source(&self, id: &str, m3u8_url: &str, dedup_key: &str) -> Result<(), sqlx::Error> {
+        let status_str = serde_json::to_string(&VideoStatus::Scraped).unwrap_or_default();
+        sqlx::query("UPDATE videos SET m3u8_url = ?, dedup_key = ?, status = ? WHERE id = ?")
+            .bind(m3u8_url)
+            .bind(dedup_key)
+            .bind(status_str)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// 获取网站下所有视频的 m3u8 地址（ID, m3u8_url）
+    pub async fn get_video_urls_by_website(&self, website_name: &str) -> Result<Vec<(String, String)>, sqlx::Error> {
+        sqlx::query_as("SELECT id, m3u8_url FROM videos WHERE website_name = ?")
+            .bind(website_name)
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    /// 更新视频的 m3u8 地址
+    pub async fn update_video_m3u8_url(&self, id: &str, m3u8_url: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE videos SET m3u8_url = ? WHERE id = ?")
+            .bind(m3u8_url)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// 获取有封面但还没有缓存的视频（ID, cover_url, website_name）
+    pub async fn get_uncached_covers(&self) -> Result<Vec<(String, String, String)>, sqlx::Error> {
+        sqlx::query_as(
+            "SELECT id, cover_url, COALESCE(website_name, '') FROM videos WHERE (cover_key IS NULL OR cover_key = '') AND cover_url IS NOT NULL AND cover_url != ''"
+        )
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    /// 设置视频的封面缓存键（clear_url 为 true 时清空原封面地址，用于 base64 封面）
+    pub async fn set_video_cover_key(&self, id: &str, key: &str, clear_url: bool) -> Result<(), sqlx::Error> {
+        let sql = if clear_url {
+            "UPDATE videos SET cover_key = ?, cover_url = NULL WHERE id = ?"
+        } else {
+            "UPDATE videos SET cover_key = ? WHERE id = ?"
+        };
+        sqlx::query(sql)
+            .bind(key)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// 获取所有被引用的封面缓存键
+    pub async fn get_cover_keys(&self) -> Result<Vec<String>, sqlx::Error> {
+        sqlx::query_scalar("SELECT DISTINCT cover_key FROM videos WHERE cover_key IS NOT NULL AND cover_key != ''")
+            .fetch_all(&self.pool)
+            .await
+    }
+
     /// 删除视频
     pub async fn delete_video(&self, id: &str) -> Result<(), sqlx::Error> {
         sqlx::query("DELETE FROM videos WHERE id = ?")
             .bind(id)
             .execute(&self.pool)
             .await?;
+        sqlx::query("DELETE FROM video_tags WHERE video_id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
         Ok(())
     }
 
@@ -543,6 +1039,9 @@ impl Database {
             .bind(status_str)
             .execute(&self.pool)
             .await?;
+        sqlx::query("DELETE FROM video_tags WHERE video_id NOT IN (SELECT id FROM videos)")
+            .execute(&self.pool)
+            .await?;
         Ok(())
     }
 
@@ -563,10 +1062,20 @@ impl Database {
             Vec::new()
         };
 
+        let proxy = self.get_setting("proxy").await?
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+
+        let retry = self.get_setting("retry_policy").await?
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+
         Ok(AppConfig {
             download_path,
             local_storage,
             default_quality,
+            proxy,
+            retry,
         })
     }
 
@@ -577,6 +1086,12 @@ impl Database {
         let local_storage_json = serde_json::to_string(&config.local_storage)
             .map_err(|e| sqlx::Error::Protocol(e.to_string()))?;
         self.set_setting("local_storage", &local_storage_json).await?;
+        let proxy_json = serde_json::to_string(&config.proxy)
+            .map_err(|e| sqlx::Error::Protocol(e.to_string()))?;
+        self.set_setting("proxy", &proxy_json).await?;
+        let retry_json = serde_json::to_string(&config.retry)
+            .map_err(|e| sqlx::Error::Protocol(e.to_string()))?;
+        self.set_setting("retry_policy", &retry_json).await?;
         Ok(())
     }
 
@@ -615,6 +1130,38 @@ impl Database {
         let extra_options = self.get_setting("ytdlp_extra_options").await?
             .unwrap_or_default();
 
+        let cookie_source = self.get_setting("ytdlp_cookie_source").await?
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_else(|| YtdlpConfig::default().cookie_source);
+
+        let hls_engine = self.get_setting("ytdlp_hls_engine").await?
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+
+        let filename_template = self.get_setting("ytdlp_filename_template").await?
+            .filter(|template| !template.trim().is_empty())
+            .unwrap_or_else(|| YtdlpConfig::default().filename_template);
+
+        let collision_policy = self.get_setting("ytdlp_collision_policy").await?
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+
+        let bandwidth_limit_kb = self.get_setting("ytdlp_bandwidth_limit_kb").await?
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(0);
+
+        let download_windows = self.get_setting("ytdlp_download_windows").await?
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+
+        let post_process = self.get_setting("ytdlp_post_process").await?
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+
+        let verify = self.get_setting("ytdlp_verify").await?
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+
         Ok(YtdlpConfig {
             quality: quality.parse().unwrap_or(0),
             format,
@@ -626,6 +1173,14 @@ impl Database {
             merge_video,
             concurrent_downloads: concurrent_downloads as u8,
             extra_options,
+            cookie_source,
+            hls_engine,
+            filename_template,
+            collision_policy,
+            bandwidth_limit_kb,
+            download_windows,
+            post_process,
+            verify,
         })
     }
 
@@ -641,6 +1196,26 @@ impl Database {
         self.set_setting("ytdlp_audio_only", &config.audio_only.to_string()).await?;
         self.set_setting("ytdlp_merge_video", &config.merge_video.to_string()).await?;
         self.set_setting("ytdlp_extra_options", &config.extra_options).await?;
+        let cookie_source_json = serde_json::to_string(&config.cookie_source)
+            .map_err(|e| sqlx::Error::Protocol(e.to_string()))?;
+        self.set_setting("ytdlp_cookie_source", &cookie_source_json).await?;
+        let hls_engine_json = serde_json::to_string(&config.hls_engine)
+            .map_err(|e| sqlx::Error::Protocol(e.to_string()))?;
+        self.set_setting("ytdlp_hls_engine", &hls_engine_json).await?;
+        self.set_setting("ytdlp_filename_template", &config.filename_template).await?;
+        let collision_policy_json = serde_json::to_string(&config.collision_policy)
+            .map_err(|e| sqlx::Error::Protocol(e.to_string()))?;
+        self.set_setting("ytdlp_collision_policy", &collision_policy_json).await?;
+        self.set_setting("ytdlp_bandwidth_limit_kb", &config.bandwidth_limit_kb.to_string()).await?;
+        let download_windows_json = serde_json::to_string(&config.download_windows)
+            .map_err(|e| sqlx::Error::Protocol(e.to_string()))?;
+        self.set_setting("ytdlp_download_windows", &download_windows_json).await?;
+        let post_process_json = serde_json::to_string(&config.post_process)
+            .map_err(|e| sqlx::Error::Protocol(e.to_string()))?;
+        self.set_setting("ytdlp_post_process", &post_process_json).await?;
+        let verify_json = serde_json::to_string(&config.verify)
+            .map_err(|e| sqlx::Error::Protocol(e.to_string()))?;
+        self.set_setting("ytdlp_verify", &verify_json).await?;
         Ok(())
     }
 
@@ -671,91 +1246,61 @@ impl Database {
 
     /// 获取所有网站
     pub async fn get_all_websites(&self) -> Result<Vec<Website>, sqlx::Error> {
-        let rows = sqlx::query("SELECT id, name, base_url, local_storage, is_default, spider FROM websites ORDER BY is_default DESC, name ASC")
+        let rows = sqlx::query("SELECT * FROM websites ORDER BY is_default DESC, name ASC")
             .fetch_all(&self.pool)
             .await?;
 
         let mut websites = Vec::new();
         for row in rows {
-            let local_storage_json: String = row.try_get("local_storage")?;
-            let local_storage: Vec<LocalStorageItem> = serde_json::from_str(&local_storage_json)
-                .unwrap_or_default();
-            let is_default: i32 = row.try_get("is_default")?;
-            let spider: String = row.try_get("spider")?;
-
-            websites.push(Website {
-                id: row.try_get("id")?,
-                name: row.try_get("name")?,
-                base_url: row.try_get("base_url")?,
-                local_storage,
-                is_default: is_default == 1,
-                spider,
-            });
+            websites.push(row_to_website(&row)?);
         }
         Ok(websites)
     }
 
     /// 获取默认网站
     pub async fn get_default_website(&self) -> Result<Option<Website>, sqlx::Error> {
-        let row = sqlx::query("SELECT id, name, base_url, local_storage, is_default, spider FROM websites WHERE is_default = 1 LIMIT 1")
+        let row = sqlx::query("SELECT * FROM websites WHERE is_default = 1 LIMIT 1")
             .fetch_optional(&self.pool)
             .await?;
 
-        if let Some(row) = row {
-            let local_storage_json: String = row.try_get("local_storage")?;
-            let local_storage: Vec<LocalStorageItem> = serde_json::from_str(&local_storage_json)
-                .unwrap_or_default();
-            let spider: String = row.try_get("spider")?;
-
-            Ok(Some(Website {
-                id: row.try_get("id")?,
-                name: row.try_get("name")?,
-                base_url: row.try_get("base_url")?,
-                local_storage,
-                is_default: true,
-                spider,
-            }))
-        } else {
-            Ok(None)
-        }
+        row.as_ref().map(row_to_website).transpose()
     }
 
     /// 根据网站名称获取网站配置
     pub async fn get_website_by_name(&self, name: &str) -> Result<Option<Website>, sqlx::Error> {
-        let row = sqlx::query("SELECT id, name, base_url, local_storage, is_default, spider FROM websites WHERE name = ? LIMIT 1")
+        let row = sqlx::query("SELECT * FROM websites WHERE name = ? LIMIT 1")
             .bind(name)
             .fetch_optional(&self.pool)
             .await?;
 
-        if let Some(row) = row {
-            let local_storage_json: String = row.try_get("local_storage")?;
-            let local_storage: Vec<LocalStorageItem> = serde_json::from_str(&local_storage_json)
-                .unwrap_or_default();
-            let is_default: i32 = row.try_get("is_default")?;
-            let spider: String = row.try_get("spider")?;
-
-            Ok(Some(Website {
-                id: row.try_get("id")?,
-                name: row.try_get("name")?,
-                base_url: row.try_get("base_url")?,
-                local_storage,
-                is_default: is_default == 1,
-                spider,
-            }))
-        } else {
-            Ok(None)
-        }
+        row.as_ref().map(row_to_website).transpose()
     }
 
     /// 添加或更新网站
     pub async fn save_website(&self, website: &Website) -> Result<(), sqlx::Error> {
         let local_storage_json = serde_json::to_string(&website.local_storage)
             .map_err(|e| sqlx::Error::Protocol(e.to_string()))?;
+        let auth_json = serde_json::to_string(&website.auth)
+            .map_err(|e| sqlx::Error::Protocol(e.to_string()))?;
+        let cookie_source_json = match website.cookie_source {
+            Some(ref source) => serde_json::to_string(source)
+                .map_err(|e| sqlx::Error::Protocol(e.to_string()))?,
+            None => String::new(),
+        };
+        let proxy_json = match website.proxy {
+            Some(ref proxy) => serde_json::to_string(proxy)
+                .map_err(|e| sqlx::Error::Protocol(e.to_string()))?,
+            None => String::new(),
+        };
+        let rate_limit_json = serde_json::to_string(&website.rate_limit)
+            .map_err(|e| sqlx::Error::Protocol(e.to_string()))?;
+        let download_profile_json = serde_json::to_string(&website.download_profile)
+            .map_err(|e| sqlx::Error::Protocol(e.to_string()))?;
         let is_default = if website.is_default { 1 } else { 0 };
 
         sqlx::query(r#"
-            INSERT OR REPLACE INTO websites (id, name, base_url, local_storage, is_default, spider)
-            VALUES (?, ?, ?, ?, ?, ?)
+            INSERT OR REPLACE INTO websites (id, name, base_url, local_storage, is_default, spider, spider_config, auth, cookie_source, proxy, rate_limit, download_profile)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
         "#)
             .bind(website.id.clone())
             .bind(website.name.clone())
@@ -763,6 +1308,12 @@ impl Database {
             .bind(local_storage_json)
             .bind(is_default)
             .bind(website.spider.clone())
+            .bind(website.spider_config.clone())
+            .bind(auth_json)
+            .bind(cookie_source_json)
+            .bind(proxy_json)
+            .bind(rate_limit_json)
+            .bind(download_profile_json)
             .execute(&self.pool)
             .await?;
         Ok(())
@@ -798,11 +1349,28 @@ impl Database {
     pub async fn save_ytdlp_task(&self, task: &YtdlpTask) -> Result<(), sqlx::Error> {
         let created_at = task.created_at.to_rfc3339();
         let completed_at = task.completed_at.map(|d| d.to_rfc3339());
+        let cookie_source = match task.cookie_source {
+            Some(ref source) => Some(serde_json::to_string(source)
+                .map_err(|e| sqlx::Error::Protocol(e.to_string()))?),
+            None => None,
+        };
+        let post_process = serde_json::to_string(&task.post_process)
+            .map_err(|e| sqlx::Error::Protocol(e.to_string()))?;
+        let verification = match task.verification {
+            Some(ref report) => Some(serde_json::to_string(report)
+                .map_err(|e| sqlx::Error::Protocol(e.to_string()))?),
+            None => None,
+        };
+        let collection = match task.collection {
+            Some(ref collection) => Some(serde_json::to_string(collection)
+                .map_err(|e| sqlx::Error::Protocol(e.to_string()))?),
+            None => None,
+        };
 
         sqlx::query(r#"
             INSERT OR REPLACE INTO ytdlp_tasks
-            (id, url, title, progress, file_path, status, message, created_at, completed_at)
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+            (id, url, title, progress, file_path, status, message, created_at, completed_at, cookie_source, post_process, verification, collection, duration)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
         "#)
             .bind(task.id.clone())
             .bind(task.url.clone())
@@ -813,6 +1381,11 @@ impl Database {
             .bind(task.message.clone())
             .bind(created_at)
             .bind(completed_at)
+            .bind(cookie_source)
+            .bind(post_process)
+            .bind(verification)
+            .bind(collection)
+            .bind(task.duration)
             .execute(&self.pool)
             .await?;
         Ok(())
@@ -885,7 +1458,7 @@ impl Database {
 
     /// 清理已完成/失败的任务
     pub async fn cleanup_ytdlp_tasks(&self) -> Result<(), sqlx::Error> {
-        sqlx::query("DELETE FROM ytdlp_tasks WHERE status IN ('Completed', 'Failed', 'Cancelled')")
+        sqlx::query("DELETE FROM ytdlp_tasks WHERE status IN ('Completed', 'Failed', 'Cancelled', 'Corrupt')")
             .execute(&self.pool)
             .await?;
         Ok(())
@@ -0,0 +1,170 @@
+use sqlx::prelude::*;
+use sqlx::sqlite::SqliteRow;
+use chrono::{DateTime, Utc};
+
+use super::Database;
+use crate::models::{DownloadKind, DownloadQueueEntry, QueueEntryStatus};
+
+/// 队列暂停状态在 settings 表中的键
+const QUEUE_PAUSED_KEY: &str = "download_queue_paused";
+
+/// 从数据库行解析 DownloadQueueEntry
+fn row_to_queue_entry(row: &SqliteRow) -> Result<DownloadQueueEntry, sqlx::Error> {
+    let kind_str: String = row.try_get("kind")?;
+    let status_str: String = row.try_get("status")?;
+    let priority: i64 = row.try_get("priority")?;
+    let bandwidth_limit_kb: i64 = row.try_get("bandwidth_limit_kb")?;
+    let created_at: DateTime<Utc> = row.try_get::<String, _>("created_at")?
+        .parse()
+        .unwrap_or_else(|_| Utc::now());
+
+    Ok(DownloadQueueEntry {
+        id: row.try_get("id")?,
+        kind: serde_json::from_str(&kind_str).unwrap_or(DownloadKind::Video),
+        target_id: row.try_get("target_id")?,
+        title: row.try_get("title")?,
+        website_name: row.try_get("website_name")?,
+        output_path: row.try_get("output_path")?,
+        priority: priority as i32,
+        position: row.try_get("position")?,
+        bandwidth_limit_kb: bandwidth_limit_kb as u32,
+        status: serde_json::from_str(&status_str).unwrap_or(QueueEntryStatus::Queued),
+        created_at,
+    })
+}
+
+fn to_json<T: serde::Serialize>(value: &T) -> Result<String, sqlx::Error> {
+    serde_json::to_string(value).map_err(|e| sqlx::Error::Protocol(e.to_string()))
+}
+
+impl Database {
+    // ===== 下载队列 =====
+
+    /// 加入下载队列（排在队尾），同一任务或视频已在队列中时返回 false
+    pub async fn enqueue_download(&self, entry: &DownloadQueueEntry) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(r#"
+            INSERT OR IGNORE INTO download_queue (id, kind, target_id, title, website_name, output_path, priority, position, bandwidth_limit_kb, status, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, (SELECT COALESCE(MAX(position), 0) + 1 FROM download_queue), ?, ?, ?)
+        "#)
+            .bind(&entry.id)
+            .bind(to_json(&entry.kind)?)
+            .bind(&entry.target_id)
+            .bind(&entry.title)
+            .bind(&entry.website_name)
+            .bind(&entry.output_path)
+            .bind(entry.priority as i64)
+            .bind(entry.bandwidth_limit_kb as i64)
+            .bind(to_json(&entry.status)?)
+            .bind(entry.created_at.to_rfc3339())
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// 获取下载队列（按优先级从高到低、同优先级按顺序）
+    pub async fn get_download_queue(&self) -> Result<Vec<DownloadQueueEntry>, sqlx::Error> {
+        let rows = sqlx::query("SELECT * FROM download_queue ORDER BY priority DESC, position ASC")
+            .fetch_all(&self.pool)
+            .await?;
+
+        rows.iter().map(row_to_queue_entry).collect()
+    }
+
+    /// 获取单个队列项
+    pub async fn get_download_queue_entry(&self, id: &str) -> Result<Option<DownloadQueueEntry>, sqlx::Error> {
+        let row = sqlx::query("SELECT * FROM download_queue WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        row.as_ref().map(row_to_queue_entry).transpose()
+    }
+
+    /// 更新队列项状态
+    pub async fn set_download_queue_status(&self, id: &str, status: QueueEntryStatus) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE download_queue SET status = ? WHERE id = ?")
+            .bind(to_json(&status)?)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// 设置队列项优先级
+    pub async fn set_download_priority(&self, id: &str, priority: i32) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE download_queue SET priority = ? WHERE id = ?")
+            .bind(priority as i64)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// 设置队列项单独的限速 (KB/s)
+    pub async fn set_download_bandwidth_limit(&self, id: &str, bandwidth_limit_kb: u32) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE download_queue SET bandwidth_limit_kb = ? WHERE id = ?")
+            .bind(bandwidth_limit_kb as i64)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// 按给定顺序重新排列队列项（未列出的队列项顺序不变）
+    pub async fn reorder_download_queue(&self, ids: &[String]) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        for (index, id) in ids.iter().enumerate() {
+            sqlx::query("UPDATE download_queue SET position = ? WHERE id = ?")
+                .bind(index as i64 + 1)
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// 从队列移除
+    pub async fn remove_download_queue_entry(&self, id: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM download_queue WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// 按任务或视频ID查找队列项
+    pub async fn get_download_queue_entry_by_target(
+        &self,
+        kind: DownloadKind,
+        target_id: &str,
+    ) -> Result<Option<DownloadQueueEntry>, sqlx::Error> {
+        let row = sqlx::query("SELECT * FROM download_queue WHERE kind = ? AND target_id = ?")
+            .bind(to_json(&kind)?)
+            .bind(target_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        row.as_ref().map(row_to_queue_entry).transpose()
+    }
+
+    /// 启动时把上次退出时仍在下载的队列项放回等待状态，返回数量
+    pub async fn requeue_running_downloads(&self) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query("UPDATE download_queue SET status = ? WHERE status = ?")
+            .bind(to_json(&QueueEntryStatus::Queued)?)
+            .bind(to_json(&QueueEntryStatus::Running)?)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+
+    /// 下载队列是否已暂停
+    pub async fn is_download_queue_paused(&self) -> Result<bool, sqlx::Error> {
+        Ok(self.get_setting(QUEUE_PAUSED_KEY).await?.as_deref() == Some("true"))
+    }
+
+    /// 暂停或恢复下载队列
+    pub async fn set_download_queue_paused(&self, paused: bool) -> Result<(), sqlx::Error> {
+        self.set_setting(QUEUE_PAUSED_KEY, &paused.to_string()).await
+    }
+}
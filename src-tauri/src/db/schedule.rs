@@ -0,0 +1,172 @@
+use sqlx::prelude::*;
+use sqlx::sqlite::SqliteRow;
+use chrono::{DateTime, Utc};
+
+use super::Database;
+use crate::models::{CrawlMode, CrawlSchedule, CrawlScheduleRun};
+
+/// 解析 RFC3339 时间字符串
+fn parse_time(value: &str) -> DateTime<Utc> {
+    value.parse().unwrap_or_else(|_| Utc::now())
+}
+
+/// 从数据库行解析 CrawlSchedule
+fn row_to_crawl_schedule(row: &SqliteRow) -> Result<CrawlSchedule, sqlx::Error> {
+    let mode_str: String = row.try_get("mode")?;
+    let start_page: i64 = row.try_get("start_page")?;
+    let end_page: Option<i64> = row.try_get("end_page")?;
+    let stop_after_known: Option<i64> = row.try_get("stop_after_known")?;
+    let interval_minutes: i64 = row.try_get("interval_minutes")?;
+    let auto_download: i32 = row.try_get("auto_download")?;
+    let enabled: i32 = row.try_get("enabled")?;
+    let last_run_at: Option<String> = row.try_get("last_run_at")?;
+    let next_run_at: String = row.try_get("next_run_at")?;
+    let created_at: String = row.try_get("created_at")?;
+
+    Ok(CrawlSchedule {
+        id: row.try_get("id")?,
+        website_id: row.try_get("website_id")?,
+        website_name: row.try_get("website_name")?,
+        mode: serde_json::from_str(&mode_str).unwrap_or(CrawlMode::Range),
+        start_page: start_page as u32,
+        end_page: end_page.map(|p| p as u32),
+        stop_after_known: stop_after_known.map(|n| n as u32),
+        interval_minutes: interval_minutes as u32,
+        cron: row.try_get("cron").unwrap_or_default(),
+        auto_download: auto_download == 1,
+        enabled: enabled == 1,
+        last_run_at: last_run_at.as_deref().map(parse_time),
+        next_run_at: parse_time(&next_run_at),
+        created_at: parse_time(&created_at),
+    })
+}
+
+/// 从数据库行解析 CrawlScheduleRun
+fn row_to_schedule_run(row: &SqliteRow) -> Result<CrawlScheduleRun, sqlx::Error> {
+    let started_at: String = row.try_get("started_at")?;
+    let finished_at: Option<String> = row.try_get("finished_at")?;
+
+    Ok(CrawlScheduleRun {
+        id: row.try_get("id")?,
+        schedule_id: row.try_get("schedule_id")?,
+        crawl_job_id: row.try_get("crawl_job_id")?,
+        started_at: parse_time(&started_at),
+        finished_at: finished_at.as_deref().map(parse_time),
+        new_count: row.try_get("new_count")?,
+        error: row.try_get("error")?,
+    })
+}
+
+impl Database {
+    // ===== 定时爬取计划 =====
+
+    /// 添加或更新定时爬取计划
+    pub async fn save_crawl_schedule(&self, schedule: &CrawlSchedule) -> Result<(), sqlx::Error> {
+        let mode = serde_json::to_string(&schedule.mode)
+            .map_err(|e| sqlx::Error::Protocol(e.to_string()))?;
+
+        sqlx::query(r#"
+            INSERT OR REPLACE INTO crawl_schedules (id, website_id, website_name, mode, start_page, end_page, stop_after_known, interval_minutes, cron, auto_download, enabled, last_run_at, next_run_at, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#)
+            .bind(&schedule.id)
+            .bind(&schedule.website_id)
+            .bind(&schedule.website_name)
+            .bind(mode)
+            .bind(schedule.start_page as i64)
+            .bind(schedule.end_page.map(|p| p as i64))
+            .bind(schedule.stop_after_known.map(|n| n as i64))
+            .bind(schedule.interval_minutes as i64)
+            .bind(schedule.cron.trim())
+            .bind(if schedule.auto_download { 1 } else { 0 })
+            .bind(if schedule.enabled { 1 } else { 0 })
+            .bind(schedule.last_run_at.map(|t| t.to_rfc3339()))
+            .bind(schedule.next_run_at.to_rfc3339())
+            .bind(schedule.created_at.to_rfc3339())
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// 获取单个定时爬取计划
+    pub async fn get_crawl_schedule(&self, id: &str) -> Result<Option<CrawlSchedule>, sqlx::Error> {
+        let row = sqlx::query("SELECT * FROM crawl_schedules WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        row.as_ref().map(row_to_crawl_schedule).transpose()
+    }
+
+    /// 获取所有定时爬取计划
+    pub async fn get_all_crawl_schedules(&self) -> Result<Vec<CrawlSchedule>, sqlx::Error> {
+        let rows = sqlx::query("SELECT * FROM crawl_schedules ORDER BY created_at DESC")
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut schedules = Vec::new();
+        for row in rows {
+            schedules.push(row_to_crawl_schedule(&row)?);
+        }
+        Ok(schedules)
+    }
+
+    /// 获取已到执行时间的启用计划
+    pub async fn get_due_crawl_schedules(&self, now: DateTime<Utc>) -> Result<Vec<CrawlSchedule>, sqlx::Error> {
+        // 时间都以 UTC 的 RFC3339 格式保存，按字符串比较即按时间先后
+        let rows = sqlx::query("SELECT * FROM crawl_schedules WHERE enabled = 1 AND next_run_at <= ? ORDER BY next_run_at ASC")
+            .bind(now.to_rfc3339())
+            .fetch_all(&self.pool)
+            .await?;
+
+        rows.iter().map(row_to_crawl_schedule).collect()
+    }
+
+    /// 删除定时爬取计划及其执行记录
+    pub async fn delete_crawl_schedule(&self, id: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM crawl_schedule_runs WHERE schedule_id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        sqlx::query("DELETE FROM crawl_schedules WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    // ===== 定时爬取执行记录 =====
+
+    /// 添加或更新执行记录
+    pub async fn save_crawl_schedule_run(&self, run: &CrawlScheduleRun) -> Result<(), sqlx::Error> {
+        sqlx::query(r#"
+            INSERT OR REPLACE INTO crawl_schedule_runs (id, schedule_id, crawl_job_id, started_at, finished_at, new_count, error)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+        "#)
+            .bind(&run.id)
+            .bind(&run.schedule_id)
+            .bind(&run.crawl_job_id)
+            .bind(run.started_at.to_rfc3339())
+            .bind(run.finished_at.map(|t| t.to_rfc3339()))
+            .bind(run.new_count)
+            .bind(&run.error)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// 获取计划的执行记录（按开始时间倒序）
+    pub async fn get_crawl_schedule_runs(&self, schedule_id: &str, limit: i64) -> Result<Vec<CrawlScheduleRun>, sqlx::Error> {
+        let rows = sqlx::query("SELECT * FROM crawl_schedule_runs WHERE schedule_id = ? ORDER BY started_at DESC LIMIT ?")
+            .bind(schedule_id)
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut runs = Vec::new();
+        for row in rows {
+            runs.push(row_to_schedule_run(&row)?);
+        }
+        Ok(runs)
+    }
+}
@@ -6,6 +6,7 @@ mod models;
 mod services;
 
 use std::path::PathBuf;
+use tauri::Manager;
 
 pub use models::{AppConfig, DownloadProgress, LocalStorageItem, LocalVideo, ScrapeResult, VideoItem, VideoStatus, Website, YtdlpConfig, YtdlpTask, YtdlpTaskStatus, YtdlpResult};
 pub use db::{Database, PaginatedVideos};
@@ -124,7 +125,34 @@ pub fn run() {
     tracing::info!("[App] 日志文件路径: {}", log_dir.display());
 
     let database = runtime.block_on(async {
-        db::Database::new(&data_dir).await.expect("Failed to initialize database")
+        let database = db::Database::new(&data_dir).await.expect("Failed to initialize database");
+        // 上次退出时仍在运行的批量爬取任务标记为暂停，等待续爬
+        match database.pause_interrupted_crawl_jobs().await {
+            Ok(count) if count > 0 => tracing::info!("[App] {} 个爬取任务已标记为暂停", count),
+            Ok(_) => {}
+            Err(e) => tracing::info!("[App] 恢复爬取任务状态失败: {}", e),
+        }
+        // 加载全局下载限速、Cookie 来源、代理和重试策略
+        match database.get_ytdlp_config().await {
+            Ok(config) => {
+                services::bandwidth::set_global_limit(services::bandwidth::kb_to_bytes(config.bandwidth_limit_kb));
+                services::cookies::set_default_source(config.cookie_source);
+            }
+            Err(e) => tracing::info!("[App] 读取 yt-dlp 配置失败: {}", e),
+        }
+        match database.get_config().await {
+            Ok(config) => {
+                services::proxy::set_default_proxy(config.proxy);
+                services::retry::set_default_policy(config.retry);
+            }
+            Err(e) => tracing::info!("[App] 读取应用配置失败: {}", e),
+        }
+        // 登记各网站主机的限速配置
+        match database.get_all_websites().await {
+            Ok(websites) => websites.iter().for_each(services::rate_limit::register_website),
+            Err(e) => tracing::info!("[App] 读取网站配置失败: {}", e),
+        }
+        database
     });
 
     let builder = tauri::Builder::default()
@@ -134,36 +162,91 @@ pub fn run() {
         .plugin(tauri_plugin_shell::init())
         .manage(database)
         .manage(app_state)
+        .setup(|app| {
+            // 启动定时爬取调度器
+            commands::schedule::start_scheduler(app.handle().clone());
+            // 启动下载队列（恢复上次退出时中断的下载）
+            commands::queue::start_download_queue(app.handle().clone());
+            // 后台缓存尚未缓存的封面并清理无用的封面文件
+            let database = app.state::<db::Database>().inner().clone();
+            tauri::async_runtime::spawn(async move {
+                services::cover_cache::maintain(&database).await;
+            });
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             commands::get_config,
             commands::update_config,
             commands::select_directory,
+            commands::proxy::test_proxy,
             commands::get_videos_paginated,
             commands::search_videos,
+            commands::get_video_tags,
             commands::scrape_video,
+            commands::cancel_scrape,
             commands::download_video,
             commands::batch_download,
             commands::delete_video,
             commands::clear_downloaded,
+            commands::rebuild_cover_cache,
             commands::check_ffmpeg,
             commands::get_websites,
             commands::get_website_by_name,
             commands::save_website,
             commands::delete_website,
             commands::set_default_website,
+            // 网站认证命令
+            commands::auth::get_website_auth_status,
+            commands::auth::refresh_website_auth,
             commands::get_scrapers,
             commands::get_videos_by_website,
+            // 爬虫录制/回放命令
+            commands::fixture::record_scrape,
+            commands::fixture::replay_scrape,
+            commands::fixture::get_scrape_fixtures,
+            commands::fixture::delete_scrape_fixture,
+            // 批量爬取命令
+            commands::crawl::start_crawl_job,
+            commands::crawl::start_incremental_crawl,
+            commands::crawl::resume_crawl_job,
+            commands::crawl::pause_crawl_job,
+            commands::crawl::get_crawl_jobs,
+            commands::crawl::delete_crawl_job,
+            // 失效检查命令
+            commands::link_check::check_video_links,
+            commands::link_check::cancel_link_check,
+            commands::link_check::rescrape_expired_videos,
+            // 定时爬取命令
+            commands::schedule::get_crawl_schedules,
+            commands::schedule::save_crawl_schedule,
+            commands::schedule::delete_crawl_schedule,
+            commands::schedule::get_crawl_schedule_runs,
+            commands::schedule::run_crawl_schedule_now,
             // yt-dlp 命令
             commands::get_ytdlp_config,
             commands::update_ytdlp_config,
             commands::get_video_info,
             commands::add_ytdlp_tasks,
+            commands::get_link_info,
+            commands::add_playlist_tasks,
             commands::delete_ytdlp_task,
             commands::start_ytdlp_task,
             commands::stop_ytdlp_task,
             commands::get_ytdlp_tasks,
             commands::cleanup_ytdlp_tasks,
             commands::open_path,
+            // 下载队列命令
+            commands::queue::get_download_queue,
+            commands::queue::pause_download_queue,
+            commands::queue::resume_download_queue,
+            commands::queue::set_download_priority,
+            commands::queue::set_download_bandwidth_limit,
+            commands::queue::reorder_download_queue,
+            commands::queue::remove_from_download_queue,
+            // Cookie 文件命令
+            commands::cookies::import_cookie_file,
+            commands::cookies::get_cookie_files,
+            commands::cookies::delete_cookie_file,
             // 本地视频命令
             commands::select_video_files,
             commands::get_file_stats,
@@ -189,6 +272,7 @@ pub fn run() {
             commands::cast::create_cast_remote_session,
             // 嗅探命令
             commands::sniffer::sniff_media,
+            commands::sniffer::cancel_sniff,
             commands::sniffer::get_sniffed_records,
             commands::sniffer::delete_sniffed_record,
             commands::sniffer::clear_sniffed_records,
@@ -203,12 +287,14 @@ pub fn run() {
     builder
         .on_window_event(|_window, event| {
             if let tauri::WindowEvent::Destroyed = event {
-                // 应用退出时清理所有转码会话和 HLS 服务器
+                // 应用退出时清理所有转码会话、HLS 服务器和浏览器
                 let runtime = tokio::runtime::Runtime::new().expect("Failed to create runtime");
                 runtime.block_on(async {
                     services::cleanup_all_transcodes().await;
                     services::cleanup_all_hls_servers().await;
                 });
+                // 关闭浏览器池中的 Chrome 进程
+                services::BrowserPool::global().shutdown();
             }
         })
         .run(tauri::generate_context!())
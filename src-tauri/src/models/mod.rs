@@ -16,12 +16,27 @@ pub struct VideoItem {
     pub website_name: String,
     /// 封面图片地址
     pub cover_url: Option<String>,
+    /// 本地封面缓存键
+    #[serde(default)]
+    pub cover_key: Option<String>,
+    /// 本地封面文件路径（由缓存键得出，不入库）
+    #[serde(default)]
+    pub cover_path: Option<String>,
     /// 收藏数
     pub favorite_count: Option<i64>,
     /// 播放数
     pub view_count: Option<i64>,
     /// 下载后的本地路径
     pub file_path: Option<String>,
+    /// 时长、标签等元数据
+    #[serde(flatten)]
+    pub metadata: VideoMetadata,
+    /// 最近一次下载后各处理步骤的状态
+    #[serde(default)]
+    pub post_process: Vec<PostProcessRecord>,
+    /// 最近一次下载的文件校验结果
+    #[serde(default)]
+    pub verification: Option<VerifyReport>,
 }
 
 impl Default for VideoItem {
@@ -36,13 +51,32 @@ impl Default for VideoItem {
             scrape_id: String::new(),
             website_name: String::new(),
             cover_url: None,
+            cover_key: None,
+            cover_path: None,
             favorite_count: None,
             view_count: None,
             file_path: None,
+            metadata: VideoMetadata::default(),
+            post_process: Vec::new(),
+            verification: None,
         }
     }
 }
 
+/// 视频元数据（爬虫能取到多少填多少）
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VideoMetadata {
+    /// 时长（秒）
+    pub duration: Option<i64>,
+    pub tags: Vec<String>,
+    pub description: Option<String>,
+    /// 上传者
+    pub uploader: Option<String>,
+    /// 发布时间
+    pub published_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum VideoStatus {
     Pending,     // 待爬取
@@ -50,6 +84,8 @@ pub enum VideoStatus {
     Downloading, // 下载中
     Downloaded,  // 已下载
     Failed,      // 失败
+    Expired,     // m3u8 已失效，需要重新爬取
+    Corrupt,     // 已下载但文件校验不通过
 }
 
 /// 应用配置
@@ -58,6 +94,12 @@ pub struct AppConfig {
     pub download_path: String,
     pub local_storage: Vec<LocalStorageItem>,
     pub default_quality: String,
+    /// 全局代理（网站未单独指定时使用）
+    #[serde(default)]
+    pub proxy: ProxyConfig,
+    /// 网络操作的重试策略
+    #[serde(default)]
+    pub retry: RetryPolicy,
 }
 
 impl Default for AppConfig {
@@ -66,16 +108,103 @@ impl Default for AppConfig {
             download_path: "./downloads".to_string(),
             local_storage: Vec::new(),
             default_quality: "auto".to_string(),
+            proxy: ProxyConfig::default(),
+            retry: RetryPolicy::default(),
+        }
+    }
+}
+
+/// 重试策略（导航、详情页请求、yt-dlp 下载和 DLNA 动作共用）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetryPolicy {
+    /// 最多尝试次数（包括第一次），1 表示不重试
+    pub max_attempts: u32,
+    /// 第一次重试前的等待时长（毫秒）
+    pub initial_delay_ms: u64,
+    /// 等待时长上限（毫秒）
+    pub max_delay_ms: u64,
+    /// 每次重试等待时长的倍数
+    pub multiplier: f64,
+    /// 在等待时长上附加的随机抖动上限（毫秒）
+    pub jitter_ms: u64,
+    /// 可以重试的失败类型
+    pub retry_on: Vec<ScrapeErrorKind>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_delay_ms: 1000,
+            max_delay_ms: 30_000,
+            multiplier: 2.0,
+            jitter_ms: 500,
+            retry_on: vec![
+                ScrapeErrorKind::BrowserLaunch,
+                ScrapeErrorKind::Navigation,
+                ScrapeErrorKind::Timeout,
+            ],
         }
     }
 }
 
+/// 代理配置
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProxyConfig {
+    /// 代理地址，支持 http/https/socks4/socks5/socks5h，如 socks5://127.0.0.1:1080，为空表示直连
+    pub url: String,
+    /// 不走代理的主机（逗号分隔），如 localhost,127.0.0.1,.example.com
+    pub no_proxy: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LocalStorageItem {
     pub key: String,
     pub value: String,
 }
 
+/// 爬取失败类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ScrapeErrorKind {
+    /// 启动浏览器或借出标签页失败
+    BrowserLaunch,
+    /// 页面导航或请求失败
+    Navigation,
+    /// 资源不存在（视频已删除或ID无效）
+    NotFound,
+    /// 等待页面或 m3u8 超时
+    Timeout,
+    /// 登录凭证过期或无效
+    AuthExpired,
+    /// 页面结构变化，无法提取数据
+    ParseFailed,
+    /// 被网站拦截（风控、限流、验证码）
+    Blocked,
+    /// 用户取消
+    Cancelled,
+}
+
+impl ScrapeErrorKind {
+    /// 是否为临时性失败，可以直接重试
+    pub fn is_transient(&self) -> bool {
+        matches!(self, Self::BrowserLaunch | Self::Navigation | Self::Timeout)
+    }
+
+    /// 根据 HTTP 状态码判断失败类型，成功状态返回 None
+    pub fn from_http_status(status: u16) -> Option<Self> {
+        match status {
+            200..=399 => None,
+            401 => Some(Self::AuthExpired),
+            403 | 429 => Some(Self::Blocked),
+            404 | 410 => Some(Self::NotFound),
+            408 | 504 => Some(Self::Timeout),
+            _ => Some(Self::Navigation),
+        }
+    }
+}
+
 /// 爬取结果
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ScrapeResult {
@@ -91,6 +220,30 @@ pub struct ScrapeResult {
     pub favorite_count: Option<i64>,
     /// 封面图片URL（页面有URL则用URL，否则用视频第一帧的Base64）
     pub cover_url: Option<String>,
+    /// 失败类型，成功时为空
+    #[serde(default)]
+    pub error_kind: Option<ScrapeErrorKind>,
+    /// 时长、标签等元数据
+    #[serde(flatten)]
+    pub metadata: VideoMetadata,
+}
+
+impl ScrapeResult {
+    /// 任务被取消时的结果
+    pub fn cancelled(video_id: Option<String>) -> Self {
+        Self {
+            success: false,
+            name: String::new(),
+            m3u8_url: String::new(),
+            message: "爬取已取消".to_string(),
+            video_id,
+            view_count: None,
+            favorite_count: None,
+            cover_url: None,
+            error_kind: Some(ScrapeErrorKind::Cancelled),
+            metadata: VideoMetadata::default(),
+        }
+    }
 }
 
 /// 下载进度
@@ -101,6 +254,9 @@ pub struct DownloadProgress {
     pub status: String,
     pub speed: String,
     pub eta: String,
+    /// 当前生效的限速（如 "512.00KB/s"），为空表示不限
+    #[serde(default)]
+    pub rate_limit: String,
 }
 
 /// 网站配置
@@ -113,6 +269,24 @@ pub struct Website {
     pub is_default: bool,
     /// 使用的爬虫名称，如 "d1"
     pub spider: String,
+    /// 爬虫配置文档（规则爬虫使用，JSON 或 TOML）
+    #[serde(default)]
+    pub spider_config: String,
+    /// 认证配置
+    #[serde(default)]
+    pub auth: AuthConfig,
+    /// Cookie 来源，None 表示使用 yt-dlp 配置中的全局来源
+    #[serde(default)]
+    pub cookie_source: Option<CookieSource>,
+    /// 代理，None 表示使用全局代理（地址为空的代理表示该网站直连）
+    #[serde(default)]
+    pub proxy: Option<ProxyConfig>,
+    /// 按主机限速配置
+    #[serde(default)]
+    pub rate_limit: RateLimitConfig,
+    /// 下载配置（输出目录、画质、格式、并发数等）
+    #[serde(default)]
+    pub download_profile: DownloadProfile,
 }
 
 impl Default for Website {
@@ -124,12 +298,262 @@ impl Default for Website {
             local_storage: Vec::new(),
             is_default: false,
             spider: "d1".to_string(),
+            spider_config: String::new(),
+            auth: AuthConfig::default(),
+            cookie_source: None,
+            proxy: None,
+            rate_limit: RateLimitConfig::default(),
+            download_profile: DownloadProfile::default(),
+        }
+    }
+}
+
+/// 按主机限速配置（爬虫、嗅探、HLS 代理和下载共用）
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RateLimitConfig {
+    /// 同一主机每秒最多请求数，0 表示不限
+    pub max_requests_per_sec: f64,
+    /// 同一主机最多同时连接数，0 表示不限
+    pub max_concurrent: u32,
+    /// 详情页抓取之间的最小间隔（毫秒）
+    pub detail_delay_ms: u64,
+    /// 在最小间隔上附加的随机抖动上限（毫秒）
+    pub detail_jitter_ms: u64,
+}
+
+/// 网站认证配置 - 凭证位置和刷新方式
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AuthConfig {
+    /// 凭证所在的 localStorage 键，为空表示网站不需要认证
+    pub storage_key: String,
+    /// m3u8 地址中携带凭证的查询参数
+    pub query_param: String,
+    /// 登录页地址（刷新凭证时打开）
+    pub login_url: String,
+    /// 在登录页执行的登录脚本（可返回 Promise），登录成功后凭证应写入 localStorage
+    pub login_script: String,
+    /// 等待新凭证写入的超时秒数
+    pub login_timeout_secs: u64,
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        Self {
+            storage_key: "token".to_string(),
+            query_param: "token".to_string(),
+            login_url: String::new(),
+            login_script: String::new(),
+            login_timeout_secs: 30,
+        }
+    }
+}
+
+// ==================== 爬取任务相关模型 ====================
+
+/// 批量爬取模式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CrawlMode {
+    /// 爬取指定页码范围
+    Range,
+    /// 一直翻页，直到某一页没有新视频
+    UntilNoNew,
+    /// 增量爬取：按顺序翻页，连续遇到指定数量的已存在视频后停止
+    Incremental,
+}
+
+/// 批量爬取任务状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CrawlStatus {
+    Pending,
+    Running,
+    Paused,
+    Completed,
+    Failed,
+}
+
+/// 批量爬取任务（游标持久化，支持断点续爬）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrawlJob {
+    pub id: String,
+    pub website_id: String,
+    pub website_name: String,
+    pub mode: CrawlMode,
+    pub start_page: u32,
+    /// 结束页（包含）；UntilNoNew 模式下作为最大页数上限，可为空
+    pub end_page: Option<u32>,
+    /// 最后完成的页码，0 表示尚未开始
+    pub current_page: u32,
+    /// 最后一个处理过的视频ID
+    pub last_item: Option<String>,
+    /// 增量模式下连续遇到多少个已存在视频后停止
+    #[serde(default)]
+    pub stop_after_known: Option<u32>,
+    pub status: CrawlStatus,
+    pub new_count: i64,
+    pub duplicate_count: i64,
+    pub message: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl CrawlJob {
+    pub fn new(website: &Website, mode: CrawlMode, start_page: u32, end_page: Option<u32>) -> Self {
+        let now = chrono::Utc::now();
+        Self {
+            id: Uuid::new_v4().to_string(),
+            website_id: website.id.clone(),
+            website_name: website.name.clone(),
+            mode,
+            start_page: start_page.max(1),
+            end_page,
+            current_page: 0,
+            last_item: None,
+            stop_after_known: None,
+            status: CrawlStatus::Pending,
+            new_count: 0,
+            duplicate_count: 0,
+            message: String::new(),
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    pub fn with_stop_after_known(mut self, count: u32) -> Self {
+        self.stop_after_known = Some(count.max(1));
+        self
+    }
+
+    /// 下一个要爬取的页码
+    pub fn next_page(&self) -> u32 {
+        if self.current_page < self.start_page {
+            self.start_page
+        } else {
+            self.current_page + 1
+        }
+    }
+
+    /// 是否已超过结束页
+    pub fn is_past_end(&self, page: u32) -> bool {
+        self.end_page.map(|end| page > end).unwrap_or(false)
+    }
+}
+
+/// 定时爬取计划（如“每 6 小时爬取网站 X 的第 1-3 页”或“每天 3 点爬取”）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrawlSchedule {
+    /// 为空时表示新建计划
+    #[serde(default)]
+    pub id: String,
+    pub website_id: String,
+    #[serde(default)]
+    pub website_name: String,
+    pub mode: CrawlMode,
+    pub start_page: u32,
+    pub end_page: Option<u32>,
+    /// 增量模式下连续遇到多少个已存在视频后停止
+    #[serde(default)]
+    pub stop_after_known: Option<u32>,
+    /// 执行间隔（分钟），设置了 cron 时不使用
+    #[serde(default)]
+    pub interval_minutes: u32,
+    /// cron 表达式（本地时间，“分 时 日 月 周”，可在最前面加秒），为空时按间隔执行
+    #[serde(default)]
+    pub cron: String,
+    /// 是否自动下载新爬取到的视频
+    pub auto_download: bool,
+    pub enabled: bool,
+    #[serde(default)]
+    pub last_run_at: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(default = "chrono::Utc::now")]
+    pub next_run_at: chrono::DateTime<chrono::Utc>,
+    #[serde(default = "chrono::Utc::now")]
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl CrawlSchedule {
+    /// 根据计划创建一次爬取任务
+    pub fn to_job(&self, website: &Website) -> CrawlJob {
+        let job = CrawlJob::new(website, self.mode, self.start_page, self.end_page);
+        match self.stop_after_known {
+            Some(count) => job.with_stop_after_known(count),
+            None => job,
+        }
+    }
+
+    /// 计算从指定时间起的下一次执行时间
+    pub fn next_run_after(&self, from: chrono::DateTime<chrono::Utc>) -> chrono::DateTime<chrono::Utc> {
+        if !self.cron.trim().is_empty() {
+            let next = Self::parse_cron(&self.cron)
+                .and_then(|cron| {
+                    cron.find_next_occurrence(&from.with_timezone(&chrono::Local), false)
+                        .map_err(|e| e.to_string())
+                });
+            match next {
+                Ok(next) => return next.with_timezone(&chrono::Utc),
+                Err(e) => tracing::warn!("[schedule] cron 表达式无效，按间隔执行: {} - {}", self.cron, e),
+            }
+        }
+        from + chrono::Duration::minutes(self.interval_minutes.max(1) as i64)
+    }
+
+    /// 解析 cron 表达式（标准 cron 语义，星期 0 为周日）
+    pub fn parse_cron(expr: &str) -> Result<croner::Cron, String> {
+        croner::Cron::new(expr.trim())
+            .with_seconds_optional()
+            .parse()
+            .map_err(|e| format!("cron 表达式无效: {}", e))
+    }
+}
+
+/// 定时爬取执行记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrawlScheduleRun {
+    pub id: String,
+    pub schedule_id: String,
+    /// 本次执行对应的爬取任务
+    pub crawl_job_id: Option<String>,
+    pub started_at: chrono::DateTime<chrono::Utc>,
+    pub finished_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub new_count: i64,
+    /// 错误信息，成功时为空
+    pub error: Option<String>,
+}
+
+impl CrawlScheduleRun {
+    pub fn new(schedule_id: &str) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            schedule_id: schedule_id.to_string(),
+            crawl_job_id: None,
+            started_at: chrono::Utc::now(),
+            finished_at: None,
+            new_count: 0,
+            error: None,
         }
     }
 }
 
 // ==================== yt-dlp 下载相关模型 ====================
 
+/// Cookie 来源（yt-dlp 和 HTTP 请求共用）
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum CookieSource {
+    /// 不携带 Cookie
+    #[default]
+    None,
+    /// 从浏览器读取（如 chrome、firefox），profile 为空表示默认配置
+    Browser {
+        browser: String,
+        #[serde(default)]
+        profile: String,
+    },
+    /// 应用管理的 Netscape 格式 cookies.txt
+    File { path: String },
+}
+
 /// yt-dlp 下载配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct YtdlpConfig {
@@ -154,6 +578,177 @@ pub struct YtdlpConfig {
     pub concurrent_downloads: u8,
     /// 其他 yt-dlp 选项 (格式为 "--option value")
     pub extra_options: String,
+    /// 全局 Cookie 来源（网站或任务未单独指定时使用）
+    #[serde(default)]
+    pub cookie_source: CookieSource,
+    /// HLS (m3u8) 使用的下载引擎
+    #[serde(default)]
+    pub hls_engine: HlsEngine,
+    /// 输出文件名模板，`/` 分隔目录，例如 `{website}/{date:%Y-%m}/{title} [{scrape_id}].{ext}`
+    #[serde(default = "default_filename_template")]
+    pub filename_template: String,
+    /// 目标文件已存在时的处理方式
+    #[serde(default)]
+    pub collision_policy: CollisionPolicy,
+    /// 全局下载限速 (KB/s)，所有下载共享，0 表示不限
+    #[serde(default)]
+    pub bandwidth_limit_kb: u32,
+    /// 允许下载的时间段，为空表示不限制
+    #[serde(default)]
+    pub download_windows: Vec<DownloadWindow>,
+    /// 下载完成后依次执行的处理步骤
+    #[serde(default)]
+    pub post_process: Vec<PostProcessStep>,
+    /// 下载后的文件完整性校验
+    #[serde(default)]
+    pub verify: VerifyConfig,
+}
+
+/// 下载后的文件完整性校验配置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VerifyConfig {
+    pub enabled: bool,
+    /// 实际时长与播放列表或元数据时长允许相差的百分比
+    pub duration_tolerance_percent: f64,
+    /// 抽样解码的片段数
+    pub sample_count: u32,
+    /// 每个抽样片段的时长（秒）
+    pub sample_seconds: u32,
+    /// 校验不通过时自动重新下载的次数
+    pub retries: u32,
+}
+
+impl Default for VerifyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            duration_tolerance_percent: 2.0,
+            sample_count: 3,
+            sample_seconds: 10,
+            retries: 1,
+        }
+    }
+}
+
+/// 文件完整性校验结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VerifyReport {
+    pub passed: bool,
+    /// 实际时长（秒）
+    pub duration: Option<f64>,
+    /// 期望时长（秒），来自播放列表的 EXTINF 总和或爬取的元数据
+    pub expected_duration: Option<f64>,
+    /// 分辨率 (如 "1920x1080")，没有视频流时为空
+    pub resolution: String,
+    /// 总码率 (kbps)
+    pub bitrate_kbps: Option<u64>,
+    /// 发现的问题（时长不符、解码错误等）
+    pub errors: Vec<String>,
+    /// 校验不通过后重新下载的次数
+    pub retries: u32,
+    pub checked_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// 允许下载的时间段（本地时间）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DownloadWindow {
+    /// 星期几（1=周一 ... 7=周日），为空表示每天
+    #[serde(default)]
+    pub days: Vec<u8>,
+    /// 开始时间 "HH:MM"
+    pub start: String,
+    /// 结束时间 "HH:MM"，早于开始时间表示到第二天，与开始时间相同表示全天
+    pub end: String,
+}
+
+fn default_filename_template() -> String {
+    crate::services::filename::DEFAULT_TEMPLATE.to_string()
+}
+
+/// 输出文件已存在时的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum CollisionPolicy {
+    /// 跳过下载，保留已有文件
+    Skip,
+    /// 在文件名后追加编号，如 `标题 (1).mp4`
+    #[default]
+    AutoNumber,
+    /// 覆盖已有文件
+    Overwrite,
+}
+
+/// 下载完成后的处理步骤
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum PostProcessStep {
+    /// 按预设转换格式，转换成功后后续步骤处理转换后的文件
+    Convert {
+        options: crate::services::converter::ConvertOptions,
+        /// 是否保留原文件
+        #[serde(default)]
+        keep_original: bool,
+    },
+    /// 截取一帧保存为同名 .jpg 封面
+    ExtractCover {
+        /// 截取的时间点（秒）
+        #[serde(default)]
+        timestamp: f64,
+    },
+    /// 计算 SHA-256 校验和，写入同名 .sha256 文件
+    Checksum,
+    /// 移动到媒体库目录（连同已生成的封面、校验和等附属文件）
+    MoveToLibrary {
+        dir: String,
+        /// 媒体库中的子目录，支持文件名模板变量，如 `{website}/{date:%Y}`
+        #[serde(default)]
+        subdir: String,
+    },
+    /// 写入同名 .json 元数据文件
+    WriteMetadata,
+    /// 执行本地命令，参数中可以使用 `{file}` `{dir}` `{title}` `{website}` `{scrape_id}` `{id}`
+    RunCommand { command: String },
+}
+
+impl PostProcessStep {
+    /// 步骤名称（用于任务记录）
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Convert { .. } => "格式转换",
+            Self::ExtractCover { .. } => "提取封面",
+            Self::Checksum => "计算校验和",
+            Self::MoveToLibrary { .. } => "移动到媒体库",
+            Self::WriteMetadata => "写入元数据",
+            Self::RunCommand { .. } => "执行命令",
+        }
+    }
+}
+
+/// 后处理步骤的执行状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PostProcessStatus {
+    Pending,
+    Running,
+    Completed,
+    Failed,
+}
+
+/// 后处理步骤的执行记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PostProcessRecord {
+    pub step: String,
+    pub status: PostProcessStatus,
+    pub message: String,
+}
+
+/// HLS (m3u8) 下载引擎
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum HlsEngine {
+    /// 内置下载引擎，不支持或失败时回退到 yt-dlp
+    #[default]
+    Native,
+    /// 始终使用 yt-dlp
+    Ytdlp,
 }
 
 impl Default for YtdlpConfig {
@@ -169,6 +764,17 @@ impl Default for YtdlpConfig {
             merge_video: true,
             concurrent_downloads: 3,
             extra_options: String::new(),
+            cookie_source: CookieSource::Browser {
+                browser: "chrome".to_string(),
+                profile: String::new(),
+            },
+            hls_engine: HlsEngine::default(),
+            filename_template: default_filename_template(),
+            collision_policy: CollisionPolicy::default(),
+            bandwidth_limit_kb: 0,
+            download_windows: Vec::new(),
+            post_process: Vec::new(),
+            verify: VerifyConfig::default(),
         }
     }
 }
@@ -183,6 +789,7 @@ pub enum YtdlpTaskStatus {
     Completed,   // 已完成
     Failed,      // 失败
     Cancelled,   // 已取消
+    Corrupt,     // 已下载但文件校验不通过
 }
 
 /// yt-dlp 下载任务（简化版）
@@ -213,6 +820,24 @@ pub struct YtdlpTask {
     /// 预估文件大小 (如 "150.5MiB")
     #[serde(default)]
     pub file_size: String,
+    /// 任务单独指定的 Cookie 来源，None 表示使用全局来源
+    #[serde(default)]
+    pub cookie_source: Option<CookieSource>,
+    /// 当前生效的限速（如 "512.00KB/s"），为空表示不限，实时广播，不入库
+    #[serde(default)]
+    pub rate_limit: String,
+    /// 下载完成后各处理步骤的状态
+    #[serde(default)]
+    pub post_process: Vec<PostProcessRecord>,
+    /// 下载后的文件校验结果
+    #[serde(default)]
+    pub verification: Option<VerifyReport>,
+    /// 所属合集（从播放列表或频道添加的任务）
+    #[serde(default)]
+    pub collection: Option<TaskCollection>,
+    /// 视频时长（秒），用于校验下载的文件
+    #[serde(default)]
+    pub duration: Option<f64>,
 }
 
 impl Default for YtdlpTask {
@@ -230,6 +855,166 @@ impl Default for YtdlpTask {
             completed_at: None,
             resolution: String::new(),
             file_size: String::new(),
+            cookie_source: None,
+            rate_limit: String::new(),
+            post_process: Vec::new(),
+            verification: None,
+            collection: None,
+            duration: None,
+        }
+    }
+}
+
+/// 任务所属的合集，同一次从播放列表或频道添加的任务共用一个合集ID
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskCollection {
+    pub id: String,
+    /// 播放列表或频道的名称
+    pub title: String,
+    /// 播放列表或频道的地址
+    pub url: String,
+    /// 在播放列表中的序号（从 1 开始）
+    pub index: u32,
+}
+
+/// 播放列表中的一项（--flat-playlist 只有基本信息）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlaylistEntry {
+    /// 在播放列表中的序号（从 1 开始）
+    pub index: u32,
+    pub id: String,
+    pub url: String,
+    pub title: String,
+    /// 时长（秒），部分站点不提供
+    #[serde(default)]
+    pub duration: Option<f64>,
+}
+
+/// 播放列表或频道的信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlaylistInfo {
+    pub id: String,
+    pub title: String,
+    pub url: String,
+    /// 作者或频道名称
+    #[serde(default)]
+    pub uploader: String,
+    pub entries: Vec<PlaylistEntry>,
+}
+
+/// 链接的信息：单个视频或播放列表（频道）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum LinkInfo {
+    Video(Box<YtdlpTask>),
+    Playlist(PlaylistInfo),
+}
+
+/// 网站的下载配置，未设置的项使用全局 yt-dlp 配置
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DownloadProfile {
+    /// 输出目录，为空时使用应用配置的下载目录
+    pub output_dir: String,
+    /// 视频质量，None 表示使用全局配置
+    pub quality: Option<u32>,
+    /// 视频格式 (mp4, mkv 等)，为空表示使用全局配置
+    pub format: String,
+    /// 该网站最多同时下载数，0 表示只受全局并发数限制
+    pub max_concurrent: u32,
+    /// 附加的 yt-dlp 选项，追加在全局选项之后
+    pub extra_options: String,
+    /// 输出文件名模板，为空表示使用全局配置
+    pub filename_template: String,
+    /// 下载完成后的处理步骤，None 表示使用全局配置
+    pub post_process: Option<Vec<PostProcessStep>>,
+}
+
+impl DownloadProfile {
+    /// 在全局配置上应用网站的下载配置
+    pub fn apply(&self, config: &YtdlpConfig) -> YtdlpConfig {
+        let mut config = config.clone();
+        if let Some(quality) = self.quality {
+            config.quality = quality;
+        }
+        if !self.format.trim().is_empty() {
+            config.format = self.format.trim().to_string();
+        }
+        if !self.extra_options.trim().is_empty() {
+            config.extra_options = format!("{} {}", config.extra_options, self.extra_options.trim())
+                .trim()
+                .to_string();
+        }
+        if !self.filename_template.trim().is_empty() {
+            config.filename_template = self.filename_template.trim().to_string();
+        }
+        if let Some(ref steps) = self.post_process {
+            config.post_process = steps.clone();
+        }
+        config
+    }
+
+    /// 输出目录，未设置时使用 default_dir
+    pub fn output_dir_or(&self, default_dir: &str) -> String {
+        match self.output_dir.trim() {
+            "" => default_dir.to_string(),
+            dir => dir.to_string(),
+        }
+    }
+}
+
+/// 下载队列中的任务类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DownloadKind {
+    /// yt-dlp 任务（下载页添加的链接）
+    Ytdlp,
+    /// 爬取的视频
+    Video,
+}
+
+/// 下载队列项的状态（结束后从队列移除，结果记录在任务或视频上）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum QueueEntryStatus {
+    Queued,
+    Running,
+}
+
+/// 下载队列中的一项
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DownloadQueueEntry {
+    pub id: String,
+    pub kind: DownloadKind,
+    /// yt-dlp 任务ID或视频ID
+    pub target_id: String,
+    pub title: String,
+    /// 所属网站（爬取的视频），决定下载配置和网站并发数
+    pub website_name: Option<String>,
+    /// 输出目录，None 表示按网站下载配置和全局配置决定
+    pub output_path: Option<String>,
+    /// 优先级，越大越先下载
+    pub priority: i32,
+    /// 同一优先级内的顺序，越小越先下载
+    pub position: i64,
+    /// 任务单独的限速 (KB/s)，0 表示只受全局限速
+    pub bandwidth_limit_kb: u32,
+    pub status: QueueEntryStatus,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl DownloadQueueEntry {
+    pub fn new(kind: DownloadKind, target_id: &str, title: &str) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            kind,
+            target_id: target_id.to_string(),
+            title: title.to_string(),
+            website_name: None,
+            output_path: None,
+            priority: 0,
+            position: 0,
+            bandwidth_limit_kb: 0,
+            status: QueueEntryStatus::Queued,
+            created_at: chrono::Utc::now(),
         }
     }
 }
@@ -242,6 +1027,9 @@ pub struct YtdlpResult {
     pub file_path: String,
     pub file_size: u64,
     pub message: String,
+    /// 目标文件已存在，按冲突策略保留了已有文件（不是这次下载的文件）
+    #[serde(default)]
+    pub skipped: bool,
 }
 
 /// 本地视频
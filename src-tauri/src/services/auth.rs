@@ -0,0 +1,236 @@
+//! 网站认证
+//!
+//! 每个网站的凭证保存在 localStorage 中（位置由 AuthConfig 指定），
+//! AuthProvider 负责读取凭证、检测过期（JWT exp 或 m3u8 返回 401/403）、
+//! 通过无头浏览器执行登录脚本刷新凭证，并统一改写 m3u8 地址中的凭证参数。
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{DateTime, TimeZone, Utc};
+use std::time::{Duration, Instant};
+
+use crate::models::{AuthConfig, CookieSource, LocalStorageItem, ProxyConfig, Website};
+use crate::services::{cookies, proxy, BrowserPool};
+
+/// 提前判定过期的余量，避免下载途中凭证失效
+const EXPIRY_MARGIN_SECS: i64 = 60;
+
+/// 登录后轮询新凭证的间隔
+const LOGIN_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// 检查 m3u8 时的请求超时
+const PLAYLIST_CHECK_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// 网站认证提供者
+#[derive(Debug, Clone)]
+pub struct AuthProvider {
+    config: AuthConfig,
+    local_storage: Vec<LocalStorageItem>,
+    cookie_source: CookieSource,
+    proxy: ProxyConfig,
+}
+
+impl AuthProvider {
+    pub fn for_website(website: &Website) -> Self {
+        Self {
+            config: website.auth.clone(),
+            local_storage: website.local_storage.clone(),
+            cookie_source: cookies::resolve(website.cookie_source.as_ref()),
+            proxy: proxy::resolve(website.proxy.as_ref()),
+        }
+    }
+
+    /// 网站是否需要认证
+    pub fn is_enabled(&self) -> bool {
+        !self.config.storage_key.is_empty()
+    }
+
+    /// 当前凭证
+    pub fn token(&self) -> Option<&str> {
+        if !self.is_enabled() {
+            return None;
+        }
+        self.local_storage
+            .iter()
+            .find(|item| item.key == self.config.storage_key)
+            .map(|item| item.value.as_str())
+            .filter(|value| !value.is_empty())
+    }
+
+    /// 凭证过期时间（仅 JWT 凭证可知）
+    pub fn expires_at(&self) -> Option<DateTime<Utc>> {
+        self.token().and_then(jwt_expiry)
+    }
+
+    /// 凭证是否已过期（或即将过期）
+    pub fn is_expired(&self) -> bool {
+        self.expires_at()
+            .map(|exp| exp <= Utc::now() + chrono::Duration::seconds(EXPIRY_MARGIN_SECS))
+            .unwrap_or(false)
+    }
+
+    /// 是否配置了登录脚本，可以自动刷新
+    pub fn can_refresh(&self) -> bool {
+        self.is_enabled() && !self.config.login_url.is_empty() && !self.config.login_script.is_empty()
+    }
+
+    /// 用当前凭证改写地址中的凭证参数（地址中没有该参数时保持不变）
+    pub fn apply_to_url(&self, url: &str) -> String {
+        match self.token() {
+            Some(token) if !self.config.query_param.is_empty() => {
+                replace_query_param(url, &self.config.query_param, token)
+            }
+            _ => url.to_string(),
+        }
+    }
+
+    /// 写入新凭证后的 localStorage
+    pub fn with_token(&self, token: &str) -> Vec<LocalStorageItem> {
+        let mut local_storage = self.local_storage.clone();
+        match local_storage.iter_mut().find(|item| item.key == self.config.storage_key) {
+            Some(item) => item.value = token.to_string(),
+            None => local_storage.push(LocalStorageItem {
+                key: self.config.storage_key.clone(),
+                value: token.to_string(),
+            }),
+        }
+        local_storage
+    }
+
+    /// 请求 m3u8 检查凭证是否被拒绝（401/403），网络错误不视为过期
+    pub async fn playlist_rejected(&self, m3u8_url: &str) -> bool {
+        if !self.is_enabled() {
+            return false;
+        }
+        let builder = reqwest::Client::builder()
+            .user_agent("Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36")
+            .timeout(PLAYLIST_CHECK_TIMEOUT);
+        let builder = cookies::apply_to_client(builder, &self.cookie_source);
+        let Ok(client) = proxy::apply_to_client(builder, &self.proxy).build()
+        else {
+            return false;
+        };
+        match client.get(self.apply_to_url(m3u8_url)).send().await {
+            Ok(resp) => matches!(resp.status().as_u16(), 401 | 403),
+            Err(_) => false,
+        }
+    }
+
+    /// 打开登录页执行登录脚本，等待 localStorage 中写入新凭证并返回
+    pub async fn refresh(&self) -> Result<String, String> {
+        if !self.can_refresh() {
+            return Err("未配置登录页或登录脚本，无法刷新凭证".to_string());
+        }
+
+        let lease = BrowserPool::global()
+            .acquire_with_proxy(self.proxy.clone())
+            .await
+            .map_err(|e| format!("启动浏览器失败: {}", e))?;
+        let tab = lease.tab();
+
+        tab.navigate_to(&self.config.login_url)
+            .and_then(|tab| tab.wait_until_navigated())
+            .map_err(|e| format!("打开登录页失败: {}", e))?;
+
+        // 先清掉旧凭证，避免把旧值当成登录结果
+        let key = serde_json::to_string(&self.config.storage_key).unwrap_or_default();
+        let _ = tab.evaluate(&format!("localStorage.removeItem({})", key), false);
+
+        tab.evaluate(&self.config.login_script, true)
+            .map_err(|e| format!("执行登录脚本失败: {}", e))?;
+
+        let get_token_js = format!("localStorage.getItem({})", key);
+        let deadline = Instant::now() + Duration::from_secs(self.config.login_timeout_secs.max(1));
+        while Instant::now() < deadline {
+            tokio::time::sleep(LOGIN_POLL_INTERVAL).await;
+            let token = tab
+                .evaluate(&get_token_js, false)
+                .ok()
+                .and_then(|result| result.value)
+                .and_then(|value| value.as_str().map(|s| s.to_string()))
+                .filter(|token| !token.is_empty());
+            if let Some(token) = token {
+                return Ok(token);
+            }
+        }
+        Err(format!("登录超时，{} 秒内未获取到新凭证", self.config.login_timeout_secs))
+    }
+}
+
+/// 解析 JWT 的 exp 声明（非 JWT 凭证返回 None）
+fn jwt_expiry(token: &str) -> Option<DateTime<Utc>> {
+    let payload = token.split('.').nth(1)?;
+    let bytes = URL_SAFE_NO_PAD.decode(payload.trim_end_matches('=')).ok()?;
+    let claims: serde_json::Value = serde_json::from_slice(&bytes).ok()?;
+    let exp = claims.get("exp")?.as_i64()?;
+    Utc.timestamp_opt(exp, 0).single()
+}
+
+/// 替换地址中指定查询参数的值，保留其他参数的原始编码
+fn replace_query_param(url: &str, param: &str, value: &str) -> String {
+    let Ok(mut parsed) = url::Url::parse(url) else {
+        return url.to_string();
+    };
+    let Some(query) = parsed.query() else {
+        return url.to_string();
+    };
+
+    let prefix = format!("{}=", param);
+    if !query.split('&').any(|p| p.starts_with(&prefix)) {
+        return url.to_string();
+    }
+    let new_query = query
+        .split('&')
+        .map(|p| {
+            if p.starts_with(&prefix) {
+                format!("{}{}", prefix, value)
+            } else {
+                p.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("&");
+    parsed.set_query(Some(&new_query));
+    parsed.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::engine::general_purpose::URL_SAFE;
+
+    fn jwt(payload: &str, engine: &impl Engine) -> String {
+        format!("eyJhbGciOiJIUzI1NiJ9.{}.signature", engine.encode(payload))
+    }
+
+    #[test]
+    fn jwt_expiry_reads_exp_claim() {
+        let expected = Utc.timestamp_opt(1_700_000_000, 0).single();
+        let payload = r#"{"exp":1700000000,"ab":1}"#;
+        assert_eq!(jwt_expiry(&jwt(payload, &URL_SAFE_NO_PAD)), expected);
+
+        // 带填充的 base64 也能解析
+        let padded = jwt(payload, &URL_SAFE);
+        assert!(padded.contains('='));
+        assert_eq!(jwt_expiry(&padded), expected);
+    }
+
+    #[test]
+    fn jwt_expiry_ignores_other_tokens() {
+        assert_eq!(jwt_expiry("plain-token"), None);
+        assert_eq!(jwt_expiry("a.!!!.c"), None);
+        assert_eq!(jwt_expiry(&jwt(r#"{"sub":"user"}"#, &URL_SAFE_NO_PAD)), None);
+        assert_eq!(jwt_expiry(&jwt(r#"{"exp":"soon"}"#, &URL_SAFE_NO_PAD)), None);
+    }
+
+    #[test]
+    fn replace_query_param_keeps_other_params() {
+        assert_eq!(
+            replace_query_param("https://cdn.example.com/v.m3u8?auth_token=1&token=old&name=a%20b", "token", "new"),
+            "https://cdn.example.com/v.m3u8?auth_token=1&token=new&name=a%20b"
+        );
+        assert_eq!(replace_query_param("https://cdn.example.com/v.m3u8?t=1", "token", "new"), "https://cdn.example.com/v.m3u8?t=1");
+        assert_eq!(replace_query_param("https://cdn.example.com/v.m3u8", "token", "new"), "https://cdn.example.com/v.m3u8");
+        assert_eq!(replace_query_param("not a url", "token", "new"), "not a url");
+    }
+}
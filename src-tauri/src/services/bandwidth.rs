@@ -0,0 +1,296 @@
+//! 下载带宽限制和下载时间段
+//!
+//! 全局限速由所有下载共享：内置 HLS 引擎每读到一块数据都向全局和任务自己的令牌桶申请，
+//! 限速随时修改随时生效；yt-dlp 只能在启动时通过 `--limit-rate` 指定，
+//! 启动时按当前同时下载的数量平分全局限速，再与任务限速取较小值，修改后下次启动生效。
+//!
+//! 下载时间段按本地时间判断，结束时间早于开始时间表示跨越午夜（属于开始那一天），
+//! 开始和结束相同表示全天。没有配置时间段时不限制。
+
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Datelike, Local, Timelike};
+
+use crate::models::DownloadWindow;
+
+/// 全局令牌桶
+static GLOBAL: Lazy<Mutex<Bucket>> = Lazy::new(|| Mutex::new(Bucket::new(None)));
+
+/// 正在下载的任务（任务ID -> 限速状态）
+static ACTIVE: Lazy<Mutex<HashMap<String, Arc<TaskState>>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// 按速率排队的令牌桶
+struct Bucket {
+    /// 每秒字节数，None 表示不限
+    bytes_per_sec: Option<u64>,
+    /// 下一块数据最早可以读取的时间
+    next_free: Instant,
+}
+
+impl Bucket {
+    fn new(bytes_per_sec: Option<u64>) -> Self {
+        Self {
+            bytes_per_sec,
+            next_free: Instant::now(),
+        }
+    }
+
+    /// 预约读取 bytes 字节，返回需要等待的时长
+    fn reserve(&mut self, bytes: usize) -> Duration {
+        let Some(rate) = self.bytes_per_sec else {
+            return Duration::ZERO;
+        };
+        let now = Instant::now();
+        let start = self.next_free.max(now);
+        self.next_free = start + Duration::from_secs_f64(bytes as f64 / rate as f64);
+        start - now
+    }
+}
+
+struct TaskState {
+    bucket: Mutex<Bucket>,
+    /// yt-dlp 启动时确定的限速
+    process_limit: Mutex<Option<u64>>,
+}
+
+/// KB/s 转为每秒字节数，0 表示不限
+pub fn kb_to_bytes(kb: u32) -> Option<u64> {
+    (kb > 0).then(|| kb as u64 * 1024)
+}
+
+/// 设置全局限速（每秒字节数，None 表示不限）
+pub fn set_global_limit(bytes_per_sec: Option<u64>) {
+    GLOBAL.lock().unwrap().bytes_per_sec = bytes_per_sec;
+}
+
+fn global_limit() -> Option<u64> {
+    GLOBAL.lock().unwrap().bytes_per_sec
+}
+
+/// 修改正在下载的任务的限速，返回任务是否正在下载
+pub fn set_task_limit(task_id: &str, bytes_per_sec: Option<u64>) -> bool {
+    match ACTIVE.lock().unwrap().get(task_id) {
+        Some(state) => {
+            state.bucket.lock().unwrap().bytes_per_sec = bytes_per_sec;
+            true
+        }
+        None => false,
+    }
+}
+
+fn min_limit(a: Option<u64>, b: Option<u64>) -> Option<u64> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+/// 登记正在下载的任务，Drop 时移除
+pub fn register(task_id: &str, bytes_per_sec: Option<u64>) -> ActiveDownload {
+    let state = Arc::new(TaskState {
+        bucket: Mutex::new(Bucket::new(bytes_per_sec)),
+        process_limit: Mutex::new(None),
+    });
+    ACTIVE.lock().unwrap().insert(task_id.to_string(), state.clone());
+    ActiveDownload {
+        task_id: task_id.to_string(),
+        state,
+    }
+}
+
+/// 正在下载的任务
+pub struct ActiveDownload {
+    task_id: String,
+    state: Arc<TaskState>,
+}
+
+impl ActiveDownload {
+    /// 内置下载器使用的限速器
+    pub fn throttle(&self) -> Throttle {
+        Throttle {
+            state: self.state.clone(),
+        }
+    }
+
+    /// 启动 yt-dlp 前确定进程的限速：全局限速按同时下载数平分，再与任务限速取较小值
+    pub fn start_process(&self) -> Option<u64> {
+        let active = ACTIVE.lock().unwrap().len().max(1) as u64;
+        let share = global_limit().map(|limit| (limit / active).max(1024));
+        let limit = min_limit(share, self.state.bucket.lock().unwrap().bytes_per_sec);
+        *self.state.process_limit.lock().unwrap() = limit;
+        limit
+    }
+
+    /// 当前生效的限速（每秒字节数，None 表示不限）
+    pub fn current_limit(&self) -> Option<u64> {
+        if let Some(limit) = *self.state.process_limit.lock().unwrap() {
+            return Some(limit);
+        }
+        min_limit(global_limit(), self.state.bucket.lock().unwrap().bytes_per_sec)
+    }
+}
+
+impl Drop for ActiveDownload {
+    fn drop(&mut self) {
+        let mut active = ACTIVE.lock().unwrap();
+        // 同一任务可能已被新的下载重新登记
+        if active.get(&self.task_id).is_some_and(|state| Arc::ptr_eq(state, &self.state)) {
+            active.remove(&self.task_id);
+        }
+    }
+}
+
+/// 内置下载器的限速器
+#[derive(Clone)]
+pub struct Throttle {
+    state: Arc<TaskState>,
+}
+
+impl Throttle {
+    /// 只受全局限速的限速器（不登记为正在下载，用于请求播放列表等少量数据）
+    pub fn global_only() -> Self {
+        Self {
+            state: Arc::new(TaskState {
+                bucket: Mutex::new(Bucket::new(None)),
+                process_limit: Mutex::new(None),
+            }),
+        }
+    }
+
+    /// 读取 bytes 字节后调用，超过全局或任务限速时等待
+    pub async fn consume(&self, bytes: usize) {
+        let global = GLOBAL.lock().unwrap().reserve(bytes);
+        let task = self.state.bucket.lock().unwrap().reserve(bytes);
+        let wait = global.max(task);
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+/// 解析 "HH:MM"，返回从午夜开始的分钟数
+fn parse_time(time: &str) -> Option<u32> {
+    let (hour, minute) = time.trim().split_once(':')?;
+    let (hour, minute): (u32, u32) = (hour.parse().ok()?, minute.parse().ok()?);
+    (hour < 24 && minute < 60).then_some(hour * 60 + minute)
+}
+
+/// 校验下载时间段
+pub fn validate_windows(windows: &[DownloadWindow]) -> Result<(), String> {
+    for window in windows {
+        for time in [&window.start, &window.end] {
+            if parse_time(time).is_none() {
+                return Err(format!("时间格式无效: {}（应为 HH:MM）", time));
+            }
+        }
+        if let Some(day) = window.days.iter().find(|day| !(1..=7).contains(*day)) {
+            return Err(format!("星期无效: {}（应为 1-7）", day));
+        }
+    }
+    Ok(())
+}
+
+/// 当前时间是否在下载时间段内（没有配置时间段时始终允许）
+pub fn in_download_window(windows: &[DownloadWindow], now: DateTime<Local>) -> bool {
+    if windows.is_empty() {
+        return true;
+    }
+    let today = now.weekday().number_from_monday() as u8;
+    let yesterday = now.weekday().pred().number_from_monday() as u8;
+    let minute = now.hour() * 60 + now.minute();
+
+    windows.iter().any(|window| {
+        let (Some(start), Some(end)) = (parse_time(&window.start), parse_time(&window.end)) else {
+            return false;
+        };
+        let on_day = |day: u8| window.days.is_empty() || window.days.contains(&day);
+        if start == end {
+            on_day(today)
+        } else if start < end {
+            on_day(today) && (start..end).contains(&minute)
+        } else {
+            (on_day(today) && minute >= start) || (on_day(yesterday) && minute < end)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn window(days: &[u8], start: &str, end: &str) -> DownloadWindow {
+        DownloadWindow {
+            days: days.to_vec(),
+            start: start.to_string(),
+            end: end.to_string(),
+        }
+    }
+
+    /// 2024-05-01 是周三
+    fn at(day: u32, hour: u32, minute: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(2024, 5, day, hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn no_windows_always_allows() {
+        assert!(in_download_window(&[], at(1, 12, 0)));
+    }
+
+    #[test]
+    fn same_day_window_excludes_end() {
+        let windows = [window(&[], "09:00", "17:00")];
+        assert!(!in_download_window(&windows, at(1, 8, 59)));
+        assert!(in_download_window(&windows, at(1, 9, 0)));
+        assert!(in_download_window(&windows, at(1, 16, 59)));
+        assert!(!in_download_window(&windows, at(1, 17, 0)));
+    }
+
+    #[test]
+    fn window_crossing_midnight_belongs_to_start_day() {
+        // 周三 22:00 到周四 06:00
+        let windows = [window(&[3], "22:00", "06:00")];
+        assert!(in_download_window(&windows, at(1, 22, 0)));
+        assert!(in_download_window(&windows, at(1, 23, 59)));
+        assert!(in_download_window(&windows, at(2, 0, 0)));
+        assert!(in_download_window(&windows, at(2, 5, 59)));
+        assert!(!in_download_window(&windows, at(2, 6, 0)));
+        // 周三凌晨属于周二开始的时间段，周二不在范围内
+        assert!(!in_download_window(&windows, at(1, 2, 0)));
+        assert!(!in_download_window(&windows, at(2, 22, 0)));
+    }
+
+    #[test]
+    fn window_crossing_midnight_on_the_day_before() {
+        // 周二 22:00 开始的时间段覆盖周三凌晨
+        let windows = [window(&[2], "22:00", "06:00")];
+        assert!(in_download_window(&windows, at(1, 5, 0)));
+        assert!(!in_download_window(&windows, at(1, 22, 0)));
+    }
+
+    #[test]
+    fn equal_start_and_end_means_whole_day() {
+        let windows = [window(&[3], "00:00", "00:00")];
+        assert!(in_download_window(&windows, at(1, 0, 0)));
+        assert!(in_download_window(&windows, at(1, 23, 59)));
+        assert!(!in_download_window(&windows, at(2, 12, 0)));
+    }
+
+    #[test]
+    fn invalid_window_never_matches() {
+        let windows = [window(&[], "25:00", "06:00")];
+        assert!(!in_download_window(&windows, at(1, 12, 0)));
+    }
+
+    #[test]
+    fn validate_windows_checks_times_and_days() {
+        assert!(validate_windows(&[window(&[1, 7], "22:00", "06:30")]).is_ok());
+        assert!(validate_windows(&[window(&[], "9:00", "24:00")]).is_err());
+        assert!(validate_windows(&[window(&[], "09:60", "10:00")]).is_err());
+        assert!(validate_windows(&[window(&[0], "09:00", "10:00")]).is_err());
+        assert!(validate_windows(&[window(&[8], "09:00", "10:00")]).is_err());
+    }
+}
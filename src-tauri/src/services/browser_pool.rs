@@ -0,0 +1,345 @@
+//! 共享 headless Chrome 浏览器池
+//!
+//! 爬虫和嗅探器不再各自启动 Chrome，而是从池中借出独立的标签页：
+//! - 每个标签页运行在单独的浏览器上下文（类似无痕窗口）中，互不共享 Cookie/localStorage
+//! - 同时运行的 Chrome 进程数和标签页数都有上限
+//! - 崩溃或断开的浏览器会被丢弃并在下次借出时重新启动
+//! - 空闲过久的浏览器会被回收
+//! - 浏览器按代理分组启动（--proxy-server），不同网站的代理互不影响
+
+use headless_chrome::protocol::cdp::Target;
+use headless_chrome::{Browser, LaunchOptions, Tab};
+use once_cell::sync::Lazy;
+use std::ffi::OsStr;
+use std::ops::Deref;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::models::ProxyConfig;
+use crate::services::proxy;
+
+/// 最多同时运行的 Chrome 进程数
+const MAX_BROWSERS: usize = 2;
+/// 每个 Chrome 进程最多同时打开的标签页数
+const MAX_TABS_PER_BROWSER: usize = 4;
+/// 空闲浏览器的回收时间
+const BROWSER_IDLE_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// 全局浏览器池
+static BROWSER_POOL: Lazy<BrowserPool> = Lazy::new(BrowserPool::new);
+
+/// 统一的 Chrome 启动参数
+const BROWSER_ARGS: &[&str] = &[
+    "--headless=new",
+    "--no-sandbox",
+    "--disable-dev-shm-usage",
+    "--disable-gpu",
+    "--disable-software-rasterizer",
+    "--mute-audio",
+    "--hide-scrollbars",
+    "--disable-translate",
+    "--disable-background-networking",
+    "--disable-sync",
+    "--disable-features=site-per-process,TranslateUI",
+    "--disable-extensions",
+];
+
+/// 代理对应的 Chrome 启动参数（不使用代理时为空），同时用于区分池中的浏览器
+fn proxy_args(proxy: &ProxyConfig) -> Vec<String> {
+    let mut args = Vec::new();
+    if !proxy.url.is_empty() {
+        args.push(format!("--proxy-server={}", proxy::chrome_proxy_server(proxy)));
+        if let Some(bypass) = proxy::chrome_bypass_list(proxy) {
+            args.push(format!("--proxy-bypass-list={}", bypass));
+        }
+    }
+    args
+}
+
+/// 用统一的启动配置和指定代理启动浏览器
+fn launch_browser(proxy_args: &[String]) -> Result<Browser, String> {
+    let options = LaunchOptions {
+        args: BROWSER_ARGS
+            .iter()
+            .map(OsStr::new)
+            .chain(proxy_args.iter().map(OsStr::new))
+            .collect(),
+        // 通过 --headless=new 参数启用新版无头模式
+        headless: false,
+        // 池中的浏览器会长时间复用，避免 WebSocket 空闲 30 秒后被断开
+        idle_browser_timeout: BROWSER_IDLE_TIMEOUT * 2,
+        ..Default::default()
+    };
+    Browser::new(options).map_err(|e| format!("启动浏览器失败: {}", e))
+}
+
+/// 池中的浏览器
+struct PooledBrowser {
+    id: u64,
+    browser: Browser,
+    /// 启动时使用的代理参数
+    proxy_args: Vec<String>,
+    active_tabs: usize,
+    last_used: Instant,
+}
+
+/// 浏览器进程和 DevTools 连接是否仍然可用（一次 CDP 往返，不要在持有池锁时调用）
+fn is_alive(browser: &Browser) -> bool {
+    browser.get_version().is_ok()
+}
+
+#[derive(Default)]
+struct PoolInner {
+    browsers: Vec<PooledBrowser>,
+    /// 正在启动的浏览器数（启动时不持有锁，计入浏览器数上限）
+    launching: usize,
+    next_id: u64,
+}
+
+/// 浏览器池
+pub struct BrowserPool {
+    inner: Mutex<PoolInner>,
+    tab_permits: Arc<Semaphore>,
+}
+
+/// 借出的标签页，Drop 时自动关闭标签页和上下文并归还名额
+pub struct BrowserLease {
+    tab: Arc<Tab>,
+    context_id: Option<String>,
+    browser_id: u64,
+    /// 标签页关闭后才归还
+    permit: Option<OwnedSemaphorePermit>,
+}
+
+impl BrowserPool {
+    fn new() -> Self {
+        Self {
+            inner: Mutex::new(PoolInner::default()),
+            tab_permits: Arc::new(Semaphore::new(MAX_BROWSERS * MAX_TABS_PER_BROWSER)),
+        }
+    }
+
+    /// 获取全局浏览器池
+    pub fn global() -> &'static BrowserPool {
+        &BROWSER_POOL
+    }
+
+    /// 借出一个使用指定代理的独立标签页（名额用尽时等待）
+    pub async fn acquire_with_proxy(&'static self, proxy: ProxyConfig) -> Result<BrowserLease, String> {
+        let permit = Arc::clone(&self.tab_permits)
+            .acquire_owned()
+            .await
+            .map_err(|e| format!("浏览器池已关闭: {}", e))?;
+
+        // 启动 Chrome 和创建标签页都是阻塞操作
+        tokio::task::spawn_blocking(move || self.checkout(permit, &proxy))
+            .await
+            .map_err(|e| format!("浏览器任务异常: {}", e))?
+    }
+
+    /// 使用全局代理的同步版本，供 spawn_blocking 中的代码使用
+    pub fn acquire_blocking(&'static self) -> Result<BrowserLease, String> {
+        let permit = tokio::runtime::Handle::current()
+            .block_on(Arc::clone(&self.tab_permits).acquire_owned())
+            .map_err(|e| format!("浏览器池已关闭: {}", e))?;
+        self.checkout(permit, &proxy::resolve(None))
+    }
+
+    /// 选择（或启动）一个浏览器并创建标签页，浏览器崩溃时重启一次
+    fn checkout(&self, permit: OwnedSemaphorePermit, proxy: &ProxyConfig) -> Result<BrowserLease, String> {
+        let mut last_error = String::new();
+
+        for _ in 0..2 {
+            let (browser_id, browser) = self.pick_browser(proxy)?;
+
+            match open_isolated_tab(&browser) {
+                Ok((tab, context_id)) => {
+                    return Ok(BrowserLease {
+                        tab,
+                        context_id,
+                        browser_id,
+                        permit: Some(permit),
+                    });
+                }
+                Err(e) => {
+                    last_error = e;
+                    // 其他借出的标签页可能还在使用这个浏览器，只有确认已断开时才丢弃
+                    if is_alive(&browser) {
+                        tracing::warn!("[browser-pool] 浏览器 #{} 创建标签页失败: {}", browser_id, last_error);
+                        self.release(browser_id);
+                    } else {
+                        tracing::warn!("[browser-pool] 浏览器 #{} 已断开，准备重启: {}", browser_id, last_error);
+                        self.discard(browser_id);
+                    }
+                }
+            }
+        }
+
+        Err(format!("创建标签页失败: {}", last_error))
+    }
+
+    /// 选出使用同一代理、负载最低的可用浏览器，必要时启动新浏览器
+    ///
+    /// 检测浏览器是否存活和启动 Chrome 都在锁外进行，不会阻塞其他借出和归还。
+    fn pick_browser(&self, proxy: &ProxyConfig) -> Result<(u64, Browser), String> {
+        let proxy_args = proxy_args(proxy);
+        loop {
+            let mut inner = self.inner.lock().unwrap();
+
+            // 回收长时间空闲的浏览器
+            inner.browsers.retain(|b| {
+                if b.active_tabs == 0 && b.last_used.elapsed() > BROWSER_IDLE_TIMEOUT {
+                    tracing::info!("[browser-pool] 回收空闲浏览器 #{}", b.id);
+                    return false;
+                }
+                true
+            });
+
+            // 浏览器数已达上限时兜底复用负载最低的浏览器（名额由信号量控制，正常不会都满载）
+            let full = inner.browsers.len() + inner.launching >= MAX_BROWSERS;
+            let candidate = inner
+                .browsers
+                .iter_mut()
+                .filter(|b| b.proxy_args == proxy_args && (full || b.active_tabs < MAX_TABS_PER_BROWSER))
+                .min_by_key(|b| b.active_tabs);
+
+            if let Some(pooled) = candidate {
+                pooled.active_tabs += 1;
+                pooled.last_used = Instant::now();
+                let (id, browser) = (pooled.id, pooled.browser.clone());
+                drop(inner);
+
+                if is_alive(&browser) {
+                    return Ok((id, browser));
+                }
+                tracing::warn!("[browser-pool] 浏览器 #{} 已断开，丢弃", id);
+                self.discard(id);
+                continue;
+            }
+
+            if full {
+                // 回收一个空闲的其他代理的浏览器，腾出名额启动新浏览器
+                let idle = inner.browsers.iter().position(|b| b.active_tabs == 0);
+                if let Some(index) = idle {
+                    let removed = inner.browsers.remove(index);
+                    tracing::info!("[browser-pool] 回收浏览器 #{}，启动使用其他代理的浏览器", removed.id);
+                    continue;
+                }
+                // 浏览器都在使用或启动中，稍后再选
+                drop(inner);
+                std::thread::sleep(Duration::from_millis(200));
+                continue;
+            }
+
+            inner.launching += 1;
+            drop(inner);
+            let launched = launch_browser(&proxy_args);
+
+            let mut inner = self.inner.lock().unwrap();
+            inner.launching -= 1;
+            let browser = launched?;
+            inner.next_id += 1;
+            let id = inner.next_id;
+            tracing::info!("[browser-pool] 启动浏览器 #{} (当前 {} 个)", id, inner.browsers.len() + 1);
+
+            inner.browsers.push(PooledBrowser {
+                id,
+                browser: browser.clone(),
+                proxy_args: proxy_args.clone(),
+                active_tabs: 1,
+                last_used: Instant::now(),
+            });
+            return Ok((id, browser));
+        }
+    }
+
+    /// 归还标签页名额
+    fn release(&self, browser_id: u64) {
+        let mut inner = self.inner.lock().unwrap();
+        if let Some(pooled) = inner.browsers.iter_mut().find(|b| b.id == browser_id) {
+            pooled.active_tabs = pooled.active_tabs.saturating_sub(1);
+            pooled.last_used = Instant::now();
+        }
+    }
+
+    /// 从池中移除浏览器（进程在最后一个引用释放时退出）
+    fn discard(&self, browser_id: u64) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.browsers.retain(|b| b.id != browser_id);
+    }
+
+    /// 关闭池中所有浏览器（应用退出时调用）
+    pub fn shutdown(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        let count = inner.browsers.len();
+        inner.browsers.clear();
+        if count > 0 {
+            tracing::info!("[browser-pool] 已关闭 {} 个浏览器", count);
+        }
+    }
+}
+
+/// 在新的浏览器上下文中打开标签页；上下文创建失败时退回普通标签页
+fn open_isolated_tab(browser: &Browser) -> Result<(Arc<Tab>, Option<String>), String> {
+    match browser.new_context() {
+        Ok(context) => {
+            let context_id = context.get_id().to_string();
+            let tab = context.new_tab().map_err(|e| e.to_string())?;
+            Ok((tab, Some(context_id)))
+        }
+        Err(e) => {
+            tracing::warn!("[browser-pool] 创建浏览器上下文失败，使用普通标签页: {}", e);
+            let tab = browser.new_tab().map_err(|e| e.to_string())?;
+            Ok((tab, None))
+        }
+    }
+}
+
+impl BrowserLease {
+    /// 获取标签页
+    pub fn tab(&self) -> Arc<Tab> {
+        Arc::clone(&self.tab)
+    }
+}
+
+impl Deref for BrowserLease {
+    type Target = Tab;
+
+    fn deref(&self) -> &Tab {
+        &self.tab
+    }
+}
+
+impl Drop for BrowserLease {
+    fn drop(&mut self) {
+        let tab = Arc::clone(&self.tab);
+        let context_id = self.context_id.take();
+        let browser_id = self.browser_id;
+        let permit = self.permit.take();
+        let teardown = move || {
+            // 销毁上下文会一并关闭其中的标签页；失败时直接关闭标签页
+            let disposed = match context_id {
+                Some(context_id) => tab
+                    .call_method(Target::DisposeBrowserContext {
+                        browser_context_id: context_id,
+                    })
+                    .is_ok(),
+                None => false,
+            };
+            if !disposed {
+                let _ = tab.close(true);
+            }
+            BROWSER_POOL.release(browser_id);
+            drop(permit);
+        };
+
+        // CDP 调用会阻塞等待浏览器响应，在异步运行时中交给阻塞线程执行
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) => {
+                handle.spawn_blocking(teardown);
+            }
+            Err(_) => teardown(),
+        }
+    }
+}
@@ -0,0 +1,145 @@
+//! 爬取/嗅探任务的取消令牌和任务注册表
+//!
+//! 每次爬取或嗅探调用都会注册一个任务ID，前端通过 cancel_scrape / cancel_sniff
+//! 按ID取消；爬虫在等待循环中检查令牌，提前返回并归还浏览器标签页。
+//! 注册表按任务类型区分，取消某类任务不会影响ID相同的其他类型任务。
+
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+
+/// 同步代码轮询取消状态的间隔
+const BLOCKING_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// 正在运行的任务（任务类型和ID -> 取消令牌）
+static JOBS: Lazy<Mutex<HashMap<(JobKind, String), CancelToken>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// 任务类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum JobKind {
+    /// 爬取和重新爬取
+    Scrape,
+    Sniff,
+    /// 失效检查
+    LinkCheck,
+    /// 内置 HLS 引擎的下载（ID 为下载任务ID）
+    Download,
+}
+
+#[derive(Default)]
+struct CancelInner {
+    cancelled: AtomicBool,
+    notify: Notify,
+}
+
+/// 取消令牌 - 克隆后共享同一状态，同步代码轮询 is_cancelled，异步代码等待 cancelled
+#[derive(Clone, Default)]
+pub struct CancelToken {
+    inner: Arc<CancelInner>,
+}
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 请求取消
+    pub fn cancel(&self) {
+        self.inner.cancelled.store(true, Ordering::SeqCst);
+        self.inner.notify.notify_waiters();
+    }
+
+    /// 是否已请求取消
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// 等待直到被取消
+    pub async fn cancelled(&self) {
+        let notified = self.inner.notify.notified();
+        tokio::pin!(notified);
+        // 先注册等待再检查状态，避免错过检查与等待之间发出的通知
+        notified.as_mut().enable();
+        if self.is_cancelled() {
+            return;
+        }
+        notified.await;
+    }
+
+    /// 可取消的异步等待，完整等待返回 true，被取消返回 false
+    pub async fn sleep(&self, duration: Duration) -> bool {
+        tokio::select! {
+            _ = tokio::time::sleep(duration) => !self.is_cancelled(),
+            _ = self.cancelled() => false,
+        }
+    }
+
+    /// 可取消的阻塞等待（在 spawn_blocking 线程中使用），完整等待返回 true，被取消返回 false
+    pub fn sleep_blocking(&self, duration: Duration) -> bool {
+        let deadline = Instant::now() + duration;
+        loop {
+            if self.is_cancelled() {
+                return false;
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return true;
+            }
+            std::thread::sleep(remaining.min(BLOCKING_POLL_INTERVAL));
+        }
+    }
+}
+
+/// 已注册的任务，Drop 时自动从注册表移除
+pub struct JobHandle {
+    kind: JobKind,
+    id: String,
+    token: CancelToken,
+}
+
+impl JobHandle {
+    /// 任务ID
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// 任务的取消令牌
+    pub fn token(&self) -> CancelToken {
+        self.token.clone()
+    }
+}
+
+impl Drop for JobHandle {
+    fn drop(&mut self) {
+        JOBS.lock().unwrap().remove(&(self.kind, std::mem::take(&mut self.id)));
+    }
+}
+
+/// 注册任务；未指定ID时自动生成，同类型中已有相同ID的任务正在运行时返回错误
+pub fn register_job(kind: JobKind, job_id: Option<String>) -> Result<JobHandle, String> {
+    let id = job_id
+        .filter(|id| !id.is_empty())
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let token = CancelToken::new();
+    let mut jobs = JOBS.lock().unwrap();
+    let key = (kind, id.clone());
+    if jobs.contains_key(&key) {
+        return Err(format!("任务正在运行: {}", id));
+    }
+    jobs.insert(key, token.clone());
+    Ok(JobHandle { kind, id, token })
+}
+
+/// 取消任务，任务不存在（已结束）时返回 false
+pub fn cancel_job(kind: JobKind, job_id: &str) -> bool {
+    match JOBS.lock().unwrap().get(&(kind, job_id.to_string())) {
+        Some(token) => {
+            token.cancel();
+            true
+        }
+        None => false,
+    }
+}
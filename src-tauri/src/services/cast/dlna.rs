@@ -5,6 +5,9 @@ use std::path::PathBuf;
 use tokio::sync::Mutex;
 use warp::Filter;
 
+use crate::models::ScrapeErrorKind;
+use crate::services::retry;
+
 use super::hls_proxy::{
     HlsProxyState,
     proxy_media_handler_by_id,
@@ -169,10 +172,8 @@ impl DlnaService {
             source_url.to_string(),
         ];
 
-        if source_url.contains("bilibili.com") || source_url.contains("bilivideo.com") || source_url.contains("hdslb.com") {
-            args.insert(8, "chrome".to_string());
-            args.insert(8, "--cookies-from-browser".to_string());
-        }
+        // 投屏没有网站/任务上下文，使用全局 Cookie 来源和代理
+        args.splice(8..8, crate::services::NetworkOptions::resolve(None, None).ytdlp_args());
 
         let mut child = Command::new(&ytdlp_path)
             .args(args)
@@ -450,26 +451,42 @@ impl DlnaService {
         Ok(())
     }
 
+    /// 发送一次 SOAP 动作
+    async fn send_action(
+        render: &crab_dlna::Render,
+        action: &str,
+        args: &str,
+    ) -> Result<(), (ScrapeErrorKind, String)> {
+        render
+            .service
+            .action(render.device.url(), action, args)
+            .await
+            .map(|_| ())
+            .map_err(|e| (ScrapeErrorKind::Navigation, format!("{} failed: {:?}", action, e)))
+    }
+
+    /// 发送 SOAP 动作，失败时按重试策略重试
+    async fn action_with_retry(render: &crab_dlna::Render, action: &str, args: &str) -> Result<(), String> {
+        retry::run(&format!("DLNA {}", action), None, move |_| Self::send_action(render, action, args))
+            .await
+            .map_err(|failure| failure.message)
+    }
+
     pub async fn stop_playback(&self, device_name: String) -> Result<(), String> {
         tracing::info!("[DLNA] Stop playback on device: {}", device_name);
 
         let render = Self::resolve_render(&device_name, 5).await?;
 
-        let service = &render.service;
-        let device_url = render.device.url();
-        
         let stop_args = "<InstanceID>0</InstanceID>";
         
-        let result = service
-            .action(device_url, "Stop", stop_args)
-            .await;
+        let result = Self::action_with_retry(&render, "Stop", stop_args).await;
 
         match result {
             Ok(_) => {
                 tracing::info!("[DLNA] Stop command success");
             }
             Err(e) => {
-                tracing::error!("[DLNA] Stop command failed: {:?}", e);
+                tracing::error!("[DLNA] Stop command failed: {}", e);
             }
         }
         
@@ -481,27 +498,19 @@ impl DlnaService {
     pub async fn pause_playback(&self, device_name: String) -> Result<(), String> {
         tracing::info!("[DLNA] Pause playback on device: {}", device_name);
         let render = Self::resolve_render(&device_name, 5).await?;
-        let service = &render.service;
-        let device_url = render.device.url();
         let pause_args = "<InstanceID>0</InstanceID>";
-        service
-            .action(device_url, "Pause", pause_args)
+        Self::action_with_retry(&render, "Pause", pause_args)
             .await
-            .map_err(|e| format!("Pause command failed: {:?}", e))?;
-        Ok(())
+            .map_err(|e| format!("Pause command failed: {}", e))
     }
 
     pub async fn resume_playback(&self, device_name: String) -> Result<(), String> {
         tracing::info!("[DLNA] Resume playback on device: {}", device_name);
         let render = Self::resolve_render(&device_name, 5).await?;
-        let service = &render.service;
-        let device_url = render.device.url();
         let play_args = "<InstanceID>0</InstanceID><Speed>1</Speed>";
-        service
-            .action(device_url, "Play", play_args)
+        Self::action_with_retry(&render, "Play", play_args)
             .await
-            .map_err(|e| format!("Resume command failed: {:?}", e))?;
-        Ok(())
+            .map_err(|e| format!("Resume command failed: {}", e))
     }
 
     pub async fn cast_to_device(
@@ -590,40 +599,26 @@ impl DlnaService {
 
         tracing::info!("[DLNA] SetAVTransportURI primary args: {}", set_payloads[0]);
 
-        // Sony TVs are more sensitive. Use retry with a small delay and explicit Stop before Set.
+        // Sony TVs are more sensitive. Send an explicit Stop before Set.
         let stop_args = "<InstanceID>0</InstanceID>";
         let _ = service.action(device_url, "Stop", stop_args).await;
 
-        let mut last_err: Option<String> = None;
-        for attempt in 1..=3 {
+        // 按重试策略重试，每次尝试依次换用更简单的元数据
+        let render = &render;
+        let set_payloads = &set_payloads;
+        retry::run("DLNA 投屏", None, move |attempt| async move {
             let payload_index = usize::min((attempt - 1) as usize, set_payloads.len() - 1);
-            let set_payload = &set_payloads[payload_index];
-            let set_result = service.action(device_url, "SetAVTransportURI", set_payload).await;
-            match set_result {
-                Ok(_) => {
-                    tracing::info!("[DLNA] SetAVTransportURI success (attempt {}), sending Play...", attempt);
-                    tokio::time::sleep(Duration::from_millis(600)).await;
-
-                    let play_args = "<InstanceID>0</InstanceID><Speed>1</Speed>";
-                    match service.action(device_url, "Play", play_args).await {
-                        Ok(_) => {
-                            tracing::info!("[DLNA] Play command success");
-                            return Ok(());
-                        }
-                        Err(e) => {
-                            last_err = Some(format!("Play failed on attempt {}: {:?}", attempt, e));
-                        }
-                    }
-                }
-                Err(e) => {
-                    last_err = Some(format!("SetAVTransportURI failed on attempt {}: {:?}", attempt, e));
-                }
-            }
-
-            tokio::time::sleep(Duration::from_millis(450)).await;
-        }
-
-        Err(last_err.unwrap_or_else(|| "DLNA cast failed with unknown error".to_string()))
+            Self::send_action(render, "SetAVTransportURI", &set_payloads[payload_index]).await?;
+            tracing::info!("[DLNA] SetAVTransportURI success (attempt {}), sending Play...", attempt);
+            tokio::time::sleep(Duration::from_millis(600)).await;
+
+            let play_args = "<InstanceID>0</InstanceID><Speed>1</Speed>";
+            Self::send_action(render, "Play", play_args).await?;
+            tracing::info!("[DLNA] Play command success");
+            Ok::<(), (ScrapeErrorKind, String)>(())
+        })
+        .await
+        .map_err(|failure| format!("{} (after {} attempts)", failure.message, failure.attempts))
     }
 }
 
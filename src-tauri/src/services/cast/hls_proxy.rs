@@ -19,8 +19,15 @@ fn browser_ua() -> &'static str {
     "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/122.0.0.0 Safari/537.36"
 }
 
+/// 代理请求使用的客户端（携带全局 Cookie 来源中的 Cookie，使用全局代理）
+fn http_client() -> Result<reqwest::Client, reqwest::Error> {
+    use crate::services::{cookies, proxy};
+    let builder = cookies::apply_to_client(reqwest::Client::builder(), &cookies::resolve(None));
+    proxy::apply_to_client(builder, &proxy::resolve(None)).build()
+}
+
 async fn fetch_with_headers(url: &str) -> Result<reqwest::Response, reqwest::Error> {
-    let client = reqwest::Client::builder().build()?;
+    let client = http_client()?;
     let mut req = client
         .get(url)
         .header(reqwest::header::USER_AGENT, browser_ua());
@@ -28,11 +35,12 @@ async fn fetch_with_headers(url: &str) -> Result<reqwest::Response, reqwest::Err
         req = req.header(reqwest::header::REFERER, referer);
         req = req.header(reqwest::header::ORIGIN, "https://www.bilibili.com");
     }
+    let _permit = crate::services::rate_limit::acquire(url, None).await;
     req.send().await
 }
 
 async fn fetch_with_headers_and_range(url: &str, range: Option<&str>) -> Result<reqwest::Response, reqwest::Error> {
-    let client = reqwest::Client::builder().build()?;
+    let client = http_client()?;
     let mut req = client
         .get(url)
         .header(reqwest::header::USER_AGENT, browser_ua());
@@ -43,6 +51,7 @@ async fn fetch_with_headers_and_range(url: &str, range: Option<&str>) -> Result<
     if let Some(r) = range {
         req = req.header(reqwest::header::RANGE, r);
     }
+    let _permit = crate::services::rate_limit::acquire(url, None).await;
     req.send().await
 }
 
@@ -70,7 +70,7 @@ pub enum ConvertStatus {
 }
 
 /// 转换选项
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ConvertOptions {
     /// 输出格式: mp4, mkv, webm, avi, mov, mp3, m4a, wav, flac, gif
     pub format: String,
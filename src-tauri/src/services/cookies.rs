@@ -0,0 +1,167 @@
+//! Cookie 来源
+//!
+//! 每个 yt-dlp 调用和 reqwest 客户端都通过这里取得 Cookie：
+//! 任务或网站可以单独指定来源，未指定时使用 yt-dlp 配置中的全局来源。
+//! 浏览器来源交给 yt-dlp 的 --cookies-from-browser 读取（reqwest 无法读取浏览器数据）；
+//! 文件来源是应用管理的 Netscape cookies.txt，yt-dlp 和 reqwest 都会使用。
+
+use once_cell::sync::Lazy;
+use reqwest::cookie::Jar;
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+
+use crate::models::CookieSource;
+
+/// 全局默认来源（启动时和保存 yt-dlp 配置时更新）
+static DEFAULT_SOURCE: Lazy<RwLock<CookieSource>> = Lazy::new(|| RwLock::new(CookieSource::default()));
+
+/// cookies.txt 中的一条 Cookie
+#[derive(Debug, Clone)]
+pub struct NetscapeCookie {
+    pub domain: String,
+    pub include_subdomains: bool,
+    pub path: String,
+    pub secure: bool,
+    /// 过期时间戳，0 表示会话 Cookie
+    pub expires: i64,
+    pub name: String,
+    pub value: String,
+}
+
+/// 设置全局默认来源
+pub fn set_default_source(source: CookieSource) {
+    *DEFAULT_SOURCE.write().unwrap() = source;
+}
+
+/// 解析实际使用的来源：指定了来源时使用指定的，否则使用全局默认来源
+pub fn resolve(source: Option<&CookieSource>) -> CookieSource {
+    match source {
+        Some(source) => source.clone(),
+        None => DEFAULT_SOURCE.read().unwrap().clone(),
+    }
+}
+
+/// 生成 yt-dlp 的 Cookie 参数
+pub fn ytdlp_args(source: &CookieSource) -> Vec<String> {
+    match source {
+        CookieSource::None => Vec::new(),
+        CookieSource::Browser { browser, profile } => {
+            let spec = if profile.is_empty() {
+                browser.clone()
+            } else {
+                format!("{}:{}", browser, profile)
+            };
+            vec!["--cookies-from-browser".to_string(), spec]
+        }
+        CookieSource::File { path } => vec!["--cookies".to_string(), path.clone()],
+    }
+}
+
+/// 给 reqwest 客户端加上 Cookie（只有文件来源可用，文件读取失败时不带 Cookie）
+pub fn apply_to_client(builder: reqwest::ClientBuilder, source: &CookieSource) -> reqwest::ClientBuilder {
+    let CookieSource::File { path } = source else {
+        return builder;
+    };
+    match load_cookie_file(Path::new(path)) {
+        Ok(cookies) => builder.cookie_provider(Arc::new(cookie_jar(&cookies))),
+        Err(e) => {
+            tracing::warn!("[cookies] {}", e);
+            builder
+        }
+    }
+}
+
+/// 读取并解析 cookies.txt
+pub fn load_cookie_file(path: &Path) -> Result<Vec<NetscapeCookie>, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("读取 Cookie 文件 {} 失败: {}", path.display(), e))?;
+    Ok(parse_netscape(&content))
+}
+
+/// 解析 Netscape 格式的 cookies.txt，跳过注释和格式错误的行
+pub fn parse_netscape(content: &str) -> Vec<NetscapeCookie> {
+    content
+        .lines()
+        .filter_map(|line| {
+            // curl/yt-dlp 导出的 HttpOnly Cookie 以 "#HttpOnly_" 为前缀
+            let line = line.strip_prefix("#HttpOnly_").unwrap_or(line);
+            if line.trim().is_empty() || line.starts_with('#') {
+                return None;
+            }
+            let fields: Vec<&str> = line.split('\t').collect();
+            if fields.len() < 7 {
+                return None;
+            }
+            Some(NetscapeCookie {
+                domain: fields[0].to_string(),
+                include_subdomains: fields[1].eq_ignore_ascii_case("TRUE"),
+                path: fields[2].to_string(),
+                secure: fields[3].eq_ignore_ascii_case("TRUE"),
+                expires: fields[4].parse().unwrap_or(0),
+                name: fields[5].to_string(),
+                value: fields[6..].join("\t"),
+            })
+        })
+        .collect()
+}
+
+/// 把 cookies.txt 中的 Cookie 放入 reqwest 的 Cookie 容器（跳过已过期的）
+fn cookie_jar(cookies: &[NetscapeCookie]) -> Jar {
+    let now = chrono::Utc::now().timestamp();
+    let jar = Jar::default();
+    for cookie in cookies.iter().filter(|c| c.expires == 0 || c.expires > now) {
+        let host = cookie.domain.trim_start_matches('.');
+        let Ok(url) = url::Url::parse(&format!("https://{}{}", host, cookie.path)) else {
+            continue;
+        };
+        let mut header = format!("{}={}; Path={}", cookie.name, cookie.value, cookie.path);
+        if cookie.include_subdomains {
+            header.push_str(&format!("; Domain={}", host));
+        }
+        if cookie.secure {
+            header.push_str("; Secure");
+        }
+        jar.add_cookie_str(&header, &url);
+    }
+    jar
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_netscape_reads_cookies_and_http_only_lines() {
+        let content = "# Netscape HTTP Cookie File\n\
+            \n\
+            .example.com\tTRUE\t/\tFALSE\t1893456000\tsession_id\tabc123\n\
+            #HttpOnly_www.example.com\tFALSE\t/account\tTRUE\t0\ttoken\tx\ty\n\
+            # 注释行\n\
+            broken.example.com\tTRUE\t/\tFALSE\n";
+        let cookies = parse_netscape(content);
+        assert_eq!(cookies.len(), 2);
+
+        let first = &cookies[0];
+        assert_eq!(first.domain, ".example.com");
+        assert!(first.include_subdomains);
+        assert_eq!(first.path, "/");
+        assert!(!first.secure);
+        assert_eq!(first.expires, 1_893_456_000);
+        assert_eq!((first.name.as_str(), first.value.as_str()), ("session_id", "abc123"));
+
+        // HttpOnly 前缀被去掉，值中的制表符保留
+        let http_only = &cookies[1];
+        assert_eq!(http_only.domain, "www.example.com");
+        assert!(!http_only.include_subdomains);
+        assert_eq!(http_only.path, "/account");
+        assert!(http_only.secure);
+        assert_eq!(http_only.expires, 0);
+        assert_eq!((http_only.name.as_str(), http_only.value.as_str()), ("token", "x\ty"));
+    }
+
+    #[test]
+    fn parse_netscape_ignores_comments_and_empty_content() {
+        assert!(parse_netscape("").is_empty());
+        assert!(parse_netscape("# Netscape HTTP Cookie File\n#HttpOnly_\n").is_empty());
+    }
+}
@@ -0,0 +1,255 @@
+//! 封面缓存
+//!
+//! 封面（远程地址或 base64 data URL）只下载一次，按内容的 SHA-256 存到应用数据目录的 covers 下，
+//! 视频记录只保存缓存键（如 "ab/ab12….jpg"），前端通过 asset 协议读取本地文件。
+//! 相同内容的封面只存一份，删除视频后由 collect_garbage 清理不再被引用的文件。
+
+use base64::Engine;
+use once_cell::sync::Lazy;
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::db::Database;
+use crate::models::ScrapeErrorKind;
+use crate::services::{cookies, proxy, rate_limit, retry, NetworkOptions};
+
+/// 封面缓存目录
+static COVERS_DIR: Lazy<PathBuf> = Lazy::new(|| super::get_app_data_dir().join("covers"));
+
+/// 封面文件大小上限
+const MAX_COVER_SIZE: usize = 10 * 1024 * 1024;
+
+/// 下载封面的超时
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// 垃圾回收跳过最近写入的文件（可能属于正在入库的视频）
+const GC_GRACE_PERIOD: Duration = Duration::from_secs(10 * 60);
+
+/// 缓存键对应的本地文件路径
+pub fn path_of(key: &str) -> PathBuf {
+    COVERS_DIR.join(key)
+}
+
+/// 根据文件头（其次是 Content-Type）判断图片扩展名
+fn extension_for(bytes: &[u8], content_type: Option<&str>) -> &'static str {
+    if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        return "jpg";
+    }
+    if bytes.starts_with(b"\x89PNG") {
+        return "png";
+    }
+    if bytes.starts_with(b"GIF8") {
+        return "gif";
+    }
+    if bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+        return "webp";
+    }
+    if bytes.len() >= 12 && &bytes[4..8] == b"ftyp" && &bytes[8..12] == b"avif" {
+        return "avif";
+    }
+    match content_type.map(|t| t.split(';').next().unwrap_or("").trim().to_lowercase()).as_deref() {
+        Some("image/jpeg") => "jpg",
+        Some("image/png") => "png",
+        Some("image/gif") => "gif",
+        Some("image/webp") => "webp",
+        Some("image/avif") => "avif",
+        Some("image/svg+xml") => "svg",
+        _ => "img",
+    }
+}
+
+/// 保存封面内容，返回缓存键（内容已存在时直接复用）
+pub fn store(bytes: &[u8], content_type: Option<&str>) -> Result<String, String> {
+    if bytes.is_empty() {
+        return Err("封面内容为空".to_string());
+    }
+    if bytes.len() > MAX_COVER_SIZE {
+        return Err(format!("封面过大: {} 字节", bytes.len()));
+    }
+
+    let hash: String = Sha256::digest(bytes).iter().map(|b| format!("{:02x}", b)).collect();
+    let key = format!("{}/{}.{}", &hash[..2], hash, extension_for(bytes, content_type));
+    let path = path_of(&key);
+    if path.exists() {
+        return Ok(key);
+    }
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| format!("创建封面目录失败: {}", e))?;
+    }
+    // 先写临时文件再改名，避免读到写了一半的文件
+    let temp = path.with_extension("tmp");
+    std::fs::write(&temp, bytes).map_err(|e| format!("写入封面失败: {}", e))?;
+    std::fs::rename(&temp, &path).map_err(|e| format!("写入封面失败: {}", e))?;
+    Ok(key)
+}
+
+/// 解析 data URL，返回内容和 MIME 类型
+fn decode_data_url(url: &str) -> Result<(Vec<u8>, Option<String>), String> {
+    let rest = url.strip_prefix("data:").ok_or("不是 data URL")?;
+    let (header, data) = rest.split_once(',').ok_or("data URL 格式无效")?;
+    let mime = header.split(';').next().filter(|m| !m.is_empty()).map(|m| m.to_string());
+    let bytes = if header.ends_with(";base64") {
+        base64::engine::general_purpose::STANDARD
+            .decode(data.trim())
+            .map_err(|e| format!("封面 base64 解码失败: {}", e))?
+    } else {
+        percent_encoding::percent_decode_str(data).collect()
+    };
+    Ok((bytes, mime))
+}
+
+/// 保存 data URL 形式的封面，返回缓存键
+pub fn store_data_url(url: &str) -> Result<String, String> {
+    let (bytes, mime) = decode_data_url(url)?;
+    store(&bytes, mime.as_deref())
+}
+
+/// 下载一次远程封面
+async fn fetch(url: &str, network: &NetworkOptions) -> Result<(Vec<u8>, Option<String>), (ScrapeErrorKind, String)> {
+    let builder = reqwest::Client::builder().timeout(DOWNLOAD_TIMEOUT);
+    let builder = cookies::apply_to_client(builder, &network.cookie_source);
+    let client = proxy::apply_to_client(builder, &network.proxy)
+        .build()
+        .map_err(|e| (ScrapeErrorKind::Navigation, format!("创建 HTTP 客户端失败: {}", e)))?;
+
+    let _permit = rate_limit::acquire(url, network.rate_limit.as_ref()).await;
+    let resp = client.get(url).send().await.map_err(|e| {
+        let kind = if e.is_timeout() { ScrapeErrorKind::Timeout } else { ScrapeErrorKind::Navigation };
+        (kind, format!("请求失败: {}", e))
+    })?;
+    let status = resp.status();
+    if let Some(kind) = ScrapeErrorKind::from_http_status(status.as_u16()) {
+        return Err((kind, format!("HTTP {}", status)));
+    }
+    let content_type = resp
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.to_string());
+    if content_type.as_deref().is_some_and(|t| !t.starts_with("image/") && !t.starts_with("application/octet-stream")) {
+        return Err((ScrapeErrorKind::ParseFailed, format!("不是图片: {}", content_type.unwrap_or_default())));
+    }
+    let bytes = resp
+        .bytes()
+        .await
+        .map_err(|e| (ScrapeErrorKind::Navigation, format!("读取封面失败: {}", e)))?;
+    Ok((bytes.to_vec(), content_type))
+}
+
+/// 缓存封面（远程地址或 data URL），返回缓存键
+pub async fn cache_cover(url: &str, network: &NetworkOptions) -> Result<String, String> {
+    if url.starts_with("data:") {
+        return store_data_url(url);
+    }
+    if !url.starts_with("http://") && !url.starts_with("https://") {
+        return Err(format!("不支持的封面地址: {}", url));
+    }
+    let (bytes, content_type) = retry::run(&format!("封面 {}", url), None, move |_| fetch(url, network))
+        .await
+        .map_err(|e| format!("下载封面失败（尝试 {} 次）: {}", e.attempts, e.message))?;
+    store(&bytes, content_type.as_deref())
+}
+
+/// 缓存还没有缓存键的视频封面，ids 为 None 时处理所有视频，返回成功缓存的数量
+///
+/// data URL 缓存后清空 cover_url，避免 base64 继续占用数据库
+pub async fn cache_pending(db: &Database, ids: Option<&[String]>) -> usize {
+    let mut pending = match db.get_uncached_covers().await {
+        Ok(pending) => pending,
+        Err(e) => {
+            tracing::warn!("[cover] 读取待缓存封面失败: {}", e);
+            return 0;
+        }
+    };
+    if let Some(ids) = ids {
+        pending.retain(|(id, _, _)| ids.contains(id));
+    }
+    if pending.is_empty() {
+        return 0;
+    }
+    let websites = db.get_all_websites().await.unwrap_or_default();
+
+    let mut cached = 0;
+    for (id, url, website_name) in pending {
+        let network = websites
+            .iter()
+            .find(|w| w.name == website_name)
+            .map(NetworkOptions::for_website)
+            .unwrap_or_else(|| NetworkOptions::resolve(None, None));
+        let key = match cache_cover(&url, &network).await {
+            Ok(key) => key,
+            Err(e) => {
+                tracing::warn!("[cover] 缓存封面失败 ({}): {}", id, e);
+                continue;
+            }
+        };
+        match db.set_video_cover_key(&id, &key, url.starts_with("data:")).await {
+            Ok(_) => cached += 1,
+            Err(e) => tracing::warn!("[cover] 保存封面缓存键失败 ({}): {}", id, e),
+        }
+    }
+    tracing::info!("[cover] 已缓存 {} 个封面", cached);
+    cached
+}
+
+/// 删除不再被任何视频引用的封面文件，返回删除的文件数
+pub async fn collect_garbage(db: &Database) -> Result<usize, String> {
+    let referenced: HashSet<String> = db
+        .get_cover_keys()
+        .await
+        .map_err(|e| format!("读取封面缓存键失败: {}", e))?
+        .into_iter()
+        .collect();
+    let removed = tokio::task::spawn_blocking(move || remove_unreferenced(&referenced))
+        .await
+        .map_err(|e| format!("清理封面缓存失败: {}", e))?;
+    if removed > 0 {
+        tracing::info!("[cover] 已清理 {} 个无用的封面文件", removed);
+    }
+    Ok(removed)
+}
+
+/// 遍历缓存目录（两级：分片目录/文件），删除未被引用的文件和空的分片目录
+fn remove_unreferenced(referenced: &HashSet<String>) -> usize {
+    let Ok(shards) = std::fs::read_dir(COVERS_DIR.as_path()) else {
+        return 0;
+    };
+
+    let mut removed = 0;
+    for shard in shards.flatten() {
+        let shard_path = shard.path();
+        let Ok(files) = std::fs::read_dir(&shard_path) else {
+            continue;
+        };
+        let shard_name = shard.file_name().to_string_lossy().to_string();
+        for file in files.flatten() {
+            let key = format!("{}/{}", shard_name, file.file_name().to_string_lossy());
+            if referenced.contains(&key) {
+                continue;
+            }
+            let recent = file
+                .metadata()
+                .and_then(|m| m.modified())
+                .ok()
+                .and_then(|modified| modified.elapsed().ok())
+                .is_some_and(|age| age < GC_GRACE_PERIOD);
+            if !recent && std::fs::remove_file(file.path()).is_ok() {
+                removed += 1;
+            }
+        }
+        // 分片目录为空时一并删除（非空时删除失败，忽略）
+        let _ = std::fs::remove_dir(&shard_path);
+    }
+    removed
+}
+
+/// 启动时的维护：缓存所有未缓存的封面，再清理无用的封面文件
+pub async fn maintain(db: &Database) {
+    cache_pending(db, None).await;
+    if let Err(e) = collect_garbage(db).await {
+        tracing::warn!("[cover] {}", e);
+    }
+}
@@ -0,0 +1,769 @@
+//! 原生 HLS 下载引擎
+//!
+//! 解析主播放列表并按 YtdlpConfig.quality 选择码流，按网站的 Cookie、代理、Referer 和限速并发下载分片（受下载带宽限制约束），
+//! 支持 AES-128 加密（EXT-X-KEY）和按字节范围切分的分片（EXT-X-BYTERANGE）。
+//! 每个分片下载完成后单独保存在工作目录中，中断后重新下载时跳过已完成的分片
+//! （工作目录记录播放列表指纹，播放列表变化时清空重新下载）；
+//! 全部完成后按顺序拼接，再用 ffmpeg 封装为 mp4。
+//! 独立音轨、SAMPLE-AES 加密和直播流不支持，返回 ParseFailed，由调用方回退到 yt-dlp。
+
+use aes::cipher::{block_padding::Pkcs7, BlockDecryptMut, KeyIvInit};
+use futures::stream::{self, StreamExt};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use tokio::sync::OnceCell;
+
+use super::NetworkOptions;
+use crate::models::ScrapeErrorKind;
+use crate::services::bandwidth::Throttle;
+use crate::services::cancel::CancelToken;
+use crate::services::{cookies, proxy, rate_limit, retry};
+
+type Aes128CbcDec = cbc::Decryptor<aes::Aes128>;
+
+/// 失败类型和原因（类型为 Cancelled 表示被用户取消）
+pub type HlsError = (ScrapeErrorKind, String);
+
+/// 同时下载的分片数（同一主机仍受网站限速约束）
+const SEGMENT_CONCURRENCY: usize = 8;
+
+/// 单次请求超时
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// 嵌套播放列表最多跟随的层数
+const MAX_PLAYLIST_DEPTH: usize = 3;
+
+/// 工作目录中记录播放列表指纹的文件
+const PLAYLIST_STATE_FILE: &str = "playlist.state";
+
+const USER_AGENT: &str =
+    "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/122.0.0.0 Safari/537.36";
+
+/// 主播放列表中的码流
+#[derive(Debug, Clone)]
+struct Variant {
+    uri: String,
+    bandwidth: u64,
+    /// 分辨率的高度，没有 RESOLUTION 属性时为 None
+    height: Option<u32>,
+    /// 引用的音轨组（EXT-X-MEDIA 的 GROUP-ID）
+    audio_group: Option<String>,
+}
+
+/// 分片使用的 AES-128 密钥
+#[derive(Debug, Clone)]
+struct SegmentKey {
+    uri: String,
+    /// 未指定时使用分片序号
+    iv: Option<[u8; 16]>,
+}
+
+/// 分片在资源中的字节范围
+#[derive(Debug, Clone)]
+struct ByteRange {
+    offset: u64,
+    length: u64,
+}
+
+/// 媒体播放列表中的分片
+#[derive(Debug, Clone)]
+struct Segment {
+    uri: String,
+    sequence: u64,
+    byte_range: Option<ByteRange>,
+    key: Option<SegmentKey>,
+    /// EXTINF 时长（秒）
+    duration: f64,
+}
+
+/// 媒体播放列表
+#[derive(Debug, Default)]
+struct MediaPlaylist {
+    /// fMP4 的初始化分片（EXT-X-MAP）
+    init: Option<Segment>,
+    segments: Vec<Segment>,
+    /// 是否有 EXT-X-ENDLIST（点播）
+    ended: bool,
+}
+
+/// 解析后的播放列表
+enum Playlist {
+    Master {
+        variants: Vec<Variant>,
+        /// 带有独立音频地址的音轨组
+        audio_groups: HashSet<String>,
+    },
+    Media(MediaPlaylist),
+}
+
+/// 下载进度
+#[derive(Debug, Clone)]
+pub struct HlsProgress {
+    /// 已完成的分片数（包括之前已下载的）
+    pub completed: usize,
+    pub total: usize,
+    /// 本次下载的字节数
+    pub downloaded_bytes: u64,
+    pub elapsed: Duration,
+}
+
+impl HlsProgress {
+    /// 完成百分比
+    pub fn percent(&self) -> u8 {
+        if self.total == 0 {
+            return 0;
+        }
+        (self.completed * 100 / self.total) as u8
+    }
+}
+
+fn parse_failed(message: impl Into<String>) -> HlsError {
+    (ScrapeErrorKind::ParseFailed, message.into())
+}
+
+fn request_error(e: reqwest::Error) -> HlsError {
+    let kind = if e.is_timeout() { ScrapeErrorKind::Timeout } else { ScrapeErrorKind::Navigation };
+    (kind, format!("请求失败: {}", e))
+}
+
+fn retry_failure(e: retry::RetryFailure) -> HlsError {
+    let message = if e.kind == ScrapeErrorKind::Cancelled {
+        e.message
+    } else {
+        format!("{}（尝试 {} 次）", e.message, e.attempts)
+    };
+    (e.kind, message)
+}
+
+fn cancelled() -> HlsError {
+    (ScrapeErrorKind::Cancelled, "已取消".to_string())
+}
+
+/// 解析属性列表，如 BANDWIDTH=1280000,RESOLUTION=1280x720,CODECS="avc1.4d401f,mp4a.40.2"
+fn parse_attributes(text: &str) -> HashMap<String, String> {
+    let mut attrs = HashMap::new();
+    let mut rest = text.trim();
+    while !rest.is_empty() {
+        let Some((name, after)) = rest.split_once('=') else {
+            break;
+        };
+        let (value, remaining) = if let Some(quoted) = after.strip_prefix('"') {
+            match quoted.split_once('"') {
+                Some((value, remaining)) => (value, remaining),
+                None => (quoted, ""),
+            }
+        } else {
+            after.split_once(',').map_or((after, ""), |(value, remaining)| (value, remaining))
+        };
+        attrs.insert(name.trim().to_uppercase(), value.trim().to_string());
+        rest = remaining.trim_start_matches(',').trim();
+    }
+    attrs
+}
+
+/// 解析 "长度[@偏移]"
+fn parse_byte_range(text: &str) -> Result<(u64, Option<u64>), HlsError> {
+    let invalid = || parse_failed(format!("字节范围无效: {}", text));
+    let (length, offset) = match text.trim().split_once('@') {
+        Some((length, offset)) => (length, Some(offset.parse().map_err(|_| invalid())?)),
+        None => (text.trim(), None),
+    };
+    let length: u64 = length.parse().map_err(|_| invalid())?;
+    if length == 0 {
+        return Err(invalid());
+    }
+    Ok((length, offset))
+}
+
+/// 解析 IV（0x 开头的 32 位十六进制）
+fn parse_iv(text: &str) -> Result<[u8; 16], HlsError> {
+    let hex = text.trim_start_matches("0x").trim_start_matches("0X");
+    u128::from_str_radix(hex, 16)
+        .map(u128::to_be_bytes)
+        .map_err(|_| parse_failed(format!("IV 无效: {}", text)))
+}
+
+fn resolve(base: &url::Url, uri: &str) -> Result<String, HlsError> {
+    base.join(uri)
+        .map(|url| url.to_string())
+        .map_err(|e| parse_failed(format!("地址无效: {} ({})", uri, e)))
+}
+
+/// 解析 EXT-X-KEY，METHOD=NONE 时返回 None
+fn parse_key(attrs: &HashMap<String, String>, base: &url::Url) -> Result<Option<SegmentKey>, HlsError> {
+    match attrs.get("METHOD").map(String::as_str) {
+        None | Some("NONE") => Ok(None),
+        Some("AES-128") => {
+            let uri = attrs.get("URI").ok_or_else(|| parse_failed("AES-128 密钥缺少 URI"))?;
+            let iv = attrs.get("IV").map(|iv| parse_iv(iv)).transpose()?;
+            Ok(Some(SegmentKey { uri: resolve(base, uri)?, iv }))
+        }
+        Some(method) => Err(parse_failed(format!("不支持的加密方式: {}", method))),
+    }
+}
+
+/// 解析播放列表（主播放列表或媒体播放列表）
+fn parse_playlist(body: &str, base_url: &str) -> Result<Playlist, HlsError> {
+    let mut lines = body
+        .lines()
+        .map(|line| line.trim_start_matches('\u{feff}').trim())
+        .filter(|line| !line.is_empty());
+    if lines.next() != Some("#EXTM3U") {
+        return Err(parse_failed("不是有效的 m3u8 播放列表"));
+    }
+    let base = url::Url::parse(base_url).map_err(|e| parse_failed(format!("播放列表地址无效: {}", e)))?;
+
+    let mut variants = Vec::new();
+    let mut audio_groups = HashSet::new();
+    let mut pending_variant: Option<Variant> = None;
+    let mut media = MediaPlaylist::default();
+    let mut sequence = 0u64;
+    let mut key: Option<SegmentKey> = None;
+    let mut pending_range: Option<(u64, Option<u64>)> = None;
+    let mut pending_duration = 0.0;
+    // 上一个按字节范围切分的分片（地址, 结束位置），省略偏移时从这里接着切
+    let mut last_range_end: Option<(String, u64)> = None;
+
+    for line in lines {
+        if let Some(attrs) = line.strip_prefix("#EXT-X-STREAM-INF:") {
+            let attrs = parse_attributes(attrs);
+            pending_variant = Some(Variant {
+                uri: String::new(),
+                bandwidth: attrs.get("BANDWIDTH").and_then(|b| b.parse().ok()).unwrap_or(0),
+                height: attrs
+                    .get("RESOLUTION")
+                    .and_then(|r| r.split_once(['x', 'X']))
+                    .and_then(|(_, h)| h.parse().ok()),
+                audio_group: attrs.get("AUDIO").cloned(),
+            });
+        } else if let Some(attrs) = line.strip_prefix("#EXT-X-MEDIA:") {
+            let attrs = parse_attributes(attrs);
+            if attrs.get("TYPE").map(String::as_str) == Some("AUDIO") && attrs.contains_key("URI") {
+                if let Some(group) = attrs.get("GROUP-ID") {
+                    audio_groups.insert(group.clone());
+                }
+            }
+        } else if let Some(value) = line.strip_prefix("#EXT-X-MEDIA-SEQUENCE:") {
+            sequence = value.trim().parse().unwrap_or(0);
+        } else if let Some(attrs) = line.strip_prefix("#EXT-X-KEY:") {
+            key = parse_key(&parse_attributes(attrs), &base)?;
+        } else if let Some(value) = line.strip_prefix("#EXTINF:") {
+            let value = value.split(',').next().unwrap_or_default();
+            pending_duration = value.trim().parse().unwrap_or(0.0);
+        } else if let Some(value) = line.strip_prefix("#EXT-X-BYTERANGE:") {
+            pending_range = Some(parse_byte_range(value)?);
+        } else if let Some(attrs) = line.strip_prefix("#EXT-X-MAP:") {
+            let attrs = parse_attributes(attrs);
+            let uri = attrs.get("URI").ok_or_else(|| parse_failed("EXT-X-MAP 缺少 URI"))?;
+            let byte_range = match attrs.get("BYTERANGE") {
+                Some(range) => {
+                    let (length, offset) = parse_byte_range(range)?;
+                    Some(ByteRange { offset: offset.unwrap_or(0), length })
+                }
+                None => None,
+            };
+            media.init = Some(Segment { uri: resolve(&base, uri)?, sequence, byte_range, key: key.clone(), duration: 0.0 });
+        } else if line == "#EXT-X-ENDLIST" {
+            media.ended = true;
+        } else if !line.starts_with('#') {
+            let uri = resolve(&base, line)?;
+            if let Some(mut variant) = pending_variant.take() {
+                variant.uri = uri;
+                variants.push(variant);
+                continue;
+            }
+            let byte_range = pending_range.take().map(|(length, offset)| {
+                let offset = offset.unwrap_or_else(|| match last_range_end {
+                    Some((ref last_uri, end)) if *last_uri == uri => end,
+                    _ => 0,
+                });
+                ByteRange { offset, length }
+            });
+            last_range_end = byte_range.as_ref().map(|r| (uri.clone(), r.offset + r.length));
+            media.segments.push(Segment { uri, sequence, byte_range, key: key.clone(), duration: pending_duration });
+            pending_duration = 0.0;
+            sequence += 1;
+        }
+    }
+
+    if !variants.is_empty() {
+        Ok(Playlist::Master { variants, audio_groups })
+    } else if media.segments.is_empty() {
+        Err(parse_failed("播放列表中没有分片"))
+    } else {
+        Ok(Playlist::Media(media))
+    }
+}
+
+/// 按画质选择码流：quality 为 0 或码流都没有分辨率时选最高的，
+/// 否则选不超过目标高度中最高的，都超过时选最低的
+fn select_variant(variants: &[Variant], quality: u32) -> Option<&Variant> {
+    let rank = |v: &&Variant| (v.height.unwrap_or(0), v.bandwidth);
+    if quality == 0 || variants.iter().all(|v| v.height.is_none()) {
+        return variants.iter().max_by_key(rank);
+    }
+    variants
+        .iter()
+        .filter(|v| v.height.is_some_and(|h| h <= quality))
+        .max_by_key(rank)
+        .or_else(|| variants.iter().min_by_key(rank))
+}
+
+/// 用 AES-128-CBC 解密分片
+fn decrypt(key: &[u8], iv: &[u8; 16], mut data: Vec<u8>) -> Result<Vec<u8>, HlsError> {
+    let decryptor = Aes128CbcDec::new_from_slices(key, iv).map_err(|e| parse_failed(format!("密钥无效: {}", e)))?;
+    let len = decryptor
+        .decrypt_padded_mut::<Pkcs7>(&mut data)
+        .map_err(|_| parse_failed("分片解密失败，密钥可能不正确"))?
+        .len();
+    data.truncate(len);
+    Ok(data)
+}
+
+/// 一次 HLS 下载使用的 HTTP 客户端、网络选项和已获取的密钥
+struct HlsClient {
+    client: reqwest::Client,
+    network: NetworkOptions,
+    throttle: Throttle,
+    cancel: CancelToken,
+    keys: Mutex<HashMap<String, Arc<OnceCell<Vec<u8>>>>>,
+}
+
+impl HlsClient {
+    fn new(network: &NetworkOptions, throttle: &Throttle, cancel: &CancelToken) -> Result<Self, HlsError> {
+        let builder = reqwest::Client::builder()
+            .user_agent(USER_AGENT)
+            .timeout(REQUEST_TIMEOUT);
+        let builder = cookies::apply_to_client(builder, &network.cookie_source);
+        let client = proxy::apply_to_client(builder, &network.proxy)
+            .build()
+            .map_err(|e| (ScrapeErrorKind::Navigation, format!("创建 HTTP 客户端失败: {}", e)))?;
+        Ok(Self {
+            client,
+            network: network.clone(),
+            throttle: throttle.clone(),
+            cancel: cancel.clone(),
+            keys: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// 请求一次（可指定字节范围）
+    async fn fetch(&self, url: &str, range: Option<&ByteRange>) -> Result<Vec<u8>, HlsError> {
+        let _permit = rate_limit::acquire(url, self.network.rate_limit.as_ref()).await;
+        let mut req = self.client.get(url);
+        if let Some(ref referer) = self.network.referer {
+            req = req.header(reqwest::header::REFERER, referer);
+        }
+        if let Some(range) = range {
+            let end = range.offset + range.length - 1;
+            req = req.header(reqwest::header::RANGE, format!("bytes={}-{}", range.offset, end));
+        }
+        let mut resp = req.send().await.map_err(request_error)?;
+        let status = resp.status();
+        if let Some(kind) = ScrapeErrorKind::from_http_status(status.as_u16()) {
+            return Err((kind, format!("HTTP {}", status)));
+        }
+        // 按块读取，每块都受下载限速约束
+        let mut bytes = Vec::new();
+        while let Some(chunk) = resp
+            .chunk()
+            .await
+            .map_err(|e| (ScrapeErrorKind::Navigation, format!("读取内容失败: {}", e)))?
+        {
+            self.throttle.consume(chunk.len()).await;
+            bytes.extend_from_slice(&chunk);
+        }
+
+        match range {
+            // 服务器忽略了 Range，返回的是完整内容
+            Some(range) if status == reqwest::StatusCode::OK => {
+                let start = range.offset as usize;
+                let end = start + range.length as usize;
+                bytes
+                    .get(start..end)
+                    .map(|slice| slice.to_vec())
+                    .ok_or_else(|| parse_failed(format!("字节范围超出内容长度: {}", url)))
+            }
+            _ => Ok(bytes),
+        }
+    }
+
+    /// 按重试策略请求
+    async fn fetch_with_retry(&self, url: &str, range: Option<&ByteRange>) -> Result<Vec<u8>, HlsError> {
+        retry::run(&format!("HLS {}", url), Some(&self.cancel), move |_| self.fetch(url, range))
+            .await
+            .map_err(retry_failure)
+    }
+
+    /// 获取 AES-128 密钥（同一地址只请求一次，请求期间不阻塞其他地址的密钥）
+    async fn key(&self, uri: &str) -> Result<Vec<u8>, HlsError> {
+        let cell = self.keys.lock().unwrap().entry(uri.to_string()).or_default().clone();
+        let key = cell
+            .get_or_try_init(|| async {
+                let key = self.fetch_with_retry(uri, None).await?;
+                if key.len() != 16 {
+                    return Err(parse_failed(format!("AES-128 密钥长度错误: {} 字节", key.len())));
+                }
+                Ok(key)
+            })
+            .await?;
+        Ok(key.clone())
+    }
+
+    /// 获取媒体播放列表，遇到主播放列表时按画质选择码流
+    async fn media_playlist(&self, url: &str, quality: u32) -> Result<MediaPlaylist, HlsError> {
+        let mut url = url.to_string();
+        for _ in 0..MAX_PLAYLIST_DEPTH {
+            let body = self.fetch_with_retry(&url, None).await?;
+            match parse_playlist(&String::from_utf8_lossy(&body), &url)? {
+                Playlist::Media(media) => return Ok(media),
+                Playlist::Master { variants, audio_groups } => {
+                    let variant = select_variant(&variants, quality).ok_or_else(|| parse_failed("主播放列表中没有码流"))?;
+                    if variant.audio_group.as_ref().is_some_and(|group| audio_groups.contains(group)) {
+                        return Err(parse_failed("码流使用独立音轨，暂不支持"));
+                    }
+                    tracing::info!(
+                        "[hls] 选择码流: {}p, {} bps ({} 个可选)",
+                        variant.height.unwrap_or(0), variant.bandwidth, variants.len()
+                    );
+                    url = variant.uri.clone();
+                }
+            }
+        }
+        Err(parse_failed("播放列表嵌套过深"))
+    }
+
+    /// 下载（并解密）一个分片，先写临时文件再改名，返回字节数
+    async fn download_segment(&self, segment: &Segment, path: &Path) -> Result<u64, HlsError> {
+        let mut data = self.fetch_with_retry(&segment.uri, segment.byte_range.as_ref()).await?;
+        if let Some(ref key) = segment.key {
+            let iv = key.iv.unwrap_or_else(|| (segment.sequence as u128).to_be_bytes());
+            data = decrypt(&self.key(&key.uri).await?, &iv, data)?;
+        }
+
+        let temp = path.with_extension("tmp");
+        let write_error = |e: std::io::Error| (ScrapeErrorKind::Navigation, format!("写入分片失败: {}", e));
+        tokio::fs::write(&temp, &data).await.map_err(write_error)?;
+        tokio::fs::rename(&temp, path).await.map_err(write_error)?;
+        Ok(data.len() as u64)
+    }
+}
+
+/// 播放列表中所有分片的 EXTINF 时长之和（秒），用于校验下载结果
+pub async fn playlist_duration(url: &str, quality: u32, network: &NetworkOptions) -> Result<f64, HlsError> {
+    let client = HlsClient::new(network, &Throttle::global_only(), &CancelToken::new())?;
+    let media = client.media_playlist(url, quality).await?;
+    Ok(media.segments.iter().map(|segment| segment.duration).sum())
+}
+
+/// 按顺序拼接分片
+async fn concat_segments(parts: &[PathBuf], output: &Path) -> Result<(), HlsError> {
+    let io_error = |e: std::io::Error| (ScrapeErrorKind::Navigation, format!("拼接分片失败: {}", e));
+    let mut file = tokio::fs::File::create(output).await.map_err(io_error)?;
+    for part in parts {
+        let mut input = tokio::fs::File::open(part).await.map_err(io_error)?;
+        tokio::io::copy(&mut input, &mut file).await.map_err(io_error)?;
+    }
+    file.flush().await.map_err(io_error)?;
+    Ok(())
+}
+
+/// 用 ffmpeg 封装为目标容器（不重新编码）
+async fn remux(ffmpeg_path: &Path, input: &Path, output: &Path) -> Result<(), HlsError> {
+    let result = Command::new(ffmpeg_path)
+        .args(["-hide_banner", "-loglevel", "error", "-y", "-i"])
+        .arg(input)
+        .args(["-map", "0:v?", "-map", "0:a?", "-c", "copy", "-movflags", "+faststart"])
+        .arg(output)
+        .output()
+        .await
+        .map_err(|e| (ScrapeErrorKind::Navigation, format!("执行 ffmpeg 失败: {}", e)))?;
+    if !result.status.success() {
+        let stderr = String::from_utf8_lossy(&result.stderr);
+        return Err((ScrapeErrorKind::ParseFailed, format!("ffmpeg 封装失败: {}", stderr.trim())));
+    }
+    Ok(())
+}
+
+/// 地址的路径部分（忽略会变化的签名参数）
+fn uri_path(uri: &str) -> String {
+    url::Url::parse(uri)
+        .map(|url| url.path().to_string())
+        .unwrap_or_else(|_| uri.to_string())
+}
+
+/// 播放列表指纹：分片数量、顺序、字节范围、时长和加密方式（分片和密钥地址只取路径）
+fn playlist_fingerprint(parts: &[(PathBuf, Segment)]) -> String {
+    let mut hasher = Sha256::new();
+    for (_, segment) in parts {
+        let path = uri_path(&segment.uri);
+        let range = segment.byte_range.as_ref().map(|r| (r.offset, r.length));
+        let key = segment.key.as_ref().map(|k| (uri_path(&k.uri), k.iv));
+        hasher.update(format!("{}|{}|{:?}|{}|{:?}\n", path, segment.sequence, range, segment.duration, key));
+    }
+    hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// 准备工作目录：播放列表与上次下载时不一致则清空已下载的分片
+async fn prepare_work_dir(work_dir: &Path, fingerprint: &str) -> Result<(), HlsError> {
+    let state_file = work_dir.join(PLAYLIST_STATE_FILE);
+    if work_dir.exists() {
+        let previous = tokio::fs::read_to_string(&state_file).await.unwrap_or_default();
+        if previous.trim() != fingerprint {
+            tracing::info!("[hls] 播放列表已变化，重新下载全部分片: {}", work_dir.display());
+            tokio::fs::remove_dir_all(work_dir)
+                .await
+                .map_err(|e| (ScrapeErrorKind::Navigation, format!("清理分片目录失败: {}", e)))?;
+        }
+    }
+
+    tokio::fs::create_dir_all(work_dir)
+        .await
+        .map_err(|e| (ScrapeErrorKind::Navigation, format!("创建分片目录失败: {}", e)))?;
+    tokio::fs::write(&state_file, fingerprint)
+        .await
+        .map_err(|e| (ScrapeErrorKind::Navigation, format!("写入播放列表指纹失败: {}", e)))
+}
+
+/// 下载 HLS 视频到 output_file
+///
+/// 分片保存在 work_dir 中，成功后删除；失败或取消时保留，下次下载同一任务且播放列表未变化时续传
+#[allow(clippy::too_many_arguments)]
+pub async fn download(
+    url: &str,
+    work_dir: &Path,
+    output_file: &Path,
+    quality: u32,
+    network: &NetworkOptions,
+    throttle: &Throttle,
+    ffmpeg_path: &Path,
+    cancel: &CancelToken,
+    mut on_progress: impl FnMut(HlsProgress) + Send,
+) -> Result<(), HlsError> {
+    let client = HlsClient::new(network, throttle, cancel)?;
+    let media = client.media_playlist(url, quality).await?;
+    if !media.ended {
+        return Err(parse_failed("直播流暂不支持"));
+    }
+
+    let mut parts: Vec<(PathBuf, Segment)> = Vec::new();
+    if let Some(init) = media.init.clone() {
+        parts.push((work_dir.join("init.seg"), init));
+    }
+    for (index, segment) in media.segments.iter().enumerate() {
+        parts.push((work_dir.join(format!("{:06}.seg", index)), segment.clone()));
+    }
+    prepare_work_dir(work_dir, &playlist_fingerprint(&parts)).await?;
+
+    let total = parts.len();
+    let pending: Vec<(PathBuf, Segment)> = parts.iter().filter(|(path, _)| !path.exists()).cloned().collect();
+    let mut progress = HlsProgress {
+        completed: total - pending.len(),
+        total,
+        downloaded_bytes: 0,
+        elapsed: Duration::ZERO,
+    };
+    if progress.completed > 0 {
+        tracing::info!("[hls] 续传: 已完成 {}/{} 个分片", progress.completed, total);
+    }
+    on_progress(progress.clone());
+
+    let started = Instant::now();
+    let client = &client;
+    let mut downloads = stream::iter(pending)
+        .map(move |(path, segment)| async move { client.download_segment(&segment, &path).await })
+        .buffer_unordered(SEGMENT_CONCURRENCY);
+    loop {
+        let next = tokio::select! {
+            next = downloads.next() => next,
+            _ = cancel.cancelled() => return Err(cancelled()),
+        };
+        let Some(result) = next else {
+            break;
+        };
+        progress.downloaded_bytes += result?;
+        progress.completed += 1;
+        progress.elapsed = started.elapsed();
+        on_progress(progress.clone());
+    }
+
+    let joined = work_dir.join(if media.init.is_some() { "joined.mp4" } else { "joined.ts" });
+    let paths: Vec<PathBuf> = parts.into_iter().map(|(path, _)| path).collect();
+    concat_segments(&paths, &joined).await?;
+    remux(ffmpeg_path, &joined, output_file).await?;
+
+    if let Err(e) = tokio::fs::remove_dir_all(work_dir).await {
+        tracing::warn!("[hls] 删除分片目录失败: {} - {}", work_dir.display(), e);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE: &str = "https://cdn.example.com/video/index.m3u8";
+
+    fn media(body: &str) -> MediaPlaylist {
+        match parse_playlist(body, BASE).unwrap() {
+            Playlist::Media(media) => media,
+            Playlist::Master { .. } => panic!("应解析为媒体播放列表"),
+        }
+    }
+
+    fn variant(bandwidth: u64, height: Option<u32>) -> Variant {
+        Variant {
+            uri: format!("{}.m3u8", bandwidth),
+            bandwidth,
+            height,
+            audio_group: None,
+        }
+    }
+
+    #[test]
+    fn parse_attributes_handles_quoted_values() {
+        let attrs = parse_attributes(r#"BANDWIDTH=1280000,resolution=1280x720,CODECS="avc1.4d401f,mp4a.40.2",AUDIO="aac""#);
+        assert_eq!(attrs["BANDWIDTH"], "1280000");
+        assert_eq!(attrs["RESOLUTION"], "1280x720");
+        assert_eq!(attrs["CODECS"], "avc1.4d401f,mp4a.40.2");
+        assert_eq!(attrs["AUDIO"], "aac");
+        assert!(parse_attributes("").is_empty());
+    }
+
+    #[test]
+    fn parse_byte_range_with_and_without_offset() {
+        assert_eq!(parse_byte_range("1000@200").unwrap(), (1000, Some(200)));
+        assert_eq!(parse_byte_range(" 500 ").unwrap(), (500, None));
+        assert!(parse_byte_range("0@10").is_err());
+        assert!(parse_byte_range("abc").is_err());
+        assert!(parse_byte_range("10@x").is_err());
+    }
+
+    #[test]
+    fn parse_iv_accepts_hex_with_prefix() {
+        let mut expected = [0u8; 16];
+        expected[15] = 0x2a;
+        assert_eq!(parse_iv("0x0000000000000000000000000000002A").unwrap(), expected);
+        assert_eq!(parse_iv("0X2a").unwrap(), expected);
+        assert!(parse_iv("0xZZ").is_err());
+    }
+
+    #[test]
+    fn parse_master_playlist() {
+        let body = "#EXTM3U\n\
+            #EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"aac\",NAME=\"en\",URI=\"audio/en.m3u8\"\n\
+            #EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"muxed\",NAME=\"default\"\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=800000,RESOLUTION=640x360\n\
+            360p/index.m3u8\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=2500000,RESOLUTION=1280x720,AUDIO=\"aac\"\n\
+            https://other.example.com/720p.m3u8\n";
+        let Playlist::Master { variants, audio_groups } = parse_playlist(body, BASE).unwrap() else {
+            panic!("应解析为主播放列表");
+        };
+        assert_eq!(variants.len(), 2);
+        assert_eq!(variants[0].uri, "https://cdn.example.com/video/360p/index.m3u8");
+        assert_eq!(variants[0].bandwidth, 800_000);
+        assert_eq!(variants[0].height, Some(360));
+        assert_eq!(variants[1].uri, "https://other.example.com/720p.m3u8");
+        assert_eq!(variants[1].audio_group.as_deref(), Some("aac"));
+        assert_eq!(audio_groups, HashSet::from(["aac".to_string()]));
+    }
+
+    #[test]
+    fn parse_media_playlist_with_map_and_key_rotation() {
+        let body = "\u{feff}#EXTM3U\n\
+            #EXT-X-MEDIA-SEQUENCE:7\n\
+            #EXT-X-MAP:URI=\"init.mp4\",BYTERANGE=\"720@0\"\n\
+            #EXT-X-KEY:METHOD=AES-128,URI=\"keys/a.key?token=1\"\n\
+            #EXTINF:4.5,\n\
+            seg0.m4s\n\
+            #EXT-X-KEY:METHOD=AES-128,URI=\"keys/b.key\",IV=0x00000000000000000000000000000001\n\
+            #EXTINF:4.0,\n\
+            seg1.m4s\n\
+            #EXT-X-KEY:METHOD=NONE\n\
+            #EXTINF:2,\n\
+            seg2.m4s\n\
+            #EXT-X-ENDLIST\n";
+        let media = media(body);
+        assert!(media.ended);
+
+        let init = media.init.unwrap();
+        assert_eq!(init.uri, "https://cdn.example.com/video/init.mp4");
+        let range = init.byte_range.unwrap();
+        assert_eq!((range.offset, range.length), (0, 720));
+        assert!(init.key.is_none());
+
+        assert_eq!(media.segments.len(), 3);
+        let sequences: Vec<u64> = media.segments.iter().map(|s| s.sequence).collect();
+        assert_eq!(sequences, [7, 8, 9]);
+        assert_eq!(media.segments[0].duration, 4.5);
+
+        let first_key = media.segments[0].key.as_ref().unwrap();
+        assert_eq!(first_key.uri, "https://cdn.example.com/video/keys/a.key?token=1");
+        assert!(first_key.iv.is_none());
+        let second_key = media.segments[1].key.as_ref().unwrap();
+        assert_eq!(second_key.uri, "https://cdn.example.com/video/keys/b.key");
+        assert_eq!(second_key.iv.unwrap()[15], 1);
+        assert!(media.segments[2].key.is_none());
+    }
+
+    #[test]
+    fn parse_byte_range_segments_continue_from_previous_end() {
+        let body = "#EXTM3U\n\
+            #EXTINF:4,\n\
+            #EXT-X-BYTERANGE:1000@500\n\
+            all.ts\n\
+            #EXTINF:4,\n\
+            #EXT-X-BYTERANGE:800\n\
+            all.ts\n\
+            #EXTINF:4,\n\
+            #EXT-X-BYTERANGE:300\n\
+            other.ts\n";
+        let ranges: Vec<(u64, u64)> = media(body)
+            .segments
+            .iter()
+            .map(|s| s.byte_range.as_ref().map(|r| (r.offset, r.length)).unwrap())
+            .collect();
+        assert_eq!(ranges, [(500, 1000), (1500, 800), (0, 300)]);
+    }
+
+    #[test]
+    fn parse_playlist_rejects_invalid_input() {
+        assert!(parse_playlist("<html></html>", BASE).is_err());
+        assert!(parse_playlist("#EXTM3U\n#EXT-X-ENDLIST\n", BASE).is_err());
+        assert!(parse_playlist("#EXTM3U\n#EXT-X-KEY:METHOD=SAMPLE-AES,URI=\"k\"\n#EXTINF:1,\na.ts\n", BASE).is_err());
+    }
+
+    #[test]
+    fn select_variant_by_quality() {
+        let variants = [variant(800, Some(360)), variant(2500, Some(720)), variant(5000, Some(1080))];
+        assert_eq!(select_variant(&variants, 0).unwrap().bandwidth, 5000);
+        assert_eq!(select_variant(&variants, 720).unwrap().bandwidth, 2500);
+        assert_eq!(select_variant(&variants, 900).unwrap().bandwidth, 2500);
+        // 都超过目标高度时选最低的
+        assert_eq!(select_variant(&variants, 240).unwrap().bandwidth, 800);
+
+        let no_resolution = [variant(800, None), variant(2500, None)];
+        assert_eq!(select_variant(&no_resolution, 360).unwrap().bandwidth, 2500);
+        assert!(select_variant(&[], 720).is_none());
+    }
+
+    #[test]
+    fn decrypt_aes_128_cbc() {
+        // NIST SP 800-38A F.2.1 的第一个分组，加上 PKCS#7 填充分组
+        let key = 0x2b7e151628aed2a6abf7158809cf4f3cu128.to_be_bytes();
+        let iv = 0x000102030405060708090a0b0c0d0e0fu128.to_be_bytes();
+        let plaintext = 0x6bc1bee22e409f96e93d7e117393172au128.to_be_bytes();
+        let mut ciphertext = 0x7649abac8119b246cee98e9b12e9197du128.to_be_bytes().to_vec();
+        ciphertext.extend(0x8964e0b149c10b7b682e6e39aaeb731cu128.to_be_bytes());
+
+        assert_eq!(decrypt(&key, &iv, ciphertext.clone()).unwrap(), plaintext);
+        assert!(decrypt(&key[..8], &iv, ciphertext.clone()).is_err());
+        assert!(decrypt(&[0u8; 16], &iv, ciphertext).is_err());
+    }
+}
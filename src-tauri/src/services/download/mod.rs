@@ -1,13 +1,19 @@
 //! yt-dlp 统一下载模块
 //!
-//! 支持直接视频链接（m3u8/mp4/mkv等）和平台视频（YouTube/B站等）的下载
-use crate::models::{DownloadProgress, YtdlpConfig, YtdlpResult, YtdlpTask, YtdlpTaskStatus};
-use crate::services::{get_sidecar_path, get_sidecar_bin_dir};
+//! 支持直接视频链接（m3u8/mp4/mkv等）和平台视频（YouTube/B站等）的下载，
+//! m3u8 默认使用内置的 HLS 引擎（见 hls 模块），不支持或失败时回退到 yt-dlp
+mod hls;
+
+use crate::models::{CollisionPolicy, CookieSource, HlsEngine, LinkInfo, PlaylistEntry, PlaylistInfo, ProxyConfig, RateLimitConfig, ScrapeErrorKind, Website, YtdlpConfig, YtdlpResult, YtdlpTask, YtdlpTaskStatus};
+use crate::services::cancel::{cancel_job, register_job, JobKind};
+use crate::services::bandwidth::{self, Throttle};
+use crate::services::filename::{self, FilenameVars};
+use crate::services::{cookies, get_sidecar_path, get_sidecar_bin_dir, proxy, rate_limit, retry};
+use chrono::Local;
 use std::path::PathBuf;
 use std::process::Stdio;
 use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::process::Command;
-use tokio::sync::broadcast;
 use tauri::AppHandle;
 
 // ==================== 常量定义 ====================
@@ -23,6 +29,48 @@ pub enum UrlType {
     Platform,
 }
 
+/// yt-dlp 调用的网络选项（Cookie 来源、代理和限速）
+#[derive(Debug, Clone, Default)]
+pub struct NetworkOptions {
+    pub cookie_source: CookieSource,
+    pub proxy: ProxyConfig,
+    /// 网站的限速配置，None 时使用目标主机已登记的配置
+    pub rate_limit: Option<RateLimitConfig>,
+    /// 请求时附带的 Referer（网站地址）
+    pub referer: Option<String>,
+    /// 任务单独的下载限速（每秒字节数），None 表示只受全局限速
+    pub bandwidth_limit: Option<u64>,
+}
+
+impl NetworkOptions {
+    /// 解析网络选项，未指定的项使用全局配置
+    pub fn resolve(cookie_source: Option<&CookieSource>, proxy: Option<&ProxyConfig>) -> Self {
+        Self {
+            cookie_source: cookies::resolve(cookie_source),
+            proxy: proxy::resolve(proxy),
+            rate_limit: None,
+            referer: None,
+            bandwidth_limit: None,
+        }
+    }
+
+    /// 网站的网络选项
+    pub fn for_website(website: &Website) -> Self {
+        Self {
+            rate_limit: Some(website.rate_limit.clone()),
+            referer: Some(website.base_url.clone()).filter(|url| !url.is_empty()),
+            ..Self::resolve(website.cookie_source.as_ref(), website.proxy.as_ref())
+        }
+    }
+
+    /// 生成 yt-dlp 参数
+    pub fn ytdlp_args(&self) -> Vec<String> {
+        let mut args = cookies::ytdlp_args(&self.cookie_source);
+        args.extend(proxy::ytdlp_args(&self.proxy));
+        args
+    }
+}
+
 // ==================== 静态变量 ====================
 
 /// 运行中的下载进程 PID 存储（用于停止下载）
@@ -105,13 +153,6 @@ fn kill_process(pid: u32) {
     std::thread::sleep(std::time::Duration::from_millis(300));
 }
 
-/// 清理文件名中的非法字符
-pub fn sanitize_filename(name: &str) -> String {
-    name.chars()
-        .map(|c| if "/\\?%*:|\"<>".contains(c) { '_' } else { c })
-        .collect()
-}
-
 /// 格式化文件大小
 fn format_file_size(bytes: u64) -> String {
     const GB: u64 = 1_073_741_824;
@@ -129,6 +170,13 @@ fn format_file_size(bytes: u64) -> String {
     }
 }
 
+/// 限速显示文本，不限时为空
+fn rate_limit_label(bytes_per_sec: Option<u64>) -> String {
+    bytes_per_sec
+        .map(|bytes| format!("{}/s", format_file_size(bytes)))
+        .unwrap_or_default()
+}
+
 /// URL 解码（处理数据库中存储的编码 URL）
 fn decode_url(url: &str) -> String {
     use percent_encoding::percent_decode_str;
@@ -175,15 +223,16 @@ pub async fn get_cast_stream_url(app_handle: &AppHandle, input_url: &str) -> Res
 
     let ytdlp_path = get_sidecar_path(app_handle, "yt-dlp")?;
 
-    let primary_args = vec![
+    let mut primary_args = vec![
         "-g".to_string(),
         "--no-playlist".to_string(),
         "-f".to_string(),
         "b[ext=mp4]/bv*[ext=mp4]+ba[ext=m4a]/b".to_string(),
-        "--cookies-from-browser".to_string(),
-        "chrome".to_string(),
-        url.clone(),
     ];
+    // 投屏没有网站/任务上下文，使用全局 Cookie 来源和代理
+    let network = NetworkOptions::resolve(None, None);
+    primary_args.extend(network.ytdlp_args());
+    primary_args.push(url.clone());
 
     let output = Command::new(&ytdlp_path)
         .args(&primary_args)
@@ -199,13 +248,14 @@ pub async fn get_cast_stream_url(app_handle: &AppHandle, input_url: &str) -> Res
         .collect();
 
     if lines.is_empty() {
-        let fallback_args = vec![
+        let mut fallback_args = vec![
             "-g".to_string(),
             "--no-playlist".to_string(),
             "-f".to_string(),
             "b".to_string(),
-            url.clone(),
         ];
+        fallback_args.extend(proxy::ytdlp_args(&network.proxy));
+        fallback_args.push(url.clone());
         let fallback = Command::new(&ytdlp_path)
             .args(&fallback_args)
             .output()
@@ -330,9 +380,8 @@ fn build_common_args(
 }
 
 /// 添加认证和模拟参数
-fn add_auth_args(args: &mut Vec<String>) {
-    args.push("--cookies-from-browser".to_string());
-    args.push("chrome".to_string());
+fn add_auth_args(args: &mut Vec<String>, network: &NetworkOptions) {
+    args.extend(network.ytdlp_args());
     args.push("--impersonate".to_string());
     args.push("chrome".to_string());
 }
@@ -493,6 +542,30 @@ async fn kill_old_process(task_id: &str) {
         // 等待足够时间让进程及其子进程完全终止
         tokio::time::sleep(tokio::time::Duration::from_millis(1500)).await;
     }
+    drop(pids);
+    // 内置 HLS 引擎的旧任务
+    if cancel_job(JobKind::Download, task_id) {
+        tracing::info!("[ytdlp-download] 发现正在运行的 HLS 任务，正在取消: {}", task_id);
+        tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+    }
+}
+
+/// 根据 yt-dlp 的错误输出判断失败类型（决定是否重试）
+fn classify_ytdlp_error(message: &str) -> ScrapeErrorKind {
+    let lower = message.to_lowercase();
+    if lower.contains("http error 404") || lower.contains("http error 410") || lower.contains("video unavailable") {
+        ScrapeErrorKind::NotFound
+    } else if lower.contains("http error 401") || lower.contains("sign in") || lower.contains("login required") {
+        ScrapeErrorKind::AuthExpired
+    } else if lower.contains("http error 403") || lower.contains("http error 429") {
+        ScrapeErrorKind::Blocked
+    } else if lower.contains("timed out") || lower.contains("timeout") {
+        ScrapeErrorKind::Timeout
+    } else if lower.contains("unsupported url") || lower.contains("no video formats") {
+        ScrapeErrorKind::ParseFailed
+    } else {
+        ScrapeErrorKind::Navigation
+    }
 }
 
 /// 执行 yt-dlp 下载并实时回调进度
@@ -558,6 +631,12 @@ async fn execute_ytdlp_download(
                                 message: format!("下载中 {}%", progress),
                                 created_at: chrono::Utc::now(),
                                 completed_at: None,
+                                cookie_source: None,
+                                rate_limit: String::new(),
+                                post_process: Vec::new(),
+                                verification: None,
+                                collection: None,
+                                duration: None,
                             });
                         }
                     }
@@ -626,15 +705,27 @@ async fn execute_ytdlp_download(
         file_path: String::new(),
         file_size: 0,
         message: "下载完成".to_string(),
+        skipped: false,
     })
 }
 
-/// 查找并重命名输出文件
+/// 整理后的输出文件
+struct OutputFile {
+    path: PathBuf,
+    size: u64,
+    /// 目标已存在且按冲突策略保留了已有文件
+    skipped: bool,
+}
+
+/// 查找输出文件并按文件名模板移动到最终位置（目标已存在时按冲突策略处理）
+///
+/// 找不到下载的文件时返回错误；无法生成或创建目标路径时保留 {task_id}.{ext} 文件
 async fn find_and_rename_output(
     output_path: &str,
     task_id: &str,
-    title: &str,
-) -> Result<(PathBuf, u64), String> {
+    naming: &FilenameVars,
+    config: &YtdlpConfig,
+) -> Result<OutputFile, String> {
     let file_prefix = format!("{}.", task_id);
     let mut video_file: Option<PathBuf> = None;
     let mut image_files: Vec<PathBuf> = Vec::new();
@@ -648,7 +739,7 @@ async fn find_and_rename_output(
             if name.starts_with(&file_prefix) {
                 let ext = path.extension().and_then(|s| s.to_str()).unwrap_or("");
                 match ext {
-                    "mp4" | "mkv" | "webm" | "mov" | "ts" => video_file = Some(path),
+                    ext if filename::VIDEO_EXTENSIONS.contains(&ext) => video_file = Some(path),
                     "jpg" | "webp" | "png" | "jpeg" => image_files.push(path),
                     _ => {}
                 }
@@ -663,26 +754,117 @@ async fn find_and_rename_output(
         .map(|m| m.len())
         .unwrap_or(0);
 
-    // 重命名文件
-    if !title.is_empty() {
-        let sanitized_title = sanitize_filename(title);
-        let ext = path.extension().unwrap_or_default().to_string_lossy();
-        let final_path = PathBuf::from(output_path).join(format!("{}.{}", sanitized_title, ext));
+    // 按模板生成最终路径
+    let ext = path.extension().unwrap_or_default().to_string_lossy().to_string();
+    let target = match filename::render(&config.filename_template, naming, &ext, Local::now()) {
+        Ok(name) => PathBuf::from(output_path).join(name),
+        Err(e) => {
+            tracing::warn!("[ytdlp-download] 生成文件名失败，保留原文件名: {} - {}", path.display(), e);
+            return Ok(OutputFile { path, size: file_size, skipped: false });
+        }
+    };
+    let remove_images = || {
+        // 清理残留的封面图
+        for img in &image_files {
+            let _ = std::fs::remove_file(img);
+        }
+    };
+
+    let Some(final_path) = filename::resolve_collision(&target, config.collision_policy) else {
+        // 跳过：保留已有文件，丢弃这次下载的文件
+        tracing::info!("[ytdlp-download] 文件已存在，保留已有文件: {}", target.display());
+        let _ = std::fs::remove_file(&path);
+        remove_images();
+        let existing_size = std::fs::metadata(&target).map(|m| m.len()).unwrap_or(0);
+        return Ok(OutputFile { path: target, size: existing_size, skipped: true });
+    };
 
-        if final_path.exists() {
-            let _ = std::fs::remove_file(&final_path);
+    if let Some(parent) = final_path.parent() {
+        if let Err(e) = std::fs::create_dir_all(parent) {
+            tracing::warn!("[ytdlp-download] 创建输出目录失败，保留原文件名: {} - {}", parent.display(), e);
+            return Ok(OutputFile { path, size: file_size, skipped: false });
         }
+    }
+    if final_path.exists() {
+        let _ = std::fs::remove_file(&final_path);
+    }
 
-        if std::fs::rename(&path, &final_path).is_ok() {
-            // 清理残留的封面图
-            for img in image_files {
-                let _ = std::fs::remove_file(img);
-            }
-            return Ok((final_path, file_size));
+    match std::fs::rename(&path, &final_path) {
+        Ok(()) => {
+            remove_images();
+            Ok(OutputFile { path: final_path, size: file_size, skipped: false })
+        }
+        Err(e) => {
+            tracing::warn!("[ytdlp-download] 重命名失败，保留原文件名: {} - {}", final_path.display(), e);
+            Ok(OutputFile { path, size: file_size, skipped: false })
         }
     }
+}
 
-    Ok((path, file_size))
+/// 使用内置 HLS 引擎下载到 {output_path}/{task_id}.mp4，分片保存在 {task_id}.hls 目录中用于续传
+#[allow(clippy::too_many_arguments)]
+async fn download_hls_native(
+    app_handle: &AppHandle,
+    url: &str,
+    output_path: &str,
+    task_id: &str,
+    title: &str,
+    config: &YtdlpConfig,
+    network: &NetworkOptions,
+    throttle: &Throttle,
+    progress_callback: &mut (impl FnMut(YtdlpTask) + Send),
+) -> Result<YtdlpResult, (ScrapeErrorKind, String)> {
+    let ffmpeg_path = get_sidecar_path(app_handle, "ffmpeg").map_err(|e| (ScrapeErrorKind::Navigation, e))?;
+    let work_dir = PathBuf::from(output_path).join(format!("{}.hls", task_id));
+    let output_file = PathBuf::from(output_path).join(format!("{}.mp4", task_id));
+    tracing::info!("[ytdlp-download] 使用内置 HLS 引擎下载: {}", url);
+
+    // 注册为可取消的任务，cancel_task 通过任务ID取消
+    let job = register_job(JobKind::Download, Some(task_id.to_string())).map_err(|e| (ScrapeErrorKind::Navigation, e))?;
+    let result = hls::download(url, &work_dir, &output_file, config.quality, network, throttle, &ffmpeg_path, &job.token(), |progress| {
+        let percent = progress.percent();
+        let speed = match progress.elapsed.as_secs_f64() {
+            secs if secs > 0.0 => format!("{}/s", format_file_size((progress.downloaded_bytes as f64 / secs) as u64)),
+            _ => String::new(),
+        };
+        progress_callback(YtdlpTask {
+            id: task_id.to_string(),
+            url: url.to_string(),
+            title: title.to_string(),
+            progress: percent.clamp(0, 99),
+            speed,
+            file_path: None,
+            status: YtdlpTaskStatus::Downloading,
+            resolution: String::new(),
+            file_size: String::new(),
+            message: format!("下载中 {}% ({}/{} 个分片)", percent, progress.completed, progress.total),
+            created_at: chrono::Utc::now(),
+            completed_at: None,
+            cookie_source: None,
+            rate_limit: String::new(),
+            post_process: Vec::new(),
+            verification: None,
+            collection: None,
+            duration: None,
+        });
+    })
+    .await;
+
+    match result {
+        Ok(()) => Ok(YtdlpResult {
+            success: true,
+            title: String::new(),
+            file_path: String::new(),
+            file_size: 0,
+            message: "下载完成".to_string(),
+            skipped: false,
+        }),
+        Err((ScrapeErrorKind::Cancelled, _)) => {
+            tracing::info!("[ytdlp-download] HLS 下载被用户终止: {}", task_id);
+            Err((ScrapeErrorKind::Cancelled, "进程被用户终止".to_string()))
+        }
+        Err(e) => Err(e),
+    }
 }
 
 // ==================== 公开接口 ====================
@@ -692,25 +874,75 @@ pub async fn get_video_info(
     app_handle: &AppHandle,
     url: &str,
     quality: u32,
+    network: &NetworkOptions,
 ) -> Result<YtdlpTask, String> {
+    let json = dump_info(app_handle, url, quality, network, false).await?;
+    Ok(task_from_info(url, &json))
+}
+
+/// 获取链接信息，播放列表和频道只展开一层（--flat-playlist），返回各项的标题和时长
+pub async fn get_link_info(
+    app_handle: &AppHandle,
+    url: &str,
+    quality: u32,
+    network: &NetworkOptions,
+) -> Result<LinkInfo, String> {
+    let json = dump_info(app_handle, url, quality, network, true).await?;
+    if json["_type"].as_str() != Some("playlist") {
+        return Ok(LinkInfo::Video(Box::new(task_from_info(url, &json))));
+    }
+
+    let mut entries = Vec::new();
+    collect_playlist_entries(&json, &mut entries);
+    if entries.is_empty() {
+        return Err("播放列表中没有视频".to_string());
+    }
+    tracing::info!("[ytdlp] 播放列表 {} 共 {} 项", url, entries.len());
+
+    Ok(LinkInfo::Playlist(PlaylistInfo {
+        id: json["id"].as_str().unwrap_or_default().to_string(),
+        title: json["title"].as_str().unwrap_or("未知播放列表").to_string(),
+        url: json["webpage_url"].as_str().unwrap_or(url).to_string(),
+        uploader: json["uploader"]
+            .as_str()
+            .or_else(|| json["channel"].as_str())
+            .unwrap_or_default()
+            .to_string(),
+        entries,
+    }))
+}
+
+/// 用 yt-dlp 读取链接信息（JSON），playlist 为 false 时只取单个视频
+async fn dump_info(
+    app_handle: &AppHandle,
+    url: &str,
+    quality: u32,
+    network: &NetworkOptions,
+    playlist: bool,
+) -> Result<serde_json::Value, String> {
     // 检查依赖
     check_dependencies(app_handle).await?;
 
     let ytdlp_path = get_sidecar_path(app_handle, "yt-dlp")?;
 
-    let args = vec![
-        "--dump-json".to_string(),
-        "--no-download".to_string(),
-        "--no-playlist".to_string(),
-        "--cookies-from-browser".to_string(),
-        "chrome".to_string(),
-        "--impersonate".to_string(),
-        "chrome".to_string(),
-        "--no-check-certificate".to_string(),
+    let base_args = || {
+        let mut args = if playlist {
+            vec!["--dump-single-json".to_string(), "--flat-playlist".to_string()]
+        } else {
+            vec!["--dump-json".to_string(), "--no-playlist".to_string()]
+        };
+        args.push("--no-download".to_string());
+        add_auth_args(&mut args, network);
+        args.push("--no-check-certificate".to_string());
+        args
+    };
+
+    let mut args = base_args();
+    args.extend([
         "-f".to_string(),
         build_format_string(quality),
         url.to_string(),
-    ];
+    ]);
 
     let output = Command::new(&ytdlp_path)
         .args(&args)
@@ -722,17 +954,8 @@ pub async fn get_video_info(
     let output = if output.status.success() {
         output
     } else {
-        let fallback_args = vec![
-            "--dump-json".to_string(),
-            "--no-download".to_string(),
-            "--no-playlist".to_string(),
-            "--cookies-from-browser".to_string(),
-            "chrome".to_string(),
-            "--impersonate".to_string(),
-            "chrome".to_string(),
-            "--no-check-certificate".to_string(),
-            url.to_string(),
-        ];
+        let mut fallback_args = base_args();
+        fallback_args.push(url.to_string());
 
         let fb = Command::new(&ytdlp_path)
             .args(&fallback_args)
@@ -748,14 +971,16 @@ pub async fn get_video_info(
     };
 
     let json_str = String::from_utf8_lossy(&output.stdout);
-    let json: serde_json::Value = serde_json::from_str(&json_str)
-        .map_err(|e| format!("解析视频信息失败: {}", e))?;
+    serde_json::from_str(&json_str).map_err(|e| format!("解析视频信息失败: {}", e))
+}
 
+/// 由单个视频的信息创建任务
+fn task_from_info(url: &str, json: &serde_json::Value) -> YtdlpTask {
     let title = json["title"].as_str().unwrap_or("未知标题").to_string();
     let resolution = json["resolution"].as_str().unwrap_or("").to_string();
     let file_size = json["filesize"].as_u64().unwrap_or(0);
 
-    Ok(YtdlpTask {
+    YtdlpTask {
         id: uuid::Uuid::new_v4().to_string(),
         url: url.to_string(),
         title,
@@ -766,9 +991,43 @@ pub async fn get_video_info(
         message: "等待下载".to_string(),
         created_at: chrono::Utc::now(),
         completed_at: None,
-        resolution: resolution.to_string(),
+        resolution,
         file_size: format_file_size(file_size),
-    })
+        cookie_source: None,
+        rate_limit: String::new(),
+        post_process: Vec::new(),
+        verification: None,
+        collection: None,
+        duration: json["duration"].as_f64().filter(|d| *d > 0.0),
+    }
+}
+
+/// 收集播放列表中的视频，频道的各个标签页（视频、直播等）作为嵌套的播放列表展开
+fn collect_playlist_entries(json: &serde_json::Value, entries: &mut Vec<PlaylistEntry>) {
+    for entry in json["entries"].as_array().into_iter().flatten() {
+        if entry["entries"].is_array() {
+            collect_playlist_entries(entry, entries);
+            continue;
+        }
+        let url = entry["url"]
+            .as_str()
+            .or_else(|| entry["webpage_url"].as_str())
+            .filter(|u| u.starts_with("http"));
+        let Some(url) = url else {
+            continue;
+        };
+        let id = entry["id"].as_str().unwrap_or_default().to_string();
+        entries.push(PlaylistEntry {
+            index: entries.len() as u32 + 1,
+            title: entry["title"]
+                .as_str()
+                .map(str::to_string)
+                .unwrap_or_else(|| if id.is_empty() { url.to_string() } else { id.clone() }),
+            id,
+            url: url.to_string(),
+            duration: entry["duration"].as_f64().filter(|d| *d > 0.0),
+        });
+    }
 }
 
 /// 下载视频（统一入口）
@@ -778,18 +1037,23 @@ pub async fn get_video_info(
 /// - `url`: 视频 URL（支持 m3u8/mp4/平台视频等）
 /// - `output_path`: 输出目录
 /// - `task_id`: 任务 ID（用于临时文件名）
-/// - `title`: 视频标题（用于重命名最终文件）
+/// - `naming`: 文件名模板变量（标题、网站等），用于生成最终文件名
 /// - `config`: 下载配置
+/// - `network`: Cookie 来源和代理
 /// - `progress_callback`: 进度回调函数
+#[allow(clippy::too_many_arguments)]
 pub async fn download_video(
     app_handle: &AppHandle,
     url: &str,
     output_path: &str,
     task_id: &str,
-    title: &str,
+    naming: &FilenameVars,
     config: &YtdlpConfig,
+    network: &NetworkOptions,
     mut progress_callback: impl FnMut(YtdlpTask) + Send,
 ) -> Result<YtdlpResult, String> {
+    let title = naming.title.as_str();
+
     // 1. 解码 URL
     let decoded_url = decode_url(url);
     tracing::info!("[ytdlp-download] URL 解码: {} -> {}", url, decoded_url);
@@ -805,9 +1069,60 @@ pub async fn download_video(
     std::fs::create_dir_all(output_path)
         .map_err(|e| format!("创建输出目录失败: {}", e))?;
 
+    // 冲突策略为跳过且目标文件已存在时不再下载
+    if config.collision_policy == CollisionPolicy::Skip {
+        let existing = filename::existing_output(
+            std::path::Path::new(output_path),
+            &config.filename_template,
+            naming,
+            Local::now(),
+        );
+        if let Some(existing) = existing {
+            tracing::info!("[ytdlp-download] 文件已存在，跳过下载: {}", existing.display());
+            let file_size = std::fs::metadata(&existing).map(|m| m.len()).unwrap_or(0);
+            let file_path = existing.to_string_lossy().to_string();
+            progress_callback(YtdlpTask {
+                id: task_id.to_string(),
+                url: decoded_url.clone(),
+                title: title.to_string(),
+                progress: 100,
+                speed: String::new(),
+                file_path: Some(file_path.clone()),
+                status: YtdlpTaskStatus::Completed,
+                message: "文件已存在，跳过下载".to_string(),
+                created_at: chrono::Utc::now(),
+                completed_at: Some(chrono::Utc::now()),
+                resolution: String::new(),
+                file_size: format_file_size(file_size),
+                cookie_source: None,
+                rate_limit: String::new(),
+                post_process: Vec::new(),
+                verification: None,
+                collection: None,
+                duration: None,
+            });
+            return Ok(YtdlpResult {
+                success: true,
+                title: title.to_string(),
+                file_path,
+                file_size,
+                message: "文件已存在，跳过下载".to_string(),
+                skipped: true,
+            });
+        }
+    }
+
     // 5. 杀死可能存在的旧进程
     kill_old_process(task_id).await;
 
+    // 登记为正在下载，参与全局限速的分配；进度事件附带当前生效的限速
+    let active = bandwidth::register(task_id, network.bandwidth_limit);
+    let active = &active;
+    let mut progress_callback = move |mut task: YtdlpTask| {
+        task.rate_limit = rate_limit_label(active.current_limit());
+        progress_callback(task);
+    };
+
     // 6. 构建参数（始终使用相同参数，--continue 会自动处理断点续传）
     let mut args = build_common_args(output_path, task_id, &ffmpeg_bin_dir);
 
@@ -825,16 +1140,12 @@ pub async fn download_video(
     }
 
     // 9. 添加认证参数
-    add_auth_args(&mut args);
+    add_auth_args(&mut args, network);
 
     // 10. 添加 URL
     args.push(decoded_url.clone());
 
-    // 11. 打印完整命令
-    let full_cmd = format!("yt-dlp {}", args.join(" "));
-    tracing::info!("[ytdlp-download] 开始下载: {}", full_cmd);
-
-    // 12. 发送初始状态
+    // 11. 发送初始状态
     progress_callback(YtdlpTask {
         id: task_id.to_string(),
         url: decoded_url.clone(),
@@ -848,47 +1159,90 @@ pub async fn download_video(
         completed_at: None,
         resolution: String::new(),
         file_size: String::new(),
+        cookie_source: None,
+        rate_limit: String::new(),
+        post_process: Vec::new(),
+        verification: None,
+        collection: None,
+        duration: None,
     });
 
-    // 13. 执行下载
-    let ytdlp_path = get_sidecar_path(app_handle, "yt-dlp")?;
-    let result = execute_ytdlp_download(&ytdlp_path, &decoded_url, args, task_id, title, |task| {
-        progress_callback(task);
-    }).await;
+    // 12. m3u8 优先使用内置 HLS 引擎，被取消时直接返回，其他失败回退到 yt-dlp
+    let native_result = if url_type == UrlType::Hls && config.hls_engine == HlsEngine::Native {
+        match download_hls_native(app_handle, &decoded_url, output_path, task_id, title, config, network, &active.throttle(), &mut progress_callback).await {
+            Ok(result) => Some(Ok(result)),
+            Err((ScrapeErrorKind::Cancelled, e)) => Some(Err(e)),
+            Err((_, e)) => {
+                tracing::warn!("[ytdlp-download] 内置 HLS 引擎下载失败，回退到 yt-dlp: {}", e);
+                None
+            }
+        }
+    } else {
+        None
+    };
 
-    // 14. 处理结果
-    match result {
-        Ok(mut ytdlp_result) => {
-            // 15. 查找并重命名输出文件
-            match find_and_rename_output(output_path, task_id, title).await {
-                Ok((final_path, file_size)) => {
-                    ytdlp_result.title = title.to_string();
-                    ytdlp_result.file_path = final_path.to_string_lossy().to_string();
-                    ytdlp_result.file_size = file_size;
-                    ytdlp_result.message = "下载完成".to_string();
-                    ytdlp_result.success = true;
-
-                    tracing::info!("[ytdlp-download] 下载完成: {}", final_path.display());
-
-                    progress_callback(YtdlpTask {
-                        id: task_id.to_string(),
-                        url: decoded_url.clone(),
-                        title: title.to_string(),
-                        progress: 100,
-                        speed: String::new(),
-                        file_path: Some(ytdlp_result.file_path.clone()),
-                        status: YtdlpTaskStatus::Completed,
-                        message: "下载完成".to_string(),
-                        created_at: chrono::Utc::now(),
-                        completed_at: Some(chrono::Utc::now()),
-                        resolution: String::new(),
-                        file_size: format_file_size(ytdlp_result.file_size),
-                    });
+    // 13. 执行 yt-dlp 下载（整个下载期间占用目标主机的一个连接名额）
+    let result = match native_result {
+        Some(result) => result,
+        None => {
+            // yt-dlp 的限速只能在启动时指定（插在 URL 之前）
+            if let Some(limit) = active.start_process() {
+                let url_index = args.len() - 1;
+                args.splice(url_index..url_index, ["--limit-rate".to_string(), limit.to_string()]);
+            }
+            // 代理参数可能带有用户名密码，写日志前隐去
+            let redacted: Vec<String> = args.iter().map(|arg| proxy::redact(arg)).collect();
+            let full_cmd = format!("yt-dlp {}", redacted.join(" "));
+            tracing::info!("[ytdlp-download] 开始下载: {}", full_cmd);
+
+            let ytdlp_path = get_sidecar_path(app_handle, "yt-dlp")?;
+            let _permit = rate_limit::acquire(&decoded_url, network.rate_limit.as_ref()).await;
+            let policy = retry::policy();
+            let label = format!("下载 {}", task_id);
+            let mut attempt = 1;
+            loop {
+                let result = execute_ytdlp_download(&ytdlp_path, &decoded_url, args.clone(), task_id, title, |task| {
+                    progress_callback(task);
+                }).await;
+                let Err(ref e) = result else {
+                    break result;
+                };
+                // 用户暂停的任务不重试
+                if CANCELLED_TASKS.lock().await.contains(task_id) {
+                    break result;
                 }
-                Err(e) => {
-                    tracing::warn!("[ytdlp-download] 查找文件失败: {}", e);
+                let Some(delay) = retry::next_delay(&policy, &label, attempt, classify_ytdlp_error(e), e) else {
+                    break result;
+                };
+                tokio::time::sleep(delay).await;
+                // 等待重试期间被暂停
+                if CANCELLED_TASKS.lock().await.contains(task_id) {
+                    break Err("进程被用户终止".to_string());
                 }
+                attempt += 1;
             }
+        }
+    };
+
+    // 14. 查找并重命名输出文件（找不到下载的文件按下载失败处理）
+    let result = match result {
+        Ok(ytdlp_result) => find_and_rename_output(output_path, task_id, naming, config)
+            .await
+            .map(|output| (ytdlp_result, output)),
+        Err(e) => Err(e),
+    };
+
+    // 15. 处理结果
+    match result {
+        Ok((mut ytdlp_result, output)) => {
+            ytdlp_result.title = title.to_string();
+            ytdlp_result.file_path = output.path.to_string_lossy().to_string();
+            ytdlp_result.file_size = output.size;
+            ytdlp_result.message = "下载完成".to_string();
+            ytdlp_result.success = true;
+            ytdlp_result.skipped = output.skipped;
+
+            tracing::info!("[ytdlp-download] 下载完成: {}", output.path.display());
 
             // 16. 发送完成状态
             progress_callback(YtdlpTask {
@@ -904,6 +1258,12 @@ pub async fn download_video(
                 completed_at: Some(chrono::Utc::now()),
                 resolution: String::new(),
                 file_size: format_file_size(ytdlp_result.file_size),
+                cookie_source: None,
+                rate_limit: String::new(),
+                post_process: Vec::new(),
+                verification: None,
+                collection: None,
+                duration: None,
             });
 
             Ok(ytdlp_result)
@@ -938,6 +1298,12 @@ pub async fn download_video(
                 completed_at: None,
                 resolution: String::new(),
                 file_size: String::new(),
+                cookie_source: None,
+                rate_limit: String::new(),
+                post_process: Vec::new(),
+                verification: None,
+                collection: None,
+                duration: None,
             });
 
             Err(e)
@@ -945,6 +1311,22 @@ pub async fn download_video(
     }
 }
 
+/// 播放列表标称的总时长（秒），只支持 m3u8，获取失败时返回 None
+pub async fn playlist_duration(url: &str, quality: u32, network: &NetworkOptions) -> Option<f64> {
+    let url = decode_url(url);
+    if detect_url_type(&url) != UrlType::Hls {
+        return None;
+    }
+    match hls::playlist_duration(&url, quality, network).await {
+        Ok(duration) if duration > 0.0 => Some(duration),
+        Ok(_) => None,
+        Err((_, e)) => {
+            tracing::warn!("[ytdlp-download] 获取播放列表时长失败: {}", e);
+            None
+        }
+    }
+}
+
 /// 取消下载任务
 pub fn cancel_task(task_id: &str) -> bool {
     let result = futures::executor::block_on(async {
@@ -960,93 +1342,13 @@ pub fn cancel_task(task_id: &str) -> bool {
             kill_process(pid);
             return Some(true);
         }
+        // 内置 HLS 引擎的任务
+        if cancel_job(JobKind::Download, task_id) {
+            tracing::info!("[ytdlp-download] 取消 HLS 下载: {}", task_id);
+            return Some(true);
+        }
         None
     });
 
     result.is_some()
 }
-
-/// 并发批量下载视频
-/// 参数:
-/// - app_handle: Tauri 应用句柄
-/// - videos: 下载列表，每项为 (视频ID, 视频标题, 视频URL, 输出目录)
-/// - max_concurrent: 最大并发数
-/// - progress_sender: 进度发送通道
-/// 返回: 每项为 (视频ID, 下载结果)
-pub async fn batch_download_concurrent(
-    app_handle: &AppHandle,
-    videos: Vec<(String, String, String, PathBuf)>,
-    max_concurrent: usize,
-    progress_sender: broadcast::Sender<DownloadProgress>,
-) -> Vec<(String, Result<YtdlpResult, String>)> {
-    use futures::stream::StreamExt;
-
-    let config = YtdlpConfig::default();
-
-    // 使用 futures::stream 并发执行下载
-    let results = futures::stream::iter(videos.into_iter().map(|(id, name, m3u8_url, output_dir)| {
-        let sender = progress_sender.clone();
-        let config = config.clone();
-        async move {
-            let video_id = id.clone();
-            let sender_for_callback = sender.clone();
-
-            // 发送开始下载消息
-            let _ = sender.send(DownloadProgress {
-                video_id: video_id.clone(),
-                progress: 0,
-                status: "准备下载...".to_string(),
-                speed: "0 KB/s".to_string(),
-                eta: "--:--".to_string(),
-            });
-
-            // 定义进度回调 - 转换 YtdlpTask 到 DownloadProgress
-            let progress_callback = move |task: YtdlpTask| {
-                let _ = sender_for_callback.send(DownloadProgress {
-                    video_id: task.id.clone(),
-                    progress: task.progress,
-                    status: task.message.clone(),
-                    speed: task.speed.clone(),
-                    eta: "--:--".to_string(),
-                });
-            };
-
-            // 调用 download_video 进行下载
-            let result = download_video(
-                app_handle,
-                &m3u8_url,
-                &output_dir.to_string_lossy(),
-                &video_id,
-                &name,
-                &config,
-                progress_callback,
-            ).await;
-
-            // 发送完成消息
-            let _ = sender.send(DownloadProgress {
-                video_id: video_id.clone(),
-                progress: 100,
-                status: if result.is_ok() { "下载完成".to_string() } else { "下载失败".to_string() },
-                speed: "0 KB/s".to_string(),
-                eta: "--:--".to_string(),
-            });
-
-            (id, result)
-        }
-    }))
-    .buffer_unordered(max_concurrent)
-    .collect()
-    .await;
-
-    results
-}
-
-/// 获取所有任务（占位实现）
-pub async fn get_all_tasks() -> Vec<YtdlpTask> {
-    Vec::new()
-}
-
-/// 清理已完成的任务（占位实现）
-pub async fn cleanup_tasks() {
-    // TODO: 实现任务清理
-}
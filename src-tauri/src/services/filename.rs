@@ -0,0 +1,343 @@
+//! 下载文件名模板
+//!
+//! 模板中的 `/` 分隔目录，例如 `{website}/{date:%Y-%m}/{title} [{scrape_id}].{ext}`，
+//! 生成的路径相对于输出目录。变量的值中的 `/` 和 `\` 不会产生新目录，
+//! 每一级名称都会清理为各平台文件系统都能使用的形式：替换非法字符和控制字符，
+//! 去掉开头和结尾的点和空格，避开 Windows 保留名，并限制长度。
+//!
+//! 支持的变量：`{title}` `{website}` `{scrape_id}` `{id}` `{ext}` `{date}`（可以指定格式，如 `{date:%Y-%m}`）
+
+use std::path::{Path, PathBuf};
+
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Local};
+
+use crate::models::CollisionPolicy;
+
+/// 默认模板（与之前按标题命名的行为一致）
+pub const DEFAULT_TEMPLATE: &str = "{title}.{ext}";
+
+/// `{date}` 未指定格式时使用的格式
+const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// 每一级名称的最大字节数（大多数文件系统限制为 255，留出编号和扩展名的空间）
+const MAX_COMPONENT_BYTES: usize = 200;
+
+/// 文件名为空时使用的名称
+const FALLBACK_NAME: &str = "video";
+
+/// 下载结果可能的视频扩展名
+pub const VIDEO_EXTENSIONS: [&str; 5] = ["mp4", "mkv", "webm", "mov", "ts"];
+
+/// 变量为空时一起去掉的括号
+const BRACKETS: [(char, char); 3] = [('[', ']'), ('(', ')'), ('【', '】')];
+
+/// Windows 保留的设备名（不区分大小写，带扩展名也不能使用）
+const RESERVED_NAMES: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8", "COM9", "LPT1",
+    "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// 模板变量
+#[derive(Debug, Clone, Default)]
+pub struct FilenameVars {
+    pub title: String,
+    /// 网站名称（下载页添加的任务为空）
+    pub website: String,
+    /// 视频在网站上的ID（下载页添加的任务为空）
+    pub scrape_id: String,
+    /// 任务ID或视频ID
+    pub id: String,
+}
+
+enum Segment<'a> {
+    Text(&'a str),
+    /// 变量名和参数
+    Var(&'a str, Option<&'a str>),
+}
+
+fn parse(template: &str) -> Result<Vec<Segment<'_>>, String> {
+    let mut segments = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find(['{', '}']) {
+        if rest[start..].starts_with('}') {
+            return Err(format!("模板中有多余的 }}: {}", template));
+        }
+        if start > 0 {
+            segments.push(Segment::Text(&rest[..start]));
+        }
+        let end = rest[start..]
+            .find('}')
+            .map(|i| start + i)
+            .ok_or_else(|| format!("模板中的 {{ 没有闭合: {}", template))?;
+        let inner = &rest[start + 1..end];
+        let (name, arg) = match inner.split_once(':') {
+            Some((name, arg)) => (name, Some(arg)),
+            None => (inner, None),
+        };
+        match (name, arg) {
+            ("title" | "website" | "scrape_id" | "id" | "ext" | "date", None) => {}
+            ("date", Some(format)) => {
+                if format.is_empty() || StrftimeItems::new(format).any(|item| matches!(item, Item::Error)) {
+                    return Err(format!("日期格式无效: {}", format));
+                }
+            }
+            _ => return Err(format!("未知的模板变量: {{{}}}", inner)),
+        }
+        segments.push(Segment::Var(name, arg));
+        rest = &rest[end + 1..];
+    }
+    if !rest.is_empty() {
+        segments.push(Segment::Text(rest));
+    }
+    Ok(segments)
+}
+
+/// 校验模板
+pub fn validate(template: &str) -> Result<(), String> {
+    if template.trim().is_empty() {
+        return Err("文件名模板不能为空".to_string());
+    }
+    parse(template).map(|_| ())
+}
+
+/// 按模板生成相对于输出目录的路径，模板中没有 `{ext}` 时在末尾加上扩展名
+pub fn render(template: &str, vars: &FilenameVars, ext: &str, now: DateTime<Local>) -> Result<PathBuf, String> {
+    let segments = parse(template)?;
+    let has_ext = segments.iter().any(|s| matches!(s, Segment::Var("ext", _)));
+
+    let mut components = expand(&segments, vars, ext, now);
+    let file_name = components.pop().unwrap_or_default();
+    let file_name = if has_ext { file_name } else { format!("{}.{}", file_name, ext) };
+
+    let mut path = join_dirs(components);
+    path.push(sanitize_file_name(&file_name, ext));
+    Ok(path)
+}
+
+/// 按模板生成相对目录（每一级都是目录，模板为空时返回空路径）
+pub fn render_dir(template: &str, vars: &FilenameVars, now: DateTime<Local>) -> Result<PathBuf, String> {
+    let segments = parse(template)?;
+    Ok(join_dirs(expand(&segments, vars, "", now)))
+}
+
+fn join_dirs(components: Vec<String>) -> PathBuf {
+    let mut path = PathBuf::new();
+    for dir in components {
+        let dir = sanitize_component(&dir);
+        if !dir.is_empty() {
+            path.push(dir);
+        }
+    }
+    path
+}
+
+/// 展开模板中的变量，按 `/` 拆分为各级名称（尚未清理）
+fn expand(segments: &[Segment<'_>], vars: &FilenameVars, ext: &str, now: DateTime<Local>) -> Vec<String> {
+    let mut components = vec![String::new()];
+    // 变量为空时去掉包围它的括号，例如没有 scrape_id 时 `{title} [{scrape_id}]` 中的 `[]`
+    let mut skip_close: Option<char> = None;
+    for segment in segments {
+        match *segment {
+            Segment::Text(text) => {
+                let text = match skip_close.take() {
+                    Some(close) => text.strip_prefix(close).unwrap_or(text),
+                    None => text,
+                };
+                for (i, part) in text.split(['/', '\\']).enumerate() {
+                    if i > 0 {
+                        components.push(String::new());
+                    }
+                    components.last_mut().unwrap().push_str(part);
+                }
+            }
+            Segment::Var(name, arg) => {
+                let value = match name {
+                    "title" if vars.title.trim().is_empty() => vars.id.clone(),
+                    "title" => vars.title.clone(),
+                    "website" => vars.website.clone(),
+                    "scrape_id" => vars.scrape_id.clone(),
+                    "id" => vars.id.clone(),
+                    "ext" => ext.to_string(),
+                    _ => now.format(arg.unwrap_or(DEFAULT_DATE_FORMAT)).to_string(),
+                };
+                let current = components.last_mut().unwrap();
+                if value.trim().is_empty() {
+                    let close = current.chars().last().and_then(|open| {
+                        BRACKETS.iter().find(|(o, _)| *o == open).map(|(_, close)| *close)
+                    });
+                    if let Some(close) = close {
+                        current.pop();
+                        skip_close = Some(close);
+                    }
+                    continue;
+                }
+                // 变量值不产生新目录
+                current.push_str(&value.replace(['/', '\\'], "_"));
+            }
+        }
+    }
+    components
+}
+
+/// 清理文件名，保留扩展名，清理后为空时使用默认名称
+fn sanitize_file_name(name: &str, ext: &str) -> String {
+    let suffix = format!(".{}", ext);
+    let Some(stem) = name.strip_suffix(&suffix) else {
+        let name = sanitize_component(name);
+        return if name.is_empty() { format!("{}{}", FALLBACK_NAME, suffix) } else { name };
+    };
+    match sanitize_component(stem) {
+        stem if stem.is_empty() => format!("{}{}", FALLBACK_NAME, suffix),
+        stem => format!("{}{}", stem, suffix),
+    }
+}
+
+/// 把一级目录名或文件名清理为各平台文件系统都能使用的名称（可能返回空字符串）
+pub fn sanitize_component(name: &str) -> String {
+    let replaced: String = name
+        .chars()
+        .map(|c| if c.is_control() || "/\\?%*:|\"<>".contains(c) { '_' } else { c })
+        .collect();
+    let collapsed = replaced.split_whitespace().collect::<Vec<_>>().join(" ");
+    let mut name = truncate_bytes(&collapsed, MAX_COMPONENT_BYTES)
+        .trim_matches(['.', ' '])
+        .to_string();
+
+    let stem = name.split('.').next().unwrap_or_default();
+    if RESERVED_NAMES.iter().any(|reserved| stem.eq_ignore_ascii_case(reserved)) {
+        let stem_len = stem.len();
+        name.insert(stem_len, '_');
+    }
+    name
+}
+
+/// 按字节截断，不截断多字节字符
+fn truncate_bytes(s: &str, max: usize) -> &str {
+    if s.len() <= max {
+        return s;
+    }
+    let mut end = max;
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+    &s[..end]
+}
+
+/// 按冲突策略决定最终路径：不存在时直接使用，Skip 返回 None，AutoNumber 追加编号
+pub fn resolve_collision(target: &Path, policy: CollisionPolicy) -> Option<PathBuf> {
+    if !target.exists() {
+        return Some(target.to_path_buf());
+    }
+    match policy {
+        CollisionPolicy::Skip => None,
+        CollisionPolicy::Overwrite => Some(target.to_path_buf()),
+        CollisionPolicy::AutoNumber => {
+            let stem = target.file_stem().unwrap_or_default().to_string_lossy();
+            let suffix = target
+                .extension()
+                .map(|ext| format!(".{}", ext.to_string_lossy()))
+                .unwrap_or_default();
+            (1..)
+                .map(|n| target.with_file_name(format!("{} ({}){}", stem, n, suffix)))
+                .find(|path| !path.exists())
+        }
+    }
+}
+
+/// 输出目录中已经存在的同名视频（用于 Skip 策略在下载前跳过）
+pub fn existing_output(output_dir: &Path, template: &str, vars: &FilenameVars, now: DateTime<Local>) -> Option<PathBuf> {
+    VIDEO_EXTENSIONS
+        .iter()
+        .filter_map(|ext| render(template, vars, ext, now).ok())
+        .map(|relative| output_dir.join(relative))
+        .find(|path| path.is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn now() -> DateTime<Local> {
+        Local.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap()
+    }
+
+    fn vars(title: &str, scrape_id: &str) -> FilenameVars {
+        FilenameVars {
+            title: title.to_string(),
+            website: "site".to_string(),
+            scrape_id: scrape_id.to_string(),
+            id: "task-1".to_string(),
+        }
+    }
+
+    #[test]
+    fn render_keeps_brackets_around_non_empty_variable() {
+        let path = render("{title} [{scrape_id}].{ext}", &vars("标题", "42"), "mp4", now()).unwrap();
+        assert_eq!(path, PathBuf::from("标题 [42].mp4"));
+    }
+
+    #[test]
+    fn render_strips_brackets_around_empty_variable() {
+        let path = render("{title} [{scrape_id}].{ext}", &vars("标题", ""), "mp4", now()).unwrap();
+        assert_eq!(path, PathBuf::from("标题.mp4"));
+
+        let path = render("{title}【{scrape_id}】({website})", &vars("标题", " "), "mkv", now()).unwrap();
+        assert_eq!(path, PathBuf::from("标题(site).mkv"));
+    }
+
+    #[test]
+    fn render_builds_directories_and_appends_missing_ext() {
+        let path = render("{website}/{date:%Y-%m}/{title}", &vars("a/b", ""), "mp4", now()).unwrap();
+        assert_eq!(path, Path::new("site").join("2024-05").join("a_b.mp4"));
+    }
+
+    #[test]
+    fn render_falls_back_to_id_for_empty_title() {
+        let path = render("{title}.{ext}", &vars("  ", ""), "mp4", now()).unwrap();
+        assert_eq!(path, PathBuf::from("task-1.mp4"));
+    }
+
+    #[test]
+    fn render_avoids_windows_reserved_names() {
+        let path = render("{title}.{ext}", &vars("con", ""), "mp4", now()).unwrap();
+        assert_eq!(path, PathBuf::from("con_.mp4"));
+
+        let path = render("{title}/{id}.{ext}", &vars("Lpt1.txt", ""), "mp4", now()).unwrap();
+        assert_eq!(path, Path::new("Lpt1_.txt").join("task-1.mp4"));
+    }
+
+    #[test]
+    fn render_dir_skips_empty_components() {
+        let dir = render_dir("{website}/{scrape_id}/{date:%Y}", &vars("标题", ""), now()).unwrap();
+        assert_eq!(dir, Path::new("site").join("2024"));
+    }
+
+    #[test]
+    fn sanitize_component_replaces_illegal_characters() {
+        assert_eq!(sanitize_component("a:b?c*d"), "a_b_c_d");
+        assert_eq!(sanitize_component(" ..name.. "), "name");
+        assert_eq!(sanitize_component("a\tb  c"), "a_b c");
+        assert_eq!(sanitize_component("..."), "");
+        assert_eq!(sanitize_component("console"), "console");
+    }
+
+    #[test]
+    fn sanitize_component_truncates_on_char_boundary() {
+        let name = sanitize_component(&"视".repeat(100));
+        assert!(name.len() <= MAX_COMPONENT_BYTES);
+        assert_eq!(name, "视".repeat(MAX_COMPONENT_BYTES / 3));
+    }
+
+    #[test]
+    fn validate_rejects_bad_templates() {
+        assert!(validate("{title}.{ext}").is_ok());
+        assert!(validate("{date:%Y}/{title}").is_ok());
+        assert!(validate("  ").is_err());
+        assert!(validate("{title").is_err());
+        assert!(validate("title}").is_err());
+        assert!(validate("{unknown}").is_err());
+        assert!(validate("{date:}").is_err());
+    }
+}
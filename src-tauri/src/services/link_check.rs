@@ -0,0 +1,219 @@
+//! m3u8 失效检查
+//!
+//! 按网站的 Cookie、代理、限速和凭证请求视频的 m3u8，确认能解析出分片并且第一个分片可以访问。
+//! 只有服务器明确拒绝（404/410、401）或播放列表中没有可用的分片时才判定为失效，
+//! 网络错误、超时和 5xx 视为无法判断，不改变视频状态。
+//! 403 也可能来自 WAF 或地区限制，只有网站凭证确认已过期时才判定为失效；
+//! 返回的内容不是 m3u8（验证页、登录页等网页）同样视为无法判断。
+
+use std::time::Duration;
+
+use crate::models::{ScrapeErrorKind, Website};
+use crate::services::auth::AuthProvider;
+use crate::services::{cookies, proxy, rate_limit, retry, NetworkOptions};
+
+/// 单次请求超时
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// 嵌套播放列表最多跟随的层数（主播放列表 -> 媒体播放列表）
+const MAX_PLAYLIST_DEPTH: usize = 2;
+
+const USER_AGENT: &str =
+    "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/122.0.0.0 Safari/537.36";
+
+/// 检查结果
+#[derive(Debug, Clone, PartialEq)]
+pub enum LinkState {
+    /// 播放列表和第一个分片都可以访问
+    Alive,
+    /// 已失效（原因）
+    Dead(String),
+    /// 无法判断（原因），例如网络错误
+    Unknown(String),
+}
+
+/// 播放列表中的下一步
+enum PlaylistEntry {
+    /// 主播放列表中的第一个码流
+    Variant(String),
+    /// 媒体播放列表中的第一个分片
+    Segment(String),
+}
+
+/// 一个网站的 m3u8 检查器
+pub struct LinkChecker {
+    client: reqwest::Client,
+    network: NetworkOptions,
+    auth: Option<AuthProvider>,
+    referer: Option<String>,
+}
+
+impl LinkChecker {
+    /// 创建检查器，website 为 None 时使用全局 Cookie 来源和代理
+    pub fn new(website: Option<&Website>) -> Result<Self, String> {
+        let network = match website {
+            Some(website) => NetworkOptions::for_website(website),
+            None => NetworkOptions::resolve(None, None),
+        };
+        let builder = reqwest::Client::builder()
+            .user_agent(USER_AGENT)
+            .timeout(REQUEST_TIMEOUT);
+        let builder = cookies::apply_to_client(builder, &network.cookie_source);
+        let client = proxy::apply_to_client(builder, &network.proxy)
+            .build()
+            .map_err(|e| format!("创建 HTTP 客户端失败: {}", e))?;
+
+        Ok(Self {
+            client,
+            network,
+            auth: website.map(AuthProvider::for_website),
+            referer: website.map(|w| w.base_url.clone()).filter(|url| !url.is_empty()),
+        })
+    }
+
+    /// 检查 m3u8 地址是否仍然有效
+    pub async fn check(&self, m3u8_url: &str) -> LinkState {
+        let mut url = match self.auth {
+            Some(ref auth) => auth.apply_to_url(m3u8_url),
+            None => m3u8_url.to_string(),
+        };
+
+        for _ in 0..MAX_PLAYLIST_DEPTH {
+            let (body, is_media) = match self.fetch_playlist(&url).await {
+                Ok(result) => result,
+                Err(failure) => return self.failure_state("播放列表", failure),
+            };
+            // 直接指向视频文件的地址，能访问即有效
+            if is_media {
+                return LinkState::Alive;
+            }
+            if !is_m3u8(&body) {
+                return LinkState::Unknown("返回的内容不是 m3u8 播放列表（可能是验证页或登录页）".to_string());
+            }
+            match parse_playlist(&body, &url) {
+                Ok(PlaylistEntry::Variant(variant)) => url = variant,
+                Ok(PlaylistEntry::Segment(segment)) => {
+                    return match self.probe_segment(&segment).await {
+                        Ok(()) => LinkState::Alive,
+                        Err(failure) => self.failure_state("第一个分片", failure),
+                    };
+                }
+                Err(reason) => return LinkState::Dead(reason),
+            }
+        }
+        LinkState::Dead("播放列表嵌套过深".to_string())
+    }
+
+    /// 请求失败时的判定：服务器明确拒绝视为失效，被拦截时只有凭证已过期才视为失效，其余无法判断
+    fn failure_state(&self, target: &str, failure: retry::RetryFailure) -> LinkState {
+        let reason = format!("{}: {}", target, failure.message);
+        let auth_expired = self.auth.as_ref().is_some_and(|auth| auth.is_expired());
+        match failure.kind {
+            ScrapeErrorKind::NotFound | ScrapeErrorKind::AuthExpired => LinkState::Dead(reason),
+            ScrapeErrorKind::Blocked if auth_expired => LinkState::Dead(format!("{}（凭证已过期）", reason)),
+            _ => LinkState::Unknown(reason),
+        }
+    }
+
+    fn request(&self, method: reqwest::Method, url: &str) -> reqwest::RequestBuilder {
+        let mut req = self.client.request(method, url);
+        if let Some(ref referer) = self.referer {
+            req = req.header(reqwest::header::REFERER, referer);
+        }
+        req
+    }
+
+    /// 请求播放列表，返回内容和是否为视频文件
+    async fn fetch_playlist(&self, url: &str) -> Result<(String, bool), retry::RetryFailure> {
+        retry::run(&format!("检查 {}", url), None, move |_| async move {
+            let _permit = rate_limit::acquire(url, self.network.rate_limit.as_ref()).await;
+            let resp = self.request(reqwest::Method::GET, url).send().await.map_err(request_error)?;
+            check_status(resp.status())?;
+
+            let is_media = resp
+                .headers()
+                .get(reqwest::header::CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+                .is_some_and(|t| t.starts_with("video/") && !t.contains("mpegurl"));
+            if is_media {
+                return Ok((String::new(), true));
+            }
+            let body = resp
+                .text()
+                .await
+                .map_err(|e| (ScrapeErrorKind::Navigation, format!("读取播放列表失败: {}", e)))?;
+            Ok::<_, (ScrapeErrorKind, String)>((body, false))
+        })
+        .await
+    }
+
+    /// 确认分片可以访问：先发 HEAD，服务器不支持时改为只取第一个字节的 GET
+    async fn probe_segment(&self, url: &str) -> Result<(), retry::RetryFailure> {
+        retry::run(&format!("检查 {}", url), None, move |_| async move {
+            let _permit = rate_limit::acquire(url, self.network.rate_limit.as_ref()).await;
+            let resp = self.request(reqwest::Method::HEAD, url).send().await.map_err(request_error)?;
+            if !matches!(resp.status().as_u16(), 405 | 501) {
+                return check_status(resp.status());
+            }
+            let resp = self
+                .request(reqwest::Method::GET, url)
+                .header(reqwest::header::RANGE, "bytes=0-0")
+                .send()
+                .await
+                .map_err(request_error)?;
+            check_status(resp.status())
+        })
+        .await
+    }
+}
+
+fn request_error(e: reqwest::Error) -> (ScrapeErrorKind, String) {
+    let kind = if e.is_timeout() { ScrapeErrorKind::Timeout } else { ScrapeErrorKind::Navigation };
+    (kind, format!("请求失败: {}", e))
+}
+
+/// 检查响应状态
+fn check_status(status: reqwest::StatusCode) -> Result<(), (ScrapeErrorKind, String)> {
+    match ScrapeErrorKind::from_http_status(status.as_u16()) {
+        Some(kind) => Err((kind, format!("HTTP {}", status))),
+        None => Ok(()),
+    }
+}
+
+/// 非空白的行（去掉 BOM）
+fn playlist_lines(body: &str) -> impl Iterator<Item = &str> {
+    body.lines()
+        .map(|line| line.trim_start_matches('\u{feff}').trim())
+        .filter(|line| !line.is_empty())
+}
+
+/// 内容是否以 #EXTM3U 开头
+fn is_m3u8(body: &str) -> bool {
+    playlist_lines(body).next() == Some("#EXTM3U")
+}
+
+/// 解析播放列表，返回第一个码流或第一个分片的绝对地址
+fn parse_playlist(body: &str, base_url: &str) -> Result<PlaylistEntry, String> {
+    let mut lines = playlist_lines(body);
+    if lines.next() != Some("#EXTM3U") {
+        return Err("不是有效的 m3u8 播放列表".to_string());
+    }
+
+    let mut is_variant = false;
+    for line in lines {
+        if line.starts_with("#EXT-X-STREAM-INF") {
+            is_variant = true;
+        } else if !line.starts_with('#') {
+            let absolute = url::Url::parse(base_url)
+                .and_then(|base| base.join(line))
+                .map(|url| url.to_string())
+                .map_err(|e| format!("分片地址无效: {} ({})", line, e))?;
+            return Ok(if is_variant {
+                PlaylistEntry::Variant(absolute)
+            } else {
+                PlaylistEntry::Segment(absolute)
+            });
+        }
+    }
+    Err("播放列表中没有分片".to_string())
+}
@@ -4,6 +4,19 @@ use tauri::AppHandle;
 use tauri_plugin_shell::ShellExt;
 
 // 子模块
+pub mod auth;
+pub mod bandwidth;
+mod browser_pool;
+pub mod cancel;
+pub mod cookies;
+pub mod cover_cache;
+pub mod filename;
+pub mod link_check;
+pub mod post_process;
+pub mod verify;
+pub mod proxy;
+pub mod rate_limit;
+pub mod retry;
 mod scraper;
 mod download;
 mod transcode;
@@ -13,9 +26,14 @@ mod cast;
 pub mod sniffer;
 pub mod converter;
 
+// 重新导出浏览器池
+pub use browser_pool::BrowserPool;
+
 // 重新导出 scraper 模块的内容
 pub use scraper::{
-    Scraper,
+    fixture,
+    ScrapeContext,
+    ScraperCapability,
     ScraperFactory,
     ScraperInfo,
     get_available_scrapers,
@@ -23,14 +41,14 @@ pub use scraper::{
 
 // 重新导出 ytdlp_download 模块的内容（统一下载入口）
 pub use download::{
-    batch_download_concurrent,
     download_video,
     check_ffmpeg,
     get_video_info,
+    get_link_info,
     get_cast_stream_url,
     cancel_task,
-    get_all_tasks,
-    cleanup_tasks,
+    playlist_duration,
+    NetworkOptions,
 };
 
 // 重新导出转码模块
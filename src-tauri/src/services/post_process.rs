@@ -0,0 +1,367 @@
+//! 下载后处理
+//!
+//! 下载成功后按全局配置（网站的下载配置可以覆盖）依次执行处理步骤，
+//! 每一步的状态都记录到任务上。某一步失败不影响下载结果，后续步骤继续处理当前文件。
+//!
+//! 封面、校验和、元数据文件与视频同名、放在同一目录，移动到媒体库时一起移动。
+
+use std::path::{Path, PathBuf};
+
+use chrono::Local;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use tauri::AppHandle;
+use tokio::process::Command;
+
+use crate::models::{CollisionPolicy, PostProcessRecord, PostProcessStatus, PostProcessStep, VideoMetadata};
+use crate::services::converter;
+use crate::services::filename::{self, FilenameVars};
+
+/// 附属文件的后缀（追加在视频文件名去掉扩展名之后）
+const COVER_SUFFIX: &str = ".jpg";
+const CHECKSUM_SUFFIX: &str = ".sha256";
+const METADATA_SUFFIX: &str = ".json";
+
+/// 待处理的下载结果
+pub struct PostProcessJob {
+    /// 任务ID或视频ID（用于转换进程的注册）
+    pub task_id: String,
+    /// 下载得到的文件
+    pub file_path: PathBuf,
+    pub vars: FilenameVars,
+    /// 下载地址
+    pub source_url: String,
+    /// 爬取到的元数据（下载页添加的任务没有）
+    pub metadata: Option<VideoMetadata>,
+    /// 移动到媒体库时目标已存在的处理方式
+    pub collision_policy: CollisionPolicy,
+}
+
+/// 处理结果
+pub struct PostProcessOutcome {
+    /// 处理后的文件（可能已转换或移动）
+    pub file_path: PathBuf,
+    pub records: Vec<PostProcessRecord>,
+}
+
+/// 处理过程中的状态
+struct State {
+    file: PathBuf,
+    /// 已生成的附属文件后缀
+    companions: Vec<&'static str>,
+    checksum: Option<String>,
+}
+
+/// 校验处理步骤
+pub fn validate(steps: &[PostProcessStep]) -> Result<(), String> {
+    for step in steps {
+        match step {
+            PostProcessStep::Convert { options, .. } if options.format.trim().is_empty() => {
+                return Err("格式转换步骤未指定输出格式".to_string());
+            }
+            PostProcessStep::ExtractCover { timestamp } if *timestamp < 0.0 => {
+                return Err("封面截取时间不能为负数".to_string());
+            }
+            PostProcessStep::MoveToLibrary { dir, subdir } => {
+                if dir.trim().is_empty() {
+                    return Err("媒体库目录不能为空".to_string());
+                }
+                filename::render_dir(subdir, &FilenameVars::default(), Local::now())?;
+            }
+            PostProcessStep::RunCommand { command } if split_command(command).is_empty() => {
+                return Err("命令不能为空".to_string());
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// 依次执行处理步骤，每一步开始和结束时通过 on_update 报告所有步骤的状态
+pub async fn run(
+    app_handle: &AppHandle,
+    job: &PostProcessJob,
+    steps: &[PostProcessStep],
+    mut on_update: impl FnMut(&[PostProcessRecord]),
+) -> PostProcessOutcome {
+    let mut records: Vec<PostProcessRecord> = steps
+        .iter()
+        .map(|step| PostProcessRecord {
+            step: step.label().to_string(),
+            status: PostProcessStatus::Pending,
+            message: String::new(),
+        })
+        .collect();
+    let mut state = State {
+        file: job.file_path.clone(),
+        companions: Vec::new(),
+        checksum: None,
+    };
+
+    for (index, step) in steps.iter().enumerate() {
+        records[index].status = PostProcessStatus::Running;
+        on_update(&records);
+
+        let result = run_step(app_handle, job, step, &mut state).await;
+        let record = &mut records[index];
+        match result {
+            Ok(message) => {
+                tracing::info!("[post_process] {} 完成: {}", record.step, message);
+                record.status = PostProcessStatus::Completed;
+                record.message = message;
+            }
+            Err(e) => {
+                tracing::warn!("[post_process] {} 失败: {} - {}", record.step, state.file.display(), e);
+                record.status = PostProcessStatus::Failed;
+                record.message = e;
+            }
+        }
+        on_update(&records);
+    }
+
+    PostProcessOutcome {
+        file_path: state.file,
+        records,
+    }
+}
+
+async fn run_step(
+    app_handle: &AppHandle,
+    job: &PostProcessJob,
+    step: &PostProcessStep,
+    state: &mut State,
+) -> Result<String, String> {
+    if !state.file.is_file() {
+        return Err(format!("文件不存在: {}", state.file.display()));
+    }
+    match step {
+        PostProcessStep::Convert { options, keep_original } => {
+            let input = state.file.to_string_lossy().to_string();
+            let target = state.file.with_extension(&options.format);
+            let output = if target == state.file {
+                converter::generate_output_path(&input, &options.format)
+            } else {
+                target.to_string_lossy().to_string()
+            };
+            let output = converter::convert_video(
+                app_handle,
+                &format!("post-{}", job.task_id),
+                &input,
+                &output,
+                options,
+                |_, _| {},
+            )
+            .await?;
+            if !keep_original {
+                if let Err(e) = std::fs::remove_file(&state.file) {
+                    tracing::warn!("[post_process] 删除原文件失败: {} - {}", state.file.display(), e);
+                }
+            }
+            state.file = PathBuf::from(&output);
+            // 已生成的校验和对应的是原文件
+            state.checksum = None;
+            Ok(output)
+        }
+        PostProcessStep::ExtractCover { timestamp } => {
+            let cover = companion(&state.file, COVER_SUFFIX);
+            let cover = converter::screenshot_video_frame(
+                app_handle,
+                &state.file.to_string_lossy(),
+                *timestamp,
+                Some(cover.to_string_lossy().to_string()),
+            )
+            .await?;
+            add_companion(state, COVER_SUFFIX);
+            Ok(cover)
+        }
+        PostProcessStep::Checksum => {
+            let file = state.file.clone();
+            let hash = tokio::task::spawn_blocking(move || sha256_file(&file))
+                .await
+                .map_err(|e| e.to_string())??;
+            write_checksum(&state.file, &hash)?;
+            add_companion(state, CHECKSUM_SUFFIX);
+            state.checksum = Some(hash.clone());
+            Ok(hash)
+        }
+        PostProcessStep::MoveToLibrary { dir, subdir } => {
+            let target_dir = Path::new(dir.trim()).join(filename::render_dir(subdir, &job.vars, Local::now())?);
+            let file_name = state.file.file_name().ok_or("文件名无效")?;
+            let target = target_dir.join(file_name);
+            if target == state.file {
+                return Ok(target.to_string_lossy().to_string());
+            }
+            let target = filename::resolve_collision(&target, job.collision_policy)
+                .ok_or_else(|| format!("媒体库中已存在同名文件，未移动: {}", target.display()))?;
+            std::fs::create_dir_all(&target_dir).map_err(|e| format!("创建目录失败: {}", e))?;
+            move_file(&state.file, &target)?;
+            for suffix in &state.companions {
+                let from = companion(&state.file, suffix);
+                if from.exists() {
+                    if let Err(e) = move_file(&from, &companion(&target, suffix)) {
+                        tracing::warn!("[post_process] 移动附属文件失败: {} - {}", from.display(), e);
+                    }
+                }
+            }
+            state.file = target;
+            // 文件名可能因编号而改变，校验和文件中的文件名需要更新
+            if let Some(ref hash) = state.checksum {
+                write_checksum(&state.file, hash)?;
+            }
+            Ok(state.file.to_string_lossy().to_string())
+        }
+        PostProcessStep::WriteMetadata => {
+            let path = companion(&state.file, METADATA_SUFFIX);
+            let file_size = std::fs::metadata(&state.file).map(|m| m.len()).unwrap_or(0);
+            let metadata = json!({
+                "id": job.vars.id,
+                "title": job.vars.title,
+                "website": job.vars.website,
+                "scrape_id": job.vars.scrape_id,
+                "source_url": job.source_url,
+                "file_name": state.file.file_name().map(|name| name.to_string_lossy().to_string()),
+                "file_size": file_size,
+                "sha256": state.checksum,
+                "downloaded_at": chrono::Utc::now().to_rfc3339(),
+                "metadata": job.metadata,
+            });
+            let content = serde_json::to_string_pretty(&metadata).map_err(|e| e.to_string())?;
+            std::fs::write(&path, content).map_err(|e| format!("写入元数据失败: {}", e))?;
+            add_companion(state, METADATA_SUFFIX);
+            Ok(path.to_string_lossy().to_string())
+        }
+        PostProcessStep::RunCommand { command } => run_command(command, job, &state.file).await,
+    }
+}
+
+/// 与视频同名的附属文件
+fn companion(file: &Path, suffix: &str) -> PathBuf {
+    let stem = file.file_stem().unwrap_or_default().to_string_lossy();
+    file.with_file_name(format!("{}{}", stem, suffix))
+}
+
+fn add_companion(state: &mut State, suffix: &'static str) {
+    if !state.companions.contains(&suffix) {
+        state.companions.push(suffix);
+    }
+}
+
+fn sha256_file(path: &Path) -> Result<String, String> {
+    let mut file = std::fs::File::open(path).map_err(|e| format!("打开文件失败: {}", e))?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher).map_err(|e| format!("读取文件失败: {}", e))?;
+    Ok(hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// 按 sha256sum 的格式写入校验和文件
+fn write_checksum(file: &Path, hash: &str) -> Result<(), String> {
+    let name = file.file_name().unwrap_or_default().to_string_lossy();
+    std::fs::write(companion(file, CHECKSUM_SUFFIX), format!("{}  {}\n", hash, name))
+        .map_err(|e| format!("写入校验和失败: {}", e))
+}
+
+/// 移动文件，跨分区时复制后删除
+fn move_file(from: &Path, to: &Path) -> Result<(), String> {
+    if std::fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    std::fs::copy(from, to).map_err(|e| format!("移动文件失败: {}", e))?;
+    std::fs::remove_file(from).map_err(|e| format!("删除原文件失败: {}", e))
+}
+
+/// 拆分命令行参数，支持用双引号包含空格
+fn split_command(command: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    let mut has_arg = false;
+    for c in command.chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                has_arg = true;
+            }
+            c if c.is_whitespace() && !quoted => {
+                if has_arg {
+                    args.push(std::mem::take(&mut current));
+                    has_arg = false;
+                }
+            }
+            c => {
+                current.push(c);
+                has_arg = true;
+            }
+        }
+    }
+    if has_arg {
+        args.push(current);
+    }
+    args
+}
+
+/// 执行用户配置的命令，参数拆分后再替换变量，路径中的空格不会拆开参数
+async fn run_command(command: &str, job: &PostProcessJob, file: &Path) -> Result<String, String> {
+    let dir = file.parent().unwrap_or(Path::new("."));
+    let args: Vec<String> = split_command(command)
+        .into_iter()
+        .map(|arg| {
+            arg.replace("{file}", &file.to_string_lossy())
+                .replace("{dir}", &dir.to_string_lossy())
+                .replace("{title}", &job.vars.title)
+                .replace("{website}", &job.vars.website)
+                .replace("{scrape_id}", &job.vars.scrape_id)
+                .replace("{id}", &job.vars.id)
+        })
+        .collect();
+    let (program, args) = args.split_first().ok_or("命令不能为空")?;
+
+    tracing::info!("[post_process] 执行命令: {} {}", program, args.join(" "));
+    let output = Command::new(program)
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .await
+        .map_err(|e| format!("启动命令失败: {}", e))?;
+
+    if output.status.success() {
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        Ok(stdout.lines().last().unwrap_or("执行成功").trim().to_string())
+    } else {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        Err(format!(
+            "命令退出码 {}: {}",
+            output.status.code().map(|c| c.to_string()).unwrap_or_else(|| "未知".to_string()),
+            stderr.lines().last().unwrap_or_default().trim()
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_command_splits_on_whitespace() {
+        assert_eq!(split_command("ffmpeg  -i {file}\t-y"), ["ffmpeg", "-i", "{file}", "-y"]);
+        assert!(split_command("   ").is_empty());
+    }
+
+    #[test]
+    fn split_command_keeps_quoted_spaces() {
+        assert_eq!(
+            split_command(r#"cp "{file}" "/mnt/My Videos/{title}.mp4""#),
+            ["cp", "{file}", "/mnt/My Videos/{title}.mp4"]
+        );
+        assert_eq!(split_command(r#"echo --name="a b"c"#), ["echo", "--name=a bc"]);
+    }
+
+    #[test]
+    fn split_command_keeps_empty_quoted_argument() {
+        assert_eq!(split_command(r#"cmd "" x"#), ["cmd", "", "x"]);
+    }
+
+    #[test]
+    fn split_command_unclosed_quote_runs_to_end() {
+        assert_eq!(split_command(r#"cmd "a b"#), ["cmd", "a b"]);
+    }
+}
@@ -0,0 +1,142 @@
+//! 代理
+//!
+//! 全局代理保存在 AppConfig 中，网站可以单独指定（包括指定直连）。
+//! 解析出的代理统一应用到 reqwest 客户端、headless Chrome 和 yt-dlp。
+//! Chrome 的 --proxy-server 不支持用户名密码，因此不接受带认证信息的代理；
+//! 写入日志或错误信息的代理地址都会隐去认证信息。
+
+use once_cell::sync::Lazy;
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+
+use crate::models::ProxyConfig;
+
+/// 支持的代理协议
+const SUPPORTED_SCHEMES: &[&str] = &["http", "https", "socks4", "socks5", "socks5h"];
+
+/// 检查代理端口的连接超时
+const ENDPOINT_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 全局默认代理（启动时和保存应用配置时更新）
+static DEFAULT_PROXY: Lazy<RwLock<ProxyConfig>> = Lazy::new(|| RwLock::new(ProxyConfig::default()));
+
+/// 设置全局默认代理
+pub fn set_default_proxy(proxy: ProxyConfig) {
+    *DEFAULT_PROXY.write().unwrap() = proxy;
+}
+
+/// 解析实际使用的代理：指定了代理时使用指定的，否则使用全局默认代理
+pub fn resolve(proxy: Option<&ProxyConfig>) -> ProxyConfig {
+    match proxy {
+        Some(proxy) => proxy.clone(),
+        None => DEFAULT_PROXY.read().unwrap().clone(),
+    }
+}
+
+/// 校验代理地址（为空表示直连，视为有效）
+pub fn validate(proxy: &ProxyConfig) -> Result<(), String> {
+    if proxy.url.is_empty() {
+        return Ok(());
+    }
+    let parsed = url::Url::parse(&proxy.url).map_err(|e| format!("代理地址无效: {}", e))?;
+    if !SUPPORTED_SCHEMES.contains(&parsed.scheme()) {
+        return Err(format!("不支持的代理协议: {}", parsed.scheme()));
+    }
+    if parsed.host_str().is_none() || parsed.port_or_known_default().is_none() {
+        return Err(format!("代理地址缺少主机或端口: {}", redact(&proxy.url)));
+    }
+    if has_credentials(&parsed) {
+        return Err("不支持带用户名密码的代理（Chrome 会忽略代理认证信息）".to_string());
+    }
+    Ok(())
+}
+
+/// 地址中是否带有用户名或密码
+fn has_credentials(url: &url::Url) -> bool {
+    !url.username().is_empty() || url.password().is_some()
+}
+
+/// 隐去地址中的用户名和密码，用于日志和错误信息（不是 URL 或不带认证信息时原样返回）
+pub fn redact(url: &str) -> String {
+    match url::Url::parse(url) {
+        Ok(mut parsed) if has_credentials(&parsed) => {
+            let _ = parsed.set_password(None);
+            let _ = parsed.set_username("***");
+            parsed.to_string()
+        }
+        _ => url.to_string(),
+    }
+}
+
+/// 代理服务器的 host:port（直连或地址无效时返回 None）
+fn endpoint(proxy: &ProxyConfig) -> Option<String> {
+    let parsed = url::Url::parse(&proxy.url).ok()?;
+    Some(format!("{}:{}", parsed.host_str()?, parsed.port_or_known_default()?))
+}
+
+/// 给 reqwest 客户端设置代理（地址无效时不设置）
+pub fn apply_to_client(builder: reqwest::ClientBuilder, proxy: &ProxyConfig) -> reqwest::ClientBuilder {
+    if proxy.url.is_empty() {
+        return builder;
+    }
+    match reqwest::Proxy::all(&proxy.url) {
+        Ok(p) => builder.proxy(p.no_proxy(reqwest::NoProxy::from_string(&proxy.no_proxy))),
+        Err(e) => {
+            tracing::warn!("[proxy] 代理地址无效，忽略: {} ({})", redact(&proxy.url), e);
+            builder
+        }
+    }
+}
+
+/// 生成 yt-dlp 的代理参数（yt-dlp 不支持排除列表）
+pub fn ytdlp_args(proxy: &ProxyConfig) -> Vec<String> {
+    if proxy.url.is_empty() {
+        return Vec::new();
+    }
+    vec!["--proxy".to_string(), proxy.url.clone()]
+}
+
+/// Chrome 的代理服务器参数值，直连时返回 "direct://"
+pub fn chrome_proxy_server(proxy: &ProxyConfig) -> String {
+    if proxy.url.is_empty() {
+        return "direct://".to_string();
+    }
+    let Ok(parsed) = url::Url::parse(&proxy.url) else {
+        return proxy.url.clone();
+    };
+    // Chrome 不识别 socks5h，SOCKS5 代理本身就会在代理端解析域名
+    let scheme = match parsed.scheme() {
+        "socks5h" => "socks5",
+        scheme => scheme,
+    };
+    // 只保留主机和端口：加入校验之前保存的代理可能带有 Chrome 不支持的认证信息
+    let host = parsed.host_str().unwrap_or_default();
+    let port = parsed.port_or_known_default().unwrap_or_default();
+    format!("{}://{}:{}", scheme, host, port)
+}
+
+/// Chrome 的代理排除列表（分号分隔）
+pub fn chrome_bypass_list(proxy: &ProxyConfig) -> Option<String> {
+    let hosts: Vec<&str> = proxy
+        .no_proxy
+        .split(',')
+        .map(|h| h.trim())
+        .filter(|h| !h.is_empty())
+        .collect();
+    if hosts.is_empty() {
+        None
+    } else {
+        Some(hosts.join(";"))
+    }
+}
+
+/// 检查代理服务器端口能否连通，返回连接耗时
+pub async fn check_endpoint(proxy: &ProxyConfig) -> Result<Duration, String> {
+    let endpoint = endpoint(proxy).ok_or_else(|| format!("代理地址无效: {}", redact(&proxy.url)))?;
+    let started = Instant::now();
+    match tokio::time::timeout(ENDPOINT_CHECK_TIMEOUT, tokio::net::TcpStream::connect(&endpoint)).await {
+        Ok(Ok(_)) => Ok(started.elapsed()),
+        Ok(Err(e)) => Err(format!("无法连接代理 {}: {}", endpoint, e)),
+        Err(_) => Err(format!("连接代理 {} 超时", endpoint)),
+    }
+}
@@ -0,0 +1,184 @@
+//! 按主机限速
+//!
+//! 所有对外请求（爬虫导航和详情页、嗅探、HLS 代理、下载）在发出前都向这里申请许可：
+//! 同一主机的请求按每秒请求数排队，并限制同时连接数。
+//! 限速配置来自网站，带网站上下文的调用会把配置登记到目标主机（包括 CDN 主机），
+//! 没有网站上下文的调用（嗅探、HLS 代理）沿用该主机已登记的配置，未登记的主机不限速。
+//! 多个网站共用同一主机（如 CDN）时，每个主机只有一份状态，限速取所有登记配置中最严格的值
+//! （放宽配置在重启后生效）。
+
+use once_cell::sync::Lazy;
+use rand::Rng;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::models::{RateLimitConfig, Website};
+
+/// 各主机的限速状态
+static HOSTS: Lazy<Mutex<HashMap<String, HostState>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+struct HostState {
+    /// 每秒最多请求数，0 表示不限
+    max_requests_per_sec: f64,
+    /// 最多同时连接数，0 表示不限
+    max_concurrent: u32,
+    /// 连接数限制，None 表示不限
+    connections: Option<Arc<Semaphore>>,
+    /// 下一个请求最早可以发出的时间
+    next_slot: Instant,
+}
+
+impl HostState {
+    fn new(config: &RateLimitConfig) -> Self {
+        let connections = (config.max_concurrent > 0)
+            .then(|| Arc::new(Semaphore::new(config.max_concurrent as usize)));
+        Self {
+            max_requests_per_sec: config.max_requests_per_sec,
+            max_concurrent: config.max_concurrent,
+            connections,
+            next_slot: Instant::now(),
+        }
+    }
+
+    /// 合并配置，各项取更严格的值；连接数收紧时从现有信号量中收回名额，已借出的连接仍然计数
+    fn merge(&mut self, config: &RateLimitConfig) {
+        self.max_requests_per_sec = stricter(self.max_requests_per_sec, config.max_requests_per_sec);
+
+        let max_concurrent = stricter(self.max_concurrent, config.max_concurrent);
+        if max_concurrent == self.max_concurrent {
+            return;
+        }
+        match &self.connections {
+            Some(semaphore) => {
+                let excess = (self.max_concurrent - max_concurrent) as usize;
+                let forgotten = semaphore.forget_permits(excess);
+                if forgotten < excess {
+                    // 其余名额正在使用，归还后收回
+                    let semaphore = Arc::clone(semaphore);
+                    let remaining = (excess - forgotten) as u32;
+                    tauri::async_runtime::spawn(async move {
+                        if let Ok(permits) = semaphore.acquire_many_owned(remaining).await {
+                            permits.forget();
+                        }
+                    });
+                }
+            }
+            None => self.connections = Some(Arc::new(Semaphore::new(max_concurrent as usize))),
+        }
+        self.max_concurrent = max_concurrent;
+    }
+}
+
+/// 两个限制中更严格的一个（0 表示不限）
+fn stricter<T: PartialOrd + Default + Copy>(a: T, b: T) -> T {
+    let unlimited = T::default();
+    if a == unlimited {
+        b
+    } else if b == unlimited || a <= b {
+        a
+    } else {
+        b
+    }
+}
+
+/// 主机许可，Drop 时归还连接名额
+pub struct HostPermit {
+    _connection: Option<OwnedSemaphorePermit>,
+}
+
+/// 提取 URL 的主机名
+fn host_of(url: &str) -> Option<String> {
+    url::Url::parse(url).ok()?.host_str().map(|h| h.to_lowercase())
+}
+
+/// 登记主机的限速配置（已有状态时合并为更严格的配置）
+fn configure(host: &str, config: &RateLimitConfig) {
+    let mut hosts = HOSTS.lock().unwrap();
+    match hosts.get_mut(host) {
+        Some(state) => state.merge(config),
+        None => {
+            hosts.insert(host.to_string(), HostState::new(config));
+        }
+    }
+}
+
+/// 登记网站主机的限速配置（启动和保存网站时调用）
+pub fn register_website(website: &Website) {
+    if let Some(host) = host_of(&website.base_url) {
+        configure(&host, &website.rate_limit);
+    }
+}
+
+/// 校验限速配置
+pub fn validate(config: &RateLimitConfig) -> Result<(), String> {
+    if !config.max_requests_per_sec.is_finite() || config.max_requests_per_sec < 0.0 {
+        return Err(format!("每秒请求数无效: {}", config.max_requests_per_sec));
+    }
+    if config.max_requests_per_sec > 0.0 && config.max_requests_per_sec < 0.001 {
+        return Err("每秒请求数不能小于 0.001".to_string());
+    }
+    Ok(())
+}
+
+/// 预约一个请求时间片，返回时间片开始时间和连接数信号量
+fn reserve(url: &str, config: Option<&RateLimitConfig>) -> (Instant, Option<Arc<Semaphore>>) {
+    let now = Instant::now();
+    let Some(host) = host_of(url) else {
+        return (now, None);
+    };
+    if let Some(config) = config {
+        configure(&host, config);
+    }
+
+    let mut hosts = HOSTS.lock().unwrap();
+    let Some(state) = hosts.get_mut(&host) else {
+        return (now, None);
+    };
+
+    let slot = state.next_slot.max(now);
+    if state.max_requests_per_sec > 0.0 {
+        state.next_slot = slot + Duration::from_secs_f64(1.0 / state.max_requests_per_sec);
+    }
+    (slot, state.connections.clone())
+}
+
+/// 申请访问 URL 的许可：等待限速时间片和连接名额
+///
+/// config 为网站的限速配置（会登记到 URL 的主机），为 None 时使用主机已登记的配置
+pub async fn acquire(url: &str, config: Option<&RateLimitConfig>) -> HostPermit {
+    let (slot, connections) = reserve(url, config);
+    let connection = match connections {
+        Some(semaphore) => semaphore.acquire_owned().await.ok(),
+        None => None,
+    };
+    tokio::time::sleep_until(slot.into()).await;
+    HostPermit { _connection: connection }
+}
+
+/// 同步版本的 acquire，供 spawn_blocking 中的代码使用
+pub fn acquire_blocking(url: &str, config: Option<&RateLimitConfig>) -> HostPermit {
+    let (slot, connections) = reserve(url, config);
+    let connection = connections.and_then(|semaphore| {
+        tokio::runtime::Handle::current()
+            .block_on(semaphore.acquire_owned())
+            .ok()
+    });
+    std::thread::sleep(slot.saturating_duration_since(Instant::now()));
+    HostPermit { _connection: connection }
+}
+
+/// 详情页抓取之间的等待时长（最小间隔加随机抖动）
+pub fn detail_delay(config: &RateLimitConfig) -> Duration {
+    Duration::from_millis(config.detail_delay_ms) + jitter(Duration::from_millis(config.detail_jitter_ms))
+}
+
+/// 0 到 max 之间的随机时长
+pub fn jitter(max: Duration) -> Duration {
+    let max_ms = max.as_millis() as u64;
+    if max_ms == 0 {
+        return Duration::ZERO;
+    }
+    Duration::from_millis(rand::thread_rng().gen_range(0..=max_ms))
+}
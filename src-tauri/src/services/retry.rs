@@ -0,0 +1,228 @@
+//! 重试策略
+//!
+//! 导航、详情页请求、yt-dlp 下载和 DLNA SOAP 动作共用同一套重试策略：
+//! 按失败类型判断能否重试，失败后指数退避并附加随机抖动，每次失败都记录日志。
+//! 策略保存在 AppConfig 中，启动时和保存应用配置时更新。
+
+use once_cell::sync::Lazy;
+use std::future::Future;
+use std::sync::RwLock;
+use std::time::Duration;
+
+use crate::models::{RetryPolicy, ScrapeErrorKind};
+use crate::services::cancel::CancelToken;
+use crate::services::rate_limit;
+
+/// 全局重试策略
+static DEFAULT_POLICY: Lazy<RwLock<RetryPolicy>> = Lazy::new(|| RwLock::new(RetryPolicy::default()));
+
+/// 重试后仍然失败
+#[derive(Debug, Clone)]
+pub struct RetryFailure {
+    /// 最后一次失败的类型，等待重试时被取消为 Cancelled
+    pub kind: ScrapeErrorKind,
+    pub message: String,
+    /// 实际尝试次数
+    pub attempts: u32,
+}
+
+/// 设置全局重试策略
+pub fn set_default_policy(policy: RetryPolicy) {
+    *DEFAULT_POLICY.write().unwrap() = policy;
+}
+
+/// 当前的全局重试策略
+pub fn policy() -> RetryPolicy {
+    DEFAULT_POLICY.read().unwrap().clone()
+}
+
+/// 校验重试策略
+pub fn validate(policy: &RetryPolicy) -> Result<(), String> {
+    if policy.max_attempts == 0 {
+        return Err("最多尝试次数不能为 0".to_string());
+    }
+    if !policy.multiplier.is_finite() || policy.multiplier < 1.0 {
+        return Err(format!("退避倍数必须不小于 1: {}", policy.multiplier));
+    }
+    Ok(())
+}
+
+/// 第 attempt 次尝试失败后的等待时长（指数退避，不超过上限，再加随机抖动）
+pub fn backoff(policy: &RetryPolicy, attempt: u32) -> Duration {
+    let exponent = attempt.saturating_sub(1).min(i32::MAX as u32) as i32;
+    let delay_ms = (policy.initial_delay_ms as f64 * policy.multiplier.powi(exponent))
+        .min(policy.max_delay_ms as f64);
+    Duration::from_millis(delay_ms as u64) + rate_limit::jitter(Duration::from_millis(policy.jitter_ms))
+}
+
+/// 第 attempt 次尝试失败后决定是否重试并记录日志，返回重试前的等待时长，不再重试时返回 None
+pub fn next_delay(
+    policy: &RetryPolicy,
+    label: &str,
+    attempt: u32,
+    kind: ScrapeErrorKind,
+    message: &str,
+) -> Option<Duration> {
+    if kind == ScrapeErrorKind::Cancelled || !policy.retry_on.contains(&kind) {
+        tracing::warn!("[retry] {} 失败（{:?}，不重试）: {}", label, kind, message);
+        return None;
+    }
+    if attempt >= policy.max_attempts {
+        tracing::warn!(
+            "[retry] {} 第 {}/{} 次尝试失败（{:?}），放弃: {}",
+            label, attempt, policy.max_attempts, kind, message
+        );
+        return None;
+    }
+    let delay = backoff(policy, attempt);
+    tracing::warn!(
+        "[retry] {} 第 {}/{} 次尝试失败（{:?}），{}ms 后重试: {}",
+        label, attempt, policy.max_attempts, kind, delay.as_millis(), message
+    );
+    Some(delay)
+}
+
+/// 按全局重试策略执行异步操作，op 的参数为第几次尝试（从 1 开始）
+///
+/// 传入取消令牌时，等待重试期间可以被取消
+pub async fn run<T, F, Fut>(label: &str, cancel: Option<&CancelToken>, mut op: F) -> Result<T, RetryFailure>
+where
+    F: FnMut(u32) -> Fut,
+    Fut: Future<Output = Result<T, (ScrapeErrorKind, String)>>,
+{
+    let policy = policy();
+    let mut attempt = 1;
+    loop {
+        let (kind, message) = match op(attempt).await {
+            Ok(value) => {
+                if attempt > 1 {
+                    tracing::info!("[retry] {} 第 {} 次尝试成功", label, attempt);
+                }
+                return Ok(value);
+            }
+            Err(e) => e,
+        };
+        let Some(delay) = next_delay(&policy, label, attempt, kind, &message) else {
+            return Err(RetryFailure { kind, message, attempts: attempt });
+        };
+        let completed = match cancel {
+            Some(cancel) => cancel.sleep(delay).await,
+            None => {
+                tokio::time::sleep(delay).await;
+                true
+            }
+        };
+        if !completed {
+            return Err(cancelled(attempt));
+        }
+        attempt += 1;
+    }
+}
+
+/// 同步版本的 run，供 spawn_blocking 中的代码使用
+pub fn run_blocking<T>(
+    label: &str,
+    cancel: &CancelToken,
+    mut op: impl FnMut(u32) -> Result<T, (ScrapeErrorKind, String)>,
+) -> Result<T, RetryFailure> {
+    let policy = policy();
+    let mut attempt = 1;
+    loop {
+        let (kind, message) = match op(attempt) {
+            Ok(value) => {
+                if attempt > 1 {
+                    tracing::info!("[retry] {} 第 {} 次尝试成功", label, attempt);
+                }
+                return Ok(value);
+            }
+            Err(e) => e,
+        };
+        let Some(delay) = next_delay(&policy, label, attempt, kind, &message) else {
+            return Err(RetryFailure { kind, message, attempts: attempt });
+        };
+        if !cancel.sleep_blocking(delay) {
+            return Err(cancelled(attempt));
+        }
+        attempt += 1;
+    }
+}
+
+fn cancelled(attempts: u32) -> RetryFailure {
+    RetryFailure {
+        kind: ScrapeErrorKind::Cancelled,
+        message: "已取消".to_string(),
+        attempts,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy_without_jitter() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 10,
+            initial_delay_ms: 1000,
+            max_delay_ms: 5000,
+            multiplier: 2.0,
+            jitter_ms: 0,
+            ..RetryPolicy::default()
+        }
+    }
+
+    #[test]
+    fn backoff_grows_exponentially_up_to_cap() {
+        let policy = policy_without_jitter();
+        let delays: Vec<u64> = (1..=5).map(|attempt| backoff(&policy, attempt).as_millis() as u64).collect();
+        assert_eq!(delays, [1000, 2000, 4000, 5000, 5000]);
+        // 次数很大时不会溢出
+        assert_eq!(backoff(&policy, u32::MAX), Duration::from_millis(5000));
+    }
+
+    #[test]
+    fn backoff_adds_bounded_jitter() {
+        let policy = RetryPolicy { jitter_ms: 300, ..policy_without_jitter() };
+        for _ in 0..50 {
+            let delay = backoff(&policy, 1);
+            assert!(delay >= Duration::from_millis(1000) && delay <= Duration::from_millis(1300));
+        }
+    }
+
+    #[test]
+    fn next_delay_stops_on_non_retryable_failures() {
+        let policy = policy_without_jitter();
+        assert_eq!(next_delay(&policy, "测试", 1, ScrapeErrorKind::Cancelled, "已取消"), None);
+        assert_eq!(next_delay(&policy, "测试", 1, ScrapeErrorKind::ParseFailed, "解析失败"), None);
+    }
+
+    #[test]
+    fn next_delay_stops_after_max_attempts() {
+        let policy = RetryPolicy { max_attempts: 3, ..policy_without_jitter() };
+        assert_eq!(
+            next_delay(&policy, "测试", 1, ScrapeErrorKind::Timeout, "超时"),
+            Some(Duration::from_millis(1000))
+        );
+        assert_eq!(
+            next_delay(&policy, "测试", 2, ScrapeErrorKind::Timeout, "超时"),
+            Some(Duration::from_millis(2000))
+        );
+        assert_eq!(next_delay(&policy, "测试", 3, ScrapeErrorKind::Timeout, "超时"), None);
+    }
+
+    #[test]
+    fn next_delay_is_capped() {
+        let policy = policy_without_jitter();
+        assert_eq!(
+            next_delay(&policy, "测试", 8, ScrapeErrorKind::Navigation, "连接失败"),
+            Some(Duration::from_millis(5000))
+        );
+    }
+
+    #[test]
+    fn validate_rejects_invalid_policies() {
+        assert!(validate(&RetryPolicy::default()).is_ok());
+        assert!(validate(&RetryPolicy { max_attempts: 0, ..RetryPolicy::default() }).is_err());
+        assert!(validate(&RetryPolicy { multiplier: 0.5, ..RetryPolicy::default() }).is_err());
+        assert!(validate(&RetryPolicy { multiplier: f64::NAN, ..RetryPolicy::default() }).is_err());
+    }
+}
@@ -1,18 +1,20 @@
-use crate::models::{LocalStorageItem, ScrapeResult, Website};
-use crate::services::scraper::Scraper;
-use headless_chrome::Browser;
-use std::ffi::OsStr;
-use std::pin::Pin;
+use crate::models::{LocalStorageItem, ProxyConfig, RateLimitConfig, ScrapeErrorKind, ScrapeResult, VideoMetadata, Website};
+use crate::services::auth::AuthProvider;
+use crate::services::scraper::fixture::ScrapeFixture;
+use crate::services::scraper::{metadata, ScrapeContext, ScrapeFuture, Scraper};
+use crate::services::{proxy, rate_limit, BrowserPool};
+use regex::Regex;
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
-use std::future::Future;
-use url::Url;
 
 /// D1 Cloudfront 爬虫 - 专门爬取 d1ibyof3mbdf0n.cloudfront.net
 #[derive(Clone)]
 pub struct D1Spider {
     base_url: String,
     local_storage: Vec<LocalStorageItem>,
+    auth: AuthProvider,
+    proxy: ProxyConfig,
+    rate_limit: RateLimitConfig,
 }
 
 impl D1Spider {
@@ -20,68 +22,9 @@ impl D1Spider {
         Self {
             base_url: website.base_url.clone(),
             local_storage: website.local_storage.clone(),
-        }
-    }
-
-    #[allow(dead_code)]
-    /// 从 localStorage 中获取 token 值
-    pub fn get_token_from_local_storage(&self) -> Option<String> {
-        self.local_storage
-            .iter()
-            .find(|item| item.key == "token")
-            .map(|item| item.value.clone())
-    }
-
-    #[allow(dead_code)]
-    /// 从 URL 中提取 token 参数
-    pub fn extract_url_token(url: &str) -> Option<String> {
-        if let Ok(parsed) = Url::parse(url) {
-            if let Some(query) = parsed.query() {
-                for param in query.split('&') {
-                    if param.starts_with("token=") {
-                        return Some(param[6..].to_string());
-                    }
-                }
-            }
-        }
-        None
-    }
-
-    #[allow(dead_code)]
-    /// 更新 URL 中的 token
-    /// 如果 URL 中的 token 与新 token 不同，则替换
-    pub fn update_url_token(url: &str, new_token: &str) -> String {
-        if let Ok(parsed) = Url::parse(url) {
-            if let Some(query) = parsed.query() {
-                // 检查 URL 中是否已有 token 参数
-                let params: Vec<&str> = query.split('&').collect();
-                let mut has_token = false;
-                let mut new_params: Vec<String> = Vec::new();
-
-                for param in params {
-                    if param.starts_with("token=") {
-                        has_token = true;
-                        new_params.push(format!("token={}", new_token));
-                    } else {
-                        new_params.push(param.to_string());
-                    }
-                }
-
-                if has_token {
-                    // 重建 URL
-                    let mut new_url = parsed;
-                    new_url.set_query(Some(&new_params.join("&")));
-                    new_url.to_string()
-                } else {
-                    // 没有 token 参数，直接返回原 URL
-                    url.to_string()
-                }
-            } else {
-                // 没有查询参数，返回原 URL
-                url.to_string()
-            }
-        } else {
-            url.to_string()
+            auth: AuthProvider::for_website(website),
+            proxy: proxy::resolve(website.proxy.as_ref()),
+            rate_limit: website.rate_limit.clone(),
         }
     }
 }
@@ -103,6 +46,97 @@ fn parse_count(count_str: &str) -> Option<i64> {
     cleaned.parse::<i64>().ok()
 }
 
+/// 是否为播放页请求 m3u8 的接口
+fn is_m3u8_api(url: &str) -> bool {
+    url.contains(".m3u8") && url.contains("/api/app/media/h5/m3u8/")
+}
+
+/// 去掉 m3u8 地址中的分片后缀 _0001
+fn clean_m3u8_url(url: &str) -> String {
+    url.replace("_0001", "")
+}
+
+/// 从播放页 HTML 中提取视频标题
+fn extract_title(html: &str) -> Option<String> {
+    let title_pattern = Regex::new(r#"(?s)<div[^>]*class="video-title"[^>]*>(.*?)</div>"#).unwrap();
+    let tag_pattern = Regex::new(r#"<[^>]+>"#).unwrap();
+    let cap = title_pattern.captures(html)?;
+    let title = tag_pattern.replace_all(cap.get(1)?.as_str(), " ");
+    let title = title.split_whitespace().collect::<Vec<_>>().join(" ");
+    (!title.is_empty()).then_some(title)
+}
+
+/// m3u8 接口失败时的提示和错误类型（status 为空表示一直没有请求）
+fn m3u8_failure(status: Option<u16>) -> (String, ScrapeErrorKind) {
+    match status {
+        // 该接口用 401/403 表示 token 无效
+        Some(code @ (401 | 403)) => (
+            format!("m3u8 请求被拒绝 (HTTP {})，token 可能已过期", code),
+            ScrapeErrorKind::AuthExpired,
+        ),
+        Some(code) => (
+            format!("m3u8 请求失败: HTTP {}", code),
+            ScrapeErrorKind::from_http_status(code).unwrap_or(ScrapeErrorKind::Navigation),
+        ),
+        None => ("未能找到 m3u8 地址".to_string(), ScrapeErrorKind::Timeout),
+    }
+}
+
+/// 用录制的播放页和网络响应回放一次爬取
+fn replay(
+    fixture: &ScrapeFixture,
+    auth: &AuthProvider,
+    page_url: &str,
+    video_id: &str,
+    ctx: &ScrapeContext,
+) -> ScrapeResult {
+    let failed = |message: String, kind: ScrapeErrorKind| ScrapeResult {
+        success: false,
+        name: String::new(),
+        m3u8_url: String::new(),
+        message,
+        video_id: Some(video_id.to_string()),
+        view_count: None,
+        favorite_count: None,
+        cover_url: None,
+        error_kind: Some(kind),
+        metadata: VideoMetadata::default(),
+    };
+
+    let html = fixture.page(page_url).unwrap_or_default();
+
+    // 与实时爬取一致：按响应顺序，第一个 m3u8 接口响应决定结果
+    let captured = fixture.responses.iter().find(|r| is_m3u8_api(&r.url));
+    let m3u8_url = match captured {
+        Some(response) if response.status < 400 => response.url.clone(),
+        other => {
+            if html.contains("资源不存在") && html.contains("404") {
+                return failed(
+                    "资源不存在，该视频可能已被删除或ID无效".to_string(),
+                    ScrapeErrorKind::NotFound,
+                );
+            }
+            let (message, kind) = m3u8_failure(other.map(|r| r.status));
+            return failed(message, kind);
+        }
+    };
+    ctx.log(format!("[回放] 捕获到m3u8: {}", m3u8_url));
+
+    let name = extract_title(html).unwrap_or_else(|| format!("视频_{}", video_id));
+    ScrapeResult {
+        success: true,
+        name,
+        m3u8_url: auth.apply_to_url(&clean_m3u8_url(&m3u8_url)),
+        message: "成功找到 m3u8 地址".to_string(),
+        video_id: Some(video_id.to_string()),
+        view_count: None,
+        favorite_count: None,
+        cover_url: None,
+        error_kind: None,
+        metadata: metadata::from_html_meta(html),
+    }
+}
+
 impl Scraper for D1Spider {
     fn id(&self) -> &'static str {
         "d1"
@@ -111,85 +145,76 @@ impl Scraper for D1Spider {
     fn scrape(
         &self,
         video_id: &str,
-        log_callback: impl Fn(String) + Clone + Send + Sync + 'static,
-    ) -> Pin<Box<dyn Future<Output = ScrapeResult> + Send>> {
+        ctx: ScrapeContext,
+    ) -> ScrapeFuture<ScrapeResult> {
         // 克隆需要在 async 块外捕获的值
         let video_id = video_id.to_string();
         let base_url = self.base_url.clone();
         let local_storage = self.local_storage.clone();
-        let log_callback = log_callback.clone();
+        let auth = self.auth.clone();
+        let proxy = self.proxy.clone();
+        let rate_limit_config = self.rate_limit.clone();
+        let log_callback = ctx.log_callback.clone();
 
         Box::pin(async move {
             let page_url = format!("{}subPage/longViodePlay/?id={}", base_url, video_id);
+            if let Some(fixture) = ctx.replay() {
+                log_callback(format!("[回放] {}", page_url));
+                return replay(&fixture, &auth, &page_url, &video_id, &ctx);
+            }
             let _ = log_callback(format!("正在爬取: {}", page_url));
 
-            // 使用明确的 headless 模式参数
-            let browser_args: Vec<&OsStr> = vec![
-                OsStr::new("--headless=new"),
-                OsStr::new("--no-sandbox"),
-                OsStr::new("--disable-dev-shm-usage"),
-                OsStr::new("--disable-gpu"),
-                OsStr::new("--disable-software-rasterizer"),
-                OsStr::new("--mute-audio"),
-                OsStr::new("--hide-scrollbars"),
-                OsStr::new("--disable-translate"),
-                OsStr::new("--disable-background-networking"),
-                OsStr::new("--disable-sync"),
-                OsStr::new("--disable-features=site-per-process,TranslateUI"),
-                OsStr::new("--disable-extensions"),
-            ];
-
-            let browser = match Browser::new(
-                headless_chrome::LaunchOptions {
-                    args: browser_args,
-                    headless: false,
-                    ..Default::default()
-                }
-            ) {
-                Ok(browser) => browser,
-                Err(e) => {
-                    return ScrapeResult {
-                        success: false,
-                        name: String::new(),
-                        m3u8_url: String::new(),
-                        message: format!("启动浏览器失败: {}", e),
-                        video_id: Some(video_id.clone()),
-                        view_count: None,
-                        favorite_count: None,
-                        cover_url: None,
-                    };
-                }
-            };
+            // 详情页之间按网站配置等待
+            if !ctx.sleep(rate_limit::detail_delay(&rate_limit_config)).await {
+                return ScrapeResult::cancelled(Some(video_id.clone()));
+            }
 
-            let tab = match browser.new_tab() {
-                Ok(tab) => tab,
+            // 从共享浏览器池借出独立标签页
+            let lease = match BrowserPool::global().acquire_with_proxy(proxy).await {
+                Ok(lease) => lease,
                 Err(e) => {
                     return ScrapeResult {
                         success: false,
                         name: String::new(),
                         m3u8_url: String::new(),
-                        message: format!("创建标签页失败: {}", e),
+                        message: format!("启动浏览器失败: {}", e),
                         video_id: Some(video_id.clone()),
                         view_count: None,
                         favorite_count: None,
                         cover_url: None,
+                        error_kind: Some(ScrapeErrorKind::BrowserLaunch),
+                        metadata: VideoMetadata::default(),
                     };
                 }
             };
+            let tab = lease.tab();
 
             // 创建共享的 m3u8 URL 捕获变量
             let captured_url = Arc::new(Mutex::new(None::<String>));
+            // m3u8 接口返回错误时记录状态码（401/403 通常表示 token 过期）
+            let failed_status = Arc::new(Mutex::new(None::<u16>));
 
             // 注册网络响应处理器
             let captured_url_clone = Arc::clone(&captured_url);
+            let failed_status_clone = Arc::clone(&failed_status);
             let log_callback_for_response = Arc::new(log_callback.clone());
+            let recorder = ctx.recorder();
 
             // 注册网络响应处理器
             let _ = tab.register_response_handling(
                 "m3u8_capture",
                 Box::new(move |params, _fetch_body| {
                     let url = params.response.url.clone();
-                    if url.contains(".m3u8") && url.contains("/api/app/media/h5/m3u8/") {
+                    let status = params.response.status as u16;
+                    if let Some(ref recorder) = recorder {
+                        recorder.record_response(&url, status, None);
+                    }
+                    if is_m3u8_api(&url) {
+                        if status >= 400 {
+                            *failed_status_clone.lock().unwrap() = Some(status);
+                            log_callback_for_response(format!("m3u8 请求失败: HTTP {}", status));
+                            return;
+                        }
                         let mut captured = captured_url_clone.lock().unwrap();
                         if captured.is_none() {
                             *captured = Some(url.clone());
@@ -202,39 +227,27 @@ impl Scraper for D1Spider {
             );
 
             // 等待浏览器稳定
-            tokio::time::sleep(Duration::from_millis(500)).await;
-
-            // 导航到页面（带重试）
-            let mut nav_success = false;
-            let mut nav_error = String::new();
-            for attempt in 1..=3 {
-                match tab.navigate_to(&page_url) {
-                    Ok(_) => {
-                        nav_success = true;
-                        break;
-                    }
-                    Err(e) => {
-                        nav_error = format!("{}", e);
-                        if attempt < 3 {
-                            tokio::time::sleep(Duration::from_secs(1)).await;
-                        }
-                    }
-                }
+            if !ctx.sleep(Duration::from_millis(500)).await {
+                return ScrapeResult::cancelled(Some(video_id.clone()));
             }
 
-            if !nav_success {
-                let _ = tab.close(true);
-                drop(tab);
-                drop(browser);
+            // 导航到页面（按重试策略重试）
+            if let Err(failure) = ctx.navigate(&tab, &page_url, &rate_limit_config).await {
+                drop(lease);
+                if failure.kind == ScrapeErrorKind::Cancelled {
+                    return ScrapeResult::cancelled(Some(video_id.clone()));
+                }
                 return ScrapeResult {
                     success: false,
                     name: String::new(),
                     m3u8_url: String::new(),
-                    message: format!("导航失败: {}", nav_error),
+                    message: format!("导航失败（尝试 {} 次）: {}", failure.attempts, failure.message),
                     video_id: Some(video_id.clone()),
                     view_count: None,
                     favorite_count: None,
                     cover_url: None,
+                    error_kind: Some(failure.kind),
+                    metadata: VideoMetadata::default(),
                 };
             }
 
@@ -253,7 +266,9 @@ impl Scraper for D1Spider {
                 let _ = tab.reload(true, None);
                 let _ = log_callback(format!("已注入 {} 个 localStorage 项", local_storage.len()));
                 // 等待页面重新加载完成
-                tokio::time::sleep(Duration::from_secs(2)).await;
+                if !ctx.sleep(Duration::from_secs(2)).await {
+                    return ScrapeResult::cancelled(Some(video_id.clone()));
+                }
             }
 
             // 等待 m3u8 请求，最多等待 15 秒
@@ -262,6 +277,12 @@ impl Scraper for D1Spider {
             let timeout = Duration::from_secs(10);
 
             while start_time.elapsed() < timeout {
+                // 用户取消时立即返回，lease 释放时关闭标签页
+                if ctx.is_cancelled() {
+                    log_callback("爬取已取消".to_string());
+                    return ScrapeResult::cancelled(Some(video_id.clone()));
+                }
+
                 // 检查是否已捕获到 m3u8
                 {
                     let captured = captured_url.lock().unwrap();
@@ -270,6 +291,9 @@ impl Scraper for D1Spider {
                         break;
                     }
                 }
+                if failed_status.lock().unwrap().is_some() {
+                    break;
+                }
 
                 // 定期检查页面是否显示 404
                 let body_text: String = match tab.evaluate("document.body.innerText", false) {
@@ -281,9 +305,10 @@ impl Scraper for D1Spider {
                 // 如果已经捕获到 m3u8，就不检查 404 了
                 if found_url.is_none() {
                     if body_text.contains("资源不存在") && body_text.contains("404") {
-                        let _ = tab.close(true);
-                        drop(tab);
-                        drop(browser);
+                        if let Some(recorder) = ctx.recorder() {
+                            recorder.record_tab(&tab, &page_url);
+                        }
+                        drop(lease);
                         return ScrapeResult {
                             success: false,
                             name: String::new(),
@@ -293,11 +318,19 @@ impl Scraper for D1Spider {
                             view_count: None,
                             favorite_count: None,
                             cover_url: None,
+                            error_kind: Some(ScrapeErrorKind::NotFound),
+                            metadata: VideoMetadata::default(),
                         };
                     }
                 }
 
-                tokio::time::sleep(Duration::from_millis(500)).await;
+                if !ctx.sleep(Duration::from_millis(500)).await {
+                    return ScrapeResult::cancelled(Some(video_id.clone()));
+                }
+            }
+
+            if let Some(recorder) = ctx.recorder() {
+                recorder.record_tab(&tab, &page_url);
             }
 
             // 如果找到 m3u8，立即提取标题和其他数据
@@ -380,7 +413,9 @@ impl Scraper for D1Spider {
 
                 // 等待视频加载
                 let _ = log_callback("等待视频加载 (3秒)...".to_string());
-                tokio::time::sleep(Duration::from_secs(3)).await;
+                if !ctx.sleep(Duration::from_secs(3)).await {
+                    return ScrapeResult::cancelled(Some(video_id.clone()));
+                }
 
                 if let Ok(result) = tab.evaluate(cover_js, false) {
                     if let Some(base64) = result.value.as_ref().and_then(|v| v.as_str()).filter(|s| !s.is_empty()) {
@@ -391,16 +426,32 @@ impl Scraper for D1Spider {
                     }
                 }
 
-                // 清理 _0001
-                let mut final_url = m3u8_url.clone();
-                if final_url.contains("_0001") {
-                    final_url = final_url.replace("_0001", "");
+                // 页面 meta 标签中的元数据，时长以播放器为准
+                let mut meta = tab
+                    .get_content()
+                    .map(|html| metadata::from_html_meta(&html))
+                    .unwrap_or_default();
+                let duration_js = r#"
+                    (() => {
+                        const video = document.querySelector('video');
+                        return video && isFinite(video.duration) ? Math.round(video.duration) : 0;
+                    })()
+                "#;
+                if let Some(secs) = tab
+                    .evaluate(duration_js, false)
+                    .ok()
+                    .and_then(|result| result.value)
+                    .and_then(|value| value.as_i64())
+                    .filter(|secs| *secs > 0)
+                {
+                    meta.duration = Some(secs);
                 }
 
+                // 清理 _0001，并统一使用网站当前凭证
+                let final_url = auth.apply_to_url(&clean_m3u8_url(m3u8_url));
+
                 // 关闭浏览器
-                let _ = tab.close(true);
-                drop(tab);
-                drop(browser);
+                drop(lease);
 
                 ScrapeResult {
                     success: true,
@@ -411,39 +462,28 @@ impl Scraper for D1Spider {
                     view_count,
                     favorite_count,
                     cover_url,
+                    error_kind: None,
+                    metadata: meta,
                 }
             } else {
-                // 未找到 m3u8
-                let _ = tab.close(true);
-                drop(tab);
-                drop(browser);
+                // 未找到 m3u8：m3u8 接口返回错误时按状态码归类，否则视为超时
+                drop(lease);
+                let status = *failed_status.lock().unwrap();
+                let (message, error_kind) = m3u8_failure(status);
 
                 ScrapeResult {
                     success: false,
                     name: String::new(),
                     m3u8_url: String::new(),
-                    message: "未能找到 m3u8 地址".to_string(),
+                    message,
                     video_id: Some(video_id.clone()),
                     view_count: None,
                     favorite_count: None,
                     cover_url: None,
+                    error_kind: Some(error_kind),
+                    metadata: VideoMetadata::default(),
                 }
             }
         })
     }
-
-    /// D1 爬虫只爬取单个视频，scrape_all 返回单个结果
-    fn scrape_all(
-        &self,
-        video_id: &str,
-        log_callback: impl Fn(String) + Clone + Send + Sync + 'static,
-    ) -> Pin<Box<dyn Future<Output = Vec<ScrapeResult>> + Send + 'static>>
-    where
-        Self: Sized,
-    {
-        let result = self.scrape(video_id, log_callback);
-        Box::pin(async move {
-            vec![result.await]
-        })
-    }
 }
@@ -1,17 +1,16 @@
-use crate::models::{LocalStorageItem, ScrapeResult, Website};
-use crate::services::scraper::Scraper;
-use headless_chrome::Browser;
+use crate::models::{LocalStorageItem, ProxyConfig, RateLimitConfig, ScrapeErrorKind, ScrapeResult, VideoMetadata, Website};
+use crate::services::scraper::{metadata, ScrapeContext, ScrapeFuture, Scraper};
+use crate::services::{proxy, BrowserPool};
 use regex::Regex;
-use std::ffi::OsStr;
-use std::pin::Pin;
 use std::time::Duration;
-use std::future::Future;
 
 /// D2 Cloudfront 爬虫 - 专门爬取 d1ibyof3mbdf0n.cloudfront.net 列表页
 #[derive(Clone)]
 pub struct D2Spider {
     base_url: String,
     local_storage: Vec<LocalStorageItem>,
+    proxy: ProxyConfig,
+    rate_limit: RateLimitConfig,
 }
 
 impl D2Spider {
@@ -19,6 +18,8 @@ impl D2Spider {
         Self {
             base_url: website.base_url.clone(),
             local_storage: website.local_storage.clone(),
+            proxy: proxy::resolve(website.proxy.as_ref()),
+            rate_limit: website.rate_limit.clone(),
         }
     }
 }
@@ -143,10 +144,10 @@ fn extract_videos_from_html(html: &str) -> Vec<VideoInfo> {
             name,
             _cover_url: cover_url,
             _m3u8_url: String::new(),
-            _duration: duration,
+            duration,
             favorite_count,
             view_count: Some(parse_view_count(&views)),
-            _tag: String::new(),
+            tags: extract_tags(tags_box),
         });
     }
 
@@ -241,10 +242,10 @@ fn extract_videos_from_html(html: &str) -> Vec<VideoInfo> {
                 name,
                 _cover_url: cover_url,
                 _m3u8_url: String::new(),
-                _duration: duration,
+                duration,
                 favorite_count,
                 view_count: Some(parse_view_count(&views)),
-                _tag: String::new(),
+                tags: extract_tags(tags_box),
             });
         }
     }
@@ -312,6 +313,90 @@ fn parse_view_count(views: &str) -> i64 {
     num_str.parse::<i64>().unwrap_or(0) * multiplier
 }
 
+/// 把新提取的视频合并到已收集列表（按ID去重），返回新增数量
+fn merge_videos(
+    all_videos: &mut Vec<VideoInfo>,
+    seen_ids: &mut std::collections::HashSet<String>,
+    videos: Vec<VideoInfo>,
+) -> usize {
+    let mut new_count = 0;
+    for video in videos {
+        if seen_ids.insert(video.id.clone()) {
+            all_videos.push(video);
+            new_count += 1;
+        }
+    }
+    new_count
+}
+
+/// 页面中没有视频卡片时的结果
+fn no_videos_result() -> ScrapeResult {
+    ScrapeResult {
+        success: false,
+        name: "未找到视频".to_string(),
+        m3u8_url: String::new(),
+        message: "页面中未找到视频卡片".to_string(),
+        video_id: None,
+        view_count: None,
+        favorite_count: None,
+        cover_url: None,
+        error_kind: Some(ScrapeErrorKind::ParseFailed),
+        metadata: VideoMetadata::default(),
+    }
+}
+
+/// 从 tags-box 中提取标签（跳过收藏数）
+fn extract_tags(tags_box: &str) -> Vec<String> {
+    let text_pattern = Regex::new(r#">([^<>]+)<"#).unwrap();
+    metadata::normalize_tags(
+        text_pattern
+            .captures_iter(tags_box)
+            .filter_map(|cap| cap.get(1).map(|m| m.as_str().trim().to_string()))
+            .filter(|text| !text.contains("收藏数") && text.parse::<f64>().is_err()),
+    )
+}
+
+/// 返回第一个视频作为主要结果
+fn first_video_result(videos: &[VideoInfo]) -> ScrapeResult {
+    let Some(first_video) = videos.first() else {
+        return no_videos_result();
+    };
+    ScrapeResult {
+        success: true,
+        name: first_video.name.clone(),
+        m3u8_url: first_video._m3u8_url.clone(),
+        message: format!("找到 {} 个视频 (点击卡片获取m3u8)", videos.len()),
+        video_id: Some(first_video.id.clone()),
+        view_count: first_video.view_count,
+        favorite_count: Some(first_video.favorite_count),
+        cover_url: None,
+        error_kind: None,
+        metadata: first_video.metadata(),
+    }
+}
+
+/// 把收集到的视频转换为 ScrapeResult
+fn videos_to_results(videos: Vec<VideoInfo>) -> Vec<ScrapeResult> {
+    if videos.is_empty() {
+        return vec![no_videos_result()];
+    }
+    videos.into_iter().map(|video| {
+        let views_str = video.view_count.map(|v| format!("{}", v)).unwrap_or_default();
+        ScrapeResult {
+            success: true,
+            name: video.name.clone(),
+            m3u8_url: video._m3u8_url.clone(),
+            message: format!("播放:{} 收藏:{}", views_str, video.favorite_count),
+            video_id: Some(video.id.clone()),
+            view_count: video.view_count,
+            favorite_count: Some(video.favorite_count),
+            cover_url: None,
+            error_kind: None,
+            metadata: video.metadata(),
+        }
+    }).collect()
+}
+
 impl Scraper for D2Spider {
     fn id(&self) -> &'static str {
         "d2"
@@ -320,39 +405,27 @@ impl Scraper for D2Spider {
     fn scrape(
         &self,
         _video_id: &str,
-        log_callback: impl Fn(String) + Clone + Send + Sync + 'static,
-    ) -> Pin<Box<dyn Future<Output = ScrapeResult> + Send>> {
+        ctx: ScrapeContext,
+    ) -> ScrapeFuture<ScrapeResult> {
         let base_url = self.base_url.clone();
         let local_storage = self.local_storage.clone();
-        let log_callback = log_callback.clone();
+        let proxy = self.proxy.clone();
+        let rate_limit_config = self.rate_limit.clone();
+        let log_callback = ctx.log_callback.clone();
 
         Box::pin(async move {
             let page_url = format!("{}", base_url);
+            if let Some(fixture) = ctx.replay() {
+                log_callback(format!("[回放] {}", page_url));
+                let videos = extract_videos_from_html(fixture.page(&page_url).unwrap_or_default());
+                log_callback(format!("找到 {} 个视频", videos.len()));
+                return first_video_result(&videos);
+            }
             let _ = log_callback(format!("正在爬取: {}", page_url));
 
-            let browser_args: Vec<&OsStr> = vec![
-                OsStr::new("--headless=new"),
-                OsStr::new("--no-sandbox"),
-                OsStr::new("--disable-dev-shm-usage"),
-                OsStr::new("--disable-gpu"),
-                OsStr::new("--disable-software-rasterizer"),
-                OsStr::new("--mute-audio"),
-                OsStr::new("--hide-scrollbars"),
-                OsStr::new("--disable-translate"),
-                OsStr::new("--disable-background-networking"),
-                OsStr::new("--disable-sync"),
-                OsStr::new("--disable-features=site-per-process,TranslateUI"),
-                OsStr::new("--disable-extensions"),
-            ];
-
-            let browser = match Browser::new(
-                headless_chrome::LaunchOptions {
-                    args: browser_args,
-                    headless: true,
-                    ..Default::default()
-                }
-            ) {
-                Ok(browser) => browser,
+            // 从共享浏览器池借出独立标签页
+            let lease = match BrowserPool::global().acquire_with_proxy(proxy).await {
+                Ok(lease) => lease,
                 Err(e) => {
                     return ScrapeResult {
                         success: false,
@@ -363,25 +436,12 @@ impl Scraper for D2Spider {
                         view_count: None,
                         favorite_count: None,
                         cover_url: None,
+                        error_kind: Some(ScrapeErrorKind::BrowserLaunch),
+                        metadata: VideoMetadata::default(),
                     };
                 }
             };
-
-            let tab = match browser.new_tab() {
-                Ok(tab) => tab,
-                Err(e) => {
-                    return ScrapeResult {
-                        success: false,
-                        name: String::new(),
-                        m3u8_url: String::new(),
-                        message: format!("创建标签页失败: {}", e),
-                        video_id: None,
-                        view_count: None,
-                        favorite_count: None,
-                        cover_url: None,
-                    };
-                }
-            };
+            let tab = lease.tab();
 
             // 先导航到 about:blank，注入 localStorage 后再跳转到目标页面
             let _ = tab.navigate_to("about:blank");
@@ -402,37 +462,23 @@ impl Scraper for D2Spider {
                 let _ = log_callback(format!("已注入 {} 个 localStorage 项", local_storage.len()));
             }
 
-            // 导航到目标页面
-            let mut nav_success = false;
-            let mut nav_error = String::new();
-            for attempt in 1..=3 {
-                match tab.navigate_to(&page_url) {
-                    Ok(_) => {
-                        nav_success = true;
-                        break;
-                    }
-                    Err(e) => {
-                        nav_error = format!("{}", e);
-                        if attempt < 3 {
-                            tokio::time::sleep(Duration::from_secs(1)).await;
-                        }
-                    }
+            // 导航到目标页面（按重试策略重试）
+            if let Err(failure) = ctx.navigate(&tab, &page_url, &rate_limit_config).await {
+                drop(lease);
+                if failure.kind == ScrapeErrorKind::Cancelled {
+                    return ScrapeResult::cancelled(None);
                 }
-            }
-
-            if !nav_success {
-                let _ = tab.close(true);
-                drop(tab);
-                drop(browser);
                 return ScrapeResult {
                     success: false,
                     name: String::new(),
                     m3u8_url: String::new(),
-                    message: format!("导航失败: {}", nav_error),
+                    message: format!("导航失败（尝试 {} 次）: {}", failure.attempts, failure.message),
                     video_id: None,
                     view_count: None,
                     favorite_count: None,
                     cover_url: None,
+                    error_kind: Some(failure.kind),
+                    metadata: VideoMetadata::default(),
                 };
             }
 
@@ -501,7 +547,10 @@ impl Scraper for D2Spider {
 
             // 等待 Vue 应用渲染（最多 8 秒）
             for i in 0..40 {
-                tokio::time::sleep(Duration::from_millis(200)).await;
+                if !ctx.sleep(Duration::from_millis(200)).await {
+                    log_callback("爬取已取消".to_string());
+                    return ScrapeResult::cancelled(None);
+                }
 
                 // 检查是否已有视频卡片
                 let has_card: bool = tab.evaluate("document.querySelector('.card-item, .longVideoCard') !== null", false)
@@ -538,9 +587,7 @@ impl Scraper for D2Spider {
             let html: String = match tab.evaluate("document.documentElement.outerHTML", false) {
                 Ok(result) => result.value.unwrap_or_default().as_str().unwrap_or("").to_string(),
                 Err(e) => {
-                    let _ = tab.close(true);
-                    drop(tab);
-                    drop(browser);
+                    drop(lease);
                     return ScrapeResult {
                         success: false,
                         name: String::new(),
@@ -550,6 +597,8 @@ impl Scraper for D2Spider {
                         view_count: None,
                         favorite_count: None,
                         cover_url: None,
+                        error_kind: Some(ScrapeErrorKind::Navigation),
+                        metadata: VideoMetadata::default(),
                     };
                 }
             };
@@ -571,86 +620,52 @@ impl Scraper for D2Spider {
             tracing::info!("[DEBUG] Contains 'video-time': {}", has_video_time);
             tracing::info!("[DEBUG] Contains 'wh-full': {}", has_wh_full);
 
+            if let Some(recorder) = ctx.recorder() {
+                recorder.record_page(&page_url, &html);
+                recorder.record_local_storage(&tab);
+            }
+
             // 提取视频列表（使用独立函数）
             let videos = extract_videos_from_html(&html);
 
             let _ = log_callback(format!("找到 {} 个视频", videos.len()));
 
-            if videos.is_empty() {
-                let _ = tab.close(true);
-                drop(tab);
-                drop(browser);
-                return ScrapeResult {
-                    success: false,
-                    name: "未找到视频".to_string(),
-                    m3u8_url: String::new(),
-                    message: "页面中未找到视频卡片".to_string(),
-                    video_id: None,
-                    view_count: None,
-                    favorite_count: None,
-                    cover_url: None,
-                };
-            }
+            // 归还标签页
+            drop(lease);
 
-            // 关闭浏览器
-            let _ = tab.close(true);
-            drop(tab);
-            drop(browser);
-
-            // 返回第一个视频作为主要结果
-            let first_video = &videos[0];
-            ScrapeResult {
-                success: true,
-                name: first_video.name.clone(),
-                m3u8_url: first_video._m3u8_url.clone(),
-                message: format!("找到 {} 个视频 (点击卡片获取m3u8)", videos.len()),
-                video_id: Some(first_video.id.clone()),
-                view_count: first_video.view_count,
-                favorite_count: Some(first_video.favorite_count),
-                cover_url: None,
-            }
+            first_video_result(&videos)
         })
     }
 
     fn scrape_all(
         &self,
         _video_id: &str,
-        log_callback: impl Fn(String) + Clone + Send + Sync + 'static,
-    ) -> Pin<Box<dyn Future<Output = Vec<ScrapeResult>> + Send + 'static>>
-    where
-        Self: Sized,
-    {
+        ctx: ScrapeContext,
+    ) -> ScrapeFuture<Vec<ScrapeResult>> {
         let base_url = self.base_url.clone();
         let local_storage = self.local_storage.clone();
-        let log_callback = log_callback.clone();
+        let proxy = self.proxy.clone();
+        let rate_limit_config = self.rate_limit.clone();
+        let log_callback = ctx.log_callback.clone();
 
         Box::pin(async move {
             let page_url = format!("{}", base_url);
+            if let Some(fixture) = ctx.replay() {
+                // 按录制顺序回放每次滚动时的页面快照
+                log_callback(format!("[回放] {}", page_url));
+                let mut all_videos: Vec<VideoInfo> = Vec::new();
+                let mut seen_ids: std::collections::HashSet<String> = std::collections::HashSet::new();
+                for (index, html) in fixture.page_snapshots(&page_url).enumerate() {
+                    let new_count = merge_videos(&mut all_videos, &mut seen_ids, extract_videos_from_html(html));
+                    log_callback(format!("快照 {}: 新增 {} 个视频，累计 {} 个", index + 1, new_count, all_videos.len()));
+                }
+                return videos_to_results(all_videos);
+            }
             let _ = log_callback(format!("正在爬取: {}", page_url));
 
-            let browser_args: Vec<&OsStr> = vec![
-                OsStr::new("--headless=new"),
-                OsStr::new("--no-sandbox"),
-                OsStr::new("--disable-dev-shm-usage"),
-                OsStr::new("--disable-gpu"),
-                OsStr::new("--disable-software-rasterizer"),
-                OsStr::new("--mute-audio"),
-                OsStr::new("--hide-scrollbars"),
-                OsStr::new("--disable-translate"),
-                OsStr::new("--disable-background-networking"),
-                OsStr::new("--disable-sync"),
-                OsStr::new("--disable-features=site-per-process,TranslateUI"),
-                OsStr::new("--disable-extensions"),
-            ];
-
-            let browser = match Browser::new(
-                headless_chrome::LaunchOptions {
-                    args: browser_args,
-                    headless: true,
-                    ..Default::default()
-                }
-            ) {
-                Ok(browser) => browser,
+            // 从共享浏览器池借出独立标签页
+            let lease = match BrowserPool::global().acquire_with_proxy(proxy).await {
+                Ok(lease) => lease,
                 Err(e) => {
                     return vec![ScrapeResult {
                         success: false,
@@ -661,25 +676,12 @@ impl Scraper for D2Spider {
                         view_count: None,
                         favorite_count: None,
                         cover_url: None,
+                        error_kind: Some(ScrapeErrorKind::BrowserLaunch),
+                        metadata: VideoMetadata::default(),
                     }];
                 }
             };
-
-            let tab = match browser.new_tab() {
-                Ok(tab) => tab,
-                Err(e) => {
-                    return vec![ScrapeResult {
-                        success: false,
-                        name: String::new(),
-                        m3u8_url: String::new(),
-                        message: format!("创建标签页失败: {}", e),
-                        video_id: None,
-                        view_count: None,
-                        favorite_count: None,
-                        cover_url: None,
-                    }];
-                }
-            };
+            let tab = lease.tab();
 
             // 先导航到 about:blank，注入 localStorage 后再跳转到目标页面
             let _ = tab.navigate_to("about:blank");
@@ -700,35 +702,23 @@ impl Scraper for D2Spider {
                 let _ = log_callback(format!("已注入 {} 个 localStorage 项", local_storage.len()));
             }
 
-            // 导航到目标页面（带重试）
-            let mut nav_success = false;
-            for attempt in 1..=3 {
-                match tab.navigate_to(&page_url) {
-                    Ok(_) => {
-                        nav_success = true;
-                        break;
-                    }
-                    Err(_) => {
-                        if attempt < 3 {
-                            tokio::time::sleep(Duration::from_secs(1)).await;
-                        }
-                    }
+            // 导航到目标页面（按重试策略重试）
+            if let Err(failure) = ctx.navigate(&tab, &page_url, &rate_limit_config).await {
+                drop(lease);
+                if failure.kind == ScrapeErrorKind::Cancelled {
+                    return vec![ScrapeResult::cancelled(None)];
                 }
-            }
-
-            if !nav_success {
-                let _ = tab.close(true);
-                drop(tab);
-                drop(browser);
                 return vec![ScrapeResult {
                     success: false,
                     name: String::new(),
                     m3u8_url: String::new(),
-                    message: "导航失败".to_string(),
+                    message: format!("导航失败（尝试 {} 次）: {}", failure.attempts, failure.message),
                     video_id: None,
                     view_count: None,
                     favorite_count: None,
                     cover_url: None,
+                    error_kind: Some(failure.kind),
+                    metadata: VideoMetadata::default(),
                 }];
             }
 
@@ -792,7 +782,10 @@ impl Scraper for D2Spider {
 
             // 等待 Vue 应用渲染
             for i in 0..40 {
-                tokio::time::sleep(Duration::from_millis(200)).await;
+                if !ctx.sleep(Duration::from_millis(200)).await {
+                    log_callback("爬取已取消".to_string());
+                    return vec![ScrapeResult::cancelled(None)];
+                }
 
                 let has_card: bool = tab.evaluate("document.querySelector('.card-item, .longVideoCard') !== null", false)
                     .map(|r| r.value.unwrap_or_default().as_bool().unwrap_or(false))
@@ -832,19 +825,13 @@ impl Scraper for D2Spider {
                     Err(_) => String::new(),
                 };
 
-                // 提取视频
-                let videos = extract_videos_from_html(&html);
-
-                // 添加新视频（去重）
-                let mut new_count = 0;
-                for video in videos {
-                    if !seen_ids.contains(&video.id) {
-                        seen_ids.insert(video.id.clone());
-                        all_videos.push(video.clone());
-                        new_count += 1;
-                    }
+                if let Some(recorder) = ctx.recorder() {
+                    recorder.record_page(&page_url, &html);
                 }
 
+                // 提取视频并添加新视频（去重）
+                let new_count = merge_videos(&mut all_videos, &mut seen_ids, extract_videos_from_html(&html));
+
                 if new_count > 0 {
                     let _ = log_callback(format!("第 {} 次滚动，新增 {} 个视频，累计 {} 个", scroll_count, new_count, all_videos.len()));
                 } else {
@@ -863,8 +850,11 @@ impl Scraper for D2Spider {
                 // 滚动到页面底部
                 let _ = tab.evaluate("window.scrollTo(0, document.body.scrollHeight)", false);
 
-                // 等待新内容加载
-                tokio::time::sleep(Duration::from_secs(2)).await;
+                // 等待新内容加载，取消时丢弃已收集的结果并归还标签页
+                if !ctx.sleep(Duration::from_secs(2)).await {
+                    log_callback("爬取已取消".to_string());
+                    return vec![ScrapeResult::cancelled(None)];
+                }
 
                 // 再次注入CSS隐藏新出现的弹窗
                 let _ = tab.evaluate(hide_ad_css, false);
@@ -873,40 +863,21 @@ impl Scraper for D2Spider {
                 tokio::time::sleep(Duration::from_millis(500)).await;
             }
 
-            // 关闭浏览器
-            let _ = tab.close(true);
-            drop(tab);
-            drop(browser);
+            if let Some(recorder) = ctx.recorder() {
+                recorder.record_local_storage(&tab);
+            }
+
+            // 归还标签页
+            drop(lease);
 
             tracing::info!("[DEBUG] Total videos collected: {}", all_videos.len());
 
             if all_videos.is_empty() {
-                return vec![ScrapeResult {
-                    success: false,
-                    name: "未找到视频".to_string(),
-                    m3u8_url: String::new(),
-                    message: "页面中未找到视频卡片".to_string(),
-                    video_id: None,
-                    view_count: None,
-                    favorite_count: None,
-                    cover_url: None,
-                }];
+                return vec![no_videos_result()];
             }
 
             // 转换为 ScrapeResult
-            let results: Vec<ScrapeResult> = all_videos.into_iter().map(|video| {
-                let views_str = video.view_count.map(|v| format!("{}", v)).unwrap_or_default();
-                ScrapeResult {
-                    success: true,
-                    name: video.name.clone(),
-                    m3u8_url: video._m3u8_url.clone(),
-                    message: format!("播放:{} 收藏:{}", views_str, video.favorite_count),
-                    video_id: Some(video.id),
-                    view_count: video.view_count,
-                    favorite_count: Some(video.favorite_count),
-                    cover_url: None,
-                }
-            }).collect();
+            let results = videos_to_results(all_videos);
 
             let _ = log_callback(format!("完成: 成功爬取 {} 个视频", results.len()));
 
@@ -923,8 +894,20 @@ struct VideoInfo {
     name: String,
     _cover_url: String,
     _m3u8_url: String,
-    _duration: String,
+    /// 时长文本（如 01:02:03）
+    duration: String,
     favorite_count: i64,
     view_count: Option<i64>,
-    _tag: String,
+    tags: Vec<String>,
+}
+
+impl VideoInfo {
+    /// 卡片上能取到的元数据
+    fn metadata(&self) -> VideoMetadata {
+        VideoMetadata {
+            duration: metadata::parse_duration(&self.duration),
+            tags: self.tags.clone(),
+            ..Default::default()
+        }
+    }
 }
@@ -0,0 +1,242 @@
+//! 爬虫录制/回放
+//!
+//! 录制模式把一次爬取中渲染后的 HTML、捕获的网络响应和 localStorage 保存到夹具目录；
+//! 回放模式读取夹具，把数据交给爬虫的提取函数处理，不访问网络，
+//! 用于站点改版后复现和调试提取逻辑。
+
+use crate::models::LocalStorageItem;
+use chrono::{DateTime, Utc};
+use headless_chrome::Tab;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// 夹具清单文件名
+const MANIFEST_FILE: &str = "fixture.json";
+
+/// 读取页面 localStorage 的脚本（返回 [[key, value], ...] 的 JSON）
+const LOCAL_STORAGE_JS: &str = "JSON.stringify(Object.entries(localStorage))";
+
+/// 录制的页面（浏览器渲染后的 HTML）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedPage {
+    pub url: String,
+    /// 夹具目录中的 HTML 文件名
+    pub file: String,
+    #[serde(skip)]
+    pub html: String,
+}
+
+/// 录制的网络响应（浏览器捕获的请求没有响应体）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedResponse {
+    pub url: String,
+    pub status: u16,
+    /// 夹具目录中的响应体文件名
+    #[serde(default)]
+    pub body_file: Option<String>,
+    #[serde(skip)]
+    pub body: Option<String>,
+}
+
+/// 录制的脚本执行结果（如浏览器中提取列表的 JS 返回值）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedScript {
+    pub url: String,
+    pub name: String,
+    pub value: String,
+}
+
+/// 一次爬取的夹具
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScrapeFixture {
+    /// 录制时使用的网站ID（回放时据此创建爬虫）
+    pub website_id: String,
+    /// 爬虫ID
+    pub spider: String,
+    /// 爬取输入（视频ID或页码）
+    pub input: String,
+    pub recorded_at: DateTime<Utc>,
+    #[serde(default)]
+    pub local_storage: Vec<LocalStorageItem>,
+    #[serde(default)]
+    pub pages: Vec<RecordedPage>,
+    #[serde(default)]
+    pub responses: Vec<RecordedResponse>,
+    #[serde(default)]
+    pub scripts: Vec<RecordedScript>,
+}
+
+impl ScrapeFixture {
+    pub fn new(website_id: &str, spider: &str, input: &str) -> Self {
+        Self {
+            website_id: website_id.to_string(),
+            spider: spider.to_string(),
+            input: input.to_string(),
+            recorded_at: Utc::now(),
+            local_storage: Vec::new(),
+            pages: Vec::new(),
+            responses: Vec::new(),
+            scripts: Vec::new(),
+        }
+    }
+
+    /// 从夹具目录加载
+    pub fn load(dir: &Path) -> Result<Self, String> {
+        let manifest = std::fs::read_to_string(dir.join(MANIFEST_FILE))
+            .map_err(|e| format!("读取夹具失败: {}", e))?;
+        let mut fixture: ScrapeFixture =
+            serde_json::from_str(&manifest).map_err(|e| format!("解析夹具失败: {}", e))?;
+
+        for page in &mut fixture.pages {
+            page.html = std::fs::read_to_string(dir.join(&page.file))
+                .map_err(|e| format!("读取夹具页面 {} 失败: {}", page.file, e))?;
+        }
+        for response in &mut fixture.responses {
+            if let Some(ref file) = response.body_file {
+                response.body = Some(
+                    std::fs::read_to_string(dir.join(file))
+                        .map_err(|e| format!("读取夹具响应 {} 失败: {}", file, e))?,
+                );
+            }
+        }
+        Ok(fixture)
+    }
+
+    /// 保存到夹具目录，页面和响应体写成单独文件便于查看
+    pub fn save(&mut self, dir: &Path) -> Result<(), String> {
+        std::fs::create_dir_all(dir).map_err(|e| format!("创建夹具目录失败: {}", e))?;
+
+        for (index, page) in self.pages.iter_mut().enumerate() {
+            page.file = format!("page_{:03}.html", index + 1);
+            std::fs::write(dir.join(&page.file), &page.html)
+                .map_err(|e| format!("写入夹具页面失败: {}", e))?;
+        }
+        for (index, response) in self.responses.iter_mut().enumerate() {
+            response.body_file = match response.body {
+                Some(ref body) => {
+                    let file = format!("response_{:03}.txt", index + 1);
+                    std::fs::write(dir.join(&file), body)
+                        .map_err(|e| format!("写入夹具响应失败: {}", e))?;
+                    Some(file)
+                }
+                None => None,
+            };
+        }
+
+        let manifest = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        std::fs::write(dir.join(MANIFEST_FILE), manifest).map_err(|e| format!("写入夹具失败: {}", e))
+    }
+
+    /// 指定 URL 最后一次录制的页面
+    pub fn page(&self, url: &str) -> Option<&str> {
+        self.pages.iter().rev().find(|p| p.url == url).map(|p| p.html.as_str())
+    }
+
+    /// 指定 URL 录制的所有页面快照（按录制顺序，如滚动加载过程中的多次快照）
+    pub fn page_snapshots<'a>(&'a self, url: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.pages.iter().filter(move |p| p.url == url).map(|p| p.html.as_str())
+    }
+
+    /// 指定 URL 的响应
+    pub fn response(&self, url: &str) -> Option<&RecordedResponse> {
+        self.responses.iter().find(|r| r.url == url)
+    }
+
+    /// 指定页面上某个脚本的执行结果
+    pub fn script(&self, url: &str, name: &str) -> Option<&str> {
+        self.scripts
+            .iter()
+            .rev()
+            .find(|s| s.url == url && s.name == name)
+            .map(|s| s.value.as_str())
+    }
+}
+
+/// 夹具录制器 - 爬取过程中可能在多个任务中并发写入
+pub struct FixtureRecorder {
+    fixture: Mutex<ScrapeFixture>,
+}
+
+impl FixtureRecorder {
+    pub fn new(website_id: &str, spider: &str, input: &str) -> Self {
+        Self {
+            fixture: Mutex::new(ScrapeFixture::new(website_id, spider, input)),
+        }
+    }
+
+    /// 记录渲染后的页面 HTML（同一 URL 可记录多次快照）
+    pub fn record_page(&self, url: &str, html: &str) {
+        self.fixture.lock().unwrap().pages.push(RecordedPage {
+            url: url.to_string(),
+            file: String::new(),
+            html: html.to_string(),
+        });
+    }
+
+    /// 记录网络响应，同一 URL 只保留最后一次
+    pub fn record_response(&self, url: &str, status: u16, body: Option<&str>) {
+        let mut fixture = self.fixture.lock().unwrap();
+        fixture.responses.retain(|r| r.url != url);
+        fixture.responses.push(RecordedResponse {
+            url: url.to_string(),
+            status,
+            body_file: None,
+            body: body.map(|b| b.to_string()),
+        });
+    }
+
+    /// 记录脚本执行结果
+    pub fn record_script(&self, url: &str, name: &str, value: &str) {
+        self.fixture.lock().unwrap().scripts.push(RecordedScript {
+            url: url.to_string(),
+            name: name.to_string(),
+            value: value.to_string(),
+        });
+    }
+
+    /// 记录标签页当前的 localStorage 状态
+    pub fn record_local_storage(&self, tab: &Tab) {
+        let Ok(result) = tab.evaluate(LOCAL_STORAGE_JS, false) else {
+            return;
+        };
+        let entries: Vec<(String, String)> = result
+            .value
+            .as_ref()
+            .and_then(|v| v.as_str())
+            .and_then(|json| serde_json::from_str(json).ok())
+            .unwrap_or_default();
+        self.fixture.lock().unwrap().local_storage = entries
+            .into_iter()
+            .map(|(key, value)| LocalStorageItem { key, value })
+            .collect();
+    }
+
+    /// 记录标签页当前渲染的 HTML 和 localStorage
+    pub fn record_tab(&self, tab: &Tab, url: &str) {
+        if let Ok(result) = tab.evaluate("document.documentElement.outerHTML", false) {
+            if let Some(html) = result.value.as_ref().and_then(|v| v.as_str()) {
+                self.record_page(url, html);
+            }
+        }
+        self.record_local_storage(tab);
+    }
+
+    /// 保存到夹具目录
+    pub fn save(&self, dir: &Path) -> Result<PathBuf, String> {
+        self.fixture.lock().unwrap().save(dir)?;
+        Ok(dir.to_path_buf())
+    }
+}
+
+/// 爬取的夹具模式
+#[derive(Clone, Default)]
+pub enum FixtureMode {
+    /// 正常访问网络
+    #[default]
+    Live,
+    /// 正常爬取并录制
+    Record(Arc<FixtureRecorder>),
+    /// 从夹具回放，不访问网络
+    Replay(Arc<ScrapeFixture>),
+}
@@ -0,0 +1,126 @@
+//! 视频元数据解析
+//!
+//! 各爬虫共用的时长、发布时间解析，以及从详情页 <meta> 标签（Open Graph、
+//! schema.org itemprop）中提取元数据的兜底逻辑。
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use regex::Regex;
+
+use crate::models::VideoMetadata;
+
+/// 解析时长为秒数，支持 "1:02:03"、"02:03"、ISO 8601 的 "PT1H2M3S" 和纯秒数
+pub fn parse_duration(text: &str) -> Option<i64> {
+    let text = text.trim();
+    if text.is_empty() {
+        return None;
+    }
+    if let Ok(secs) = text.parse::<f64>() {
+        return (secs >= 0.0).then_some(secs as i64);
+    }
+    if let Some(iso) = text.strip_prefix("PT").or_else(|| text.strip_prefix("pt")) {
+        let pattern = Regex::new(r"(?i)^(?:(\d+)H)?(?:(\d+)M)?(?:(\d+(?:\.\d+)?)S)?$").unwrap();
+        let cap = pattern.captures(iso)?;
+        let hours: i64 = cap.get(1).map_or(Some(0), |m| m.as_str().parse().ok())?;
+        let minutes: i64 = cap.get(2).map_or(Some(0), |m| m.as_str().parse().ok())?;
+        let seconds: f64 = cap.get(3).map_or(Some(0.0), |m| m.as_str().parse().ok())?;
+        return Some(hours * 3600 + minutes * 60 + seconds as i64);
+    }
+
+    let parts: Vec<i64> = text
+        .split(':')
+        .map(|p| p.trim().parse::<i64>().ok())
+        .collect::<Option<_>>()?;
+    match parts.as_slice() {
+        [m, s] => Some(m * 60 + s),
+        [h, m, s] => Some(h * 3600 + m * 60 + s),
+        _ => None,
+    }
+}
+
+/// 解析发布时间，支持 RFC 3339 和常见的 "2024-01-02 03:04:05"、"2024-01-02"、"2024/01/02" 格式
+pub fn parse_published(text: &str) -> Option<DateTime<Utc>> {
+    let text = text.trim();
+    if let Ok(dt) = DateTime::parse_from_rfc3339(text) {
+        return Some(dt.with_timezone(&Utc));
+    }
+    for format in ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S", "%Y/%m/%d %H:%M:%S"] {
+        if let Ok(dt) = NaiveDateTime::parse_from_str(text, format) {
+            return Some(dt.and_utc());
+        }
+    }
+    for format in ["%Y-%m-%d", "%Y/%m/%d", "%Y年%m月%d日"] {
+        if let Ok(date) = NaiveDate::parse_from_str(text, format) {
+            return date.and_hms_opt(0, 0, 0).map(|dt| dt.and_utc());
+        }
+    }
+    None
+}
+
+/// 去掉首尾空白、空标签和重复标签（保持原有顺序）
+pub fn normalize_tags(tags: impl IntoIterator<Item = String>) -> Vec<String> {
+    let mut result: Vec<String> = Vec::new();
+    for tag in tags {
+        let tag = tag.trim().trim_start_matches('#').trim().to_string();
+        if !tag.is_empty() && !result.contains(&tag) {
+            result.push(tag);
+        }
+    }
+    result
+}
+
+/// 从 HTML 的 <meta> 标签中提取元数据
+pub fn from_html_meta(html: &str) -> VideoMetadata {
+    let meta_pattern = Regex::new(r#"(?is)<meta\b[^>]*>"#).unwrap();
+    let key_pattern = Regex::new(r#"(?i)\b(?:property|name|itemprop)\s*=\s*["']([^"']+)["']"#).unwrap();
+    let content_pattern = Regex::new(r#"(?is)\bcontent\s*=\s*(?:"([^"]*)"|'([^']*)')"#).unwrap();
+
+    let mut metadata = VideoMetadata::default();
+    let mut tags: Vec<String> = Vec::new();
+    let mut keywords: Vec<String> = Vec::new();
+
+    for tag in meta_pattern.find_iter(html) {
+        let tag = tag.as_str();
+        let (Some(key), Some(content)) = (
+            key_pattern.captures(tag).and_then(|c| c.get(1)),
+            content_pattern.captures(tag).and_then(|c| c.get(1).or_else(|| c.get(2))),
+        ) else {
+            continue;
+        };
+        let content = decode_entities(content.as_str().trim());
+        if content.is_empty() {
+            continue;
+        }
+
+        match key.as_str().to_lowercase().as_str() {
+            "video:duration" | "duration" => {
+                metadata.duration = metadata.duration.or_else(|| parse_duration(&content));
+            }
+            "video:tag" | "article:tag" => tags.push(content),
+            "keywords" => keywords.extend(content.split([',', '，']).map(|k| k.to_string())),
+            "og:description" | "description" => {
+                metadata.description.get_or_insert(content);
+            }
+            "author" | "video:director" => {
+                metadata.uploader.get_or_insert(content);
+            }
+            "article:published_time" | "video:release_date" | "uploaddate" | "datepublished" => {
+                metadata.published_at = metadata.published_at.or_else(|| parse_published(&content));
+            }
+            _ => {}
+        }
+    }
+
+    // 没有专门的标签时使用关键词
+    metadata.tags = normalize_tags(if tags.is_empty() { keywords } else { tags });
+    metadata
+}
+
+/// 解码 meta 内容中常见的 HTML 实体
+fn decode_entities(text: &str) -> String {
+    text.replace("&amp;", "&")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&nbsp;", " ")
+}
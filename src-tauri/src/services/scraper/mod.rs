@@ -1,110 +1,173 @@
-use crate::models::{ScrapeResult, Website};
+use crate::models::{RateLimitConfig, ScrapeErrorKind, ScrapeResult, Website};
+use crate::services::cancel::CancelToken;
+use crate::services::rate_limit;
+use crate::services::retry::{self, RetryFailure};
+use headless_chrome::Tab;
 use serde::{Deserialize, Serialize};
 use std::future::Future;
 use std::pin::Pin;
+use std::sync::Arc;
+
+/// 爬虫能力标记
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScraperCapability {
+    /// 按视频ID爬取单个视频
+    SingleVideo,
+    /// 一次爬取列表页中的多个视频
+    ListPage,
+    /// 输入为页码，支持翻页
+    Pagination,
+    /// 依赖 headless Chrome
+    Browser,
+    /// 通过 Website.spider_config 配置
+    Configurable,
+}
 
 /// 爬虫信息
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ScraperInfo {
     pub id: String,
     pub name: String,
+    /// 爬虫能力
+    #[serde(default)]
+    pub capabilities: Vec<ScraperCapability>,
+    /// spider_config 的 JSON Schema（仅可配置爬虫）
+    #[serde(default)]
+    pub config_schema: Option<serde_json::Value>,
 }
 
-/// 爬虫 trait - 定义所有爬虫必须实现的方法
-pub trait Scraper: Send + Sync {
-    /// 获取爬虫标识名称
-    fn id(&self) -> &'static str;
+impl ScraperInfo {
+    pub fn new(id: &str, name: &str, capabilities: &[ScraperCapability]) -> Self {
+        Self {
+            id: id.to_string(),
+            name: name.to_string(),
+            capabilities: capabilities.to_vec(),
+            config_schema: None,
+        }
+    }
 
-    /// 爬取单个视频
-    fn scrape(
-        &self,
-        video_id: &str,
-        log_callback: impl Fn(String) + Clone + Send + Sync + 'static,
-    ) -> Pin<Box<dyn Future<Output = ScrapeResult> + Send>>;
-
-    /// 爬取所有视频（每个爬虫必须实现）
-    fn scrape_all(
-        &self,
-        video_id: &str,
-        log_callback: impl Fn(String) + Clone + Send + Sync + 'static,
-    ) -> Pin<Box<dyn Future<Output = Vec<ScrapeResult>> + Send + 'static>>;
+    pub fn with_config_schema(mut self, schema: serde_json::Value) -> Self {
+        self.config_schema = Some(schema);
+        self
+    }
+
+    /// 是否具备指定能力
+    pub fn supports(&self, capability: ScraperCapability) -> bool {
+        self.capabilities.contains(&capability)
+    }
 }
 
-/// 爬虫类型枚举
+/// 爬虫错误
+#[derive(Debug, thiserror::Error)]
+pub enum ScraperError {
+    #[error("未知的爬虫: {0}")]
+    UnknownScraper(String),
+    #[error("爬虫 {id} 配置无效: {message}")]
+    InvalidConfig { id: String, message: String },
+}
+
+/// 日志回调
+pub type LogCallback = Arc<dyn Fn(String) + Send + Sync>;
+
+/// 爬虫返回的 Future
+pub type ScrapeFuture<T> = Pin<Box<dyn Future<Output = T> + Send>>;
+
+/// 爬取上下文 - 携带日志回调、取消令牌、夹具模式等运行时依赖
 #[derive(Clone)]
-pub enum AnyScraper {
-    D1(D1Spider),
-    D2(D2Spider),
-    Srl(SrlSpider),
-    Nine(NineSpider),
+pub struct ScrapeContext {
+    pub log_callback: LogCallback,
+    pub cancel: CancelToken,
+    pub fixture: FixtureMode,
 }
 
-impl AnyScraper {
-    pub fn id(&self) -> &'static str {
-        match self {
-            AnyScraper::D1(scraper) => scraper.id(),
-            AnyScraper::D2(scraper) => scraper.id(),
-            AnyScraper::Srl(scraper) => scraper.id(),
-            AnyScraper::Nine(scraper) => scraper.id(),
+impl ScrapeContext {
+    pub fn new(log_callback: impl Fn(String) + Send + Sync + 'static) -> Self {
+        Self {
+            log_callback: Arc::new(log_callback),
+            cancel: CancelToken::new(),
+            fixture: FixtureMode::Live,
         }
     }
-}
 
-impl Scraper for AnyScraper {
-    fn id(&self) -> &'static str {
-        self.id()
-    }
-
-    fn scrape(
-        &self,
-        video_id: &str,
-        log_callback: impl Fn(String) + Clone + Send + Sync + 'static,
-    ) -> Pin<Box<dyn Future<Output = ScrapeResult> + Send>> {
-        match self {
-            AnyScraper::D1(scraper) => scraper.scrape(video_id, log_callback),
-            AnyScraper::D2(scraper) => scraper.scrape(video_id, log_callback),
-            AnyScraper::Srl(scraper) => scraper.scrape(video_id, log_callback),
-            AnyScraper::Nine(scraper) => scraper.scrape(video_id, log_callback),
+    /// 使用外部的取消令牌
+    pub fn with_cancel(mut self, cancel: CancelToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    /// 设置录制/回放模式
+    pub fn with_fixture(mut self, fixture: FixtureMode) -> Self {
+        self.fixture = fixture;
+        self
+    }
+
+    /// 录制模式下的录制器
+    pub fn recorder(&self) -> Option<Arc<FixtureRecorder>> {
+        match self.fixture {
+            FixtureMode::Record(ref recorder) => Some(recorder.clone()),
+            _ => None,
         }
     }
 
-    fn scrape_all(
-        &self,
-        video_id: &str,
-        log_callback: impl Fn(String) + Clone + Send + Sync + 'static,
-    ) -> Pin<Box<dyn Future<Output = Vec<ScrapeResult>> + Send>>
-    where
-        Self: Sized,
-    {
-        match self {
-            AnyScraper::D1(scraper) => scraper.scrape_all(video_id, log_callback),
-            AnyScraper::D2(scraper) => scraper.scrape_all(video_id, log_callback),
-            AnyScraper::Srl(scraper) => scraper.scrape_all(video_id, log_callback),
-            AnyScraper::Nine(scraper) => scraper.scrape_all(video_id, log_callback),
+    /// 回放模式下的夹具
+    pub fn replay(&self) -> Option<Arc<ScrapeFixture>> {
+        match self.fixture {
+            FixtureMode::Replay(ref fixture) => Some(fixture.clone()),
+            _ => None,
         }
     }
+
+    /// 输出日志
+    pub fn log(&self, message: impl Into<String>) {
+        (self.log_callback)(message.into());
+    }
+
+    /// 是否已被取消
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.is_cancelled()
+    }
+
+    /// 可取消的等待，被取消时返回 false
+    pub async fn sleep(&self, duration: std::time::Duration) -> bool {
+        self.cancel.sleep(duration).await
+    }
+
+    /// 导航标签页：遵守网站限速，失败时按重试策略重试，等待重试期间可以取消
+    pub async fn navigate(&self, tab: &Tab, url: &str, rate_limit: &RateLimitConfig) -> Result<(), RetryFailure> {
+        retry::run(&format!("导航 {}", url), Some(&self.cancel), move |_| async move {
+            let _permit = rate_limit::acquire(url, Some(rate_limit)).await;
+            tab.navigate_to(url)
+                .map(|_| ())
+                .map_err(|e| (ScrapeErrorKind::Navigation, e.to_string()))
+        })
+        .await
+    }
+}
+
+/// 爬虫 trait - 定义所有爬虫必须实现的方法（对象安全，可装箱注册）
+pub trait Scraper: Send + Sync {
+    /// 获取爬虫标识名称
+    fn id(&self) -> &'static str;
+
+    /// 爬取单个视频
+    fn scrape(&self, video_id: &str, ctx: ScrapeContext) -> ScrapeFuture<ScrapeResult>;
+
+    /// 爬取所有视频（默认只返回 scrape 的单个结果）
+    fn scrape_all(&self, video_id: &str, ctx: ScrapeContext) -> ScrapeFuture<Vec<ScrapeResult>> {
+        let result = self.scrape(video_id, ctx);
+        Box::pin(async move { vec![result.await] })
+    }
+
+    /// 重新爬取已入库的单个视频（video_id 为爬虫返回的视频ID，默认同 scrape）
+    fn refresh(&self, video_id: &str, ctx: ScrapeContext) -> ScrapeFuture<ScrapeResult> {
+        self.scrape(video_id, ctx)
+    }
 }
 
 /// 获取所有可用的爬虫列表
 pub fn get_available_scrapers() -> Vec<ScraperInfo> {
-    vec![
-        ScraperInfo {
-            id: "d1".to_string(),
-            name: "D1 CloudFront".to_string(),
-        },
-        ScraperInfo {
-            id: "d2".to_string(),
-            name: "D2 CloudFront List".to_string(),
-        },
-        ScraperInfo {
-            id: "srl".to_string(),
-            name: "SRL Wiki".to_string(),
-        },
-        ScraperInfo {
-            id: "nine".to_string(),
-            name: "91吃瓜".to_string(),
-        },
-    ]
+    ScraperRegistry::global().infos()
 }
 
 /// 爬虫工厂
@@ -112,17 +175,29 @@ pub struct ScraperFactory;
 
 impl ScraperFactory {
     /// 根据网站配置创建对应的爬虫
-    pub fn create_scraper(website: &Website) -> AnyScraper {
-        match website.spider.as_str() {
-            "d1" => AnyScraper::D1(D1Spider::new(website)),
-            "d2" => AnyScraper::D2(D2Spider::new(website)),
-            "srl" => AnyScraper::Srl(SrlSpider::new(website)),
-            "nine" => AnyScraper::Nine(NineSpider::new(website)),
-            _ => panic!("未知的爬虫: {}", website.spider),
-        }
+    pub fn create_scraper(website: &Website) -> Result<Box<dyn Scraper>, ScraperError> {
+        ScraperRegistry::global().create(website)
+    }
+
+    /// 获取网站使用的爬虫信息
+    pub fn scraper_info(website: &Website) -> Result<ScraperInfo, ScraperError> {
+        ScraperRegistry::global()
+            .info(&website.spider)
+            .ok_or_else(|| ScraperError::UnknownScraper(website.spider.clone()))
     }
 }
 
+// 录制/回放夹具
+pub mod fixture;
+use fixture::{FixtureMode, FixtureRecorder, ScrapeFixture};
+
+// 元数据解析
+mod metadata;
+
+// 爬虫注册表
+mod registry;
+pub use registry::ScraperRegistry;
+
 // D1 爬虫实现
 mod d1_spider;
 pub use d1_spider::D1Spider;
@@ -137,3 +212,7 @@ pub use srl_spider::SrlSpider;
 // 91 爬虫实现
 mod nine_spider;
 pub use nine_spider::NineSpider;
+
+// 规则爬虫实现（配置驱动）
+mod rule_spider;
+pub use rule_spider::RuleSpider;
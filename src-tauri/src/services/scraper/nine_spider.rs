@@ -1,16 +1,16 @@
-use crate::models::ScrapeResult;
-use crate::services::scraper::Scraper;
+use crate::models::{ScrapeErrorKind, ScrapeResult, VideoMetadata};
+use crate::services::scraper::{metadata, ScrapeContext, ScrapeFuture, Scraper};
+use crate::services::{cookies, proxy, rate_limit, retry, BrowserPool};
 use crate::Website;
-use headless_chrome::Browser;
 use reqwest::Client;
 use regex::Regex;
 use serde::Deserialize;
-use std::ffi::OsStr;
-use std::pin::Pin;
 use std::time::Duration;
-use std::future::Future;
 use tokio::sync::mpsc;
 
+/// 录制列表页时提取脚本结果的名称
+const VIDEO_LIST_SCRIPT: &str = "video_list";
+
 /// 视频列表项（包含ID和封面）
 #[derive(Debug, Clone, Deserialize)]
 pub struct VideoListItem {
@@ -28,8 +28,10 @@ pub struct NineSpider {
 
 impl NineSpider {
     pub fn new(website: &Website) -> Self {
-        let client = Client::builder()
-            .user_agent("Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36")
+        let builder = Client::builder()
+            .user_agent("Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36");
+        let builder = cookies::apply_to_client(builder, &cookies::resolve(website.cookie_source.as_ref()));
+        let client = proxy::apply_to_client(builder, &proxy::resolve(website.proxy.as_ref()))
             .build()
             .expect("Failed to create HTTP client");
 
@@ -59,51 +61,29 @@ impl NineSpider {
     }
 
     /// 使用headless_chrome提取视频列表（包含ID和封面），返回日志和视频列表
-    async fn extract_video_list_with_chrome(&self, page_number: &str) -> (Vec<String>, Vec<VideoListItem>) {
+    async fn extract_video_list_with_chrome(&self, page_number: &str, ctx: &ScrapeContext) -> (Vec<String>, Vec<VideoListItem>) {
         let page_url = self.build_url(&format!("/page/{}", page_number));
+        if let Some(fixture) = ctx.replay() {
+            let mut logs = vec![format!("[回放] 列表页: {}", page_url)];
+            let video_items = parse_video_items(fixture.script(&page_url, VIDEO_LIST_SCRIPT).unwrap_or("[]"), &mut logs);
+            return (logs, video_items);
+        }
         let mut logs = vec![format!("[Chrome] 访问列表页: {}", page_url)];
 
-        let browser_args: Vec<&OsStr> = vec![
-            OsStr::new("--headless=new"),
-            OsStr::new("--no-sandbox"),
-            OsStr::new("--disable-dev-shm-usage"),
-            OsStr::new("--disable-gpu"),
-            OsStr::new("--disable-software-rasterizer"),
-            OsStr::new("--mute-audio"),
-            OsStr::new("--hide-scrollbars"),
-            OsStr::new("--disable-translate"),
-            OsStr::new("--disable-background-networking"),
-            OsStr::new("--disable-sync"),
-            OsStr::new("--disable-features=site-per-process,TranslateUI"),
-            OsStr::new("--disable-extensions"),
-        ];
-
-        let browser = match Browser::new(
-            headless_chrome::LaunchOptions {
-                args: browser_args,
-                headless: true,
-                ..Default::default()
-            }
-        ) {
-            Ok(browser) => browser,
-            Err(e) => {
-                logs.push(format!("[Chrome] 启动浏览器失败: {}", e));
-                return (logs, Vec::new());
-            }
-        };
-
-        let tab = match browser.new_tab() {
-            Ok(tab) => tab,
+        // 从共享浏览器池借出独立标签页
+        let proxy = proxy::resolve(self.website.proxy.as_ref());
+        let lease = match BrowserPool::global().acquire_with_proxy(proxy).await {
+            Ok(lease) => lease,
             Err(e) => {
-                logs.push(format!("[Chrome] 创建标签页失败: {}", e));
+                logs.push(format!("[Chrome] {}", e));
                 return (logs, Vec::new());
             }
         };
+        let tab = lease.tab();
 
         // 导航到列表页
-        if tab.navigate_to(&page_url).is_err() {
-            logs.push("[Chrome] 导航失败".to_string());
-            let _ = tab.close(true);
+        if let Err(failure) = ctx.navigate(&tab, &page_url, &self.website.rate_limit).await {
+            logs.push(format!("[Chrome] 导航失败: {}", failure.message));
             return (logs, Vec::new());
         }
 
@@ -145,52 +125,78 @@ impl NineSpider {
             Err(_) => "[]".to_string(),
         };
 
-        // 关闭浏览器
-        let _ = tab.close(true);
+        if let Some(recorder) = ctx.recorder() {
+            recorder.record_script(&page_url, VIDEO_LIST_SCRIPT, &video_items_str);
+            recorder.record_tab(&tab, &page_url);
+        }
 
-        // 解析视频列表
-        let video_items: Vec<VideoListItem> = if video_items_str.starts_with("[") {
-            let parsed: Result<Vec<VideoListItem>, _> = serde_json::from_str(&video_items_str);
-            match parsed {
-                Ok(items) => items,
-                Err(e) => {
-                    logs.push(format!("[Chrome] 解析失败: {}", e));
-                    Vec::new()
-                }
-            }
-        } else {
-            Vec::new()
-        };
+        // 归还标签页
+        drop(lease);
 
-        logs.push(format!("[Chrome] 找到 {} 个视频", video_items.len()));
+        // 解析视频列表
+        let video_items = parse_video_items(&video_items_str, &mut logs);
         (logs, video_items)
     }
 
-    /// 使用reqwest从详情页提取m3u8
-    async fn fetch_m3u8_from_detail(&self, video_id: &str) -> Option<String> {
+    /// 使用reqwest获取详情页HTML（回放模式从夹具读取，录制模式记录响应）
+    async fn fetch_detail_html(&self, video_id: &str, ctx: &ScrapeContext) -> Option<String> {
         let video_url = self.build_url(&format!("/archives/{}", video_id));
 
-        match self.client.get(&video_url).send().await {
-            Ok(resp) if resp.status().is_success() => {
-                let html = resp.text().await.unwrap_or_default();
-                // 提取m3u8 URL
-                let m3u8_pattern = Regex::new(r#""([^"]+\.m3u8[^"]*)""#).unwrap();
-                if let Some(cap) = m3u8_pattern.captures(&html) {
-                    if let Some(m) = cap.get(1) {
-                        let url = m.as_str().to_string();
-                        return Some(if !url.starts_with("http") {
-                            self.build_url(&url)
-                        } else {
-                            url
-                        });
-                    }
-                }
-                None
-            }
-            _ => None,
+        if let Some(fixture) = ctx.replay() {
+            return fixture
+                .response(&video_url)
+                .filter(|r| (200..300).contains(&r.status))
+                .and_then(|r| r.body.clone());
+        }
+
+        // 详情页之间按网站配置等待
+        if !ctx.sleep(rate_limit::detail_delay(&self.website.rate_limit)).await {
+            return None;
+        }
+        let url = video_url.as_str();
+        retry::run(&format!("详情页 {}", url), Some(&ctx.cancel), move |_| self.request_detail_html(url, ctx))
+            .await
+            .ok()
+    }
+
+    /// 请求一次详情页（遵守网站限速，录制模式记录响应）
+    async fn request_detail_html(&self, video_url: &str, ctx: &ScrapeContext) -> Result<String, (ScrapeErrorKind, String)> {
+        let _permit = rate_limit::acquire(video_url, Some(&self.website.rate_limit)).await;
+        let resp = self.client.get(video_url).send().await.map_err(|e| {
+            let kind = if e.is_timeout() { ScrapeErrorKind::Timeout } else { ScrapeErrorKind::Navigation };
+            (kind, format!("请求失败: {}", e))
+        })?;
+        let status = resp.status();
+        let html = resp
+            .text()
+            .await
+            .map_err(|e| (ScrapeErrorKind::Navigation, format!("读取响应失败: {}", e)))?;
+        if let Some(recorder) = ctx.recorder() {
+            recorder.record_response(video_url, status.as_u16(), Some(&html));
+        }
+        match ScrapeErrorKind::from_http_status(status.as_u16()) {
+            Some(kind) => Err((kind, format!("HTTP {}", status))),
+            None => Ok(html),
         }
     }
 
+    /// 从详情页提取m3u8
+    async fn fetch_m3u8_from_detail(&self, video_id: &str, ctx: &ScrapeContext) -> Option<String> {
+        let html = self.fetch_detail_html(video_id, ctx).await?;
+        self.extract_m3u8(&html)
+    }
+
+    /// 从详情页HTML中提取m3u8 URL
+    fn extract_m3u8(&self, html: &str) -> Option<String> {
+        let m3u8_pattern = Regex::new(r#""([^"]+\.m3u8[^"]*)""#).unwrap();
+        let url = m3u8_pattern.captures(html)?.get(1)?.as_str().to_string();
+        Some(if !url.starts_with("http") {
+            self.build_url(&url)
+        } else {
+            url
+        })
+    }
+
     /// 提取标题
     fn extract_title(&self, html: &str) -> String {
         let title_pattern = Regex::new(
@@ -216,27 +222,100 @@ impl NineSpider {
     }
 }
 
+/// 解析列表页提取脚本返回的 JSON
+fn parse_video_items(video_items_str: &str, logs: &mut Vec<String>) -> Vec<VideoListItem> {
+    let video_items: Vec<VideoListItem> = if video_items_str.starts_with("[") {
+        let parsed: Result<Vec<VideoListItem>, _> = serde_json::from_str(video_items_str);
+        match parsed {
+            Ok(items) => items,
+            Err(e) => {
+                logs.push(format!("[Chrome] 解析失败: {}", e));
+                Vec::new()
+            }
+        }
+    } else {
+        Vec::new()
+    };
+
+    logs.push(format!("[Chrome] 找到 {} 个视频", video_items.len()));
+    video_items
+}
+
 impl Scraper for NineSpider {
     fn id(&self) -> &'static str {
         "nine"
     }
 
+    fn refresh(&self, video_id: &str, ctx: ScrapeContext) -> ScrapeFuture<ScrapeResult> {
+        let video_id = video_id.to_string();
+        let spider = self.clone();
+
+        // 直接请求详情页，不经过列表页
+        Box::pin(async move {
+            let html = spider.fetch_detail_html(&video_id, &ctx).await;
+            if ctx.is_cancelled() {
+                return ScrapeResult::cancelled(Some(video_id));
+            }
+            let (m3u8_url, html) = match html {
+                Some(html) => (spider.extract_m3u8(&html), html),
+                None => (None, String::new()),
+            };
+            let Some(m3u8_url) = m3u8_url else {
+                let (message, error_kind) = if html.is_empty() {
+                    ("获取详情页失败", ScrapeErrorKind::Navigation)
+                } else {
+                    ("详情页中未找到 m3u8 地址", ScrapeErrorKind::ParseFailed)
+                };
+                return ScrapeResult {
+                    success: false,
+                    name: format!("视频_{}", video_id),
+                    m3u8_url: String::new(),
+                    message: message.to_string(),
+                    video_id: Some(video_id),
+                    view_count: None,
+                    favorite_count: None,
+                    cover_url: None,
+                    error_kind: Some(error_kind),
+                    metadata: VideoMetadata::default(),
+                };
+            };
+
+            let title = spider.extract_title(&html);
+            ScrapeResult {
+                success: true,
+                name: if title.is_empty() { format!("视频_{}", video_id) } else { title },
+                m3u8_url,
+                message: "成功获取视频".to_string(),
+                video_id: Some(video_id),
+                view_count: None,
+                favorite_count: None,
+                cover_url: None,
+                error_kind: None,
+                metadata: metadata::from_html_meta(&html),
+            }
+        })
+    }
+
     fn scrape(
         &self,
         page_number: &str,
-        log_callback: impl Fn(String) + Clone + Send + Sync + 'static,
-    ) -> Pin<Box<dyn Future<Output = ScrapeResult> + Send>> {
+        ctx: ScrapeContext,
+    ) -> ScrapeFuture<ScrapeResult> {
         let page_number = page_number.to_string();
-        let log_callback = log_callback.clone();
+        let log_callback = ctx.log_callback.clone();
         let spider = self.clone();
 
         Box::pin(async move {
             // 1. 用headless_chrome提取视频列表（ID+封面）
-            let (chrome_logs, video_items) = spider.extract_video_list_with_chrome(&page_number).await;
+            let (chrome_logs, video_items) = spider.extract_video_list_with_chrome(&page_number, &ctx).await;
             for log in chrome_logs {
                 log_callback(log);
             }
 
+            if ctx.is_cancelled() {
+                return ScrapeResult::cancelled(None);
+            }
+
             if video_items.is_empty() {
                 return ScrapeResult {
                     success: false,
@@ -247,6 +326,8 @@ impl Scraper for NineSpider {
                     view_count: None,
                     favorite_count: None,
                     cover_url: None,
+                    error_kind: Some(ScrapeErrorKind::ParseFailed),
+                    metadata: VideoMetadata::default(),
                 };
             }
 
@@ -257,9 +338,10 @@ impl Scraper for NineSpider {
                 let video_id = item.video_id.clone();
                 let spider = spider.clone();
                 let result_tx = result_tx.clone();
+                let ctx = ctx.clone();
 
                 tokio::spawn(async move {
-                    let m3u8_url = spider.fetch_m3u8_from_detail(&video_id).await;
+                    let m3u8_url = spider.fetch_m3u8_from_detail(&video_id, &ctx).await;
                     let _ = result_tx.send((video_id.clone(), m3u8_url.clone())).await;
                     
                 });
@@ -277,14 +359,7 @@ impl Scraper for NineSpider {
                 if let Some((_, m3u8_url)) = results.iter().find(|(id, _)| id == &item.video_id) {
                     if let Some(ref url) = m3u8_url {
                         // 获取标题
-                        let video_url = spider.build_url(&format!("/archives/{}", item.video_id));
-                        let html = match spider.client.get(&video_url).send().await {
-                            Ok(resp) => match resp.text().await {
-                                Ok(text) => text,
-                                Err(_) => String::new(),
-                            },
-                            Err(_) => String::new(),
-                        };
+                        let html = spider.fetch_detail_html(&item.video_id, &ctx).await.unwrap_or_default();
                         let title = spider.extract_title(&html);
 
                         let cover_url = item.cover.clone().filter(|c| c.starts_with("data:image"));
@@ -298,6 +373,8 @@ impl Scraper for NineSpider {
                             view_count: None,
                             favorite_count: None,
                             cover_url,
+                            error_kind: None,
+                            metadata: metadata::from_html_meta(&html),
                         };
                     } else {
                         continue;
@@ -314,6 +391,8 @@ impl Scraper for NineSpider {
                 view_count: None,
                 favorite_count: None,
                 cover_url: None,
+                error_kind: Some(ScrapeErrorKind::ParseFailed),
+                metadata: VideoMetadata::default(),
             }
         })
     }
@@ -321,22 +400,23 @@ impl Scraper for NineSpider {
     fn scrape_all(
         &self,
         page_number: &str,
-        log_callback: impl Fn(String) + Clone + Send + Sync + 'static,
-    ) -> Pin<Box<dyn Future<Output = Vec<ScrapeResult>> + Send>>
-    where
-        Self: Sized,
-    {
+        ctx: ScrapeContext,
+    ) -> ScrapeFuture<Vec<ScrapeResult>> {
         let page_number = page_number.to_string();
-        let log_callback = log_callback.clone();
+        let log_callback = ctx.log_callback.clone();
         let spider = self.clone();
 
         Box::pin(async move {
             // 1. 用headless_chrome提取视频列表
-            let (chrome_logs, video_items) = spider.extract_video_list_with_chrome(&page_number).await;
+            let (chrome_logs, video_items) = spider.extract_video_list_with_chrome(&page_number, &ctx).await;
             for log in chrome_logs {
                 log_callback(log);
             }
 
+            if ctx.is_cancelled() {
+                return vec![ScrapeResult::cancelled(None)];
+            }
+
             let total_count = video_items.len();
             log_callback(format!("开始爬取 {} 个视频...", total_count));
 
@@ -350,6 +430,8 @@ impl Scraper for NineSpider {
                     view_count: None,
                     favorite_count: None,
                     cover_url: None,
+                    error_kind: Some(ScrapeErrorKind::ParseFailed),
+                    metadata: VideoMetadata::default(),
                 }];
             }
 
@@ -360,9 +442,10 @@ impl Scraper for NineSpider {
                 let video_id = item.video_id.clone();
                 let spider = spider.clone();
                 let result_tx = result_tx.clone();
+                let ctx = ctx.clone();
 
                 tokio::spawn(async move {
-                    let m3u8_url = spider.fetch_m3u8_from_detail(&video_id).await;
+                    let m3u8_url = spider.fetch_m3u8_from_detail(&video_id, &ctx).await;
                     let _ = result_tx.send((video_id.clone(), m3u8_url.clone())).await;
                 });
             }
@@ -388,14 +471,7 @@ impl Scraper for NineSpider {
 
                 if let Some(url) = m3u8_url {
                     // 获取标题
-                    let video_url = spider.build_url(&format!("/archives/{}", item.video_id));
-                    let html = match spider.client.get(&video_url).send().await {
-                        Ok(resp) => match resp.text().await {
-                            Ok(text) => text,
-                            Err(_) => String::new(),
-                        },
-                        Err(_) => String::new(),
-                    };
+                    let html = spider.fetch_detail_html(&item.video_id, &ctx).await.unwrap_or_default();
                     let title = spider.extract_title(&html);
 
                     results.push(ScrapeResult {
@@ -407,6 +483,8 @@ impl Scraper for NineSpider {
                         view_count: None,
                         favorite_count: None,
                         cover_url,
+                        error_kind: None,
+                        metadata: metadata::from_html_meta(&html),
                     });
                     success_count += 1;
                 } else {
@@ -419,11 +497,16 @@ impl Scraper for NineSpider {
                         view_count: None,
                         favorite_count: None,
                         cover_url: None,
+                        error_kind: Some(ScrapeErrorKind::ParseFailed),
+                        metadata: VideoMetadata::default(),
                     });
                 }
 
-                // 短暂延迟
-                tokio::time::sleep(Duration::from_millis(200)).await;
+                // 短暂延迟，期间被取消则放弃剩余视频
+                if !ctx.sleep(Duration::from_millis(200)).await {
+                    log_callback("爬取已取消".to_string());
+                    return vec![ScrapeResult::cancelled(None)];
+                }
             }
 
             log_callback(format!("完成: 成功 {} / 总数 {}", success_count, total_count));
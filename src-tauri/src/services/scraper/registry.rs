@@ -0,0 +1,161 @@
+use super::{
+    D1Spider, D2Spider, NineSpider, RuleSpider, Scraper, ScraperCapability, ScraperError,
+    ScraperInfo, SrlSpider,
+};
+use crate::models::Website;
+use once_cell::sync::Lazy;
+use std::sync::{Arc, RwLock};
+
+/// 爬虫构造函数
+pub type ScraperConstructor =
+    Arc<dyn Fn(&Website) -> Result<Box<dyn Scraper>, ScraperError> + Send + Sync>;
+
+/// 注册表条目
+struct ScraperEntry {
+    info: ScraperInfo,
+    constructor: ScraperConstructor,
+}
+
+/// 爬虫注册表 - 按 id 保存爬虫元数据和构造函数，支持运行时注册
+pub struct ScraperRegistry {
+    entries: RwLock<Vec<ScraperEntry>>,
+}
+
+/// 全局注册表（内置爬虫在首次访问时注册）
+static REGISTRY: Lazy<ScraperRegistry> = Lazy::new(ScraperRegistry::with_builtin);
+
+impl ScraperRegistry {
+    /// 创建空注册表
+    pub fn new() -> Self {
+        Self {
+            entries: RwLock::new(Vec::new()),
+        }
+    }
+
+    /// 获取全局注册表
+    pub fn global() -> &'static ScraperRegistry {
+        &REGISTRY
+    }
+
+    /// 创建包含内置爬虫的注册表
+    fn with_builtin() -> Self {
+        use ScraperCapability::*;
+
+        let registry = Self::new();
+        registry.register(
+            ScraperInfo::new("d1", "D1 CloudFront", &[SingleVideo, Browser]),
+            |website| Ok(Box::new(D1Spider::new(website))),
+        );
+        registry.register(
+            ScraperInfo::new("d2", "D2 CloudFront List", &[ListPage, Browser]),
+            |website| Ok(Box::new(D2Spider::new(website))),
+        );
+        registry.register(
+            ScraperInfo::new("srl", "SRL Wiki", &[ListPage, Pagination, Browser]),
+            |website| Ok(Box::new(SrlSpider::new(website))),
+        );
+        registry.register(
+            ScraperInfo::new("nine", "91吃瓜", &[ListPage, Pagination, Browser]),
+            |website| Ok(Box::new(NineSpider::new(website))),
+        );
+        registry.register(
+            ScraperInfo::new("rule", "规则爬虫 (自定义)", &[ListPage, Pagination, SingleVideo, Configurable])
+                .with_config_schema(rule_config_schema()),
+            |website| {
+                RuleSpider::new(website)
+                    .map(|spider| Box::new(spider) as Box<dyn Scraper>)
+                    .map_err(|message| ScraperError::InvalidConfig {
+                        id: "rule".to_string(),
+                        message,
+                    })
+            },
+        );
+        registry
+    }
+
+    /// 注册爬虫，id 已存在时替换
+    pub fn register<F>(&self, info: ScraperInfo, constructor: F)
+    where
+        F: Fn(&Website) -> Result<Box<dyn Scraper>, ScraperError> + Send + Sync + 'static,
+    {
+        let mut entries = self.entries.write().unwrap();
+        let entry = ScraperEntry {
+            info,
+            constructor: Arc::new(constructor),
+        };
+        match entries.iter_mut().find(|e| e.info.id == entry.info.id) {
+            Some(existing) => *existing = entry,
+            None => entries.push(entry),
+        }
+    }
+
+    /// 获取所有爬虫信息（按注册顺序）
+    pub fn infos(&self) -> Vec<ScraperInfo> {
+        self.entries
+            .read()
+            .unwrap()
+            .iter()
+            .map(|e| e.info.clone())
+            .collect()
+    }
+
+    /// 获取指定爬虫的信息
+    pub fn info(&self, id: &str) -> Option<ScraperInfo> {
+        self.entries
+            .read()
+            .unwrap()
+            .iter()
+            .find(|e| e.info.id == id)
+            .map(|e| e.info.clone())
+    }
+
+    /// 根据网站配置创建爬虫
+    pub fn create(&self, website: &Website) -> Result<Box<dyn Scraper>, ScraperError> {
+        // 先取出构造函数再释放锁，避免构造过程中持有读锁
+        let constructor = self
+            .entries
+            .read()
+            .unwrap()
+            .iter()
+            .find(|e| e.info.id == website.spider)
+            .map(|e| Arc::clone(&e.constructor))
+            .ok_or_else(|| ScraperError::UnknownScraper(website.spider.clone()))?;
+
+        constructor(website)
+    }
+}
+
+impl Default for ScraperRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 规则爬虫配置的 JSON Schema
+fn rule_config_schema() -> serde_json::Value {
+    serde_json::json!({
+        "type": "object",
+        "properties": {
+            "list_url": { "type": "string", "description": "列表页地址模板，支持 {page}" },
+            "item_pattern": { "type": "string", "description": "列表项正则，命名分组 id/url/title/cover" },
+            "detail_url": { "type": "string", "description": "详情页地址模板，支持 {id}" },
+            "m3u8_pattern": { "type": "string", "description": "详情页 m3u8 提取正则" },
+            "title_pattern": { "type": "string", "description": "标题提取正则" },
+            "cover_pattern": { "type": "string", "description": "封面提取正则" },
+            "view_count_pattern": { "type": "string", "description": "播放数提取正则" },
+            "favorite_count_pattern": { "type": "string", "description": "收藏数提取正则" },
+            "duration_pattern": { "type": "string", "description": "时长提取正则" },
+            "tags_pattern": { "type": "string", "description": "标签提取正则（匹配所有结果）" },
+            "description_pattern": { "type": "string", "description": "简介提取正则" },
+            "uploader_pattern": { "type": "string", "description": "上传者提取正则" },
+            "published_pattern": { "type": "string", "description": "发布时间提取正则" },
+            "headers": { "type": "object", "additionalProperties": { "type": "string" } },
+            "concurrency": { "type": "integer", "minimum": 1, "default": 4 },
+            "delay_ms": { "type": "integer", "minimum": 0, "default": 200 }
+        },
+        "anyOf": [
+            { "required": ["list_url", "item_pattern"] },
+            { "required": ["detail_url"] }
+        ]
+    })
+}
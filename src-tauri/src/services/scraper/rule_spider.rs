@@ -0,0 +1,635 @@
+use crate::models::{ScrapeErrorKind, ScrapeResult, VideoMetadata};
+use crate::services::{cookies, proxy, rate_limit, retry};
+use crate::services::scraper::{metadata, ScrapeContext, ScrapeFuture, Scraper};
+use crate::Website;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// 规则爬虫配置（存放在 Website.spider_config 中，支持 JSON 或 TOML）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RuleConfig {
+    /// 列表页地址模板，支持 {page} 占位符，可以是相对 base_url 的路径
+    pub list_url: String,
+    /// 列表项正则，命名分组：id（必需）、url、title、cover
+    pub item_pattern: String,
+    /// 详情页地址模板，支持 {id} 占位符；为空时使用列表项的 url 分组
+    pub detail_url: String,
+    /// 详情页 m3u8 提取正则（命名分组 url 或第一个分组）
+    pub m3u8_pattern: String,
+    /// 标题提取正则（作用于详情页）
+    pub title_pattern: String,
+    /// 封面提取正则（作用于详情页，列表项 cover 分组优先）
+    pub cover_pattern: String,
+    /// 播放数提取正则
+    pub view_count_pattern: String,
+    /// 收藏数提取正则
+    pub favorite_count_pattern: String,
+    /// 时长提取正则（支持 01:02:03、PT1H2M3S 或秒数）
+    pub duration_pattern: String,
+    /// 标签提取正则（匹配所有结果，命名分组 tag 或第一个分组）
+    pub tags_pattern: String,
+    /// 简介提取正则
+    pub description_pattern: String,
+    /// 上传者提取正则
+    pub uploader_pattern: String,
+    /// 发布时间提取正则
+    pub published_pattern: String,
+    /// 额外请求头
+    pub headers: HashMap<String, String>,
+    /// 详情页并发数
+    pub concurrency: usize,
+    /// 详情页请求间隔（毫秒）
+    pub delay_ms: u64,
+    /// 解析时编译好的正则
+    #[serde(skip)]
+    patterns: RulePatterns,
+}
+
+/// 编译后的规则正则（未配置的为 None）
+#[derive(Debug, Clone, Default)]
+struct RulePatterns {
+    item: Option<Regex>,
+    m3u8: Option<Regex>,
+    title: Option<Regex>,
+    cover: Option<Regex>,
+    view_count: Option<Regex>,
+    favorite_count: Option<Regex>,
+    duration: Option<Regex>,
+    tags: Option<Regex>,
+    description: Option<Regex>,
+    uploader: Option<Regex>,
+    published: Option<Regex>,
+}
+
+impl Default for RuleConfig {
+    fn default() -> Self {
+        Self {
+            list_url: String::new(),
+            item_pattern: String::new(),
+            detail_url: String::new(),
+            m3u8_pattern: r#""([^"]+\.m3u8[^"]*)""#.to_string(),
+            title_pattern: r#"<title>(.*?)</title>"#.to_string(),
+            cover_pattern: String::new(),
+            view_count_pattern: String::new(),
+            favorite_count_pattern: String::new(),
+            duration_pattern: String::new(),
+            tags_pattern: String::new(),
+            description_pattern: String::new(),
+            uploader_pattern: String::new(),
+            published_pattern: String::new(),
+            headers: HashMap::new(),
+            concurrency: 4,
+            delay_ms: 200,
+            patterns: RulePatterns::default(),
+        }
+    }
+}
+
+impl RuleConfig {
+    /// 解析规则文档：以 { 开头按 JSON 解析，否则按 TOML 解析
+    pub fn parse(doc: &str) -> Result<Self, String> {
+        let doc = doc.trim();
+        if doc.is_empty() {
+            return Err("规则配置为空".to_string());
+        }
+        let mut config: RuleConfig = if doc.starts_with('{') {
+            serde_json::from_str(doc).map_err(|e| format!("规则 JSON 解析失败: {}", e))?
+        } else {
+            toml::from_str(doc).map_err(|e| format!("规则 TOML 解析失败: {}", e))?
+        };
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// 校验规则中的必填项并编译正则
+    fn validate(&mut self) -> Result<(), String> {
+        if self.list_url.is_empty() && self.detail_url.is_empty() {
+            return Err("list_url 和 detail_url 至少需要配置一个".to_string());
+        }
+        if !self.list_url.is_empty() && self.item_pattern.is_empty() {
+            return Err("配置了 list_url 时必须配置 item_pattern".to_string());
+        }
+        self.patterns = RulePatterns {
+            item: compile_pattern("item_pattern", &self.item_pattern)?,
+            m3u8: compile_pattern("m3u8_pattern", &self.m3u8_pattern)?,
+            title: compile_pattern("title_pattern", &self.title_pattern)?,
+            cover: compile_pattern("cover_pattern", &self.cover_pattern)?,
+            view_count: compile_pattern("view_count_pattern", &self.view_count_pattern)?,
+            favorite_count: compile_pattern("favorite_count_pattern", &self.favorite_count_pattern)?,
+            duration: compile_pattern("duration_pattern", &self.duration_pattern)?,
+            tags: compile_pattern("tags_pattern", &self.tags_pattern)?,
+            description: compile_pattern("description_pattern", &self.description_pattern)?,
+            uploader: compile_pattern("uploader_pattern", &self.uploader_pattern)?,
+            published: compile_pattern("published_pattern", &self.published_pattern)?,
+        };
+        Ok(())
+    }
+}
+
+/// 编译规则中的正则（为空表示未配置）
+fn compile_pattern(field: &str, pattern: &str) -> Result<Option<Regex>, String> {
+    if pattern.is_empty() {
+        return Ok(None);
+    }
+    Regex::new(pattern)
+        .map(Some)
+        .map_err(|e| format!("{} 正则无效: {}", field, e))
+}
+
+/// HTML 标签
+static TAG_PATTERN: Lazy<Regex> = Lazy::new(|| Regex::new(r#"<[^>]+>"#).unwrap());
+
+/// 列表页中提取到的条目
+#[derive(Debug, Clone)]
+struct RuleItem {
+    id: String,
+    url: Option<String>,
+    title: Option<String>,
+    cover: Option<String>,
+}
+
+/// 规则爬虫 - 根据 Website 上的规则文档用 reqwest + 正则爬取，无需重新编译
+#[derive(Clone)]
+pub struct RuleSpider {
+    website: Website,
+    config: Arc<RuleConfig>,
+    client: Client,
+}
+
+impl RuleSpider {
+    pub fn new(website: &Website) -> Result<Self, String> {
+        let config = RuleConfig::parse(&website.spider_config)?;
+        let builder = Client::builder()
+            .user_agent("Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36")
+            .timeout(Duration::from_secs(30));
+        let builder = cookies::apply_to_client(builder, &cookies::resolve(website.cookie_source.as_ref()));
+        let client = proxy::apply_to_client(builder, &proxy::resolve(website.proxy.as_ref()))
+            .build()
+            .map_err(|e| format!("创建 HTTP 客户端失败: {}", e))?;
+
+        Ok(Self {
+            website: website.clone(),
+            config: Arc::new(config),
+            client,
+        })
+    }
+
+    /// 构建完整URL
+    fn build_url(&self, path: &str) -> String {
+        let base = self.website.base_url.trim_end_matches('/');
+        if path.starts_with("http") {
+            path.to_string()
+        } else if let Some(rest) = path.strip_prefix("//") {
+            format!("https://{}", rest)
+        } else if path.starts_with('/') {
+            format!("{}{}", base, path)
+        } else {
+            format!("{}/{}", base, path)
+        }
+    }
+
+    /// 请求页面文本，失败时返回失败类型和原因（回放模式从夹具读取，录制模式记录响应）
+    async fn fetch_text(
+        &self,
+        config: &RuleConfig,
+        url: &str,
+        ctx: &ScrapeContext,
+    ) -> Result<String, (ScrapeErrorKind, String)> {
+        if let Some(fixture) = ctx.replay() {
+            let response = fixture
+                .response(url)
+                .ok_or_else(|| (ScrapeErrorKind::Navigation, format!("夹具中没有该请求: {}", url)))?;
+            if let Some(kind) = ScrapeErrorKind::from_http_status(response.status) {
+                return Err((kind, format!("HTTP {}", response.status)));
+            }
+            return Ok(response.body.clone().unwrap_or_default());
+        }
+
+        retry::run(&format!("请求 {}", url), Some(&ctx.cancel), move |_| self.request_text(config, url, ctx))
+            .await
+            .map_err(|failure| (failure.kind, failure.message))
+    }
+
+    /// 请求一次页面文本（遵守网站限速，录制模式记录响应）
+    async fn request_text(
+        &self,
+        config: &RuleConfig,
+        url: &str,
+        ctx: &ScrapeContext,
+    ) -> Result<String, (ScrapeErrorKind, String)> {
+        let _permit = rate_limit::acquire(url, Some(&self.website.rate_limit)).await;
+        let mut request = self.client.get(url);
+        for (key, value) in &config.headers {
+            request = request.header(key.as_str(), value.as_str());
+        }
+        let resp = request.send().await.map_err(|e| {
+            let kind = if e.is_timeout() { ScrapeErrorKind::Timeout } else { ScrapeErrorKind::Navigation };
+            (kind, format!("请求失败: {}", e))
+        })?;
+        let status = resp.status();
+        let text = resp
+            .text()
+            .await
+            .map_err(|e| (ScrapeErrorKind::Navigation, format!("读取响应失败: {}", e)))?;
+        if let Some(recorder) = ctx.recorder() {
+            recorder.record_response(url, status.as_u16(), Some(&text));
+        }
+        if let Some(kind) = ScrapeErrorKind::from_http_status(status.as_u16()) {
+            return Err((kind, format!("HTTP {}", status)));
+        }
+        Ok(text)
+    }
+
+    /// 按规则提取元数据，规则未配置或没有匹配的项使用页面 meta 标签中的值
+    fn extract_metadata(config: &RuleConfig, html: &str) -> VideoMetadata {
+        let fallback = metadata::from_html_meta(html);
+        let patterns = &config.patterns;
+        let tags = capture_all(patterns.tags.as_ref(), html, "tag");
+        VideoMetadata {
+            duration: capture_first(patterns.duration.as_ref(), html, "duration")
+                .and_then(|d| metadata::parse_duration(&clean_text(&d)))
+                .or(fallback.duration),
+            tags: if tags.is_empty() {
+                fallback.tags
+            } else {
+                metadata::normalize_tags(tags.into_iter().map(|t| clean_text(&t)))
+            },
+            description: capture_first(patterns.description.as_ref(), html, "description")
+                .map(|d| clean_text(&d))
+                .filter(|d| !d.is_empty())
+                .or(fallback.description),
+            uploader: capture_first(patterns.uploader.as_ref(), html, "uploader")
+                .map(|u| clean_text(&u))
+                .filter(|u| !u.is_empty())
+                .or(fallback.uploader),
+            published_at: capture_first(patterns.published.as_ref(), html, "published")
+                .and_then(|p| metadata::parse_published(&clean_text(&p)))
+                .or(fallback.published_at),
+        }
+    }
+
+    /// 从列表页提取条目
+    fn extract_items(config: &RuleConfig, html: &str) -> Vec<RuleItem> {
+        let Some(pattern) = &config.patterns.item else {
+            return Vec::new();
+        };
+
+        let mut items: Vec<RuleItem> = Vec::new();
+        for cap in pattern.captures_iter(html) {
+            let id = match cap.name("id").or_else(|| cap.get(1)) {
+                Some(m) => m.as_str().trim().to_string(),
+                None => continue,
+            };
+            if id.is_empty() || items.iter().any(|i| i.id == id) {
+                continue;
+            }
+            items.push(RuleItem {
+                id,
+                url: cap.name("url").map(|m| m.as_str().to_string()),
+                title: cap.name("title").map(|m| clean_text(m.as_str())),
+                cover: cap.name("cover").map(|m| m.as_str().to_string()),
+            });
+        }
+        items
+    }
+
+    /// 爬取单个详情页
+    async fn scrape_detail(&self, config: &RuleConfig, item: &RuleItem, ctx: &ScrapeContext) -> ScrapeResult {
+        let detail_url = if !config.detail_url.is_empty() {
+            self.build_url(&config.detail_url.replace("{id}", &item.id))
+        } else if let Some(ref url) = item.url {
+            self.build_url(url)
+        } else {
+            self.build_url(&item.id)
+        };
+
+        // 详情页之间按网站配置等待（回放模式不等待）
+        if ctx.replay().is_none() && !ctx.sleep(rate_limit::detail_delay(&self.website.rate_limit)).await {
+            return ScrapeResult::cancelled(Some(item.id.clone()));
+        }
+
+        let html = match self.fetch_text(config, &detail_url, ctx).await {
+            Ok(html) => html,
+            Err((kind, e)) => {
+                return ScrapeResult {
+                    success: false,
+                    name: item.title.clone().unwrap_or_else(|| format!("视频_{}", item.id)),
+                    m3u8_url: String::new(),
+                    message: format!("详情页获取失败: {}", e),
+                    video_id: Some(item.id.clone()),
+                    view_count: None,
+                    favorite_count: None,
+                    cover_url: None,
+                    error_kind: Some(kind),
+                    metadata: VideoMetadata::default(),
+                };
+            }
+        };
+
+        let patterns = &config.patterns;
+        let m3u8_url = capture_first(patterns.m3u8.as_ref(), &html, "url")
+            .map(|url| self.build_url(&url.replace("\\/", "/")));
+        let title = capture_first(patterns.title.as_ref(), &html, "title")
+            .map(|t| clean_text(&t))
+            .filter(|t| !t.is_empty())
+            .or_else(|| item.title.clone())
+            .unwrap_or_else(|| format!("视频_{}", item.id));
+        let cover_url = item
+            .cover
+            .clone()
+            .or_else(|| capture_first(patterns.cover.as_ref(), &html, "cover"))
+            .map(|c| if c.starts_with("data:") { c } else { self.build_url(&c) });
+        let view_count = capture_first(patterns.view_count.as_ref(), &html, "count")
+            .and_then(|s| parse_count(&s));
+        let favorite_count = capture_first(patterns.favorite_count.as_ref(), &html, "count")
+            .and_then(|s| parse_count(&s));
+        let meta = Self::extract_metadata(config, &html);
+
+        match m3u8_url {
+            Some(url) => ScrapeResult {
+                success: true,
+                name: title,
+                m3u8_url: url,
+                message: "爬取成功".to_string(),
+                video_id: Some(item.id.clone()),
+                view_count,
+                favorite_count,
+                cover_url,
+                error_kind: None,
+                metadata: meta,
+            },
+            None => ScrapeResult {
+                success: false,
+                name: title,
+                m3u8_url: String::new(),
+                message: "未找到m3u8".to_string(),
+                video_id: Some(item.id.clone()),
+                view_count: None,
+                favorite_count: None,
+                cover_url: None,
+                error_kind: Some(ScrapeErrorKind::ParseFailed),
+                metadata: VideoMetadata::default(),
+            },
+        }
+    }
+
+    /// 按规则爬取：有 list_url 时把输入当作页码（或完整列表页地址），否则当作详情页ID
+    async fn run(&self, input: &str, ctx: &ScrapeContext) -> Vec<ScrapeResult> {
+        let config = self.config.as_ref();
+
+        let items = if config.list_url.is_empty() {
+            vec![RuleItem {
+                id: input.to_string(),
+                url: None,
+                title: None,
+                cover: None,
+            }]
+        } else {
+            let list_url = if input.starts_with("http") {
+                input.to_string()
+            } else {
+                self.build_url(&config.list_url.replace("{page}", input))
+            };
+            ctx.log(format!("[Rule] 访问列表页: {}", list_url));
+
+            let html = match self.fetch_text(config, &list_url, ctx).await {
+                Ok(html) => html,
+                Err((kind, e)) => {
+                    ctx.log(format!("[Rule] 列表页获取失败: {}", e));
+                    return vec![failed_result(input, &format!("列表页获取失败: {}", e), kind)];
+                }
+            };
+            Self::extract_items(config, &html)
+        };
+
+        if items.is_empty() {
+            ctx.log("[Rule] 未匹配到任何列表项".to_string());
+            return vec![failed_result(input, "未找到视频链接", ScrapeErrorKind::ParseFailed)];
+        }
+
+        let total_count = items.len();
+        ctx.log(format!("[Rule] 开始爬取 {} 个视频...", total_count));
+
+        // 按 concurrency 分批并发爬取详情页
+        let mut results = Vec::with_capacity(total_count);
+        for chunk in items.chunks(config.concurrency.max(1)) {
+            if ctx.is_cancelled() {
+                ctx.log("[Rule] 爬取已取消".to_string());
+                return vec![ScrapeResult::cancelled(None)];
+            }
+            let futures = chunk.iter().map(|item| self.scrape_detail(config, item, ctx));
+            for result in futures::future::join_all(futures).await {
+                if result.success {
+                    ctx.log(format!("[Rule] 成功: {}", result.name));
+                } else {
+                    ctx.log(format!("[Rule] 失败: {} - {}", result.name, result.message));
+                }
+                results.push(result);
+            }
+            if config.delay_ms > 0 && !ctx.sleep(Duration::from_millis(config.delay_ms)).await {
+                ctx.log("[Rule] 爬取已取消".to_string());
+                return vec![ScrapeResult::cancelled(None)];
+            }
+        }
+
+        let success_count = results.iter().filter(|r| r.success).count();
+        ctx.log(format!("[Rule] 完成: 成功 {} / 总数 {}", success_count, total_count));
+        results
+    }
+}
+
+/// 用正则提取内容：优先命名分组，其次第一个分组，最后整个匹配
+fn capture_first(pattern: Option<&Regex>, text: &str, group: &str) -> Option<String> {
+    let cap = pattern?.captures(text)?;
+    cap.name(group)
+        .or_else(|| cap.get(1))
+        .or_else(|| cap.get(0))
+        .map(|m| m.as_str().trim().to_string())
+}
+
+/// 用正则提取所有匹配（命名分组优先，其次第一个分组）
+fn capture_all(pattern: Option<&Regex>, text: &str, group: &str) -> Vec<String> {
+    let Some(pattern) = pattern else {
+        return Vec::new();
+    };
+    pattern
+        .captures_iter(text)
+        .filter_map(|cap| cap.name(group).or_else(|| cap.get(1)).or_else(|| cap.get(0)))
+        .map(|m| m.as_str().trim().to_string())
+        .collect()
+}
+
+/// 去除 HTML 标签并合并空白
+fn clean_text(text: &str) -> String {
+    let cleaned = TAG_PATTERN.replace_all(text, " ");
+    cleaned.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// 解析数量字符串（支持 万/亿）
+fn parse_count(count_str: &str) -> Option<i64> {
+    let cleaned = count_str.trim().replace([',', ' '], "");
+    for (unit, multiplier) in [('亿', 100_000_000.0), ('万', 10_000.0)] {
+        if let Some(num_part) = cleaned.strip_suffix(unit) {
+            return num_part.parse::<f64>().ok().map(|n| (n * multiplier) as i64);
+        }
+    }
+    cleaned.parse::<i64>().ok()
+}
+
+fn failed_result(input: &str, message: &str, kind: ScrapeErrorKind) -> ScrapeResult {
+    ScrapeResult {
+        success: false,
+        name: format!("第{}页", input),
+        m3u8_url: String::new(),
+        message: message.to_string(),
+        video_id: None,
+        view_count: None,
+        favorite_count: None,
+        cover_url: None,
+        error_kind: Some(kind),
+        metadata: VideoMetadata::default(),
+    }
+}
+
+impl Scraper for RuleSpider {
+    fn id(&self) -> &'static str {
+        "rule"
+    }
+
+    fn scrape(
+        &self,
+        input: &str,
+        ctx: ScrapeContext,
+    ) -> ScrapeFuture<ScrapeResult> {
+        let input = input.to_string();
+        let spider = self.clone();
+
+        Box::pin(async move {
+            let results = spider.run(&input, &ctx).await;
+            results
+                .iter()
+                .find(|r| r.success)
+                .or_else(|| results.first())
+                .cloned()
+                .unwrap_or_else(|| failed_result(&input, "未找到可用的视频", ScrapeErrorKind::ParseFailed))
+        })
+    }
+
+    fn refresh(&self, video_id: &str, ctx: ScrapeContext) -> ScrapeFuture<ScrapeResult> {
+        let spider = self.clone();
+        let item = RuleItem {
+            id: video_id.to_string(),
+            url: None,
+            title: None,
+            cover: None,
+        };
+
+        Box::pin(async move {
+            // 配置了详情页模板时直接请求详情页，否则按普通输入爬取
+            if spider.config.detail_url.is_empty() {
+                return spider.scrape(&item.id, ctx).await;
+            }
+            spider.scrape_detail(&spider.config, &item, &ctx).await
+        })
+    }
+
+    fn scrape_all(
+        &self,
+        input: &str,
+        ctx: ScrapeContext,
+    ) -> ScrapeFuture<Vec<ScrapeResult>> {
+        let input = input.to_string();
+        let spider = self.clone();
+
+        Box::pin(async move { spider.run(&input, &ctx).await })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIST_HTML: &str = r#"
+        <a href="/v/101" data-id="101"><img src="/c/101.jpg"><span><b>第一个</b>  视频</span></a>
+        <a href="/v/102" data-id="102"><img src="/c/102.jpg"><span>第二个视频</span></a>
+        <a href="/v/101" data-id="101"><img src="/c/101.jpg"><span>重复</span></a>
+    "#;
+
+    fn list_config() -> RuleConfig {
+        RuleConfig::parse(
+            r#"
+            list_url = "/list?page={page}"
+            item_pattern = '<a href="(?P<url>[^"]+)" data-id="(?P<id>\d+)"><img src="(?P<cover>[^"]+)"><span>(?P<title>.*?)</span>'
+            "#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn parse_json_and_toml() {
+        let json = RuleConfig::parse(r#"{"detail_url": "/v/{id}", "concurrency": 2}"#).unwrap();
+        assert_eq!(json.detail_url, "/v/{id}");
+        assert_eq!(json.concurrency, 2);
+        // 未配置的字段使用默认值
+        assert_eq!(json.delay_ms, 200);
+        assert!(json.patterns.m3u8.is_some());
+        assert!(json.patterns.cover.is_none());
+
+        let toml = list_config();
+        assert_eq!(toml.list_url, "/list?page={page}");
+        assert!(toml.patterns.item.is_some());
+    }
+
+    #[test]
+    fn parse_rejects_invalid_config() {
+        assert!(RuleConfig::parse("  ").is_err());
+        assert!(RuleConfig::parse("{not json").is_err());
+        assert!(RuleConfig::parse(r#"{"concurrency": 2}"#).unwrap_err().contains("至少需要配置一个"));
+        assert!(RuleConfig::parse(r#"{"list_url": "/list"}"#).unwrap_err().contains("item_pattern"));
+        let err = RuleConfig::parse(r#"{"detail_url": "/v/{id}", "title_pattern": "(unclosed"}"#).unwrap_err();
+        assert!(err.starts_with("title_pattern 正则无效"));
+    }
+
+    #[test]
+    fn extract_items_uses_named_groups_and_skips_duplicates() {
+        let items = RuleSpider::extract_items(&list_config(), LIST_HTML);
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].id, "101");
+        assert_eq!(items[0].url.as_deref(), Some("/v/101"));
+        assert_eq!(items[0].cover.as_deref(), Some("/c/101.jpg"));
+        assert_eq!(items[0].title.as_deref(), Some("第一个 视频"));
+        assert_eq!(items[1].id, "102");
+    }
+
+    #[test]
+    fn extract_items_falls_back_to_first_group() {
+        let config = RuleConfig::parse(r#"{"list_url": "/list", "item_pattern": "data-id=\"(\\d+)\""}"#).unwrap();
+        let items = RuleSpider::extract_items(&config, LIST_HTML);
+        let ids: Vec<&str> = items.iter().map(|i| i.id.as_str()).collect();
+        assert_eq!(ids, ["101", "102"]);
+        assert!(items[0].url.is_none());
+    }
+
+    #[test]
+    fn capture_first_prefers_named_group_then_first_group_then_match() {
+        let html = r#"<div class="views">播放 1.5万</div><script>var src = "https:\/\/cdn.example.com\/a.m3u8";</script>"#;
+        let named = Regex::new(r#"播放 (?P<count>[\d.]+万)"#).unwrap();
+        assert_eq!(capture_first(Some(&named), html, "count").as_deref(), Some("1.5万"));
+        let first = Regex::new(r#"src = "([^"]+)""#).unwrap();
+        assert_eq!(
+            capture_first(Some(&first), html, "url").as_deref(),
+            Some(r#"https:\/\/cdn.example.com\/a.m3u8"#)
+        );
+        let whole = Regex::new(r#"\d+\.\d+万"#).unwrap();
+        assert_eq!(capture_first(Some(&whole), html, "count").as_deref(), Some("1.5万"));
+        assert_eq!(capture_first(None, html, "count"), None);
+        let missing = Regex::new("不存在").unwrap();
+        assert_eq!(capture_first(Some(&missing), html, "count"), None);
+    }
+}
@@ -1,6 +1,7 @@
-use headless_chrome::Browser;
+use crate::models::ScrapeErrorKind;
+use crate::services::cancel::CancelToken;
+use crate::services::{rate_limit, retry, BrowserPool};
 use serde::{Deserialize, Serialize};
-use std::ffi::OsStr;
 
 /// 嗅探到的媒体资源
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -30,57 +31,50 @@ pub struct SniffResult {
     pub success: bool,
     /// 消息
     pub message: String,
+    /// 是否被用户取消
+    #[serde(default)]
+    pub cancelled: bool,
 }
 
-/// 嗅探指定页面中的媒体资源
-pub fn sniff_page(url: &str, timeout_secs: u64, log_callback: impl Fn(String)) -> SniffResult {
-    log_callback(format!("开始嗅探: {}", url));
-
-    // 启动浏览器
-    let browser_args: Vec<&OsStr> = vec![
-        OsStr::new("--headless=new"),
-        OsStr::new("--no-sandbox"),
-        OsStr::new("--disable-dev-shm-usage"),
-        OsStr::new("--disable-gpu"),
-        OsStr::new("--disable-software-rasterizer"),
-        OsStr::new("--mute-audio"),
-        OsStr::new("--hide-scrollbars"),
-        OsStr::new("--disable-translate"),
-        OsStr::new("--disable-background-networking"),
-        OsStr::new("--disable-sync"),
-        OsStr::new("--disable-features=site-per-process,TranslateUI"),
-        OsStr::new("--disable-extensions"),
-    ];
-
-    let browser = match Browser::new(headless_chrome::LaunchOptions {
-        args: browser_args,
-        headless: false, // headless=new via args
-        ..Default::default()
-    }) {
-        Ok(b) => b,
-        Err(e) => {
-            return SniffResult {
-                page_url: url.to_string(),
-                page_title: String::new(),
-                media_list: vec![],
-                success: false,
-                message: format!("启动浏览器失败: {}", e),
-            };
+impl SniffResult {
+    fn failed(url: &str, message: String) -> Self {
+        Self {
+            page_url: url.to_string(),
+            page_title: String::new(),
+            media_list: vec![],
+            success: false,
+            message,
+            cancelled: false,
         }
-    };
+    }
 
-    let tab = match browser.new_tab() {
-        Ok(t) => t,
-        Err(e) => {
-            return SniffResult {
-                page_url: url.to_string(),
-                page_title: String::new(),
-                media_list: vec![],
-                success: false,
-                message: format!("创建标签页失败: {}", e),
-            };
+    fn cancelled(url: &str) -> Self {
+        Self {
+            cancelled: true,
+            ..Self::failed(url, "嗅探已取消".to_string())
         }
+    }
+}
+
+/// 嗅探指定页面中的媒体资源（在阻塞线程中运行，等待期间轮询取消令牌）
+pub fn sniff_page(
+    url: &str,
+    timeout_secs: u64,
+    cancel: &CancelToken,
+    log_callback: impl Fn(String),
+) -> SniffResult {
+    log_callback(format!("开始嗅探: {}", url));
+
+    // 从共享浏览器池借出独立标签页（在 spawn_blocking 线程中调用）
+    let lease = match BrowserPool::global().acquire_blocking() {
+        Ok(lease) => lease,
+        Err(e) => return SniffResult::failed(url, format!("启动浏览器失败: {}", e)),
     };
+    let tab = lease.tab();
+
+    if cancel.is_cancelled() {
+        return SniffResult::cancelled(url);
+    }
 
     log_callback("正在加载页面...".to_string());
 
@@ -93,21 +87,26 @@ pub fn sniff_page(url: &str, timeout_secs: u64, log_callback: impl Fn(String)) -
         report_direct_socket_traffic: None,
     });
 
-    // 导航到页面
-    if let Err(e) = tab.navigate_to(url) {
-        return SniffResult {
-            page_url: url.to_string(),
-            page_title: String::new(),
-            media_list: vec![],
-            success: false,
-            message: format!("导航失败: {}", e),
-        };
+    // 导航到页面（遵守该主机已登记的限速，按重试策略重试）
+    let navigation = retry::run_blocking(&format!("嗅探导航 {}", url), cancel, |_| {
+        let _permit = rate_limit::acquire_blocking(url, None);
+        tab.navigate_to(url)
+            .map(|_| ())
+            .map_err(|e| (ScrapeErrorKind::Navigation, e.to_string()))
+    });
+    match navigation {
+        Ok(()) => {}
+        Err(failure) if failure.kind == ScrapeErrorKind::Cancelled => return SniffResult::cancelled(url),
+        Err(failure) => return SniffResult::failed(url, format!("导航失败: {}", failure.message)),
     }
 
-    // 等待页面加载
+    // 等待页面加载，取消时立即返回（lease 释放时关闭标签页）
     let wait_duration = std::time::Duration::from_secs(timeout_secs.min(30));
     let _ = tab.wait_until_navigated();
-    std::thread::sleep(wait_duration);
+    if !cancel.sleep_blocking(wait_duration) {
+        log_callback("嗅探已取消".to_string());
+        return SniffResult::cancelled(url);
+    }
 
     log_callback("页面加载完成，正在分析媒体资源...".to_string());
 
@@ -310,6 +309,7 @@ pub fn sniff_page(url: &str, timeout_secs: u64, log_callback: impl Fn(String)) -
         media_list,
         success: true,
         message: format!("发现 {} 个媒体资源", count),
+        cancelled: false,
     }
 }
 
@@ -0,0 +1,171 @@
+//! 下载后的文件完整性校验
+//!
+//! yt-dlp 退出码为 0 不代表文件可以播放，HLS 分片合并时经常得到被截断的文件。
+//! 下载完成后用 ffprobe 读取时长、分辨率和码率，与播放列表的 EXTINF 总时长（或爬取的元数据）比较，
+//! 再在文件中均匀抽取几段用 ffmpeg 解码，有解码错误即视为损坏。
+
+use std::path::Path;
+
+use serde_json::Value;
+use tauri::AppHandle;
+use tokio::process::Command;
+
+use crate::models::{VerifyConfig, VerifyReport};
+use crate::services::get_sidecar_path;
+
+/// 时长误差的下限（秒），短视频按百分比计算的误差太小
+const MIN_DURATION_TOLERANCE: f64 = 2.0;
+
+/// ffprobe 读取到的媒体信息
+struct Probe {
+    duration: Option<f64>,
+    resolution: String,
+    bitrate_kbps: Option<u64>,
+    has_stream: bool,
+}
+
+/// 校验下载的文件，ffprobe 或 ffmpeg 无法启动时返回 Err（无法判断文件是否损坏）
+pub async fn verify_file(
+    app_handle: &AppHandle,
+    path: &Path,
+    expected_duration: Option<f64>,
+    config: &VerifyConfig,
+) -> Result<VerifyReport, String> {
+    let ffprobe_path = get_sidecar_path(app_handle, "ffprobe")?;
+    let ffmpeg_path = get_sidecar_path(app_handle, "ffmpeg")?;
+    tracing::info!("[verify] 校验文件: {}", path.display());
+
+    let mut report = VerifyReport {
+        passed: false,
+        duration: None,
+        expected_duration,
+        resolution: String::new(),
+        bitrate_kbps: None,
+        errors: Vec::new(),
+        retries: 0,
+        checked_at: chrono::Utc::now(),
+    };
+
+    let probe = match probe(&ffprobe_path, path).await? {
+        Ok(probe) => probe,
+        Err(e) => {
+            report.errors.push(format!("无法读取文件: {}", e));
+            return Ok(report);
+        }
+    };
+    report.duration = probe.duration;
+    report.resolution = probe.resolution;
+    report.bitrate_kbps = probe.bitrate_kbps;
+
+    if !probe.has_stream {
+        report.errors.push("文件中没有音视频流".to_string());
+    }
+    match (probe.duration, expected_duration) {
+        (None, _) => report.errors.push("无法获取时长".to_string()),
+        (Some(actual), Some(expected)) if expected > 0.0 => {
+            let tolerance = (expected * config.duration_tolerance_percent / 100.0).max(MIN_DURATION_TOLERANCE);
+            if (expected - actual).abs() > tolerance {
+                report.errors.push(format!("时长不符: 实际 {:.1}s，应为 {:.1}s", actual, expected));
+            }
+        }
+        _ => {}
+    }
+
+    for start in sample_starts(probe.duration.unwrap_or(0.0), config) {
+        if let Some(error) = decode_sample(&ffmpeg_path, path, start, config.sample_seconds).await? {
+            report.errors.push(format!("解码错误 ({:.0}s 处): {}", start, error));
+        }
+    }
+
+    report.passed = report.errors.is_empty();
+    if report.passed {
+        tracing::info!("[verify] 校验通过: {}", path.display());
+    } else {
+        tracing::warn!("[verify] 校验不通过: {} - {}", path.display(), report.errors.join("; "));
+    }
+    Ok(report)
+}
+
+/// 用 ffprobe 读取媒体信息，外层 Err 表示 ffprobe 无法启动，内层 Err 表示文件无法解析
+async fn probe(ffprobe_path: &Path, path: &Path) -> Result<Result<Probe, String>, String> {
+    let output = Command::new(ffprobe_path)
+        .args(["-v", "error", "-print_format", "json", "-show_format", "-show_streams"])
+        .arg(path)
+        .output()
+        .await
+        .map_err(|e| format!("执行 ffprobe 失败: {}", e))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Ok(Err(stderr.lines().next().unwrap_or("ffprobe 执行失败").trim().to_string()));
+    }
+    let json: Value = match serde_json::from_slice(&output.stdout) {
+        Ok(json) => json,
+        Err(e) => return Ok(Err(format!("解析 ffprobe 输出失败: {}", e))),
+    };
+
+    let number = |value: Option<&Value>| value.and_then(Value::as_str).and_then(|s| s.parse::<f64>().ok());
+    let format = json.get("format");
+    let streams = json.get("streams").and_then(Value::as_array).cloned().unwrap_or_default();
+    let video = streams.iter().find(|s| s.get("codec_type").and_then(Value::as_str) == Some("video"));
+    let resolution = video
+        .and_then(|v| Some((v.get("width")?.as_u64()?, v.get("height")?.as_u64()?)))
+        .filter(|(width, height)| *width > 0 && *height > 0)
+        .map(|(width, height)| format!("{}x{}", width, height))
+        .unwrap_or_default();
+
+    Ok(Ok(Probe {
+        duration: number(format.and_then(|f| f.get("duration"))).filter(|d| *d > 0.0),
+        resolution,
+        bitrate_kbps: number(format.and_then(|f| f.get("bit_rate"))).map(|b| (b / 1000.0) as u64),
+        has_stream: streams
+            .iter()
+            .any(|s| matches!(s.get("codec_type").and_then(Value::as_str), Some("video" | "audio"))),
+    }))
+}
+
+/// 抽样片段的开始时间：均匀分布，包括开头和结尾（截断通常发生在结尾）
+fn sample_starts(duration: f64, config: &VerifyConfig) -> Vec<f64> {
+    let count = config.sample_count;
+    let length = config.sample_seconds as f64;
+    if count == 0 || config.sample_seconds == 0 {
+        return Vec::new();
+    }
+    let last = (duration - length).max(0.0);
+    if count == 1 || last == 0.0 {
+        return vec![last / 2.0];
+    }
+    (0..count)
+        .map(|i| last * i as f64 / (count - 1) as f64)
+        .collect()
+}
+
+/// 解码一段，返回第一条错误
+async fn decode_sample(ffmpeg_path: &Path, path: &Path, start: f64, seconds: u32) -> Result<Option<String>, String> {
+    let output = Command::new(ffmpeg_path)
+        .args(["-hide_banner", "-nostdin", "-v", "error", "-ss", &format!("{:.3}", start), "-t", &seconds.to_string(), "-i"])
+        .arg(path)
+        .args(["-f", "null", "-"])
+        .output()
+        .await
+        .map_err(|e| format!("执行 ffmpeg 失败: {}", e))?;
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let first_error = stderr.lines().map(str::trim).find(|line| !line.is_empty());
+    Ok(match first_error {
+        Some(error) => Some(error.to_string()),
+        None if !output.status.success() => Some("ffmpeg 解码失败".to_string()),
+        None => None,
+    })
+}
+
+/// 把秒数格式化为 "HH:MM:SS" 或 "MM:SS"
+pub fn format_duration(seconds: f64) -> String {
+    let total = seconds as u64;
+    let (hours, minutes, seconds) = (total / 3600, total % 3600 / 60, total % 60);
+    if hours > 0 {
+        format!("{:02}:{:02}:{:02}", hours, minutes, seconds)
+    } else {
+        format!("{:02}:{:02}", minutes, seconds)
+    }
+}
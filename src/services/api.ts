@@ -1,5 +1,5 @@
 import { invoke } from '@tauri-apps/api/core'
-import type { Website, AppConfig, ScrapeResult, PaginatedVideos, YtdlpTask, YtdlpConfig, ScraperInfo, LocalVideo, SniffResult, SniffedMediaRecord, ConvertTask, ConvertOptions } from '../types'
+import type { Website, AuthStatus, AppConfig, ScrapeResult, PaginatedVideos, VideoFilter, TagCount, YtdlpTask, YtdlpConfig, LinkInfo, PlaylistInfo, DownloadQueueState, CookieSource, CookieFileInfo, ProxyConfig, ProxyTestResult, ScraperInfo, CrawlJob, CrawlMode, CrawlSchedule, CrawlScheduleRun, LinkCheckProgress, RescrapeSummary, FixtureRecording, ScrapeFixtureInfo, LocalVideo, SniffResult, SniffedMediaRecord, ConvertTask, ConvertOptions } from '../types'
 
 // ==================== 通用 API ====================
 
@@ -16,18 +16,26 @@ export async function selectDirectory(): Promise<string | null> {
   return await invoke<string | null>('select_directory')
 }
 
+export async function testProxy(proxy?: ProxyConfig, targetUrl?: string): Promise<ProxyTestResult> {
+  return await invoke<ProxyTestResult>('test_proxy', { proxy, targetUrl })
+}
+
 export async function checkFfmpeg(): Promise<{ ffmpeg: boolean; 'yt-dlp': boolean }> {
   return await invoke<{ ffmpeg: boolean; 'yt-dlp': boolean }>('check_ffmpeg')
 }
 
 // ==================== 视频管理 API ====================
 
-export async function getVideos(page = 1, pageSize = 20): Promise<PaginatedVideos> {
-  return await invoke<PaginatedVideos>('get_videos_paginated', { page, pageSize })
+export async function getVideos(page = 1, pageSize = 20, filter?: VideoFilter): Promise<PaginatedVideos> {
+  return await invoke<PaginatedVideos>('get_videos_paginated', { page, pageSize, filter })
+}
+
+export async function searchVideos(query: string, page = 1, pageSize = 20, filter?: VideoFilter): Promise<PaginatedVideos> {
+  return await invoke<PaginatedVideos>('search_videos', { query, page, pageSize, filter })
 }
 
-export async function searchVideos(query: string, page = 1, pageSize = 20): Promise<PaginatedVideos> {
-  return await invoke<PaginatedVideos>('search_videos', { query, page, pageSize })
+export async function getVideoTags(): Promise<TagCount[]> {
+  return await invoke<TagCount[]>('get_video_tags')
 }
 
 export async function getVideosByWebsite(websiteName: string, page = 1, pageSize = 20): Promise<PaginatedVideos> {
@@ -42,6 +50,11 @@ export async function clearDownloadedVideos(): Promise<void> {
   await invoke('clear_downloaded')
 }
 
+// 缓存所有未缓存的封面并清理无用的封面文件，返回 [新缓存数, 删除文件数]
+export async function rebuildCoverCache(): Promise<[number, number]> {
+  return await invoke<[number, number]>('rebuild_cover_cache')
+}
+
 export const clearDownloaded = clearDownloadedVideos
 
 export async function downloadVideo(videoId: string): Promise<void> {
@@ -70,14 +83,130 @@ export async function setDefaultWebsite(id: string): Promise<void> {
   await invoke('set_default_website', { websiteId: id })
 }
 
+export async function getWebsiteAuthStatus(websiteId: string): Promise<AuthStatus> {
+  return await invoke<AuthStatus>('get_website_auth_status', { websiteId })
+}
+
+export async function refreshWebsiteAuth(websiteId: string): Promise<AuthStatus> {
+  return await invoke<AuthStatus>('refresh_website_auth', { websiteId })
+}
+
 export async function getScrapers(): Promise<ScraperInfo[]> {
   return await invoke<ScraperInfo[]>('get_scrapers')
 }
 
 // ==================== 爬虫 API ====================
 
-export async function scrapeVideo(websiteId: string, url: string): Promise<ScrapeResult> {
-  return await invoke<ScrapeResult>('scrape_video', { websiteId, url })
+// jobId 用于 cancelScrape，未指定时后端自动生成并通过 scrape-started 事件通知
+export async function scrapeVideo(websiteId: string, url: string, jobId?: string): Promise<ScrapeResult> {
+  return await invoke<ScrapeResult>('scrape_video', { websiteId, url, jobId: jobId ?? null })
+}
+
+export async function cancelScrape(jobId: string): Promise<boolean> {
+  return await invoke<boolean>('cancel_scrape', { jobId })
+}
+
+// ==================== 爬虫录制/回放 API ====================
+
+export async function recordScrape(websiteId: string, input: string): Promise<FixtureRecording> {
+  return await invoke<FixtureRecording>('record_scrape', { websiteId, input })
+}
+
+export async function replayScrape(fixtureDir: string): Promise<ScrapeResult[]> {
+  return await invoke<ScrapeResult[]>('replay_scrape', { fixtureDir })
+}
+
+export async function getScrapeFixtures(): Promise<ScrapeFixtureInfo[]> {
+  return await invoke<ScrapeFixtureInfo[]>('get_scrape_fixtures')
+}
+
+export async function deleteScrapeFixture(fixtureDir: string): Promise<void> {
+  await invoke('delete_scrape_fixture', { fixtureDir })
+}
+
+// ==================== 批量爬取 API ====================
+
+export async function startCrawlJob(
+  websiteId: string,
+  mode: CrawlMode,
+  startPage: number,
+  endPage?: number,
+  stopAfterKnown?: number,
+): Promise<CrawlJob> {
+  return await invoke<CrawlJob>('start_crawl_job', {
+    websiteId,
+    mode,
+    startPage,
+    endPage: endPage ?? null,
+    stopAfterKnown: stopAfterKnown ?? null,
+  })
+}
+
+// 增量爬取：websiteIds 为空时爬取所有网站，返回每个网站的任务（含新增数量）
+export async function startIncrementalCrawl(
+  websiteIds?: string[],
+  stopAfterKnown?: number,
+  maxPages?: number,
+): Promise<CrawlJob[]> {
+  return await invoke<CrawlJob[]>('start_incremental_crawl', {
+    websiteIds: websiteIds ?? null,
+    stopAfterKnown: stopAfterKnown ?? null,
+    maxPages: maxPages ?? null,
+  })
+}
+
+export async function resumeCrawlJob(jobId: string): Promise<CrawlJob> {
+  return await invoke<CrawlJob>('resume_crawl_job', { jobId })
+}
+
+export async function pauseCrawlJob(jobId: string): Promise<void> {
+  await invoke('pause_crawl_job', { jobId })
+}
+
+export async function getCrawlJobs(): Promise<CrawlJob[]> {
+  return await invoke<CrawlJob[]>('get_crawl_jobs')
+}
+
+export async function deleteCrawlJob(jobId: string): Promise<void> {
+  await invoke('delete_crawl_job', { jobId })
+}
+
+// ==================== 失效检查 API ====================
+
+export async function checkVideoLinks(websiteName?: string, jobId?: string): Promise<LinkCheckProgress> {
+  return await invoke<LinkCheckProgress>('check_video_links', { websiteName, jobId })
+}
+
+export async function cancelLinkCheck(jobId: string): Promise<boolean> {
+  return await invoke<boolean>('cancel_link_check', { jobId })
+}
+
+export async function rescrapeExpiredVideos(websiteName?: string, jobId?: string): Promise<RescrapeSummary> {
+  return await invoke<RescrapeSummary>('rescrape_expired_videos', { websiteName, jobId })
+}
+
+// ==================== 定时爬取 API ====================
+
+export async function getCrawlSchedules(): Promise<CrawlSchedule[]> {
+  return await invoke<CrawlSchedule[]>('get_crawl_schedules')
+}
+
+export async function saveCrawlSchedule(
+  schedule: Omit<CrawlSchedule, 'id' | 'website_name' | 'next_run_at' | 'created_at'> & Partial<CrawlSchedule>,
+): Promise<CrawlSchedule> {
+  return await invoke<CrawlSchedule>('save_crawl_schedule', { schedule })
+}
+
+export async function deleteCrawlSchedule(scheduleId: string): Promise<void> {
+  await invoke('delete_crawl_schedule', { scheduleId })
+}
+
+export async function getCrawlScheduleRuns(scheduleId: string): Promise<CrawlScheduleRun[]> {
+  return await invoke<CrawlScheduleRun[]>('get_crawl_schedule_runs', { scheduleId })
+}
+
+export async function runCrawlScheduleNow(scheduleId: string): Promise<CrawlScheduleRun> {
+  return await invoke<CrawlScheduleRun>('run_crawl_schedule_now', { scheduleId })
 }
 
 export async function batchDownload(videoIds: string[]): Promise<void> {
@@ -98,12 +227,20 @@ export async function getYtdlpTasks(): Promise<YtdlpTask[]> {
   return await invoke<YtdlpTask[]>('get_ytdlp_tasks')
 }
 
-export async function getVideoInfo(url: string, quality: number = 1080): Promise<YtdlpTask> {
-  return await invoke<YtdlpTask>('get_video_info', { url, quality })
+export async function getVideoInfo(url: string, quality: number = 1080, cookieSource?: CookieSource): Promise<YtdlpTask> {
+  return await invoke<YtdlpTask>('get_video_info', { url, quality, cookieSource })
+}
+
+export async function addYtdlpTasks(urls: string[], quality: number = 1080, cookieSource?: CookieSource): Promise<void> {
+  await invoke('add_ytdlp_tasks', { urls, quality, cookieSource })
+}
+
+export async function getLinkInfo(url: string, quality: number = 1080, cookieSource?: CookieSource): Promise<LinkInfo> {
+  return await invoke<LinkInfo>('get_link_info', { url, quality, cookieSource })
 }
 
-export async function addYtdlpTasks(urls: string[], quality: number = 1080): Promise<void> {
-  await invoke('add_ytdlp_tasks', { urls, quality })
+export async function addPlaylistTasks(playlist: PlaylistInfo, cookieSource?: CookieSource): Promise<YtdlpTask[]> {
+  return await invoke<YtdlpTask[]>('add_playlist_tasks', { playlist, cookieSource })
 }
 
 export async function startYtdlpTask(taskId: string, outputPath: string): Promise<void> {
@@ -122,10 +259,54 @@ export async function cleanupYtdlpTasks(): Promise<void> {
   await invoke('cleanup_ytdlp_tasks')
 }
 
+// ==================== 下载队列 API ====================
+
+export async function getDownloadQueue(): Promise<DownloadQueueState> {
+  return await invoke<DownloadQueueState>('get_download_queue')
+}
+
+export async function pauseDownloadQueue(): Promise<void> {
+  await invoke('pause_download_queue')
+}
+
+export async function resumeDownloadQueue(): Promise<void> {
+  await invoke('resume_download_queue')
+}
+
+export async function setDownloadPriority(entryId: string, priority: number): Promise<void> {
+  await invoke('set_download_priority', { entryId, priority })
+}
+
+export async function setDownloadBandwidthLimit(entryId: string, bandwidthLimitKb: number): Promise<void> {
+  await invoke('set_download_bandwidth_limit', { entryId, bandwidthLimitKb })
+}
+
+export async function reorderDownloadQueue(entryIds: string[]): Promise<void> {
+  await invoke('reorder_download_queue', { entryIds })
+}
+
+export async function removeFromDownloadQueue(entryId: string): Promise<void> {
+  await invoke('remove_from_download_queue', { entryId })
+}
+
 export async function openPath(path: string): Promise<void> {
   await invoke('open_path', { path })
 }
 
+// ==================== Cookie 文件 API ====================
+
+export async function importCookieFile(sourcePath: string, name: string): Promise<CookieFileInfo> {
+  return await invoke<CookieFileInfo>('import_cookie_file', { sourcePath, name })
+}
+
+export async function getCookieFiles(): Promise<CookieFileInfo[]> {
+  return await invoke<CookieFileInfo[]>('get_cookie_files')
+}
+
+export async function deleteCookieFile(path: string): Promise<void> {
+  await invoke('delete_cookie_file', { path })
+}
+
 // ==================== 本地视频管理 API ====================
 
 export async function getLocalVideos(): Promise<LocalVideo[]> {
@@ -213,8 +394,13 @@ export async function createCastRemoteSession(
 
 // ==================== 嗅探 API ====================
 
-export async function sniffMedia(url: string, timeoutSecs = 5): Promise<SniffResult> {
-  return await invoke<SniffResult>('sniff_media', { url, timeoutSecs })
+// jobId 用于 cancelSniff，未指定时后端自动生成并通过 sniff-started 事件通知
+export async function sniffMedia(url: string, timeoutSecs = 5, jobId?: string): Promise<SniffResult> {
+  return await invoke<SniffResult>('sniff_media', { url, timeoutSecs, jobId: jobId ?? null })
+}
+
+export async function cancelSniff(jobId: string): Promise<boolean> {
+  return await invoke<boolean>('cancel_sniff', { jobId })
 }
 
 export async function getSniffedRecords(): Promise<SniffedMediaRecord[]> {
@@ -5,6 +5,8 @@ export enum VideoStatus {
   Downloading = 'Downloading',
   Downloaded = 'Downloaded',
   Failed = 'Failed',
+  Expired = 'Expired',
+  Corrupt = 'Corrupt',  // 已下载但文件校验不通过
 }
 
 // 视频条目
@@ -18,9 +20,31 @@ export interface VideoItem {
   scrape_id: string
   website_name: string
   cover_url?: string
+  cover_key?: string   // 本地封面缓存键
+  cover_path?: string  // 本地封面文件路径（通过 asset 协议显示）
   view_count?: number
   favorite_count?: number
   file_path?: string  // 下载后的本地路径
+  duration?: number   // 时长（秒）
+  tags: string[]
+  description?: string
+  uploader?: string
+  published_at?: string
+  post_process?: PostProcessRecord[]  // 最近一次下载后各处理步骤的状态
+  verification?: VerifyReport | null  // 最近一次下载的文件校验结果
+}
+
+// 视频列表筛选条件
+export interface VideoFilter {
+  tag?: string
+  min_duration?: number  // 最短时长（秒）
+  max_duration?: number  // 最长时长（秒）
+}
+
+// 标签及其视频数
+export interface TagCount {
+  name: string
+  count: number
 }
 
 // 应用配置
@@ -28,6 +52,34 @@ export interface AppConfig {
   download_path: string
   local_storage: LocalStorageItem[]
   default_quality: string
+  proxy?: ProxyConfig  // 全局代理
+  retry?: RetryPolicy  // 网络操作的重试策略
+}
+
+// 重试策略（导航、详情页请求、yt-dlp 下载和 DLNA 动作共用）
+export interface RetryPolicy {
+  max_attempts: number        // 最多尝试次数（包括第一次），1 表示不重试
+  initial_delay_ms: number    // 第一次重试前的等待（毫秒）
+  max_delay_ms: number        // 等待时长上限（毫秒）
+  multiplier: number          // 每次重试等待时长的倍数
+  jitter_ms: number           // 随机抖动上限（毫秒）
+  retry_on: ScrapeErrorKind[] // 可以重试的失败类型
+}
+
+// 代理配置
+export interface ProxyConfig {
+  url: string       // 如 http://127.0.0.1:7890、socks5://127.0.0.1:1080，为空表示直连
+  no_proxy: string  // 不走代理的主机（逗号分隔）
+}
+
+// 代理连通性测试结果
+export interface ProxyTestResult {
+  success: boolean
+  proxy_reachable?: boolean  // 代理端口能否连通（直连时为空）
+  connect_ms?: number
+  status?: number            // 测试地址的响应状态码
+  latency_ms?: number
+  message: string
 }
 
 // LocalStorage项
@@ -36,12 +88,29 @@ export interface LocalStorageItem {
   value: string
 }
 
+// 爬取失败类型
+export type ScrapeErrorKind =
+  | 'BrowserLaunch'
+  | 'Navigation'
+  | 'NotFound'
+  | 'Timeout'
+  | 'AuthExpired'
+  | 'ParseFailed'
+  | 'Blocked'
+  | 'Cancelled'
+
 // 爬取结果
 export interface ScrapeResult {
   success: boolean
   name: string
   m3u8_url: string
   message: string
+  error_kind?: ScrapeErrorKind  // 失败类型，成功时为空
+  duration?: number             // 时长（秒）
+  tags?: string[]
+  description?: string
+  uploader?: string
+  published_at?: string
 }
 
 // 下载进度
@@ -51,6 +120,7 @@ export interface DownloadProgress {
   status: string
   speed: string
   eta: string
+  rate_limit?: string  // 当前生效的限速，为空表示不限
 }
 
 // 分页结果
@@ -70,16 +140,182 @@ export interface Website {
   local_storage: LocalStorageItem[]
   is_default: boolean
   spider: string
+  spider_config?: string  // 规则爬虫配置（JSON / TOML）
+  auth?: AuthConfig       // 认证配置
+  cookie_source?: CookieSource | null  // Cookie 来源，为空时使用 yt-dlp 配置中的全局来源
+  proxy?: ProxyConfig | null           // 代理，为空时使用全局代理（地址为空表示直连）
+  rate_limit?: RateLimitConfig         // 按主机限速
+  download_profile?: DownloadProfile   // 下载配置，未设置的项使用全局配置
+}
+
+// 网站下载配置（空字符串 / null / 0 表示使用全局配置）
+export interface DownloadProfile {
+  output_dir: string            // 输出目录
+  quality: number | null        // 视频质量
+  format: string                // 视频格式 (mp4, mkv 等)
+  max_concurrent: number        // 该网站最多同时下载数
+  extra_options: string         // 附加的 yt-dlp 选项
+  filename_template: string     // 输出文件名模板
+  post_process?: PostProcessStep[] | null  // 下载后处理步骤，null 表示使用全局配置
+}
+
+// 按主机限速配置（0 表示不限）
+export interface RateLimitConfig {
+  max_requests_per_sec: number  // 同一主机每秒最多请求数
+  max_concurrent: number        // 同一主机最多同时连接数
+  detail_delay_ms: number       // 详情页抓取之间的最小间隔（毫秒）
+  detail_jitter_ms: number      // 附加的随机抖动上限（毫秒）
+}
+
+// 网站认证配置
+export interface AuthConfig {
+  storage_key: string          // 凭证所在的 localStorage 键，为空表示不需要认证
+  query_param: string          // m3u8 地址中携带凭证的查询参数
+  login_url: string            // 登录页地址
+  login_script: string         // 在登录页执行的登录脚本
+  login_timeout_secs: number   // 等待新凭证的超时秒数
+}
+
+// 网站认证状态
+export interface AuthStatus {
+  website_id: string
+  enabled: boolean
+  has_token: boolean
+  expires_at?: string      // 凭证过期时间（仅 JWT 凭证可知）
+  expired: boolean
+  can_refresh: boolean     // 是否配置了登录脚本
 }
 
+// 爬虫能力
+export type ScraperCapability = 'single_video' | 'list_page' | 'pagination' | 'browser' | 'configurable'
+
 // 爬虫信息
 export interface ScraperInfo {
   id: string
   name: string
+  capabilities: ScraperCapability[]
+  config_schema?: Record<string, unknown>  // spider_config 的 JSON Schema
+}
+
+// ==================== 批量爬取相关类型 ====================
+
+// 批量爬取模式：页码范围 / 直到没有新视频 / 增量（连续遇到已存在视频后停止）
+export type CrawlMode = 'Range' | 'UntilNoNew' | 'Incremental'
+
+// 批量爬取任务状态
+export enum CrawlStatus {
+  Pending = 'Pending',
+  Running = 'Running',
+  Paused = 'Paused',
+  Completed = 'Completed',
+  Failed = 'Failed',
+}
+
+// m3u8 失效检查进度（link-check-progress 事件）
+export interface LinkCheckProgress {
+  job_id: string
+  total: number
+  checked: number
+  alive: number
+  expired: number
+  unknown: number   // 无法判断（网络错误、超时等）
+  errors: string[]  // 无法检查的网站及原因
+  finished: boolean
+  cancelled: boolean
+}
+
+// 重新爬取失效视频的结果
+export interface RescrapeSummary {
+  total: number
+  refreshed: number
+  failed: number
+  cancelled: boolean
+}
+
+// 批量爬取任务（游标持久化，支持断点续爬）
+export interface CrawlJob {
+  id: string
+  website_id: string
+  website_name: string
+  mode: CrawlMode
+  start_page: number
+  end_page?: number        // 结束页（包含），UntilNoNew 模式下为最大页数上限
+  current_page: number     // 最后完成的页码，0 表示尚未开始
+  last_item?: string       // 最后一个处理过的视频ID
+  stop_after_known?: number  // 增量模式下连续遇到多少个已存在视频后停止
+  status: CrawlStatus
+  new_count: number
+  duplicate_count: number
+  message: string
+  created_at: string
+  updated_at: string
+}
+
+// 定时爬取计划（如“每 6 小时爬取网站 X 的第 1-3 页”）
+export interface CrawlSchedule {
+  id: string               // 为空时表示新建计划
+  website_id: string
+  website_name: string
+  mode: CrawlMode
+  start_page: number
+  end_page?: number
+  stop_after_known?: number
+  interval_minutes: number // 执行间隔（分钟），设置了 cron 时不使用
+  cron?: string            // cron 表达式（本地时间，分 时 日 月 周），为空时按间隔执行
+  auto_download: boolean   // 是否自动下载新爬取到的视频
+  enabled: boolean
+  last_run_at?: string
+  next_run_at: string
+  created_at: string
+}
+
+// 定时爬取执行记录
+export interface CrawlScheduleRun {
+  id: string
+  schedule_id: string
+  crawl_job_id?: string
+  started_at: string
+  finished_at?: string
+  new_count: number
+  error?: string           // 错误信息，成功时为空
+}
+
+// ==================== 爬虫录制/回放相关类型 ====================
+
+// 录制结果
+export interface FixtureRecording {
+  fixture_dir: string      // 夹具目录
+  results: ScrapeResult[]
+}
+
+// 已录制的夹具
+export interface ScrapeFixtureInfo {
+  fixture_dir: string
+  website_id: string
+  spider: string
+  input: string            // 录制时的视频ID或页码
+  recorded_at: string
+  page_count: number
+  response_count: number
 }
 
 // ==================== yt-dlp 下载相关类型 ====================
 
+// Cookie 来源（yt-dlp 和 HTTP 请求共用）
+export type CookieSource =
+  | { type: 'None' }
+  | { type: 'Browser'; browser: string; profile?: string }  // 如 chrome、firefox，profile 为空表示默认配置
+  | { type: 'File'; path: string }                           // 应用管理的 cookies.txt
+
+// 已导入的 Cookie 文件
+export interface CookieFileInfo {
+  name: string
+  path: string             // 作为 CookieSource File 的 path
+  cookie_count: number
+  domains: string[]
+  modified_at?: string
+}
+
 // yt-dlp 下载配置
 export interface YtdlpConfig {
   // 视频质量：分辨率数值，如 0=最佳，480=480p，720=720p，1080=1080p，2160=4K
@@ -94,8 +330,93 @@ export interface YtdlpConfig {
   merge_video: boolean
   concurrent_downloads: number
   extra_options: string
+  cookie_source?: CookieSource  // 全局 Cookie 来源
+  hls_engine?: HlsEngine  // m3u8 使用的下载引擎
+  filename_template?: string  // 输出文件名模板，/ 分隔子目录
+  collision_policy?: CollisionPolicy  // 文件已存在时的处理方式
+  bandwidth_limit_kb?: number  // 全局下载限速 (KB/s)，0 表示不限
+  download_windows?: DownloadWindow[]  // 允许下载的时间段，为空表示不限制
+  post_process?: PostProcessStep[]  // 下载完成后依次执行的处理步骤
+  verify?: VerifyConfig  // 下载后的文件完整性校验
+}
+
+// 下载后的文件完整性校验配置
+export interface VerifyConfig {
+  enabled: boolean
+  duration_tolerance_percent: number  // 与播放列表或元数据时长允许相差的百分比
+  sample_count: number    // 抽样解码的片段数
+  sample_seconds: number  // 每个抽样片段的时长（秒）
+  retries: number         // 校验不通过时自动重新下载的次数
 }
 
+// 文件完整性校验结果
+export interface VerifyReport {
+  passed: boolean
+  duration?: number | null           // 实际时长（秒）
+  expected_duration?: number | null  // 期望时长（秒）
+  resolution: string
+  bitrate_kbps?: number | null
+  errors: string[]
+  retries: number  // 重新下载的次数
+  checked_at: string
+}
+
+// 下载完成后的处理步骤
+export type PostProcessStep =
+  | { type: 'Convert'; options: ConvertOptions; keep_original: boolean }  // 按预设转换格式
+  | { type: 'ExtractCover'; timestamp: number }  // 截取一帧保存为同名 .jpg
+  | { type: 'Checksum' }  // 计算 SHA-256，写入同名 .sha256
+  | { type: 'MoveToLibrary'; dir: string; subdir: string }  // 移动到媒体库，subdir 支持文件名模板变量
+  | { type: 'WriteMetadata' }  // 写入同名 .json 元数据
+  | { type: 'RunCommand'; command: string }  // 执行本地命令，可用 {file} {dir} {title} {website} {scrape_id} {id}
+
+export type PostProcessStatus = 'Pending' | 'Running' | 'Completed' | 'Failed'
+
+// 后处理步骤的执行记录
+export interface PostProcessRecord {
+  step: string
+  status: PostProcessStatus
+  message: string
+}
+
+// 允许下载的时间段（本地时间），结束早于开始表示到第二天，相同表示全天
+export interface DownloadWindow {
+  days: number[]  // 1=周一 ... 7=周日，为空表示每天
+  start: string   // "HH:MM"
+  end: string     // "HH:MM"
+}
+
+// 文件已存在时：Skip=跳过下载，AutoNumber=追加编号，Overwrite=覆盖
+export type CollisionPolicy = 'Skip' | 'AutoNumber' | 'Overwrite'
+
+
+// 下载队列项：Ytdlp=下载页添加的任务，Video=爬取的视频
+export interface DownloadQueueEntry {
+  id: string
+  kind: 'Ytdlp' | 'Video'
+  target_id: string             // yt-dlp 任务ID或视频ID
+  title: string
+  website_name?: string | null
+  output_path?: string | null
+  priority: number              // 越大越先下载
+  position: number              // 同一优先级内的顺序
+  bandwidth_limit_kb: number    // 单独的限速 (KB/s)，0 表示只受全局限速
+  status: 'Queued' | 'Running'
+  created_at: string
+}
+
+// 下载队列状态（download-queue-updated 事件）
+export interface DownloadQueueState {
+  paused: boolean
+  max_concurrent: number
+  window_open: boolean          // 当前是否在允许下载的时间段内
+  bandwidth_limit_kb: number    // 全局下载限速 (KB/s)
+  entries: DownloadQueueEntry[]
+}
+
+// HLS 下载引擎：Native=内置引擎（失败时回退到 yt-dlp），Ytdlp=始终使用 yt-dlp
+export type HlsEngine = 'Native' | 'Ytdlp'
+
 // yt-dlp 任务状态
 export enum YtdlpTaskStatus {
   Pending = 'Pending',
@@ -105,6 +426,7 @@ export enum YtdlpTaskStatus {
   Completed = 'Completed',
   Failed = 'Failed',
   Cancelled = 'Cancelled',
+  Corrupt = 'Corrupt',  // 已下载但文件校验不通过
 }
 
 // yt-dlp 下载任务（简化版）
@@ -121,8 +443,45 @@ export interface YtdlpTask {
   completed_at?: string
   resolution?: string      // 视频分辨率 (如 "1920x1080")
   file_size?: string        // 预估文件大小 (如 "150.5MB")
+  cookie_source?: CookieSource | null  // 任务单独指定的 Cookie 来源
+  rate_limit?: string       // 当前生效的限速 (如 "512.00KB/s")，为空表示不限
+  post_process?: PostProcessRecord[]  // 下载完成后各处理步骤的状态
+  verification?: VerifyReport | null  // 下载后的文件校验结果
+  collection?: TaskCollection | null  // 所属合集（从播放列表或频道添加的任务）
+  duration?: number | null  // 视频时长（秒）
+}
+
+// 任务所属的合集，同一次从播放列表或频道添加的任务共用一个合集ID
+export interface TaskCollection {
+  id: string
+  title: string
+  url: string
+  index: number  // 在播放列表中的序号（从 1 开始）
+}
+
+// 播放列表中的一项
+export interface PlaylistEntry {
+  index: number
+  id: string
+  url: string
+  title: string
+  duration?: number | null
 }
 
+// 播放列表或频道的信息
+export interface PlaylistInfo {
+  id: string
+  title: string
+  url: string
+  uploader: string
+  entries: PlaylistEntry[]
+}
+
+// 链接的信息：单个视频或播放列表（频道）
+export type LinkInfo =
+  | ({ kind: 'video' } & YtdlpTask)
+  | ({ kind: 'playlist' } & PlaylistInfo)
+
 // yt-dlp 下载结果
 export interface YtdlpResult {
   success: boolean
@@ -130,6 +489,7 @@ export interface YtdlpResult {
   file_path: string
   file_size: number
   message: string
+  skipped?: boolean
 }
 
 // ==================== 嗅探相关类型 ====================
@@ -150,6 +510,7 @@ export interface SniffResult {
   media_list: SniffedMedia[]
   success: boolean
   message: string
+  cancelled: boolean  // 是否被用户取消
 }
 
 // 嗅探记录（数据库持久化）